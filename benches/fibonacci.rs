@@ -0,0 +1,71 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use halo2_examples::{
+    batch::{prove_many, verify_many},
+    example1, example2, example3, example4,
+    fibo::{FiboLayout, FiboPublicInputs},
+};
+use halo2_proofs::{
+    circuit::Value,
+    dev::MockProver,
+    pasta::{EqAffine, Fp},
+    plonk::{keygen_pk, keygen_vk, Circuit},
+    poly::commitment::Params,
+};
+
+// Every example fits in k = 4; the larger k show how much of the cost is the
+// domain size rather than the layout.
+const KS: [u32; 3] = [4, 6, 8];
+
+// Keygen, witness synthesis, proving and verification for one of the
+// examples. Synthesis is timed through the mock prover, which runs the
+// circuit's `synthesize` into a fresh assignment without committing to it.
+fn bench_example<C: Circuit<Fp> + Sync>(c: &mut Criterion, name: &str, circuit: impl Fn() -> C) {
+    let inputs = FiboPublicInputs::new(Fp::from(1), Fp::from(1));
+    let instances = [inputs.to_instances(&FiboLayout::single())];
+
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+    for k in KS {
+        let params = Params::<EqAffine>::new(k);
+
+        group.bench_with_input(BenchmarkId::new("keygen", k), &k, |b, _| {
+            b.iter(|| {
+                let vk = keygen_vk(&params, &circuit()).unwrap();
+                keygen_pk(&params, vk, &circuit()).unwrap()
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("synthesis", k), &k, |b, &k| {
+            b.iter(|| MockProver::run(k, &circuit(), instances[0].clone()).unwrap())
+        });
+
+        let vk = keygen_vk(&params, &circuit()).unwrap();
+        let pk = keygen_pk(&params, vk, &circuit()).unwrap();
+        group.bench_with_input(BenchmarkId::new("prove", k), &k, |b, _| {
+            b.iter(|| prove_many(&params, &pk, &[circuit()], &instances))
+        });
+
+        let proof = prove_many(&params, &pk, &[circuit()], &instances);
+        group.bench_with_input(BenchmarkId::new("verify", k), &k, |b, _| {
+            b.iter(|| assert!(verify_many(&params, pk.get_vk(), &instances, &proof)))
+        });
+    }
+    group.finish();
+}
+
+fn examples(c: &mut Criterion) {
+    // three advice columns, a region per row
+    bench_example(c, "example1", || example1::MyCircuit::<Fp> {
+        a: Value::known(Fp::from(1)),
+        b: Value::known(Fp::from(1)),
+    });
+    // one advice column, the whole table in one region
+    bench_example(c, "example2", example2::MyCircuit::<Fp>::default);
+    // two advice columns, two terms per row
+    bench_example(c, "example3", example3::MyCircuit::<Fp>::default);
+    // example2's table, the gate looking back with Rotation::prev()
+    bench_example(c, "example4", example4::MyCircuit::<Fp>::default);
+}
+
+criterion_group!(benches, examples);
+criterion_main!(benches);
@@ -0,0 +1,47 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use halo2_examples::{
+    batch::prove_many_with,
+    example3,
+    fibo::{FiboLayout, FiboPublicInputs},
+    prover::ProverConfig,
+};
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{keygen_pk, keygen_vk},
+    poly::commitment::Params,
+};
+
+// Large enough that the MSMs and FFTs are most of the proving time, rather
+// than the circuit, which fits in k = 4.
+const K: u32 = 14;
+
+// Proving time on 1, 2, 4, ... threads, up to one per core.
+fn threads(c: &mut Criterion) {
+    let circuit = example3::MyCircuit::<Fp>::default;
+    let inputs = FiboPublicInputs::new(Fp::from(1), Fp::from(1));
+    let instances = [inputs.to_instances(&FiboLayout::single())];
+
+    let params = Params::<EqAffine>::new(K);
+    let vk = keygen_vk(&params, &circuit()).unwrap();
+    let pk = keygen_pk(&params, vk, &circuit()).unwrap();
+
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut counts: Vec<usize> = (0..)
+        .map(|i| 1 << i)
+        .take_while(|threads| *threads < cores)
+        .collect();
+    counts.push(cores);
+
+    let mut group = c.benchmark_group("threads");
+    group.sample_size(10);
+    for threads in counts {
+        let config = ProverConfig::threads(threads);
+        group.bench_with_input(BenchmarkId::new("prove", threads), &threads, |b, _| {
+            b.iter(|| prove_many_with(&config, &params, &pk, &[circuit()], &instances))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, threads);
+criterion_main!(benches);
@@ -0,0 +1,5 @@
+fn main() {
+    // the gRPC server's messages and service, see proto/fibo.proto
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/fibo.proto").unwrap();
+}
@@ -0,0 +1,2 @@
+// Everything the C interface exports, see include/fibo.h.
+pub use halo2_examples::ffi::*;
@@ -0,0 +1,114 @@
+// Builds the circuits from arbitrary field elements, k and instance columns
+// and runs them through the mock prover, along with the near misses of
+// each instance from `testing`. A bad witness or a k that's too small should
+// come back as an error; a panic anywhere on the way is a bug. The instance
+// values' non-canonical encodings have to be turned away by calldata
+// decoding.
+//
+// Specs are always built for F[OUT_TERM], and a claimed output only goes into
+// the instance after `build`, since `build` turns away anything else before
+// the circuit exists.
+//
+//     cargo +nightly fuzz run mock_prover
+
+#![no_main]
+
+use halo2_examples::{
+    batch::FiboSegment,
+    evm::{decode_calldata, encode_calldata},
+    fibo::{FiboLayout, FiboPublicInputs, OUT_TERM},
+    spec::{Backend, CircuitSpec, Variant},
+    testing::{near_misses, non_canonical_encodings},
+};
+use halo2_proofs::{dev::MockProver, pasta::Fp, plonk::Circuit};
+use libfuzzer_sys::{arbitrary::Arbitrary, fuzz_target};
+
+// MockProver allocates 2^k rows for every column, so anything above this
+// only slows the fuzzer down.
+const MAX_K: u8 = 10;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    variant: u8,
+    k: u8,
+    // picks FiboSegment's length
+    steps: u8,
+    a: [u64; 4],
+    b: [u64; 4],
+    // a claimed output instead of the right one
+    out: Option<[u64; 4]>,
+    // (column, rows) pairs: rows added to the column if positive, dropped if
+    // negative, with a column past the last one adding a new column
+    resize: Vec<(u8, i8)>,
+}
+
+// Grows, shrinks or adds instance columns.
+fn resize(instance: &mut Vec<Vec<Fp>>, changes: &[(u8, i8)]) {
+    for (column, rows) in changes.iter().take(4) {
+        let column = *column as usize % (instance.len() + 1);
+        if column == instance.len() {
+            instance.push(vec![]);
+        }
+        let len = instance[column].len() as isize + *rows as isize;
+        instance[column].resize(len.max(0) as usize, Fp::one());
+    }
+}
+
+// Every value of `instance` in calldata as itself plus a multiple of the
+// modulus, which has to fail to decode.
+fn check_non_canonical(instance: &[Vec<Fp>]) {
+    let rows: Vec<usize> = instance.iter().map(|column| column.len()).collect();
+    let calldata = encode_calldata(instance, &[]);
+    for (i, value) in instance.iter().flatten().enumerate() {
+        for (note, mut repr) in non_canonical_encodings(value) {
+            repr.reverse();
+            let mut calldata = calldata.clone();
+            calldata[32 * i..32 * (i + 1)].copy_from_slice(&repr);
+            assert!(decode_calldata::<Fp>(&calldata, &rows).is_err(), "{}", note);
+        }
+    }
+}
+
+fn mock<C: Circuit<Fp>>(k: u32, circuit: &C, instance: Vec<Vec<Fp>>) {
+    if let Ok(prover) = MockProver::run(k, circuit, instance) {
+        let _ = prover.verify();
+    }
+}
+
+fuzz_target!(|input: Input| {
+    let k = (input.k % (MAX_K + 1)) as u32;
+    let (a, b) = (Fp::from_raw(input.a), Fp::from_raw(input.b));
+
+    let variant =
+        [Variant::Example1, Variant::Example2, Variant::Example3][input.variant as usize % 3];
+    let spec = CircuitSpec {
+        variant,
+        k: Some(k),
+        steps: OUT_TERM,
+        backend: Backend::Ipa,
+    };
+    if let Ok((circuit, mut instance)) = spec.build(&FiboPublicInputs::new(a, b)) {
+        if let Some(out) = input.out {
+            let mut claimed = FiboPublicInputs::new(a, b);
+            claimed.out = Fp::from_raw(out);
+            instance = claimed.to_instances(&FiboLayout::single());
+        }
+        check_non_canonical(&instance);
+        for (_, instance) in near_misses(&instance) {
+            let _ = circuit.mock(k, &instance);
+        }
+        resize(&mut instance, &input.resize);
+        let _ = circuit.mock(k, &instance);
+    }
+
+    // FiboSegment's length is a const parameter, so pick from a few
+    let (a_out, b_out) = FiboSegment::<8>::run(a, b);
+    let mut instance = vec![vec![a, b, a_out, b_out]];
+    resize(&mut instance, &input.resize);
+    match input.steps % 4 {
+        0 => mock(k, &FiboSegment::<1>::new(a, b), instance),
+        1 => mock(k, &FiboSegment::<8>::new(a, b), instance),
+        2 => mock(k, &FiboSegment::<15>::new(a, b), instance),
+        _ => mock(k, &FiboSegment::<64>::new(a, b), instance),
+    }
+});
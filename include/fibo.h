@@ -0,0 +1,30 @@
+#ifndef FIBO_H
+#define FIBO_H
+
+/* Generated with cbindgen from src/ffi.rs, don't edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum FiboStatus {
+  FIBO_STATUS_OK = 0,
+  FIBO_STATUS_INVALID = 1,
+  FIBO_STATUS_BAD_INPUT = 2,
+  FIBO_STATUS_INTERNAL = 3,
+} FiboStatus;
+
+typedef struct FiboProof {
+  uint8_t *data;
+  size_t len;
+} FiboProof;
+
+FiboStatus fibo_prove(uint64_t a, uint64_t b, size_t n, struct FiboProof *proof);
+
+FiboStatus fibo_verify(const uint8_t *proof, size_t len, uint64_t a, uint64_t b, uint64_t out);
+
+void fibo_free_proof(struct FiboProof *proof);
+
+#endif /* FIBO_H */
@@ -0,0 +1,340 @@
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::{EqAffine, Fp},
+    plonk::{
+        create_proof, verify_proof, Advice, BatchVerifier, Circuit, Column, ConstraintSystem,
+        Error, Instance as InstanceColumn, ProvingKey, Selector, SingleVerifier, VerifyingKey,
+    },
+    poly::{commitment::Params, Rotation},
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255},
+};
+use rand_core::OsRng;
+
+use crate::prover::ProverConfig;
+
+// Batch proving and verification of one circuit. This is not aggregation: no
+// circuit here verifies a proof, since that needs an IPA verifier gadget over
+// the other curve of the cycle, which neither this crate nor this halo2
+// version has. What batching gets without one:
+//
+// - `prove_many` proves several instances of the circuit in a single proof.
+//   Their columns are committed to side by side and opened with one IPA, so
+//   each extra instance only adds its commitments and evaluations.
+// - `verify_batch` checks separate proofs with one multiscalar multiplication:
+//   every proof's final IPA check is scaled by a random factor and folded into
+//   a shared accumulator, and only the accumulator is evaluated at the end.
+// - `prove_chain` splits a long Fibonacci computation into `FiboSegment`
+//   proofs that each pick up where the last one stopped, and `verify_chain`
+//   checks they link up and batch verifies them. This is not recursion or
+//   IVC: every segment is a separate proof and the verifier reads them all.
+//   Folding each proof into the next one needs the verifier gadget too.
+
+// An instance's columns, as `create_proof` and `verify_proof` take them.
+pub type Instance = Vec<Vec<Fp>>;
+
+fn columns(instances: &[Instance]) -> Vec<Vec<&[Fp]>> {
+    instances
+        .iter()
+        .map(|instance| instance.iter().map(|column| &column[..]).collect())
+        .collect()
+}
+
+pub fn prove_many<C: Circuit<Fp> + Sync>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuits: &[C],
+    instances: &[Instance],
+) -> Vec<u8> {
+    prove_many_with(&ProverConfig::default(), params, pk, circuits, instances)
+}
+
+// `prove_many` on the threads `config` asks for.
+pub fn prove_many_with<C: Circuit<Fp> + Sync>(
+    config: &ProverConfig,
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuits: &[C],
+    instances: &[Instance],
+) -> Vec<u8> {
+    assert_eq!(circuits.len(), instances.len());
+    let columns = columns(instances);
+    let instances: Vec<&[&[Fp]]> = columns.iter().map(|columns| &columns[..]).collect();
+
+    // the verifying key only knows its domain, which knows the row count
+    let rows = pk.get_vk().get_domain().empty_lagrange().len();
+    config.install(rows, || {
+        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+        create_proof(params, pk, circuits, &instances, OsRng, &mut transcript)
+            .expect("proof generation failed");
+        transcript.finalize()
+    })
+}
+
+// Checks a proof from `prove_many` against the instances it was made for, in
+// the same order.
+pub fn verify_many(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    instances: &[Instance],
+    proof: &[u8],
+) -> bool {
+    let columns = columns(instances);
+    let instances: Vec<&[&[Fp]]> = columns.iter().map(|columns| &columns[..]).collect();
+
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+    let strategy = SingleVerifier::new(params);
+    verify_proof(params, vk, strategy, &instances, &mut transcript).is_ok()
+}
+
+// Checks separate proofs, each with its own instance, all at once. It only
+// says whether every proof is valid; finding a bad one means checking them one
+// at a time.
+pub fn verify_batch(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proofs: &[(Vec<u8>, Instance)],
+) -> bool {
+    let mut strategy = BatchVerifier::new(params, OsRng);
+    for (proof, instance) in proofs {
+        let columns = columns(std::slice::from_ref(instance));
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+        strategy = match verify_proof(params, vk, strategy, &[&columns[0]], &mut transcript) {
+            Ok(strategy) => strategy,
+            Err(_) => return false,
+        };
+    }
+    strategy.finalize()
+}
+
+#[derive(Debug, Clone)]
+pub struct FiboSegmentConfig {
+    pub advice: [Column<Advice>; 2],
+    pub selector: Selector,
+    pub instance: Column<InstanceColumn>,
+}
+
+// `STEPS` Fibonacci steps from the pair (a, b). The instance column is
+// `[a, b, a', b']`, the pair it starts from and the pair it ends on.
+#[derive(Default)]
+pub struct FiboSegment<const STEPS: usize> {
+    pub a: Value<Fp>,
+    pub b: Value<Fp>,
+}
+
+impl<const STEPS: usize> FiboSegment<STEPS> {
+    pub fn new(a: Fp, b: Fp) -> Self {
+        Self {
+            a: Value::known(a),
+            b: Value::known(b),
+        }
+    }
+
+    pub fn run(a: Fp, b: Fp) -> (Fp, Fp) {
+        (0..STEPS).fold((a, b), |(a, b), _| (b, a + b))
+    }
+}
+
+impl<const STEPS: usize> Circuit<Fp> for FiboSegment<STEPS> {
+    type Config = FiboSegmentConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column()];
+        let selector = meta.selector();
+        let instance = meta.instance_column();
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        meta.create_gate("step", |meta| {
+            //
+            // advice[0] | advice[1] | selector
+            //    a           b           1
+            //    b         a + b
+            //
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let b = meta.query_advice(advice[1], Rotation::cur());
+            let a_next = meta.query_advice(advice[0], Rotation::next());
+            let b_next = meta.query_advice(advice[1], Rotation::next());
+            vec![s.clone() * (b.clone() - a_next), s * (a + b - b_next)]
+        });
+
+        FiboSegmentConfig {
+            advice,
+            selector,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let cells = layouter.assign_region(
+            || "steps",
+            |mut region| {
+                let mut a = region.assign_advice(|| "a", config.advice[0], 0, || self.a)?;
+                let mut b = region.assign_advice(|| "b", config.advice[1], 0, || self.b)?;
+                let first = [a.clone(), b.clone()];
+                for offset in 0..STEPS {
+                    config.selector.enable(&mut region, offset)?;
+                    let sum = a.value().copied() + b.value();
+                    a = b.copy_advice(|| "a", &mut region, config.advice[0], offset + 1)?;
+                    b = region.assign_advice(|| "b", config.advice[1], offset + 1, || sum)?;
+                }
+                Ok([first[0].clone(), first[1].clone(), a, b])
+            },
+        )?;
+
+        for (row, cell) in cells.iter().enumerate() {
+            layouter.constrain_instance(cell.cell(), config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+// Proves `segments * STEPS` Fibonacci steps from (a, b), one proof per
+// segment.
+pub fn prove_chain<const STEPS: usize>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    (mut a, mut b): (Fp, Fp),
+    segments: usize,
+) -> Vec<(Vec<u8>, Instance)> {
+    let mut chain = vec![];
+    for _ in 0..segments {
+        let (a_next, b_next) = FiboSegment::<STEPS>::run(a, b);
+        let instance = vec![vec![a, b, a_next, b_next]];
+        let circuit = FiboSegment::<STEPS>::new(a, b);
+        let proof = prove_many(params, pk, &[circuit], std::slice::from_ref(&instance));
+        chain.push((proof, instance));
+        (a, b) = (a_next, b_next);
+    }
+    chain
+}
+
+// Checks a chain of segment proofs starting from (a, b) and returns the pair
+// it ends on, with `verify_batch` over every segment's proof.
+pub fn verify_chain(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    start: (Fp, Fp),
+    chain: &[(Vec<u8>, Instance)],
+) -> Option<(Fp, Fp)> {
+    let mut pair = start;
+    for (_, instance) in chain {
+        match instance[..] {
+            [ref column] if column.len() == 4 && (column[0], column[1]) == pair => {
+                pair = (column[2], column[3]);
+            }
+            _ => return None,
+        }
+    }
+    verify_batch(params, vk, chain).then_some(pair)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        example1::MyCircuit,
+        fibo::{FiboLayout, FiboPublicInputs},
+    };
+    use halo2_proofs::{
+        circuit::Value,
+        plonk::{keygen_pk, keygen_vk},
+    };
+
+    const K: u32 = 4;
+
+    // The Fibonacci circuit from `a` and `b`, and its instance.
+    fn fibonacci(a: u64, b: u64) -> (MyCircuit<Fp>, Instance) {
+        let public_inputs = FiboPublicInputs::new(Fp::from(a), Fp::from(b));
+        let circuit = MyCircuit {
+            a: Value::known(public_inputs.a),
+            b: Value::known(public_inputs.b),
+        };
+        (circuit, public_inputs.to_instances(&FiboLayout::single()))
+    }
+
+    #[test]
+    fn test_batch() {
+        let _guard = crate::testing::heavy_test();
+        let params = Params::<EqAffine>::new(K);
+        let vk = keygen_vk(&params, &MyCircuit::<Fp>::default()).unwrap();
+        let pk = keygen_pk(&params, vk, &MyCircuit::<Fp>::default()).unwrap();
+
+        let (circuits, instances): (Vec<_>, Vec<_>) = [(1, 1), (2, 3), (5, 8)]
+            .map(|(a, b)| fibonacci(a, b))
+            .into_iter()
+            .unzip();
+        assert_eq!(instances[0][0][2], Fp::from(55));
+
+        // one proof for all three, smaller than three of them
+        let proof = prove_many(&params, &pk, &circuits, &instances);
+        assert!(verify_many(&params, pk.get_vk(), &instances, &proof));
+        let single = prove_many(&params, &pk, &circuits[..1], &instances[..1]);
+        assert!(proof.len() < 3 * single.len());
+
+        // the same proof on a single thread
+        let config = ProverConfig::threads(1);
+        let proof = prove_many_with(&config, &params, &pk, &circuits, &instances);
+        assert!(verify_many(&params, pk.get_vk(), &instances, &proof));
+
+        let mut wrong = instances.clone();
+        wrong[2][0][2] += Fp::one();
+        assert!(!verify_many(&params, pk.get_vk(), &wrong, &proof));
+        assert!(!verify_many(&params, pk.get_vk(), &instances[..2], &proof));
+
+        // three separate proofs, checked together
+        let mut proofs: Vec<_> = circuits
+            .into_iter()
+            .zip(instances.iter())
+            .map(|(circuit, instance)| {
+                let proof = prove_many(&params, &pk, &[circuit], std::slice::from_ref(instance));
+                (proof, instance.clone())
+            })
+            .collect();
+        assert!(verify_batch(&params, pk.get_vk(), &proofs));
+
+        // one of them checked against another's instance
+        proofs[1].1 = instances[0].clone();
+        assert!(!verify_batch(&params, pk.get_vk(), &proofs));
+    }
+
+    #[test]
+    fn test_chain() {
+        let _guard = crate::testing::heavy_test();
+        const STEPS: usize = 8;
+        let params = Params::<EqAffine>::new(5);
+        let vk = keygen_vk(&params, &FiboSegment::<STEPS>::default()).unwrap();
+        let pk = keygen_pk(&params, vk, &FiboSegment::<STEPS>::default()).unwrap();
+
+        // F(0), F(1) on to F(32), F(33)
+        let start = (Fp::zero(), Fp::one());
+        let chain = prove_chain::<STEPS>(&params, &pk, start, 4);
+        let end = verify_chain(&params, pk.get_vk(), start, &chain);
+        assert_eq!(end, Some((Fp::from(2178309), Fp::from(3524578))));
+
+        // from somewhere else, with a segment missing, and with one that
+        // doesn't pick up where the last stopped
+        assert_eq!(
+            verify_chain(&params, pk.get_vk(), (Fp::one(), Fp::one()), &chain),
+            None
+        );
+        let gap = [chain[0].clone(), chain[2].clone()];
+        assert_eq!(verify_chain(&params, pk.get_vk(), start, &gap), None);
+        let mut forged = chain.clone();
+        forged[1].1[0][3] += Fp::one();
+        forged[2].1[0][1] += Fp::one();
+        assert_eq!(verify_chain(&params, pk.get_vk(), start, &forged), None);
+    }
+}
@@ -0,0 +1,622 @@
+use halo2_examples::{
+    batch::{prove_many_with, verify_many},
+    circuits::{
+        aes, age, battleship, convergent, hash_chain, histogram, matmul, merkle_root,
+        weighted_average, wordle,
+    },
+    cost::{cost_report, CostReport},
+    dot::dot_graph_with_copies,
+    example1, example2, example3,
+    fibo::{FiboLayout, FiboPublicInputs},
+    io::FiboInput,
+    keys::KeyCache,
+    params::ParamsFile,
+    prover::ProverConfig,
+    spec::CircuitSpec,
+    stats::{keygen_fixed_columns, min_k_for, CircuitStats},
+};
+use halo2_proofs::{
+    circuit::Value,
+    dev::{CircuitCost, MockProver},
+    pasta::{Eq, EqAffine, Fp},
+    plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, SingleVerifier},
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255},
+};
+use rand_core::OsRng;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+const USAGE: &str = "usage: fibo compare [--k K] [CIRCUIT...]\n       fibo cost [--k K] [--json]\n       fibo dot VARIANT\n       fibo prove INPUT.json [--params FILE] [--threads N] [--chunk-size N]\n       fibo run SPEC.toml INPUT.json [--cache DIR]\n       fibo setup K OUT.params\n       fibo profile SPEC.toml INPUT.json (with the profiling feature)\n       fibo layout VARIANT OUT.png|OUT.svg [--k K] [--no-labels] [--equality] [--v1] (with the dev-graph feature)\n       fibo serve [--port PORT] [--workers N] [--cache DIR] (with the http feature)";
+
+// The circuits `compare` knows about, each with some example inputs.
+const CIRCUITS: [&str; 10] = [
+    "aes",
+    "age",
+    "battleship",
+    "convergent",
+    "hash_chain",
+    "histogram",
+    "matmul",
+    "merkle_root",
+    "weighted_average",
+    "wordle",
+];
+
+struct Report {
+    name: String,
+    k: u32,
+    // what the circuit configures, and the rows its regions cover
+    stats: CircuitStats,
+    // fixed columns in the verifying key, once selectors are folded into them
+    fixed: usize,
+    proof_size: usize,
+    // what halo2's cost model expects the proof size to be
+    estimate: usize,
+    prove: Duration,
+    verify: Duration,
+}
+
+// Proves and verifies once at `k`, or the smallest k the circuit fits in.
+fn measure<C: Circuit<Fp>>(
+    name: &str,
+    k: Option<u32>,
+    circuit: C,
+    instance: Vec<Fp>,
+) -> Result<Report, String> {
+    let fits = |k: u32| {
+        MockProver::run(k, &circuit, vec![instance.clone()])
+            .is_ok_and(|prover| prover.verify().is_ok())
+    };
+    let k = match k {
+        Some(k) if fits(k) => k,
+        Some(k) => return Err(format!("{} doesn't fit in k = {}", name, k)),
+        None => {
+            let k = min_k_for(&circuit, &vec![instance.clone()])
+                .map_err(|e| format!("{} {}", name, e))?;
+            if !fits(k) {
+                return Err(format!("{} doesn't verify", name));
+            }
+            k
+        }
+    };
+
+    let estimate = CircuitCost::<Eq, C>::measure(k as usize, &circuit)
+        .proof_size(1)
+        .into();
+
+    let stats = CircuitStats::collect_with_rows(k, &circuit, vec![instance.clone()])?;
+    let fixed = keygen_fixed_columns(k, &circuit)?;
+
+    let params = Params::<EqAffine>::new(k);
+    let vk = keygen_vk(&params, &circuit).unwrap();
+    let pk = keygen_pk(&params, vk, &circuit).unwrap();
+
+    let start = Instant::now();
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof(
+        &params,
+        &pk,
+        &[circuit],
+        &[&[&instance]],
+        OsRng,
+        &mut transcript,
+    )
+    .unwrap();
+    let proof = transcript.finalize();
+    let prove = start.elapsed();
+
+    let start = Instant::now();
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+    let strategy = SingleVerifier::new(&params);
+    verify_proof(
+        &params,
+        pk.get_vk(),
+        strategy,
+        &[&[&instance]],
+        &mut transcript,
+    )
+    .unwrap();
+    let verify = start.elapsed();
+
+    Ok(Report {
+        name: name.to_string(),
+        k,
+        stats,
+        fixed,
+        proof_size: proof.len(),
+        estimate,
+        prove,
+        verify,
+    })
+}
+
+fn run(name: &str, k: Option<u32>) -> Result<Report, String> {
+    let salt = 0x5eed;
+    match name {
+        "aes" => {
+            let key = *b"sixteen byte key";
+            let plaintext = *b"attack at dawn!!";
+            measure(
+                name,
+                k,
+                aes::Aes128Circuit::<Fp>::new(key),
+                aes::aes_instance(&plaintext, &aes::encrypt(key, plaintext)),
+            )
+        }
+        "age" => {
+            let birthdate = 19800101;
+            measure(
+                name,
+                k,
+                age::AgeCircuit::<Fp>::new(birthdate, salt),
+                vec![
+                    age::commit(birthdate, salt),
+                    Fp::from(age::threshold(20240315, 18)),
+                ],
+            )
+        }
+        "battleship" => {
+            let fleet = [
+                (0, 0, false),
+                (9, 1, true),
+                (2, 4, false),
+                (4, 6, true),
+                (8, 8, false),
+            ];
+            measure(
+                name,
+                k,
+                battleship::BattleshipCircuit::<Fp>::new(&fleet, salt),
+                vec![
+                    battleship::commit(&fleet, salt),
+                    Fp::from(9),
+                    Fp::from(1),
+                    Fp::one(),
+                ],
+            )
+        }
+        "convergent" => measure(
+            name,
+            k,
+            convergent::GoldenConvergentCircuit::<Fp>::new(10),
+            vec![Fp::from(144), Fp::from(89)],
+        ),
+        "hash_chain" => {
+            let seed = Fp::from(salt);
+            measure(
+                name,
+                k,
+                hash_chain::HashChainCircuit::<Fp, 8>::new(seed),
+                vec![Fp::from(5), hash_chain::hash_chain(seed, 5)],
+            )
+        }
+        "histogram" => {
+            let values = [3, 17, 42, 99, 100, 250, 0, 10, 1000];
+            let boundaries = [10, 100, 500];
+            let counts = histogram::histogram(&values, &boundaries);
+            measure(
+                name,
+                k,
+                histogram::HistogramCircuit::<Fp>::new(&values),
+                histogram::histogram_instance(&boundaries, &counts),
+            )
+        }
+        "matmul" => {
+            let a = [[1, 2, 3], [4, 5, 6]].map(|row| row.map(Fp::from));
+            let b = [[7, 8], [9, 10], [11, 12]].map(|row| row.map(Fp::from));
+            measure(
+                name,
+                k,
+                matmul::MatMulCircuit::new(a, b),
+                vec![matmul::commit(&matmul::product(&a, &b))],
+            )
+        }
+        "merkle_root" => {
+            let leaves: [Fp; 8] = std::array::from_fn(|i| Fp::from(i as u64));
+            measure(
+                name,
+                k,
+                merkle_root::MerkleRootCircuit::new(leaves),
+                vec![halo2_examples::gadgets::merkle::merkle_root(&leaves)],
+            )
+        }
+        "weighted_average" => {
+            let (values, weights) = ([90, 76, 82], [3, 2, 5]);
+            let average = weighted_average::weighted_average(&values, &weights);
+            measure(
+                name,
+                k,
+                weighted_average::WeightedAverageCircuit::<Fp>::new(&values),
+                weighted_average::weighted_average_instance(average, 1, &weights),
+            )
+        }
+        "wordle" => {
+            let (secret, guess) = (b"crane", b"caper");
+            measure(
+                name,
+                k,
+                wordle::WordleCircuit::<Fp>::new(secret, salt),
+                wordle::wordle_instance(
+                    wordle::commit(secret, salt),
+                    guess,
+                    &wordle::feedback(secret, guess),
+                ),
+            )
+        }
+        _ => unreachable!("compare only runs known circuits"),
+    }
+}
+
+fn compare(args: &[String]) -> Result<(), String> {
+    let mut k = None;
+    let mut names = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--k" {
+            let value = args.next().ok_or(USAGE)?;
+            k = Some(value.parse().map_err(|_| format!("bad k: {}", value))?);
+        } else {
+            names.push(arg.as_str());
+        }
+    }
+    if names.is_empty() {
+        names = CIRCUITS.to_vec();
+    }
+    if let Some(name) = names.iter().find(|name| !CIRCUITS.contains(name)) {
+        return Err(format!(
+            "unknown circuit {}, expected one of {}",
+            name,
+            CIRCUITS.join(", ")
+        ));
+    }
+
+    println!(
+        "{:<18} {:>3} {:>8} {:>6} {:>5} {:>8} {:>7} {:>9} {:>8} {:>17}",
+        "circuit",
+        "k",
+        "rows",
+        "advice",
+        "fixed",
+        "instance",
+        "lookups",
+        "proof (B)",
+        "estimate",
+        "prove/verify (ms)"
+    );
+    for name in names {
+        let report = run(name, k)?;
+        println!(
+            "{:<18} {:>3} {:>8} {:>6} {:>5} {:>8} {:>7} {:>9} {:>8} {:>17}",
+            report.name,
+            report.k,
+            report.stats.rows.unwrap(),
+            report.stats.advice_columns,
+            report.fixed,
+            report.stats.instance_columns,
+            report.stats.lookups,
+            report.proof_size,
+            report.estimate,
+            format!("{}/{}", report.prove.as_millis(), report.verify.as_millis()),
+        );
+    }
+    Ok(())
+}
+
+// Proves and verifies the Fibonacci run in a JSON input file.
+fn prove(args: &[String]) -> Result<(), String> {
+    let Some((path, args)) = args.split_first() else {
+        return Err(USAGE.to_string());
+    };
+    let mut config = ProverConfig::default();
+    let mut params = None;
+    let mut flags = args.iter();
+    while let Some(flag) = flags.next() {
+        let value = flags.next().ok_or(USAGE)?;
+        let count = || {
+            value
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("bad {}: {}", flag, value))
+        };
+        match flag.as_str() {
+            "--params" => params = Some(Params::<EqAffine>::load_k(value, 4)?),
+            "--threads" => config.threads = count()?,
+            "--chunk-size" => config.chunk_size = count()?,
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    let (circuit, instance) = FiboInput::load(path)?.circuit()?;
+
+    let params = params.unwrap_or_else(|| Params::new(4));
+    let vk = keygen_vk(&params, &circuit).unwrap();
+    let pk = keygen_pk(&params, vk, &circuit).unwrap();
+    let instances = [instance];
+    let proof = prove_many_with(&config, &params, &pk, &[circuit], &instances);
+    if !verify_many(&params, pk.get_vk(), &instances, &proof) {
+        return Err("proof didn't verify".to_string());
+    }
+    println!("proved {}: {} byte proof", path, proof.len());
+    Ok(())
+}
+
+// Writes parameters for `k` to a file, for `prove --params` and the like.
+fn setup(args: &[String]) -> Result<(), String> {
+    let [k, path] = args else {
+        return Err(USAGE.to_string());
+    };
+    let k = k.parse().map_err(|_| format!("bad k: {}", k))?;
+    Params::<EqAffine>::setup_and_save(k, path)?;
+    println!("wrote parameters for k = {} to {}", k, path);
+    Ok(())
+}
+
+// Proves the Fibonacci run in a JSON input file with the circuit and `k` a
+// TOML spec picks.
+fn run_spec(args: &[String]) -> Result<(), String> {
+    let (spec, input, cache) = match args {
+        [spec, input] => (spec, input, KeyCache::new()),
+        [spec, input, flag, dir] if flag == "--cache" => (spec, input, KeyCache::on_disk(dir)),
+        _ => return Err(USAGE.to_string()),
+    };
+    let spec = CircuitSpec::load(spec)?;
+    let input = FiboInput::load(input)?;
+    if input.n_steps != spec.steps {
+        return Err(format!(
+            "the input has {} steps, the spec {}",
+            input.n_steps, spec.steps
+        ));
+    }
+    let (circuit, instance) = spec.build(&input.public_inputs()?)?;
+    let start = Instant::now();
+    let keys = cache.get(&spec)?;
+    let proof = circuit.prove_with(&keys, instance)?;
+    println!(
+        "{:?} at k = {}: {} byte proof in {} ms",
+        spec.variant,
+        keys.k,
+        proof.len(),
+        start.elapsed().as_millis()
+    );
+    Ok(())
+}
+
+// Like `run`, with the time and peak memory of every proving phase.
+#[cfg(feature = "profiling")]
+fn profile(args: &[String]) -> Result<(), String> {
+    let [spec, input] = args else {
+        return Err(USAGE.to_string());
+    };
+    let spec = CircuitSpec::load(spec)?;
+    let input = FiboInput::load(input)?;
+    if input.n_steps != spec.steps {
+        return Err(format!(
+            "the input has {} steps, the spec {}",
+            input.n_steps, spec.steps
+        ));
+    }
+    let (circuit, instance) = spec.build(&input.public_inputs()?)?;
+    let k = match spec.k {
+        Some(k) => k,
+        None => circuit.min_k(&instance)?,
+    };
+    println!("{:?} at k = {}", spec.variant, k);
+    println!("{}", circuit.profile(k, instance)?);
+    Ok(())
+}
+
+// The cost model's report for each of the Fibonacci examples.
+fn cost(args: &[String]) -> Result<(), String> {
+    let mut k = 4;
+    let mut json = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--k" => {
+                let value = args.next().ok_or(USAGE)?;
+                k = value.parse().map_err(|_| format!("bad k: {}", value))?;
+            }
+            "--json" => json = true,
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    if k < 4 {
+        return Err(format!("the examples don't fit in k = {}", k));
+    }
+
+    let reports: [(&str, CostReport); 3] = [
+        (
+            "example1",
+            cost_report(example1::MyCircuit::<Fp>::default(), k),
+        ),
+        (
+            "example2",
+            cost_report(example2::MyCircuit::<Fp>::default(), k),
+        ),
+        (
+            "example3",
+            cost_report(example3::MyCircuit::<Fp>::default(), k),
+        ),
+    ];
+    if json {
+        let reports: BTreeMap<_, _> = reports.into_iter().collect();
+        println!("{}", serde_json::to_string_pretty(&reports).unwrap());
+    } else {
+        for (name, report) in reports {
+            println!("{}\n{}\n", name, report);
+        }
+    }
+    Ok(())
+}
+
+// Prints one of the Fibonacci examples' columns, gates and copy constraints
+// as a Graphviz graph, for `dot -Tsvg`.
+fn dot(args: &[String]) -> Result<(), String> {
+    let [variant] = args else {
+        return Err(USAGE.to_string());
+    };
+    let inputs = FiboPublicInputs::new(Fp::from(1), Fp::from(1));
+    let instance = inputs.to_instances(&FiboLayout::single());
+    let graph = match variant.as_str() {
+        "example1" => {
+            let circuit = example1::MyCircuit::<Fp> {
+                a: Value::known(inputs.a),
+                b: Value::known(inputs.b),
+            };
+            let k = min_k_for(&circuit, &instance)?;
+            dot_graph_with_copies(k, &circuit, instance)
+        }
+        "example2" => {
+            let circuit = example2::MyCircuit::<Fp>::default();
+            let k = min_k_for(&circuit, &instance)?;
+            dot_graph_with_copies(k, &circuit, instance)
+        }
+        "example3" => {
+            let circuit = example3::MyCircuit::<Fp>::default();
+            let k = min_k_for(&circuit, &instance)?;
+            dot_graph_with_copies(k, &circuit, instance)
+        }
+        _ => Err(format!(
+            "unknown variant {}, expected example1, example2 or example3",
+            variant
+        )),
+    }?;
+    print!("{}", graph);
+    Ok(())
+}
+
+// Draws one of the Fibonacci examples' layouts to a PNG or SVG file.
+#[cfg(feature = "dev-graph")]
+fn layout(args: &[String]) -> Result<(), String> {
+    use halo2_examples::{
+        layout::{render_layout, Format, LayoutOptions},
+        planner::Planned,
+    };
+    use halo2_proofs::circuit::floor_planner::V1;
+
+    let [variant, path, flags @ ..] = args else {
+        return Err(USAGE.to_string());
+    };
+    let format = Format::from_path(path)?;
+    let mut k = 4;
+    let mut v1 = false;
+    let mut options = LayoutOptions {
+        title: Some(format!("{} layout", variant)),
+        ..LayoutOptions::default()
+    };
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "--k" => {
+                let value = flags.next().ok_or(USAGE)?;
+                k = value.parse().map_err(|_| format!("bad k: {}", value))?;
+            }
+            "--no-labels" => options.labels = false,
+            "--equality" => options.equality = true,
+            "--v1" => v1 = true,
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    if k < 4 {
+        return Err(format!("the examples don't fit in k = {}", k));
+    }
+
+    // the same circuit laid out by either floor planner
+    fn render<C: Circuit<Fp>>(
+        circuit: C,
+        v1: bool,
+        k: u32,
+        path: &str,
+        format: Format,
+        options: &LayoutOptions,
+    ) -> Result<(), String> {
+        if v1 {
+            render_layout(&Planned::<_, V1>::new(circuit), k, path, format, options)
+        } else {
+            render_layout(&circuit, k, path, format, options)
+        }
+    }
+
+    match variant.as_str() {
+        "example1" => render(
+            example1::MyCircuit::<Fp>::default(),
+            v1,
+            k,
+            path,
+            format,
+            &options,
+        ),
+        "example2" => render(
+            example2::MyCircuit::<Fp>::default(),
+            v1,
+            k,
+            path,
+            format,
+            &options,
+        ),
+        "example3" => render(
+            example3::MyCircuit::<Fp>::default(),
+            v1,
+            k,
+            path,
+            format,
+            &options,
+        ),
+        _ => Err(format!(
+            "unknown variant {}, expected example1, example2 or example3",
+            variant
+        )),
+    }
+}
+
+// Serves the JSON API in src/http.rs, by default on port 8080 with a worker
+// per core.
+#[cfg(feature = "http")]
+fn serve(args: &[String]) -> Result<(), String> {
+    let mut port = 8080;
+    let mut workers = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut keys = KeyCache::new();
+    let mut flags = args.iter();
+    while let Some(flag) = flags.next() {
+        let value = flags.next().ok_or(USAGE)?;
+        match flag.as_str() {
+            "--port" => port = value.parse().map_err(|_| format!("bad port: {}", value))?,
+            "--workers" => {
+                workers = value
+                    .parse()
+                    .map_err(|_| format!("bad worker count: {}", value))?
+            }
+            "--cache" => keys = KeyCache::on_disk(value),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    println!("listening on port {} with {} workers", port, workers);
+    tokio::runtime::Runtime::new()
+        .map_err(|e| e.to_string())?
+        .block_on(halo2_examples::http::serve(port, workers, keys))
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("compare") => compare(&args[1..]),
+        Some("cost") => cost(&args[1..]),
+        Some("dot") => dot(&args[1..]),
+        Some("prove") => prove(&args[1..]),
+        Some("run") => run_spec(&args[1..]),
+        Some("setup") => setup(&args[1..]),
+        #[cfg(feature = "profiling")]
+        Some("profile") => profile(&args[1..]),
+        #[cfg(feature = "dev-graph")]
+        Some("layout") => layout(&args[1..]),
+        #[cfg(feature = "http")]
+        Some("serve") => serve(&args[1..]),
+        _ => Err(USAGE.to_string()),
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
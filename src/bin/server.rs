@@ -0,0 +1,107 @@
+// Proves and verifies Fibonacci runs over gRPC, see proto/fibo.proto:
+//
+//     cargo run --release --features grpc --bin server -- [ADDR [KEY_DIR]]
+//
+// Requests are proved on tokio's blocking pool, sharing the keys of each
+// circuit through a `ProvingService`. With KEY_DIR the parameters are kept
+// there between runs, see `KeyCache`.
+
+use halo2_examples::{
+    io::FiboInput,
+    keys::KeyCache,
+    service::ProvingService,
+    spec::{Backend, CircuitSpec},
+};
+use std::sync::Arc;
+use tonic::{transport::Server, Request, Response, Status};
+
+mod proto {
+    tonic::include_proto!("fibo");
+}
+
+use proto::{
+    prover_server::{Prover, ProverServer},
+    CircuitParams, ProveRequest, ProveResponse, PublicInputs, VerifyRequest, VerifyResponse,
+};
+
+const ADDR: &str = "127.0.0.1:50051";
+
+fn spec(params: Option<CircuitParams>) -> Result<CircuitSpec, Status> {
+    let params = params.ok_or_else(|| Status::invalid_argument("missing circuit"))?;
+    Ok(CircuitSpec {
+        variant: params.variant.parse().map_err(Status::invalid_argument)?,
+        k: params.k,
+        steps: params.steps as usize,
+        backend: Backend::Ipa,
+    })
+}
+
+fn input(publics: Option<PublicInputs>, steps: usize) -> Result<FiboInput, Status> {
+    let publics = publics.ok_or_else(|| Status::invalid_argument("missing public inputs"))?;
+    Ok(FiboInput {
+        a: publics.a,
+        b: publics.b,
+        out: publics.out,
+        n_steps: steps,
+    })
+}
+
+// Runs a request's proving or verifying off the async threads.
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, Status> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?
+        .map_err(Status::invalid_argument)
+}
+
+struct FiboProver {
+    service: Arc<ProvingService>,
+}
+
+#[tonic::async_trait]
+impl Prover for FiboProver {
+    async fn prove(
+        &self,
+        request: Request<ProveRequest>,
+    ) -> Result<Response<ProveResponse>, Status> {
+        let request = request.into_inner();
+        let spec = spec(request.circuit)?;
+        let input = input(request.publics, spec.steps)?;
+        let service = self.service.clone();
+        let proof = blocking(move || service.prove(&spec, &input)).await?;
+        Ok(Response::new(ProveResponse { proof }))
+    }
+
+    async fn verify(
+        &self,
+        request: Request<VerifyRequest>,
+    ) -> Result<Response<VerifyResponse>, Status> {
+        let request = request.into_inner();
+        let spec = spec(request.circuit)?;
+        let input = input(request.publics, spec.steps)?;
+        let service = self.service.clone();
+        let proof = request.proof;
+        let valid = blocking(move || service.verify(&spec, &input, &proof)).await?;
+        Ok(Response::new(VerifyResponse { valid }))
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let addr = std::env::args().nth(1).unwrap_or_else(|| ADDR.to_string());
+    let keys = match std::env::args().nth(2) {
+        Some(dir) => KeyCache::on_disk(dir),
+        None => KeyCache::new(),
+    };
+    let prover = FiboProver {
+        service: Arc::new(ProvingService::with_cache(keys)),
+    };
+    println!("listening on {}", addr);
+    Server::builder()
+        .add_service(ProverServer::new(prover))
+        .serve(addr.parse()?)
+        .await?;
+    Ok(())
+}
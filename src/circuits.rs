@@ -0,0 +1,164 @@
+pub mod aes;
+pub mod age;
+pub mod battleship;
+pub mod blake2b;
+pub mod commit_reveal;
+pub mod convergent;
+pub mod fibonacci_gating;
+pub mod fibonacci_index;
+pub mod fibonacci_matrix;
+pub mod fibonacci_membership;
+pub mod fibonacci_padded;
+pub mod fibonacci_mod;
+pub mod hash_chain;
+pub mod histogram;
+pub mod hmac;
+pub mod kth_smallest;
+pub mod matmul;
+pub mod merkle_root;
+pub mod mixer;
+pub mod mpt;
+pub mod padovan;
+pub mod pedersen_opening;
+pub mod pell;
+pub mod percentile;
+pub mod permutation_chain;
+pub mod preimage;
+pub mod rollup;
+pub mod semaphore;
+#[cfg(feature = "sinsemilla")]
+pub mod sinsemilla_merkle;
+pub mod solvency;
+pub mod sudoku;
+pub mod tribonacci;
+pub mod vm;
+pub mod vote;
+pub mod weighted_average;
+pub mod wordle;
+pub mod zkvm;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::{keygen_fixed_columns, CircuitStats};
+    use halo2_proofs::{pasta::Fp, plonk::Circuit};
+
+    // Fixed columns the circuit would need with one column per selector, and
+    // the number its verifying key actually commits to.
+    fn fixed_columns<C: Circuit<Fp>>(k: u32, circuit: &C) -> (usize, usize) {
+        let stats = CircuitStats::collect::<C>();
+        let uncombined = stats.fixed_columns + stats.selectors;
+        (uncombined, keygen_fixed_columns(k, circuit).unwrap())
+    }
+
+    #[test]
+    fn test_selector_combining() {
+        let _guard = crate::testing::heavy_test();
+        // Keygen folds mutually exclusive simple selectors into shared fixed
+        // columns, so composite circuits don't pay a column per gadget gate.
+        // Complex selectors stay separate, as lookups need them on their own.
+        let (uncombined, combined) =
+            fixed_columns(10, &semaphore::SemaphoreCircuit::<Fp, 3>::default());
+        assert!(combined < uncombined);
+
+        let (uncombined, combined) = fixed_columns(9, &age::AgeCircuit::default());
+        assert!(combined < uncombined);
+    }
+
+    #[test]
+    fn test_instance_near_misses() {
+        use crate::testing::assert_rejects_near_misses;
+        let _guard = crate::testing::heavy_test();
+
+        // born on the cutoff, so a stricter one fails
+        let (birthdate, salt, today) = (20060315, 0xa9e, 20240315);
+        assert_rejects_near_misses(
+            9,
+            &age::AgeCircuit::<Fp>::new(birthdate, salt),
+            vec![vec![
+                age::commit(birthdate, salt),
+                Fp::from(age::threshold(today, 18)),
+            ]],
+            &[],
+        );
+
+        let fleet = [
+            (0, 0, false),
+            (9, 1, true),
+            (2, 4, false),
+            (4, 6, true),
+            (8, 8, false),
+        ];
+        // the first square of a ship, so the squares before it are misses
+        let (x, y) = (9, 1);
+        assert_rejects_near_misses(
+            10,
+            &battleship::BattleshipCircuit::<Fp>::new(&fleet, salt),
+            vec![vec![
+                battleship::commit(&fleet, salt),
+                Fp::from(x),
+                Fp::from(y),
+                Fp::from(battleship::is_hit(&fleet, x, y) as u64),
+            ]],
+            &[],
+        );
+
+        assert_rejects_near_misses(
+            9,
+            &convergent::GoldenConvergentCircuit::<Fp>::new(10),
+            vec![vec![Fp::from(144), Fp::from(89)]],
+            &[],
+        );
+
+        // a value just below every boundary, so lowering one moves it
+        let values = [3, 17, 42, 99, 100, 250, 0, 9, 499, 1000];
+        let boundaries = [10, 100, 500];
+        let counts = histogram::histogram(&values, &boundaries);
+        assert_rejects_near_misses(
+            10,
+            &histogram::HistogramCircuit::<Fp>::new(&values),
+            vec![histogram::histogram_instance(&boundaries, &counts)],
+            &[],
+        );
+
+        let (a, b) = ([[1, 2], [3, 4]], [[5, 6], [7, 8]]);
+        let (a, b) = (
+            a.map(|row| row.map(Fp::from)),
+            b.map(|row| row.map(Fp::from)),
+        );
+        assert_rejects_near_misses(
+            8,
+            &matmul::MatMulCircuit::new(a, b),
+            vec![vec![matmul::commit(&matmul::product(&a, &b))]],
+            &[],
+        );
+
+        let (values, weights) = ([90, 76, 82], [3, 2, 5]);
+        let average = weighted_average::weighted_average(&values, &weights);
+        assert_rejects_near_misses(
+            9,
+            &weighted_average::WeightedAverageCircuit::<Fp>::new(&values),
+            vec![weighted_average::weighted_average_instance(
+                average, 1, &weights,
+            )],
+            // a tolerance as big as the average takes in any average
+            &["column 0: rows 0 and 1 swapped"],
+        );
+
+        // every guess letter and its neighbours get different feedback than
+        // the letter before it in the alphabet would
+        let (secret, guess) = (b"crane", b"scone");
+        assert_rejects_near_misses(
+            9,
+            &wordle::WordleCircuit::<Fp>::new(secret, salt),
+            vec![wordle::wordle_instance(
+                wordle::commit(secret, salt),
+                guess,
+                &wordle::feedback(secret, guess),
+            )],
+            // the guess is public and isn't checked to be letters, and whatever
+            // a gray letter is negated to isn't in the secret either
+            &["column 0: row 1 negated", "column 0: row 3 negated"],
+        );
+    }
+}
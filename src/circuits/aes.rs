@@ -0,0 +1,305 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use crate::gadgets::{
+    gf256::{gf_mul, Gf256Chip, Gf256Config},
+    sbox::{sbox, SboxChip, SboxConfig},
+};
+
+pub const ROUNDS: usize = 10;
+
+const RCON: [u8; ROUNDS] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36];
+
+// Blocks are column major, as in FIPS-197: byte i is row i % 4 of column i / 4.
+fn shift_rows<T: Clone>(state: &[T]) -> Vec<T> {
+    (0..16)
+        .map(|i| {
+            let (c, r) = (i / 4, i % 4);
+            state[4 * ((c + r) % 4) + r].clone()
+        })
+        .collect()
+}
+
+pub fn expand_key(key: [u8; 16]) -> [[u8; 16]; ROUNDS + 1] {
+    let mut words: Vec<[u8; 4]> = key.chunks(4).map(|w| w.try_into().unwrap()).collect();
+    for i in 4..4 * (ROUNDS + 1) {
+        let mut temp = words[i - 1];
+        if i % 4 == 0 {
+            temp.rotate_left(1);
+            temp = temp.map(sbox);
+            temp[0] ^= RCON[i / 4 - 1];
+        }
+        let prev = words[i - 4];
+        words.push(std::array::from_fn(|j| prev[j] ^ temp[j]));
+    }
+    std::array::from_fn(|round| std::array::from_fn(|i| words[4 * round + i / 4][i % 4]))
+}
+
+pub fn encrypt(key: [u8; 16], plaintext: [u8; 16]) -> [u8; 16] {
+    let round_keys = expand_key(key);
+    let xor = |a: [u8; 16], b: [u8; 16]| std::array::from_fn(|i| a[i] ^ b[i]);
+
+    let mut state = xor(plaintext, round_keys[0]);
+    for (round, round_key) in round_keys.iter().enumerate().skip(1) {
+        state = shift_rows(&state.map(sbox)).try_into().unwrap();
+        if round < ROUNDS {
+            state = std::array::from_fn(|i| {
+                let (c, r) = (i / 4, i % 4);
+                let a = &state[4 * c..4 * c + 4];
+                gf_mul(a[r], 2) ^ gf_mul(a[(r + 1) % 4], 3) ^ a[(r + 2) % 4] ^ a[(r + 3) % 4]
+            });
+        }
+        state = xor(state, *round_key);
+    }
+    state
+}
+
+// The instance column: the plaintext bytes, then the ciphertext bytes.
+pub fn aes_instance<F: FieldExt>(plaintext: &[u8; 16], ciphertext: &[u8; 16]) -> Vec<F> {
+    plaintext
+        .iter()
+        .chain(ciphertext.iter())
+        .map(|b| F::from(*b as u64))
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct AesConfig {
+    pub advice: [Column<Advice>; 9],
+    pub instance: Column<Instance>,
+    pub gf256: Gf256Config,
+    pub sbox: SboxConfig,
+}
+
+// Proves ciphertext = AES128(key, plaintext) for a private key, with the
+// plaintext and ciphertext public. The key isn't committed to, so this shows
+// knowledge of some key; circuits that need a particular key should hash it.
+#[derive(Default)]
+pub struct Aes128Circuit<F> {
+    pub key: [Value<F>; 16],
+}
+
+impl<F: FieldExt> Aes128Circuit<F> {
+    pub fn new(key: [u8; 16]) -> Self {
+        Self {
+            key: key.map(|b| Value::known(F::from(b as u64))),
+        }
+    }
+}
+
+type Bytes<F> = Vec<AssignedCell<F, F>>;
+
+fn xor_bytes<F: FieldExt>(
+    gf256: &Gf256Chip<F>,
+    layouter: &mut impl Layouter<F>,
+    a: &[AssignedCell<F, F>],
+    b: &[AssignedCell<F, F>],
+) -> Result<Bytes<F>, Error> {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| gf256.xor(layouter.namespace(|| "xor"), a, b))
+        .collect()
+}
+
+fn sub_bytes<F: FieldExt>(
+    sbox: &SboxChip<F>,
+    layouter: &mut impl Layouter<F>,
+    a: &[AssignedCell<F, F>],
+) -> Result<Bytes<F>, Error> {
+    a.iter()
+        .map(|a| sbox.sub_byte(layouter.namespace(|| "sub byte"), a))
+        .collect()
+}
+
+// b_r = 2·a_r + 3·a_r+1 + a_r+2 + a_r+3, computed as a_r + t + 2·(a_r + a_r+1)
+// where t is the sum of the whole column.
+fn mix_column<F: FieldExt>(
+    gf256: &Gf256Chip<F>,
+    layouter: &mut impl Layouter<F>,
+    a: &[AssignedCell<F, F>],
+) -> Result<Bytes<F>, Error> {
+    let mut t = a[0].clone();
+    for a in &a[1..] {
+        t = gf256.xor(layouter.namespace(|| "t"), &t, a)?;
+    }
+    (0..4)
+        .map(|r| {
+            let pair = gf256.xor(layouter.namespace(|| "pair"), &a[r], &a[(r + 1) % 4])?;
+            let double = gf256.xtime(layouter.namespace(|| "double"), &pair)?;
+            let b = gf256.xor(layouter.namespace(|| "a + t"), &a[r], &t)?;
+            gf256.xor(layouter.namespace(|| "b"), &b, &double)
+        })
+        .collect()
+}
+
+impl<F: FieldExt> Circuit<F> for Aes128Circuit<F> {
+    type Config = AesConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 9].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        let constants = meta.fixed_column();
+
+        meta.enable_equality(instance);
+        meta.enable_constant(constants);
+
+        let gf256 = Gf256Chip::configure(meta, advice);
+        let sbox = SboxChip::configure(meta, [advice[0], advice[1]]);
+
+        AesConfig {
+            advice,
+            instance,
+            gf256,
+            sbox,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let gf256 = Gf256Chip::construct(config.gf256.clone());
+        let sbox = SboxChip::construct(config.sbox.clone());
+        gf256.load(&mut layouter)?;
+        sbox.load(&mut layouter)?;
+
+        let (key, plaintext, rcon) = layouter.assign_region(
+            || "inputs",
+            |mut region| {
+                let mut key = vec![];
+                let mut plaintext = vec![];
+                for i in 0..16 {
+                    key.push(region.assign_advice(
+                        || "key",
+                        config.advice[0],
+                        i,
+                        || self.key[i],
+                    )?);
+                    plaintext.push(region.assign_advice_from_instance(
+                        || "plaintext",
+                        config.instance,
+                        i,
+                        config.advice[1],
+                        i,
+                    )?);
+                }
+                let rcon = RCON
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| {
+                        region.assign_advice_from_constant(
+                            || "rcon",
+                            config.advice[2],
+                            i,
+                            F::from(*c as u64),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((key, plaintext, rcon))
+            },
+        )?;
+
+        let round_keys: Vec<Bytes<F>> = {
+            let mut layouter = layouter.namespace(|| "key schedule");
+            let mut words: Vec<Bytes<F>> = key.chunks(4).map(|w| w.to_vec()).collect();
+            for i in 4..4 * (ROUNDS + 1) {
+                let mut temp = words[i - 1].clone();
+                if i % 4 == 0 {
+                    temp.rotate_left(1);
+                    temp = sub_bytes(&sbox, &mut layouter, &temp)?;
+                    temp[0] =
+                        gf256.xor(layouter.namespace(|| "rcon"), &temp[0], &rcon[i / 4 - 1])?;
+                }
+                let word = xor_bytes(&gf256, &mut layouter, &words[i - 4], &temp)?;
+                words.push(word);
+            }
+            words.chunks(4).map(|round| round.concat()).collect()
+        };
+
+        let mut layouter = layouter.namespace(|| "rounds");
+        let mut state = xor_bytes(&gf256, &mut layouter, &plaintext, &round_keys[0])?;
+        for (round, round_key) in round_keys.iter().enumerate().skip(1) {
+            state = shift_rows(&sub_bytes(&sbox, &mut layouter, &state)?);
+            if round < ROUNDS {
+                let mut mixed = vec![];
+                for column in state.chunks(4) {
+                    mixed.extend(mix_column(&gf256, &mut layouter, column)?);
+                }
+                state = mixed;
+            }
+            state = xor_bytes(&gf256, &mut layouter, &state, round_key)?;
+        }
+
+        for (i, byte) in state.iter().enumerate() {
+            layouter.constrain_instance(byte.cell(), config.instance, 16 + i)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 11;
+
+    fn hex(s: &str) -> [u8; 16] {
+        std::array::from_fn(|i| u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap())
+    }
+
+    #[test]
+    fn test_encrypt() {
+        // FIPS-197 appendices B and C.1
+        let key = hex("2b7e151628aed2a6abf7158809cf4f3c");
+        assert_eq!(expand_key(key)[10], hex("d014f9a8c9ee2589e13f0cc8b6630ca6"));
+        assert_eq!(
+            encrypt(key, hex("3243f6a8885a308d313198a2e0370734")),
+            hex("3925841d02dc09fbdc118597196a0b32")
+        );
+        assert_eq!(
+            encrypt(
+                hex("000102030405060708090a0b0c0d0e0f"),
+                hex("00112233445566778899aabbccddeeff")
+            ),
+            hex("69c4e0d86a7b0430d8cdb78070b4c55a")
+        );
+    }
+
+    #[test]
+    fn test_aes128() {
+        let _guard = crate::testing::heavy_test();
+        let key = hex("000102030405060708090a0b0c0d0e0f");
+        let plaintext = hex("00112233445566778899aabbccddeeff");
+        let ciphertext = encrypt(key, plaintext);
+
+        let circuit = Aes128Circuit::<Fp>::new(key);
+        let prover =
+            MockProver::run(K, &circuit, vec![aes_instance(&plaintext, &ciphertext)]).unwrap();
+        prover.assert_satisfied();
+
+        // a ciphertext off by one bit, and the right one under another key
+        let mut wrong = ciphertext;
+        wrong[7] ^= 0x10;
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![aes_instance(&plaintext, &wrong)],
+            &[Failure::Copy("A2"), Failure::Copy("I0")],
+        );
+        let mut other = key;
+        other[0] ^= 1;
+        assert_unsatisfied_with(
+            K,
+            &Aes128Circuit::<Fp>::new(other),
+            vec![aes_instance(&plaintext, &ciphertext)],
+            &[Failure::Copy("A2"), Failure::Copy("I0")],
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use crate::gadgets::{
+    compare::{CompareChip, CompareConfig},
+    poseidon::{self, PoseidonChip, PoseidonConfig},
+    range_check::RangeCheckChip,
+    tables::TableRegistry,
+};
+
+// Dates are YYYYMMDD integers, which order the same way as the dates do.
+pub const DATE_BYTES: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct AgeConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 4],
+    pub instance: Column<Instance>,
+    pub compare: CompareConfig<DATE_BYTES>,
+    pub poseidon: PoseidonConfig<F>,
+    pub tables: TableRegistry,
+}
+
+// Proves a committed birthdate is on or before the public threshold date,
+// i.e. the holder is at least `years` old on the day the verifier picked the
+// threshold for. The instance column is `[commitment, threshold]` with the
+// commitment `hash(birthdate, salt)` as issued with the credential.
+#[derive(Default)]
+pub struct AgeCircuit<F> {
+    pub birthdate: Value<F>,
+    pub salt: Value<F>,
+}
+
+impl<F: FieldExt> AgeCircuit<F> {
+    pub fn new(birthdate: u64, salt: u64) -> Self {
+        Self {
+            birthdate: Value::known(F::from(birthdate)),
+            salt: Value::known(F::from(salt)),
+        }
+    }
+}
+
+pub fn commit<F: FieldExt>(birthdate: u64, salt: u64) -> F {
+    poseidon::hash(&[F::from(birthdate), F::from(salt)])
+}
+
+// Latest birthdate that is `years` old on `today`. Someone born on 29 Feb
+// gets 29 Feb of the threshold year, which sits between 28 Feb and 1 Mar.
+pub fn threshold(today: u64, years: u64) -> u64 {
+    today - years * 10000
+}
+
+impl<F: FieldExt> Circuit<F> for AgeCircuit<F> {
+    type Config = AgeConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(instance);
+
+        let mut tables = TableRegistry::default();
+        let bytes = tables.bytes(meta);
+        let range = RangeCheckChip::configure(meta, [advice[0], advice[1]], bytes);
+        let compare = CompareChip::configure(meta, advice, range);
+        let poseidon =
+            PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+
+        AgeConfig {
+            advice,
+            instance,
+            compare,
+            poseidon,
+            tables,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let range = RangeCheckChip::construct(config.compare.range.clone());
+        config.tables.load(&mut layouter)?;
+
+        let (salt, threshold) = layouter.assign_region(
+            || "inputs",
+            |mut region| {
+                let salt = region.assign_advice(|| "salt", config.advice[2], 0, || self.salt)?;
+                let threshold = region.assign_advice_from_instance(
+                    || "threshold",
+                    config.instance,
+                    1,
+                    config.advice[3],
+                    0,
+                )?;
+                Ok((salt, threshold))
+            },
+        )?;
+
+        let birthdate = range
+            .witness_checked::<DATE_BYTES>(layouter.namespace(|| "birthdate"), self.birthdate)?;
+        range.range_check::<DATE_BYTES>(layouter.namespace(|| "threshold"), &threshold)?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon);
+        let commitment = poseidon.hash(
+            layouter.namespace(|| "commitment"),
+            &[birthdate.clone(), salt],
+        )?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 0)?;
+
+        let compare = CompareChip::construct(config.compare);
+        compare.assert_le(
+            layouter.namespace(|| "birthdate <= threshold"),
+            &birthdate,
+            &threshold,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 9;
+
+    #[test]
+    fn test_age_over_threshold() {
+        let salt = 0xa9e;
+        let today = 20240315;
+        let cutoff = threshold(today, 18);
+
+        for (birthdate, ok) in [
+            (19800101, true),
+            (20060315, true),
+            (20060316, false),
+            (20101231, false),
+        ] {
+            let circuit = AgeCircuit::<Fp>::new(birthdate, salt);
+            let public_input = vec![commit(birthdate, salt), Fp::from(cutoff)];
+            let prover = MockProver::run(K, &circuit, vec![public_input]).unwrap();
+            assert_eq!(prover.verify().is_ok(), ok, "born {}", birthdate);
+        }
+
+        // someone else's credential
+        let circuit = AgeCircuit::<Fp>::new(19800101, salt);
+        let public_input = vec![commit(20101231, salt), Fp::from(cutoff)];
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![public_input],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+    }
+
+    #[test]
+    fn test_leap_day() {
+        assert_eq!(threshold(20240229, 18), 20060229);
+        assert!(20060228 <= threshold(20240228, 18));
+        assert!(20060229 > threshold(20240228, 18));
+        assert!(20060301 > threshold(20240229, 18));
+    }
+}
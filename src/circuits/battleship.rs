@@ -0,0 +1,360 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::gadgets::{
+    distinct::{DistinctChip, DistinctConfig},
+    is_equal::{IsEqualChip, IsEqualConfig},
+    poseidon::{self, PoseidonChip, PoseidonConfig},
+    range_table::RangeTableConfig,
+};
+
+pub const BOARD_SIZE: u64 = 10;
+pub const SHIPS: [u64; 5] = [5, 4, 3, 3, 2];
+
+// A ship is (x, y, down): its bow square and whether it runs down the board
+// rather than across it.
+pub type Ship = (u64, u64, bool);
+
+#[derive(Debug, Clone)]
+pub struct BattleshipConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 6],
+    pub offset: Column<Fixed>,
+    pub q_square: Selector,
+    pub q_hits: Selector,
+    pub instance: Column<Instance>,
+    pub coords: RangeTableConfig,
+    pub distinct: DistinctConfig,
+    pub is_equal: IsEqualConfig,
+    pub poseidon: PoseidonConfig<F>,
+}
+
+// Proves a hit/miss answer for the public query square against a hidden fleet,
+// and that the fleet is a legal layout: every ship is on the board and no two
+// ships share a square. The instance column is `[commitment, x, y, hit]` with
+// the commitment `hash(x_0, y_0, down_0, ..., salt)`, so every answer given
+// for the same commitment is about the same board.
+#[derive(Default)]
+pub struct BattleshipCircuit<F> {
+    pub ships: [[Value<F>; 3]; SHIPS.len()],
+    pub salt: Value<F>,
+}
+
+impl<F: FieldExt> BattleshipCircuit<F> {
+    pub fn new(ships: &[Ship; SHIPS.len()], salt: u64) -> Self {
+        Self {
+            ships: ships.map(|(x, y, down)| [x, y, down as u64].map(|v| Value::known(F::from(v)))),
+            salt: Value::known(F::from(salt)),
+        }
+    }
+}
+
+pub fn commit<F: FieldExt>(ships: &[Ship; SHIPS.len()], salt: u64) -> F {
+    let mut message = vec![];
+    for (x, y, down) in ships {
+        message.extend([*x, *y, *down as u64].map(F::from));
+    }
+    message.push(F::from(salt));
+    poseidon::hash(&message)
+}
+
+pub fn is_hit(ships: &[Ship; SHIPS.len()], x: u64, y: u64) -> bool {
+    ships.iter().zip(SHIPS).any(|((sx, sy, down), len)| {
+        if *down {
+            x == *sx && y >= *sy && y < sy + len
+        } else {
+            y == *sy && x >= *sx && x < sx + len
+        }
+    })
+}
+
+impl<F: FieldExt> Circuit<F> for BattleshipCircuit<F> {
+    type Config = BattleshipConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 6].map(|_| meta.advice_column());
+        let offset = meta.fixed_column();
+        let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+        // used by a lookup, so it can't be a simple selector
+        let q_square = meta.complex_selector();
+        let q_hits = meta.selector();
+
+        meta.enable_equality(instance);
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        let [col_x, col_y, col_down, col_sx, col_sy, _] = advice;
+
+        let coords = RangeTableConfig::configure(meta, 0, BOARD_SIZE - 1);
+        coords.lookup(meta, q_square, col_sx);
+        coords.lookup(meta, q_square, col_sy);
+
+        meta.create_gate("square", |meta| {
+            //
+            // x | y | down | sx | sy | index | offset | q_square
+            //
+            // (sx, sy) is `offset` squares from the bow (x, y), and has to be
+            // on the board. index numbers the squares row by row.
+            let s = meta.query_selector(q_square);
+            let [x, y, down, sx, sy, index] =
+                advice.map(|column| meta.query_advice(column, Rotation::cur()));
+            let t = meta.query_fixed(offset, Rotation::cur());
+            let one = Expression::Constant(F::one());
+            let size = Expression::Constant(F::from(BOARD_SIZE));
+            vec![
+                s.clone() * down.clone() * (one.clone() - down.clone()),
+                s.clone() * (x + t.clone() * (one - down.clone()) - sx.clone()),
+                s.clone() * (y + t * down - sy.clone()),
+                s * (sy * size + sx - index),
+            ]
+        });
+
+        meta.create_gate("hits", |meta| {
+            //
+            // advice[0] | advice[1] | q_hits
+            //   e_0        0            1
+            //   e_1        acc_1        1
+            //   ...
+            //              acc_n
+            //
+            let s = meta.query_selector(q_hits);
+            let e = meta.query_advice(col_x, Rotation::cur());
+            let acc = meta.query_advice(col_y, Rotation::cur());
+            let acc_next = meta.query_advice(col_y, Rotation::next());
+            vec![s * (acc + e - acc_next)]
+        });
+
+        let distinct = DistinctChip::configure(meta, [col_x, col_y, col_down]);
+        let is_equal = IsEqualChip::configure(meta, [col_x, col_y, col_down, col_sx]);
+        let poseidon = PoseidonChip::configure(meta, [col_x, col_y, col_down], rc, constants);
+
+        BattleshipConfig {
+            advice,
+            offset,
+            q_square,
+            q_hits,
+            instance,
+            coords,
+            distinct,
+            is_equal,
+            poseidon,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.coords.load(&mut layouter)?;
+
+        let [col_x, col_y, col_down, col_sx, col_sy, col_index] = config.advice;
+
+        let (ships, salt) = layouter.assign_region(
+            || "fleet",
+            |mut region| {
+                let mut ships = vec![];
+                for (offset, ship) in self.ships.iter().enumerate() {
+                    let mut cells = vec![];
+                    for (column, v) in [col_x, col_y, col_down].iter().zip(ship.iter()) {
+                        cells.push(region.assign_advice(|| "ship", *column, offset, || *v)?);
+                    }
+                    ships.push(cells);
+                }
+                let salt = region.assign_advice(|| "salt", col_sx, 0, || self.salt)?;
+                Ok((ships, salt))
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon.clone());
+        let mut message: Vec<_> = ships.iter().flatten().cloned().collect();
+        message.push(salt);
+        let commitment = poseidon.hash(layouter.namespace(|| "commitment"), &message)?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 0)?;
+
+        let squares = layouter.assign_region(
+            || "ship squares",
+            |mut region| {
+                let mut squares = vec![];
+                let mut offset = 0;
+                for (ship, len) in ships.iter().zip(SHIPS) {
+                    for t in 0..len {
+                        config.q_square.enable(&mut region, offset)?;
+                        region.assign_fixed(
+                            || "offset",
+                            config.offset,
+                            offset,
+                            || Value::known(F::from(t)),
+                        )?;
+
+                        let x = ship[0].copy_advice(|| "x", &mut region, col_x, offset)?;
+                        let y = ship[1].copy_advice(|| "y", &mut region, col_y, offset)?;
+                        let down = ship[2].copy_advice(|| "down", &mut region, col_down, offset)?;
+
+                        let t = Value::known(F::from(t));
+                        let across = Value::known(F::one()) - down.value();
+                        let sx = x.value().copied() + t * across;
+                        let sy = y.value().copied() + t * down.value();
+                        let index = sy * Value::known(F::from(BOARD_SIZE)) + sx;
+
+                        region.assign_advice(|| "sx", col_sx, offset, || sx)?;
+                        region.assign_advice(|| "sy", col_sy, offset, || sy)?;
+                        squares.push(region.assign_advice(
+                            || "index",
+                            col_index,
+                            offset,
+                            || index,
+                        )?);
+
+                        offset += 1;
+                    }
+                }
+                Ok(squares)
+            },
+        )?;
+
+        let distinct = DistinctChip::construct(config.distinct.clone());
+        distinct.assign(layouter.namespace(|| "no overlaps"), &squares)?;
+
+        // the query goes through the same gate as a one-square ship, which
+        // also keeps it on the board
+        let query = layouter.assign_region(
+            || "query",
+            |mut region| {
+                config.q_square.enable(&mut region, 0)?;
+                region.assign_fixed(|| "offset", config.offset, 0, || Value::known(F::zero()))?;
+
+                let x = region.assign_advice_from_instance(|| "x", config.instance, 1, col_x, 0)?;
+                let y = region.assign_advice_from_instance(|| "y", config.instance, 2, col_y, 0)?;
+                region.assign_advice_from_constant(|| "down", col_down, 0, F::zero())?;
+                region.assign_advice(|| "sx", col_sx, 0, || x.value().copied())?;
+                region.assign_advice(|| "sy", col_sy, 0, || y.value().copied())?;
+                let index = y.value().copied() * Value::known(F::from(BOARD_SIZE)) + x.value();
+                region.assign_advice(|| "index", col_index, 0, || index)
+            },
+        )?;
+
+        let is_equal = IsEqualChip::construct(config.is_equal.clone());
+        let mut matches = vec![];
+        for square in squares.iter() {
+            matches.push(is_equal.is_equal(
+                layouter.namespace(|| "query == square"),
+                &query,
+                square,
+            )?);
+        }
+
+        // the squares are distinct, so at most one of them matches
+        let hit = layouter.assign_region(
+            || "hits",
+            |mut region| {
+                let mut acc = region.assign_advice_from_constant(|| "acc", col_y, 0, F::zero())?;
+                for (offset, e) in matches.iter().enumerate() {
+                    config.q_hits.enable(&mut region, offset)?;
+                    let e = e.copy_advice(|| "e", &mut region, col_x, offset)?;
+                    let acc_next = acc.value().copied() + e.value();
+                    acc = region.assign_advice(|| "acc", col_y, offset + 1, || acc_next)?;
+                }
+                Ok(acc)
+            },
+        )?;
+        layouter.constrain_instance(hit.cell(), config.instance, 3)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 10;
+
+    const FLEET: [Ship; 5] = [
+        (0, 0, false),
+        (9, 1, true),
+        (2, 4, false),
+        (4, 6, true),
+        (8, 8, false),
+    ];
+
+    fn public_input(commitment: Fp, x: u64, y: u64, hit: bool) -> Vec<Fp> {
+        vec![commitment, Fp::from(x), Fp::from(y), Fp::from(hit as u64)]
+    }
+
+    #[test]
+    fn test_battleship() {
+        let salt = 0xb0a7;
+        let commitment = commit::<Fp>(&FLEET, salt);
+        let circuit = BattleshipCircuit::<Fp>::new(&FLEET, salt);
+
+        for (x, y) in [
+            (0, 0),
+            (4, 0),
+            (5, 0),
+            (9, 4),
+            (9, 5),
+            (4, 8),
+            (9, 8),
+            (5, 5),
+        ] {
+            let hit = is_hit(&FLEET, x, y);
+            let prover =
+                MockProver::run(K, &circuit, vec![public_input(commitment, x, y, hit)]).unwrap();
+            prover.assert_satisfied();
+
+            assert_unsatisfied_with(
+                K,
+                &circuit,
+                vec![public_input(commitment, x, y, !hit)],
+                &[Failure::Copy("A1"), Failure::Copy("I0")],
+            );
+        }
+
+        // queries off the board
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![public_input(commitment, 10, 0, false)],
+            &[Failure::Lookup(0)],
+        );
+    }
+
+    #[test]
+    fn test_battleship_invalid_fleet() {
+        let salt = 0xb0a7;
+
+        let mut overlapping = FLEET;
+        overlapping[2] = (2, 0, true);
+
+        let mut off_board = FLEET;
+        off_board[0] = (6, 0, false);
+
+        for (fleet, failure) in [
+            (overlapping, Failure::Gate("not equal")),
+            (off_board, Failure::Lookup(0)),
+        ] {
+            let circuit = BattleshipCircuit::<Fp>::new(&fleet, salt);
+            let input = public_input(commit(&fleet, salt), 5, 5, false);
+            assert_unsatisfied_with(K, &circuit, vec![input], &[failure]);
+        }
+
+        // the right fleet with the wrong salt doesn't open the commitment
+        let circuit = BattleshipCircuit::<Fp>::new(&FLEET, salt + 1);
+        let input = public_input(commit(&FLEET, salt), 5, 5, false);
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![input],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+    }
+}
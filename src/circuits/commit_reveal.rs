@@ -0,0 +1,223 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use crate::gadgets::poseidon::{self, PoseidonChip, PoseidonConfig};
+
+// Where both circuits' public inputs go, so the commitment one proof is made
+// against is the same instance value the other is checked against.
+pub const COMMITMENT_ROW: usize = 0;
+pub const VALUE_ROW: usize = 1;
+
+#[derive(Debug, Clone)]
+pub struct CommitRevealConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; poseidon::WIDTH],
+    pub instance: Column<Instance>,
+    pub poseidon: PoseidonConfig<F>,
+}
+
+// A commit-reveal scheme in two proofs, with the commitment
+// `hash(value, salt)`. At commit time `CommitCircuit` proves the committer
+// knows a value and salt behind a public commitment, `[commitment]`, so it
+// isn't just a random field element. At reveal time `RevealCircuit` proves the
+// now public value is the committed one, `[commitment, value]`, without
+// giving away the salt.
+//
+// The salt makes the commitment hiding: without it anyone could hash the
+// likely values and compare. The hash makes it binding, as a second value
+// with a matching salt would be a collision.
+#[derive(Default)]
+pub struct CommitCircuit<F> {
+    pub value: Value<F>,
+    pub salt: Value<F>,
+}
+
+impl<F: FieldExt> CommitCircuit<F> {
+    pub fn new(value: F, salt: F) -> Self {
+        Self {
+            value: Value::known(value),
+            salt: Value::known(salt),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RevealCircuit<F> {
+    pub salt: Value<F>,
+}
+
+impl<F: FieldExt> RevealCircuit<F> {
+    pub fn new(salt: F) -> Self {
+        Self {
+            salt: Value::known(salt),
+        }
+    }
+}
+
+pub fn commit<F: FieldExt>(value: F, salt: F) -> F {
+    poseidon::hash(&[value, salt])
+}
+
+pub fn commit_instance<F: FieldExt>(commitment: F) -> Vec<F> {
+    vec![commitment]
+}
+
+pub fn reveal_instance<F: FieldExt>(commitment: F, value: F) -> Vec<F> {
+    let mut instance = commit_instance(commitment);
+    instance.insert(VALUE_ROW, value);
+    instance
+}
+
+fn configure<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> CommitRevealConfig<F> {
+    let advice = [(); poseidon::WIDTH].map(|_| meta.advice_column());
+    let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+    let constants = meta.fixed_column();
+    let instance = meta.instance_column();
+
+    meta.enable_equality(instance);
+
+    CommitRevealConfig {
+        advice,
+        instance,
+        poseidon: PoseidonChip::configure(meta, advice, rc, constants),
+    }
+}
+
+// Hashes `value` with a private `salt` and constrains the digest to the
+// public commitment.
+fn constrain_commitment<F: FieldExt>(
+    config: CommitRevealConfig<F>,
+    mut layouter: impl Layouter<F>,
+    value: AssignedCell<F, F>,
+    salt: Value<F>,
+) -> Result<(), Error> {
+    let salt = layouter.assign_region(
+        || "salt",
+        |mut region| region.assign_advice(|| "salt", config.advice[1], 0, || salt),
+    )?;
+
+    let poseidon = PoseidonChip::construct(config.poseidon);
+    let commitment = poseidon.hash(layouter.namespace(|| "commitment"), &[value, salt])?;
+    layouter.constrain_instance(commitment.cell(), config.instance, COMMITMENT_ROW)
+}
+
+impl<F: FieldExt> Circuit<F> for CommitCircuit<F> {
+    type Config = CommitRevealConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let value = layouter.assign_region(
+            || "value",
+            |mut region| region.assign_advice(|| "value", config.advice[0], 0, || self.value),
+        )?;
+        constrain_commitment(config, layouter, value, self.salt)
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for RevealCircuit<F> {
+    type Config = CommitRevealConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let value = layouter.assign_region(
+            || "value",
+            |mut region| {
+                region.assign_advice_from_instance(
+                    || "value",
+                    config.instance,
+                    VALUE_ROW,
+                    config.advice[0],
+                    0,
+                )
+            },
+        )?;
+        constrain_commitment(config, layouter, value, self.salt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        explain::{assert_unsatisfied_with, Failure},
+        testing::assert_rejects_near_misses,
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 7;
+
+    #[test]
+    fn test_commit_reveal() {
+        let (value, salt) = (Fp::from(42), Fp::from(0x5a17));
+        let commitment = commit(value, salt);
+
+        // the commitment the first proof is checked against is the one the
+        // reveal is
+        let commit_proof = MockProver::run(
+            K,
+            &CommitCircuit::new(value, salt),
+            vec![commit_instance(commitment)],
+        )
+        .unwrap();
+        commit_proof.assert_satisfied();
+        let instance = reveal_instance(commitment, value);
+        assert_eq!(instance[COMMITMENT_ROW], commitment);
+        let reveal_proof = MockProver::run(K, &RevealCircuit::new(salt), vec![instance]).unwrap();
+        reveal_proof.assert_satisfied();
+
+        // a commitment to something else
+        assert_unsatisfied_with(
+            K,
+            &CommitCircuit::new(value + Fp::one(), salt),
+            vec![commit_instance(commitment)],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+
+        // another value revealed, and the right one with the wrong salt
+        assert_unsatisfied_with(
+            K,
+            &RevealCircuit::new(salt),
+            vec![reveal_instance(commitment, value + Fp::one())],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+        assert_unsatisfied_with(
+            K,
+            &RevealCircuit::new(salt + Fp::one()),
+            vec![reveal_instance(commitment, value)],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+    }
+
+    #[test]
+    fn test_near_misses() {
+        let (value, salt) = (Fp::from(7), Fp::from(0xbeef));
+        assert_rejects_near_misses(
+            K,
+            &RevealCircuit::new(salt),
+            vec![reveal_instance(commit(value, salt), value)],
+            &[],
+        );
+    }
+}
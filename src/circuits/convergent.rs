@@ -0,0 +1,141 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+use std::marker::PhantomData;
+
+use crate::gadgets::{
+    convergent::{ConvergentChip, ConvergentConfig},
+    coprime::{CoprimeChip, CoprimeConfig},
+    range_check::RangeCheckChip,
+    tables::TableRegistry,
+};
+
+// The convergents of the golden ratio fit in COPRIME_BYTES up to this index.
+pub const MAX_INDEX: usize = 90;
+
+#[derive(Debug, Clone)]
+pub struct GoldenConvergentConfig {
+    pub convergent: ConvergentConfig,
+    pub coprime: CoprimeConfig,
+    pub instance: Column<Instance>,
+    pub tables: TableRegistry,
+}
+
+// Proves the public (p, q) is the n-th convergent of the golden ratio
+// [1; 1, 1, ...], i.e. p = F(n + 2) and q = F(n + 1), and that p / q is in
+// lowest terms.
+#[derive(Default)]
+pub struct GoldenConvergentCircuit<F> {
+    pub n: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F> GoldenConvergentCircuit<F> {
+    pub fn new(n: usize) -> Self {
+        assert!(n <= MAX_INDEX);
+        Self {
+            n,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for GoldenConvergentCircuit<F> {
+    type Config = GoldenConvergentConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.n)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let quotient = meta.fixed_column();
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(instance);
+
+        let convergent =
+            ConvergentChip::configure(meta, [advice[0], advice[1]], quotient, constants);
+        let mut tables = TableRegistry::default();
+        let bytes = tables.bytes(meta);
+        let range = RangeCheckChip::configure(meta, [advice[2], advice[3]], bytes);
+        let coprime = CoprimeChip::configure(meta, advice, range);
+
+        GoldenConvergentConfig {
+            convergent,
+            coprime,
+            instance,
+            tables,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.tables.load(&mut layouter)?;
+
+        let convergent = ConvergentChip::construct(config.convergent);
+        let (p, q) =
+            convergent.assign(layouter.namespace(|| "convergents"), &vec![1; self.n + 1])?;
+
+        let coprime = CoprimeChip::construct(config.coprime);
+        coprime.assert_coprime(layouter.namespace(|| "lowest terms"), &p, &q)?;
+
+        layouter.constrain_instance(p.cell(), config.instance, 0)?;
+        layouter.constrain_instance(q.cell(), config.instance, 1)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[test]
+    fn test_golden_convergent() {
+        let k = 9;
+
+        // 1/1, 2/1, 3/2, 5/3, ... 144/89
+        let circuit = GoldenConvergentCircuit::<Fp>::new(10);
+
+        let public_input = vec![Fp::from(144), Fp::from(89)];
+        let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+        prover.assert_satisfied();
+
+        for (public_input, failures) in [
+            (
+                vec![Fp::from(89), Fp::from(144)],
+                &[
+                    Failure::Copy("A0"),
+                    Failure::Copy("A1"),
+                    Failure::Copy("I0"),
+                ][..],
+            ),
+            (
+                vec![Fp::from(144), Fp::from(88)],
+                &[Failure::Copy("A1"), Failure::Copy("I0")],
+            ),
+        ] {
+            assert_unsatisfied_with(k, &circuit, vec![public_input], failures);
+        }
+    }
+
+    #[test]
+    fn test_golden_convergent_max_index() {
+        let k = 9;
+
+        let (mut p, mut q) = (1u64, 1u64);
+        for _ in 0..MAX_INDEX {
+            (p, q) = (p + q, p);
+        }
+
+        let circuit = GoldenConvergentCircuit::<Fp>::new(MAX_INDEX);
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(p), Fp::from(q)]]).unwrap();
+        prover.assert_satisfied();
+    }
+}
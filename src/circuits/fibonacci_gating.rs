@@ -0,0 +1,299 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+use crate::fibo::fibonacci_sequence;
+
+// What switches the gates on, picked by `FiboGatingCircuit`'s type parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gating {
+    // a simple selector per gate, which keygen can fold into one column
+    Simple,
+    // a complex selector per gate, which keygen leaves alone
+    Complex,
+    // one fixed column for both gates, folded by hand
+    Fixed,
+}
+
+pub trait GatingKind {
+    const GATING: Gating;
+}
+
+pub struct SimpleSelectors;
+pub struct ComplexSelectors;
+pub struct FixedColumn;
+
+impl GatingKind for SimpleSelectors {
+    const GATING: Gating = Gating::Simple;
+}
+
+impl GatingKind for ComplexSelectors {
+    const GATING: Gating = Gating::Complex;
+}
+
+impl GatingKind for FixedColumn {
+    const GATING: Gating = Gating::Fixed;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Switches {
+    // for the "step" and "half step" gates
+    Selectors([Selector; 2]),
+    // 1 for "step", 2 for "half step"
+    Fixed(Column<Fixed>),
+}
+
+const STEP: usize = 0;
+const HALF_STEP: usize = 1;
+
+// 0 where `gate` is off, and not 0 where it's on.
+fn switch<F: FieldExt>(
+    meta: &mut VirtualCells<'_, F>,
+    switches: Switches,
+    gate: usize,
+) -> Expression<F> {
+    match switches {
+        Switches::Selectors(selectors) => meta.query_selector(selectors[gate]),
+        Switches::Fixed(column) => {
+            let q = meta.query_fixed(column, Rotation::cur());
+            let constant = |c: u64| Expression::Constant(F::from(c));
+            match gate {
+                // 1 at q = 1, 0 at q = 0 and 2
+                STEP => q.clone() * (constant(2) - q),
+                // 2 at q = 2, 0 at q = 0 and 1
+                _ => q.clone() * (q - constant(1)),
+            }
+        }
+    }
+}
+
+fn enable<F: FieldExt>(
+    region: &mut Region<'_, F>,
+    switches: Switches,
+    gate: usize,
+    row: usize,
+) -> Result<(), Error> {
+    match switches {
+        Switches::Selectors(selectors) => selectors[gate].enable(region, row),
+        Switches::Fixed(column) => region
+            .assign_fixed(
+                || "q",
+                column,
+                row,
+                || Value::known(F::from(gate as u64 + 1)),
+            )
+            .map(|_| ()),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FiboGatingConfig {
+    pub advice: [Column<Advice>; 2],
+    pub switches: Switches,
+    pub instance: Column<Instance>,
+}
+
+// Proves F(n) in example3's layout, two terms a row, with the gates switched
+// on three ways. The instance column is `[a, b, F(n)]`.
+//
+// A full step makes both terms of the next row. When n is even, the last row
+// only needs its first term, so that row's step is a half step checking just
+// that one; a full step there would need an F(n + 1) nothing else uses.
+//
+// With a selector per gate, "step" and "half step" are never on in the same
+// row, and keygen folds simple selectors like that into one fixed column,
+// telling them apart by its value. That raises the gates' degree, as the
+// column's value has to be turned into a 0 or 1 for each gate. Complex
+// selectors, which lookups need, are never folded, so they cost a column
+// each. The fixed column variant does keygen's folding by hand: q is 1 for a
+// step and 2 for a half step, and each gate multiplies by a polynomial in q
+// that's zero where the gate is off.
+pub struct FiboGatingCircuit<F, G> {
+    pub n: usize,
+    _marker: PhantomData<(F, G)>,
+}
+
+impl<F, G> FiboGatingCircuit<F, G> {
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            _marker: PhantomData,
+        }
+    }
+}
+
+pub fn fibonacci_gating_instance<F: FieldExt>(a: u64, b: u64, n: usize) -> Vec<F> {
+    let (a, b) = (F::from(a), F::from(b));
+    vec![a, b, fibonacci_sequence(a, b, n + 1)[n]]
+}
+
+impl<F: FieldExt, G: GatingKind> Circuit<F> for FiboGatingCircuit<F, G> {
+    type Config = FiboGatingConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.n)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 2].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        let switches = match G::GATING {
+            Gating::Simple => Switches::Selectors([meta.selector(), meta.selector()]),
+            Gating::Complex => {
+                Switches::Selectors([meta.complex_selector(), meta.complex_selector()])
+            }
+            Gating::Fixed => Switches::Fixed(meta.fixed_column()),
+        };
+
+        meta.enable_equality(instance);
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("step", |meta| {
+            //
+            // col_a | col_b | switches
+            //   a       b      step
+            //   c       d
+            //
+            let s = switch(meta, switches, STEP);
+            let [a, b] = advice.map(|column| meta.query_advice(column, Rotation::cur()));
+            let [c, d] = advice.map(|column| meta.query_advice(column, Rotation::next()));
+            vec![s.clone() * (a + b.clone() - c.clone()), s * (b + c - d)]
+        });
+
+        meta.create_gate("half step", |meta| {
+            //
+            // col_a | col_b | switches
+            //   a       b    half step
+            //   c
+            //
+            let s = switch(meta, switches, HALF_STEP);
+            let [a, b] = advice.map(|column| meta.query_advice(column, Rotation::cur()));
+            let c = meta.query_advice(advice[0], Rotation::next());
+            vec![s * (a + b - c)]
+        });
+
+        FiboGatingConfig {
+            advice,
+            switches,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let [col_a, col_b] = config.advice;
+        let rows = self.n / 2;
+
+        let out = layouter.assign_region(
+            || "entire fibonacci table",
+            |mut region| {
+                let a = region.assign_advice_from_instance(|| "a", config.instance, 0, col_a, 0)?;
+                let b = region.assign_advice_from_instance(|| "b", config.instance, 1, col_b, 0)?;
+                let table = a
+                    .value()
+                    .zip(b.value())
+                    .map(|(a, b)| fibonacci_sequence(*a, *b, 2 * rows + 2));
+                let term = |i: usize| table.as_ref().map(|table| table[i]);
+
+                let mut cells = vec![a, b];
+                for row in 0..rows {
+                    // the last row of an even n only needs its first term
+                    if row + 1 < rows || self.n % 2 == 1 {
+                        enable(&mut region, config.switches, STEP, row)?;
+                        cells.push(region.assign_advice(
+                            || "a",
+                            col_a,
+                            row + 1,
+                            || term(2 * row + 2),
+                        )?);
+                        cells.push(region.assign_advice(
+                            || "b",
+                            col_b,
+                            row + 1,
+                            || term(2 * row + 3),
+                        )?);
+                    } else {
+                        enable(&mut region, config.switches, HALF_STEP, row)?;
+                        cells.push(region.assign_advice(
+                            || "a",
+                            col_a,
+                            row + 1,
+                            || term(2 * row + 2),
+                        )?);
+                    }
+                }
+
+                Ok(cells.swap_remove(self.n))
+            },
+        )?;
+
+        layouter.constrain_instance(out.cell(), config.instance, 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        explain::{assert_unsatisfied_with, Failure},
+        stats::{keygen_fixed_columns, CircuitStats},
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 5;
+
+    fn check<G: GatingKind>() {
+        for n in [0, 1, 2, 3, 9, 10, 30] {
+            let circuit = FiboGatingCircuit::<Fp, G>::new(n);
+            let instance = fibonacci_gating_instance(1, 1, n);
+            let prover = MockProver::run(K, &circuit, vec![instance]).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // F(10) claimed as F(9), whose cell is in the other column
+        let circuit = FiboGatingCircuit::<Fp, G>::new(9);
+        let mut instance = fibonacci_gating_instance(1, 1, 9);
+        instance[2] = fibonacci_gating_instance(1, 1, 10)[2];
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![instance],
+            &[Failure::Copy("A1"), Failure::Copy("I0")],
+        );
+    }
+
+    #[test]
+    fn test_fibonacci_gating() {
+        check::<SimpleSelectors>();
+        check::<ComplexSelectors>();
+        check::<FixedColumn>();
+    }
+
+    // Selectors and fixed columns as configured, and the fixed columns the
+    // verifying key ends up with.
+    fn columns<G: GatingKind>() -> (usize, usize, usize) {
+        let stats = CircuitStats::collect::<FiboGatingCircuit<Fp, G>>();
+        let circuit = FiboGatingCircuit::<Fp, G>::new(10);
+        (
+            stats.selectors,
+            stats.fixed_columns,
+            keygen_fixed_columns(K, &circuit).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_selector_columns() {
+        let _guard = crate::testing::heavy_test();
+        // two selectors folded into one column
+        assert_eq!(columns::<SimpleSelectors>(), (2, 0, 1));
+        // complex selectors stay a column each
+        assert_eq!(columns::<ComplexSelectors>(), (2, 0, 2));
+        // one column from the start
+        assert_eq!(columns::<FixedColumn>(), (0, 1, 1));
+    }
+}
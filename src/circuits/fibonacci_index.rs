@@ -0,0 +1,205 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+use crate::fibo::fibonacci_sequence;
+
+#[derive(Debug, Clone)]
+pub struct FiboIndexConfig {
+    pub advice: [Column<Advice>; 5],
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+}
+
+// Proves F(n) for F(0) = a and F(1) = b with the index n public too, rather
+// than fixed when the circuit is built. The instance column is
+// `[a, b, n, F(n)]`, for any n up to `MAX`.
+//
+// The table always has MAX steps, like hash_chain's. A counter starts at n
+// and goes down by one a step; while it isn't zero the pair (x, y) moves on
+// to (y, x + y), and once it is both carry over to the last row, whose x is
+// the output. The counter has to reach zero by the last row, so n can't be
+// more than MAX, and it only reaches zero after exactly n steps, so the
+// output can't be any other term.
+#[derive(Default)]
+pub struct FiboIndexCircuit<F, const MAX: usize> {
+    _marker: PhantomData<F>,
+}
+
+pub fn fibonacci_index_instance<F: FieldExt>(a: u64, b: u64, n: usize) -> Vec<F> {
+    let (a, b) = (F::from(a), F::from(b));
+    vec![a, b, F::from(n as u64), fibonacci_sequence(a, b, n + 1)[n]]
+}
+
+impl<F: FieldExt, const MAX: usize> Circuit<F> for FiboIndexCircuit<F, MAX> {
+    type Config = FiboIndexConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 5].map(|_| meta.advice_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+        let selector = meta.selector();
+
+        meta.enable_equality(instance);
+        meta.enable_constant(constants);
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("step", |meta| {
+            //
+            // x  | y  | left | inv | done | selector
+            // x    y     r     1/r    b        1
+            // x'   y'    r'
+            //
+            // b is 1 once no steps are left, r = 0, and 0 before. While
+            // steps are left x' = y, y' = x + y and r' = r - 1; after that
+            // all three carry over.
+            let s = meta.query_selector(selector);
+            let one = Expression::Constant(F::one());
+            let [x, y, left, inv, done] =
+                advice.map(|column| meta.query_advice(column, Rotation::cur()));
+            let x_next = meta.query_advice(advice[0], Rotation::next());
+            let y_next = meta.query_advice(advice[1], Rotation::next());
+            let left_next = meta.query_advice(advice[2], Rotation::next());
+            let step = one.clone() - done.clone();
+
+            vec![
+                s.clone() * (left.clone() * inv - step.clone()),
+                s.clone() * left.clone() * done,
+                s.clone() * (x.clone() + step.clone() * (y.clone() - x.clone()) - x_next),
+                s.clone() * (y + step.clone() * x - y_next),
+                s * (left - step - left_next),
+            ]
+        });
+
+        FiboIndexConfig {
+            advice,
+            selector,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let [col_x, col_y, col_left, col_inv, col_done] = config.advice;
+
+        let out = layouter.assign_region(
+            || "steps",
+            |mut region| {
+                let mut x =
+                    region.assign_advice_from_instance(|| "a", config.instance, 0, col_x, 0)?;
+                let mut y =
+                    region.assign_advice_from_instance(|| "b", config.instance, 1, col_y, 0)?;
+                let mut left =
+                    region.assign_advice_from_instance(|| "n", config.instance, 2, col_left, 0)?;
+
+                for row in 0..MAX {
+                    config.selector.enable(&mut region, row)?;
+                    let done = left.value().map(|left| {
+                        if *left == F::zero() {
+                            F::one()
+                        } else {
+                            F::zero()
+                        }
+                    });
+                    let inv = left.value().map(|left| left.invert().unwrap_or(F::zero()));
+                    region.assign_advice(|| "inv", col_inv, row, || inv)?;
+                    region.assign_advice(|| "done", col_done, row, || done)?;
+
+                    let pair = x.value().zip(y.value()).zip(done).map(|((x, y), done)| {
+                        if done == F::one() {
+                            (*x, *y)
+                        } else {
+                            (*y, *x + y)
+                        }
+                    });
+                    let remaining = left.value().copied() - Value::known(F::one()) + done;
+                    x = region.assign_advice(|| "x", col_x, row + 1, || pair.map(|p| p.0))?;
+                    y = region.assign_advice(|| "y", col_y, row + 1, || pair.map(|p| p.1))?;
+                    left = region.assign_advice(|| "left", col_left, row + 1, || remaining)?;
+                }
+
+                // more than MAX steps don't fit
+                region.constrain_constant(left.cell(), F::zero())?;
+                Ok(x)
+            },
+        )?;
+
+        layouter.constrain_instance(out.cell(), config.instance, 3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        explain::{assert_unsatisfied_with, Failure},
+        testing::assert_rejects_near_misses,
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 5;
+    const MAX: usize = 12;
+
+    #[test]
+    fn test_fibonacci_index() {
+        let circuit = FiboIndexCircuit::<Fp, MAX>::default();
+        assert_eq!(fibonacci_index_instance::<Fp>(1, 1, 9)[3], Fp::from(55));
+        assert_eq!(fibonacci_index_instance::<Fp>(3, 4, 0)[3], Fp::from(3));
+
+        // one circuit, and so one set of keys, for every n up to MAX
+        for n in 0..=MAX {
+            let instance = fibonacci_index_instance(1, 1, n);
+            let prover = MockProver::run(K, &circuit, vec![instance]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_wrong_index() {
+        let circuit = FiboIndexCircuit::<Fp, MAX>::default();
+
+        // F(9) claimed as the term either side of it
+        for n in [8, 10] {
+            let mut instance = fibonacci_index_instance(1, 1, 9);
+            instance[2] = Fp::from(n);
+            assert_unsatisfied_with(
+                K,
+                &circuit,
+                vec![instance],
+                &[Failure::Copy("A0"), Failure::Copy("I0")],
+            );
+        }
+
+        // more steps than fit, and an index that's -1 in the field, both with
+        // the term MAX steps do reach, so only the counter is off
+        let reached = fibonacci_index_instance::<Fp>(1, 1, MAX)[3];
+        for n in [Fp::from(MAX as u64 + 1), -Fp::one()] {
+            let instance = vec![Fp::one(), Fp::one(), n, reached];
+            assert_unsatisfied_with(
+                K,
+                &circuit,
+                vec![instance],
+                &[Failure::Copy("A2"), Failure::Copy("F0")],
+            );
+        }
+    }
+
+    #[test]
+    fn test_near_misses() {
+        assert_rejects_near_misses(
+            K,
+            &FiboIndexCircuit::<Fp, MAX>::default(),
+            vec![fibonacci_index_instance(2, 7, 10)],
+            &[],
+        );
+    }
+}
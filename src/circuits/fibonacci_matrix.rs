@@ -0,0 +1,251 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+// Bits of the index, so any u64 fits.
+pub const BITS: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct FiboMatrixConfig {
+    pub advice: [Column<Advice>; 4],
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+}
+
+// Whether the index is one of the public inputs or only known to the prover.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Index {
+    Public,
+    Private,
+}
+
+// Proves F(n) for F(0) = 0, F(1) = 1 and any u64 n, in BITS steps rather than
+// the n rows example1-3 would take. The instance column is `[F(n)]`, or
+// `[F(n), n]` for a public index.
+//
+// F(n) is the corner of [[1, 1], [1, 0]]^n, worked out by squaring and
+// multiplying, one bit of n at a time from the top. The power
+// [[F(k + 1), F(k)], [F(k), F(k - 1)]] is kept as the pair (F(k), F(k + 1)),
+// the other two entries following from those, and squaring it gives
+//
+//     F(2k) = F(k) * (2 * F(k + 1) - F(k))
+//     F(2k + 1) = F(k)^2 + F(k + 1)^2
+//
+// A set bit multiplies by the matrix once more, (F(2k + 1), F(2k + 2)). The
+// bits are added up alongside, so the ones witnessed are the bits of n.
+pub struct FiboMatrixCircuit<F> {
+    pub n: Value<u64>,
+    pub index: Index,
+    _marker: PhantomData<F>,
+}
+
+impl<F> FiboMatrixCircuit<F> {
+    pub fn new(n: u64, index: Index) -> Self {
+        Self {
+            n: Value::known(n),
+            index,
+            _marker: PhantomData,
+        }
+    }
+}
+
+// (F(n), F(n + 1)) on the host, the same way as the circuit.
+fn fibonacci_pair<F: FieldExt>(n: u64) -> (F, F) {
+    (0..BITS).rev().fold((F::zero(), F::one()), |(x, y), i| {
+        let (even, odd) = (x * (y.double() - x), x.square() + y.square());
+        if (n >> i) & 1 == 1 {
+            (odd, even + odd)
+        } else {
+            (even, odd)
+        }
+    })
+}
+
+pub fn fibonacci_matrix<F: FieldExt>(n: u64) -> F {
+    fibonacci_pair(n).0
+}
+
+pub fn fibonacci_matrix_instance<F: FieldExt>(n: u64, index: Index) -> Vec<F> {
+    match index {
+        Index::Public => vec![fibonacci_matrix(n), F::from(n)],
+        Index::Private => vec![fibonacci_matrix(n)],
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for FiboMatrixCircuit<F> {
+    type Config = FiboMatrixConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            n: Value::unknown(),
+            index: self.index,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+        let selector = meta.selector();
+
+        meta.enable_equality(instance);
+        meta.enable_constant(constants);
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("square and multiply", |meta| {
+            //
+            // bit | acc  | x     | y        | selector
+            //  b    m      F(k)    F(k + 1)      1
+            //       m'     x'      y'
+            //
+            // m' = 2m + b, and (x', y') is (F(2k), F(2k + 1)) for b = 0 and
+            // (F(2k + 1), F(2k + 2)) for b = 1
+            let s = meta.query_selector(selector);
+            let one = Expression::Constant(F::one());
+            let two = Expression::Constant(F::from(2));
+            let [bit, acc, x, y] = advice.map(|column| meta.query_advice(column, Rotation::cur()));
+            let acc_next = meta.query_advice(advice[1], Rotation::next());
+            let x_next = meta.query_advice(advice[2], Rotation::next());
+            let y_next = meta.query_advice(advice[3], Rotation::next());
+
+            let even = x.clone() * (two.clone() * y.clone() - x.clone());
+            let odd = x.clone() * x + y.clone() * y;
+            vec![
+                s.clone() * bit.clone() * (one - bit.clone()),
+                s.clone() * (two * acc + bit.clone() - acc_next),
+                s.clone() * (even.clone() + bit.clone() * (odd.clone() - even.clone()) - x_next),
+                s * (odd + bit * even - y_next),
+            ]
+        });
+
+        FiboMatrixConfig {
+            advice,
+            selector,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let [col_bit, col_acc, col_x, col_y] = config.advice;
+
+        let (n, out) = layouter.assign_region(
+            || "square and multiply",
+            |mut region| {
+                let mut acc = region.assign_advice_from_constant(|| "0", col_acc, 0, F::zero())?;
+                let mut x = region.assign_advice_from_constant(|| "F(0)", col_x, 0, F::zero())?;
+                region.assign_advice_from_constant(|| "F(1)", col_y, 0, F::one())?;
+
+                for row in 0..BITS {
+                    config.selector.enable(&mut region, row)?;
+                    // the top bit first
+                    let bit = self.n.map(|n| (n >> (BITS - 1 - row)) & 1);
+                    region.assign_advice(|| "bit", col_bit, row, || bit.map(F::from))?;
+
+                    let prefix = self.n.map(|n| n >> (BITS - 1 - row));
+                    let (next_x, next_y) = prefix.map(fibonacci_pair::<F>).unzip();
+                    acc =
+                        region.assign_advice(|| "acc", col_acc, row + 1, || prefix.map(F::from))?;
+                    x = region.assign_advice(|| "x", col_x, row + 1, || next_x)?;
+                    region.assign_advice(|| "y", col_y, row + 1, || next_y)?;
+                }
+
+                Ok((acc, x))
+            },
+        )?;
+
+        layouter.constrain_instance(out.cell(), config.instance, 0)?;
+        match self.index {
+            Index::Public => layouter.constrain_instance(n.cell(), config.instance, 1),
+            Index::Private => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        explain::{assert_unsatisfied_with, Failure},
+        fibo::fibonacci_sequence,
+        stats::min_k_for,
+        testing::assert_rejects_near_misses,
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 7;
+
+    #[test]
+    fn test_fibonacci_matrix() {
+        let sequence = fibonacci_sequence(Fp::zero(), Fp::one(), 100);
+        for (n, term) in sequence.iter().enumerate() {
+            assert_eq!(fibonacci_matrix::<Fp>(n as u64), *term);
+        }
+
+        for n in [0, 1, 2, 10, 99, 1 << 40, u64::MAX] {
+            for index in [Index::Public, Index::Private] {
+                let circuit = FiboMatrixCircuit::<Fp>::new(n, index);
+                let instance = fibonacci_matrix_instance(n, index);
+                let prover = MockProver::run(K, &circuit, vec![instance]).unwrap();
+                prover.assert_satisfied();
+            }
+        }
+    }
+
+    #[test]
+    fn test_wrong_terms() {
+        let n = 1 << 40;
+        let circuit = FiboMatrixCircuit::<Fp>::new(n, Index::Public);
+        let out = fibonacci_matrix::<Fp>(n);
+
+        // the term after, and the right term for another index
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![vec![fibonacci_matrix(n + 1), Fp::from(n)]],
+            &[Failure::Copy("A2"), Failure::Copy("I0")],
+        );
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![vec![out, Fp::from(n + 1)]],
+            &[Failure::Copy("A1"), Failure::Copy("I0")],
+        );
+
+        // a private index is still only the one with that term
+        let circuit = FiboMatrixCircuit::<Fp>::new(n + 1, Index::Private);
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![vec![out]],
+            &[Failure::Copy("A2"), Failure::Copy("I0")],
+        );
+    }
+
+    // The same rows for any index, where example3 needs a row per term.
+    #[test]
+    fn test_rows() {
+        for n in [1, u64::MAX] {
+            let instance = vec![fibonacci_matrix_instance(n, Index::Public)];
+            let circuit = FiboMatrixCircuit::<Fp>::new(n, Index::Public);
+            assert_eq!(min_k_for(&circuit, &instance), Ok(K));
+        }
+    }
+
+    #[test]
+    fn test_near_misses() {
+        let n = 0xdead_beef;
+        assert_rejects_near_misses(
+            K,
+            &FiboMatrixCircuit::<Fp>::new(n, Index::Public),
+            vec![fibonacci_matrix_instance(n, Index::Public)],
+            &[],
+        );
+    }
+}
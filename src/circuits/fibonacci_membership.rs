@@ -0,0 +1,136 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+use crate::fibo::fibonacci_sequence;
+
+#[derive(Debug, Clone)]
+pub struct FiboMembershipConfig {
+    pub x: Column<Advice>,
+    pub selector: Selector,
+    pub table: TableColumn,
+    pub instance: Column<Instance>,
+}
+
+// Proves the public x is one of F(0) to F(N - 1), for F(0) = 0 and F(1) = 1,
+// with a single lookup into a fixed table of those N terms. The instance
+// column is `[x]`.
+//
+// example1-3 check every step of the recurrence with a gate, a row per term
+// up to the one they prove. Here the terms are worked out when the keys are,
+// so proving any one of them is one row whichever it is, but the table costs
+// N rows in every proof, and nothing says which term x is.
+#[derive(Default)]
+pub struct FiboMembershipCircuit<F, const N: usize> {
+    _marker: PhantomData<F>,
+}
+
+// F(0) to F(n - 1) as integers; past F(93) they don't fit in a u64.
+pub fn fibonacci_terms(n: usize) -> Vec<u64> {
+    let mut terms = vec![0, 1];
+    while terms.len() < n {
+        terms.push(terms[terms.len() - 2] + terms[terms.len() - 1]);
+    }
+    terms.truncate(n);
+    terms
+}
+
+pub fn is_fibonacci(x: u64, n: usize) -> bool {
+    fibonacci_terms(n).contains(&x)
+}
+
+impl<F: FieldExt, const N: usize> Circuit<F> for FiboMembershipCircuit<F, N> {
+    type Config = FiboMembershipConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let x = meta.advice_column();
+        let selector = meta.complex_selector();
+        let table = meta.lookup_table_column();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(x);
+        meta.enable_equality(instance);
+
+        meta.lookup(|meta| {
+            //
+            // x | selector | table
+            // x     1        F(0)
+            //                ...
+            //                F(N - 1)
+            //
+            // rows where the selector is off look up F(0) = 0
+            let s = meta.query_selector(selector);
+            let x = meta.query_advice(x, Rotation::cur());
+            vec![(s * x, table)]
+        });
+
+        FiboMembershipConfig {
+            x,
+            selector,
+            table,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_table(
+            || "fibonacci numbers",
+            |mut table| {
+                for (row, term) in fibonacci_sequence(F::zero(), F::one(), N)
+                    .into_iter()
+                    .enumerate()
+                {
+                    table.assign_cell(|| "term", config.table, row, || Value::known(term))?;
+                }
+                Ok(())
+            },
+        )?;
+
+        layouter.assign_region(
+            || "membership",
+            |mut region| {
+                config.selector.enable(&mut region, 0)?;
+                region.assign_advice_from_instance(|| "x", config.instance, 0, config.x, 0)
+            },
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 7;
+    const N: usize = 90;
+
+    #[test]
+    fn test_fibonacci_membership() {
+        let circuit = FiboMembershipCircuit::<Fp, N>::default();
+        let last = fibonacci_terms(N)[N - 1];
+
+        for x in [0, 1, 2, 3, 55, 6765, last] {
+            assert!(is_fibonacci(x, N));
+            let prover = MockProver::run(K, &circuit, vec![vec![Fp::from(x)]]).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // numbers between terms, and the first term past the table
+        let next = fibonacci_terms(N + 1)[N];
+        for x in [4, 54, 56, 6766, next] {
+            assert!(!is_fibonacci(x, N));
+            assert_unsatisfied_with(K, &circuit, vec![vec![Fp::from(x)]], &[Failure::Lookup(0)]);
+        }
+        assert!(is_fibonacci(next, N + 1));
+    }
+}
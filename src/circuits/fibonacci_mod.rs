@@ -0,0 +1,267 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+use crate::gadgets::{
+    compare::{CompareChip, CompareConfig},
+    range_check::RangeCheckChip,
+    tables::TableRegistry,
+};
+
+// The modulus and every term fit in this many bytes.
+pub const VALUE_BYTES: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct FiboModConfig {
+    pub advice: [Column<Advice>; 5],
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+    pub compare: CompareConfig<VALUE_BYTES>,
+    pub tables: TableRegistry,
+}
+
+// Proves F(n) mod m for the sequence F(0) = a, F(1) = b taken mod a public m,
+// below 2^(8 * VALUE_BYTES). The instance column is `[a, b, m, F(n) mod m]`,
+// with a and b already reduced.
+//
+// Each step witnesses the quotient and remainder of x + y by m. Both terms are
+// below m, so the quotient is 0 or 1, and a remainder that's range checked and
+// below m is the only one that fits; without those checks any field element
+// would do, since x + y - q * m can be made to equal anything.
+#[derive(Default)]
+pub struct FiboModCircuit<F> {
+    pub n: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F> FiboModCircuit<F> {
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            _marker: PhantomData,
+        }
+    }
+}
+
+pub fn fibonacci_mod(a: u64, b: u64, m: u64, n: usize) -> u64 {
+    (0..n).fold((a % m, b % m), |(a, b), _| (b, (a + b) % m)).0
+}
+
+pub fn fibonacci_mod_instance<F: FieldExt>(a: u64, b: u64, m: u64, n: usize) -> Vec<F> {
+    [a, b, m, fibonacci_mod(a, b, m, n)].map(F::from).to_vec()
+}
+
+// Range checks `cell`, which the comparison needs, and constrains it below m,
+// using `column` for the comparison's result.
+fn assert_reduced<F: FieldExt>(
+    range: &RangeCheckChip<F>,
+    compare: &CompareChip<F, VALUE_BYTES>,
+    mut layouter: impl Layouter<F>,
+    column: Column<Advice>,
+    cell: &AssignedCell<F, F>,
+    m: &AssignedCell<F, F>,
+) -> Result<(), Error> {
+    range.range_check::<VALUE_BYTES>(layouter.namespace(|| "range"), cell)?;
+    let lt = compare.less_than(layouter.namespace(|| "below m"), cell, m)?;
+    // in a cell of its own, since MockProver can't place a failure in a
+    // region with no cells
+    layouter.assign_region(
+        || "is reduced",
+        |mut region| {
+            let lt = lt.copy_advice(|| "lt", &mut region, column, 0)?;
+            region.constrain_constant(lt.cell(), F::one())
+        },
+    )
+}
+
+impl<F: FieldExt> Circuit<F> for FiboModCircuit<F> {
+    type Config = FiboModConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.n)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 5].map(|_| meta.advice_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+        let selector = meta.selector();
+
+        meta.enable_equality(instance);
+        meta.enable_constant(constants);
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("add mod", |meta| {
+            //
+            // x | y | q | r | m | selector
+            // x   y   q   r   m      1
+            //
+            // x + y = q * m + r, with q boolean
+            let s = meta.query_selector(selector);
+            let [x, y, q, r, m] = advice.map(|column| meta.query_advice(column, Rotation::cur()));
+            vec![
+                s.clone() * q.clone() * (Expression::Constant(F::one()) - q.clone()),
+                s * (x + y - q * m - r),
+            ]
+        });
+
+        let mut tables = TableRegistry::default();
+        let bytes = tables.bytes(meta);
+        let range = RangeCheckChip::configure(meta, [advice[0], advice[1]], bytes);
+        let compare =
+            CompareChip::configure(meta, [advice[0], advice[1], advice[2], advice[3]], range);
+
+        FiboModConfig {
+            advice,
+            selector,
+            instance,
+            compare,
+            tables,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let range = RangeCheckChip::construct(config.compare.range.clone());
+        let compare = CompareChip::construct(config.compare.clone());
+        config.tables.load(&mut layouter)?;
+
+        let [a, b, m] = layouter.assign_region(
+            || "public inputs",
+            |mut region| {
+                let cells = (0..3)
+                    .map(|row| {
+                        region.assign_advice_from_instance(
+                            || "input",
+                            config.instance,
+                            row,
+                            config.advice[row],
+                            0,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(cells.try_into().unwrap())
+            },
+        )?;
+        range.range_check::<VALUE_BYTES>(layouter.namespace(|| "m"), &m)?;
+        assert_reduced(
+            &range,
+            &compare,
+            layouter.namespace(|| "a"),
+            config.advice[4],
+            &a,
+            &m,
+        )?;
+        assert_reduced(
+            &range,
+            &compare,
+            layouter.namespace(|| "b"),
+            config.advice[4],
+            &b,
+            &m,
+        )?;
+
+        let (mut x, mut y) = (a, b);
+        for _ in 0..self.n {
+            let r = layouter.assign_region(
+                || "add mod",
+                |mut region| {
+                    config.selector.enable(&mut region, 0)?;
+                    let [col_x, col_y, col_q, col_r, col_m] = config.advice;
+
+                    let x = x.copy_advice(|| "x", &mut region, col_x, 0)?;
+                    let y = y.copy_advice(|| "y", &mut region, col_y, 0)?;
+                    let m = m.copy_advice(|| "m", &mut region, col_m, 0)?;
+
+                    let sum = x
+                        .value()
+                        .zip(y.value())
+                        .map(|(x, y)| x.get_lower_128() + y.get_lower_128());
+                    let m = m.value().map(|m| m.get_lower_128());
+                    // a zero m fails the checks on a and b anyway
+                    let qr = sum
+                        .zip(m)
+                        .map(|(sum, m)| sum.checked_div(m).map_or((0, sum), |q| (q, sum % m)));
+                    region.assign_advice(|| "q", col_q, 0, || qr.map(|(q, _)| F::from_u128(q)))?;
+                    region.assign_advice(|| "r", col_r, 0, || qr.map(|(_, r)| F::from_u128(r)))
+                },
+            )?;
+            assert_reduced(
+                &range,
+                &compare,
+                layouter.namespace(|| "r"),
+                config.advice[4],
+                &r,
+                &m,
+            )?;
+            (x, y) = (y, r);
+        }
+
+        layouter.constrain_instance(x.cell(), config.instance, 3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_rejects_near_misses;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 9;
+
+    #[test]
+    fn test_fibonacci_mod() {
+        assert_eq!(fibonacci_mod(1, 1, 1000, 9), 55);
+        assert_eq!(fibonacci_mod(1, 1, 10, 9), 5);
+        // the Pisano period of 10 is 60
+        assert_eq!(fibonacci_mod(0, 1, 10, 60), 0);
+        assert_eq!(fibonacci_mod(0, 1, 10, 61), 1);
+        assert_eq!(fibonacci_mod(3, 4, 1, 5), 0);
+
+        for (a, b, m, n) in [
+            (1, 1, 10, 9),
+            (0, 1, 7, 20),
+            (5, 6, 7, 3),
+            (0, 0, 1, 4),
+            (0xffff_fffe, 0xffff_fffd, 0xffff_ffff, 15),
+            (1, 1, 1000, 0),
+        ] {
+            let circuit = FiboModCircuit::<Fp>::new(n);
+            let instance = fibonacci_mod_instance(a, b, m, n);
+            let prover = MockProver::run(K, &circuit, vec![instance]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_unreduced() {
+        let circuit = FiboModCircuit::<Fp>::new(9);
+        // the true F(9) for a and b that aren't reduced, and a modulus too big
+        // for the range checks
+        for instance in [
+            [12, 1, 10, 9],
+            [1, 10, 10, 5],
+            [1, 1, 1 << 32, 55],
+            [1, 1, 0, 55],
+        ] {
+            let prover = MockProver::run(K, &circuit, vec![instance.map(Fp::from).to_vec()]);
+            assert!(prover.unwrap().verify().is_err(), "{:?}", instance);
+        }
+    }
+
+    #[test]
+    fn test_near_misses() {
+        let _guard = crate::testing::heavy_test();
+        assert_rejects_near_misses(
+            K,
+            &FiboModCircuit::<Fp>::new(12),
+            vec![fibonacci_mod_instance(3, 8, 1009, 12)],
+            &[],
+        );
+    }
+}
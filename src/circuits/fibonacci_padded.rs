@@ -0,0 +1,238 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+use crate::fibo::fibonacci_sequence;
+
+#[derive(Debug, Clone)]
+pub struct FiboPaddedConfig {
+    pub advice: [Column<Advice>; 4],
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+}
+
+// Proves F(n) for F(0) = a and F(1) = b and any length n up to `MAX`, with the
+// same shape, and so the same keys, for every n. The instance column is
+// `[a, b, n, F(n)]`.
+//
+// The table is MAX steps long whatever n is. Each row has an `active` flag:
+// an active row takes a Fibonacci step, and the rows after the last active
+// one are padding, which copy the final pair down to the last row. Once a row
+// is padding every row after it is too, and the active rows are counted, so
+// with n of them the pair on the last row is (F(n), F(n + 1)).
+// fibonacci_index does the same with a counter that runs down to zero.
+#[derive(Default)]
+pub struct FiboPaddedCircuit<F, const MAX: usize> {
+    pub n: Value<usize>,
+    _marker: PhantomData<F>,
+}
+
+impl<F, const MAX: usize> FiboPaddedCircuit<F, MAX> {
+    pub fn new(n: usize) -> Self {
+        Self {
+            n: Value::known(n),
+            _marker: PhantomData,
+        }
+    }
+}
+
+pub fn fibonacci_padded_instance<F: FieldExt>(a: u64, b: u64, n: usize) -> Vec<F> {
+    let (a, b) = (F::from(a), F::from(b));
+    vec![a, b, F::from(n as u64), fibonacci_sequence(a, b, n + 1)[n]]
+}
+
+impl<F: FieldExt, const MAX: usize> Circuit<F> for FiboPaddedCircuit<F, MAX> {
+    type Config = FiboPaddedConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+        let selector = meta.selector();
+
+        meta.enable_equality(instance);
+        meta.enable_constant(constants);
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        //
+        // x  | y  | active | count | selector
+        // x    y     f        c         1
+        // x'   y'    f'       c'
+        //
+        meta.create_gate("active", |meta| {
+            // f is boolean, a row after padding is padding too, and c counts
+            // the active rows so far
+            let s = meta.query_selector(selector);
+            let one = Expression::Constant(F::one());
+            let active = meta.query_advice(advice[2], Rotation::cur());
+            let active_next = meta.query_advice(advice[2], Rotation::next());
+            let count = meta.query_advice(advice[3], Rotation::cur());
+            let count_next = meta.query_advice(advice[3], Rotation::next());
+            vec![
+                s.clone() * active.clone() * (one.clone() - active.clone()),
+                s.clone() * active_next * (one - active.clone()),
+                s * (count + active - count_next),
+            ]
+        });
+
+        meta.create_gate("fibonacci", |meta| {
+            // x' = y and y' = x + y on an active row
+            let s = meta.query_selector(selector);
+            let [x, y, active] = [0, 1, 2].map(|i| meta.query_advice(advice[i], Rotation::cur()));
+            let x_next = meta.query_advice(advice[0], Rotation::next());
+            let y_next = meta.query_advice(advice[1], Rotation::next());
+            vec![
+                s.clone() * active.clone() * (y.clone() - x_next),
+                s * active * (x + y - y_next),
+            ]
+        });
+
+        meta.create_gate("padding", |meta| {
+            // x' = x and y' = y on a padding row
+            let s = meta.query_selector(selector);
+            let one = Expression::Constant(F::one());
+            let [x, y, active] = [0, 1, 2].map(|i| meta.query_advice(advice[i], Rotation::cur()));
+            let x_next = meta.query_advice(advice[0], Rotation::next());
+            let y_next = meta.query_advice(advice[1], Rotation::next());
+            let padding = one - active;
+            vec![
+                s.clone() * padding.clone() * (x - x_next),
+                s * padding * (y - y_next),
+            ]
+        });
+
+        FiboPaddedConfig {
+            advice,
+            selector,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let [col_x, col_y, col_active, col_count] = config.advice;
+
+        let (count, out) = layouter.assign_region(
+            || "padded table",
+            |mut region| {
+                let mut x =
+                    region.assign_advice_from_instance(|| "a", config.instance, 0, col_x, 0)?;
+                let mut y =
+                    region.assign_advice_from_instance(|| "b", config.instance, 1, col_y, 0)?;
+                let mut count =
+                    region.assign_advice_from_constant(|| "count", col_count, 0, F::zero())?;
+
+                for row in 0..=MAX {
+                    let active = self.n.map(|n| row < n);
+                    region.assign_advice(
+                        || "active",
+                        col_active,
+                        row,
+                        || active.map(|active| F::from(active as u64)),
+                    )?;
+                    if row == MAX {
+                        break;
+                    }
+                    config.selector.enable(&mut region, row)?;
+
+                    let pair = x
+                        .value()
+                        .zip(y.value())
+                        .zip(active)
+                        .map(
+                            |((x, y), active)| {
+                                if active {
+                                    (*y, *x + y)
+                                } else {
+                                    (*x, *y)
+                                }
+                            },
+                        );
+                    let next = count.value().copied() + active.map(|active| F::from(active as u64));
+                    x = region.assign_advice(|| "x", col_x, row + 1, || pair.map(|p| p.0))?;
+                    y = region.assign_advice(|| "y", col_y, row + 1, || pair.map(|p| p.1))?;
+                    count = region.assign_advice(|| "count", col_count, row + 1, || next)?;
+                }
+
+                Ok((count, x))
+            },
+        )?;
+
+        layouter.constrain_instance(count.cell(), config.instance, 2)?;
+        layouter.constrain_instance(out.cell(), config.instance, 3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        batch::{prove_many, verify_many},
+        explain::{assert_unsatisfied_with, Failure},
+        testing::assert_rejects_near_misses,
+    };
+    use halo2_proofs::{
+        dev::MockProver,
+        pasta::{EqAffine, Fp},
+        poly::commitment::Params,
+    };
+
+    const K: u32 = 5;
+    const MAX: usize = 12;
+
+    #[test]
+    fn test_fibonacci_padded() {
+        for n in 0..=MAX {
+            let circuit = FiboPaddedCircuit::<Fp, MAX>::new(n);
+            let instance = fibonacci_padded_instance(1, 1, n);
+            let prover = MockProver::run(K, &circuit, vec![instance]).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // F(9) claimed as the term at the lengths either side
+        let circuit = FiboPaddedCircuit::<Fp, MAX>::new(9);
+        for n in [8, 10] {
+            let mut instance = fibonacci_padded_instance(1, 1, 9);
+            instance[2] = Fp::from(n);
+            assert_unsatisfied_with(
+                K,
+                &circuit,
+                vec![instance],
+                &[Failure::Copy("A3"), Failure::Copy("I0")],
+            );
+        }
+    }
+
+    #[test]
+    fn test_near_misses() {
+        assert_rejects_near_misses(
+            K,
+            &FiboPaddedCircuit::<Fp, MAX>::new(7),
+            vec![fibonacci_padded_instance(2, 7, 7)],
+            &[],
+        );
+    }
+
+    // One set of keys for a short table and a full one.
+    #[test]
+    fn test_one_key() {
+        let _guard = crate::testing::heavy_test();
+        let params = Params::<EqAffine>::new(K);
+        let vk = keygen_vk(&params, &FiboPaddedCircuit::<Fp, MAX>::default()).unwrap();
+        let pk = keygen_pk(&params, vk, &FiboPaddedCircuit::<Fp, MAX>::default()).unwrap();
+
+        let circuits = [2, MAX].map(FiboPaddedCircuit::<Fp, MAX>::new);
+        let instances = [2, MAX].map(|n| vec![fibonacci_padded_instance(1, 1, n)]);
+        let proof = prove_many(&params, &pk, &circuits, &instances);
+        assert!(verify_many(&params, pk.get_vk(), &instances, &proof));
+    }
+}
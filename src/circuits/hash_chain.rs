@@ -0,0 +1,245 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::gadgets::poseidon::{self, PoseidonChip, PoseidonConfig};
+
+#[derive(Debug, Clone)]
+pub struct HashChainConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 5],
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+    pub poseidon: PoseidonConfig<F>,
+}
+
+// Proves `out = H^n(seed)` for a private seed, with `n` and `out` public and
+// H the Poseidon hash of a single element. The circuit is laid out for up to
+// `MAX` steps: every step is hashed, and the chain, one long region like
+// example2's table, takes the digest while steps are left and keeps the link
+// after that. Every step costs a permutation's 65 rows whatever `n` is, so
+// the rows grow linearly with `MAX`. The instance column is `[n, out]`.
+pub struct HashChainCircuit<F, const MAX: usize> {
+    pub seed: Value<F>,
+}
+
+impl<F: FieldExt, const MAX: usize> HashChainCircuit<F, MAX> {
+    pub fn new(seed: F) -> Self {
+        Self {
+            seed: Value::known(seed),
+        }
+    }
+}
+
+impl<F: FieldExt, const MAX: usize> Default for HashChainCircuit<F, MAX> {
+    fn default() -> Self {
+        Self {
+            seed: Value::unknown(),
+        }
+    }
+}
+
+pub fn hash_chain<F: FieldExt>(seed: F, n: usize) -> F {
+    let params = poseidon::PoseidonParams::new();
+    (0..n).fold(seed, |link, _| params.hash(&[link]))
+}
+
+impl<F: FieldExt, const MAX: usize> Circuit<F> for HashChainCircuit<F, MAX> {
+    type Config = HashChainConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 5].map(|_| meta.advice_column());
+        let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+        let selector = meta.selector();
+
+        meta.enable_equality(instance);
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        let poseidon =
+            PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+
+        meta.create_gate("chain", |meta| {
+            //
+            // link | digest | left | inv   | done | selector
+            //   h      H(h)     r     1/r     b        1
+            //   h'              r'
+            //
+            // b is 1 once no steps are left, r = 0, and 0 before. While
+            // steps are left the next link is the digest and one fewer step
+            // is left; after that both carry over.
+            let s = meta.query_selector(selector);
+            let one = Expression::Constant(F::one());
+            let link = meta.query_advice(advice[0], Rotation::cur());
+            let digest = meta.query_advice(advice[1], Rotation::cur());
+            let left = meta.query_advice(advice[2], Rotation::cur());
+            let inv = meta.query_advice(advice[3], Rotation::cur());
+            let done = meta.query_advice(advice[4], Rotation::cur());
+            let link_next = meta.query_advice(advice[0], Rotation::next());
+            let left_next = meta.query_advice(advice[2], Rotation::next());
+
+            vec![
+                s.clone() * (left.clone() * inv - (one.clone() - done.clone())),
+                s.clone() * left.clone() * done.clone(),
+                s.clone()
+                    * (done.clone() * link + (one.clone() - done.clone()) * digest - link_next),
+                s * (left - (one - done) - left_next),
+            ]
+        });
+
+        HashChainConfig {
+            advice,
+            selector,
+            instance,
+            poseidon,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let advice = config.advice;
+        let params = config.poseidon.params.clone();
+
+        // the chain with every digest worked out on the host, checked against
+        // the Poseidon chip below
+        let (steps, out) = layouter.assign_region(
+            || "chain",
+            |mut region| {
+                let mut link = region.assign_advice(|| "seed", advice[0], 0, || self.seed)?;
+                let mut left =
+                    region.assign_advice_from_instance(|| "n", config.instance, 0, advice[2], 0)?;
+
+                let mut steps = vec![];
+                for row in 0..MAX {
+                    config.selector.enable(&mut region, row)?;
+                    let digest = link.value().map(|link| params.hash(&[*link]));
+                    let digest = region.assign_advice(|| "digest", advice[1], row, || digest)?;
+
+                    let done = left.value().map(|left| {
+                        if *left == F::zero() {
+                            F::one()
+                        } else {
+                            F::zero()
+                        }
+                    });
+                    let inv = left.value().map(|left| left.invert().unwrap_or(F::zero()));
+                    region.assign_advice(|| "inv", advice[3], row, || inv)?;
+                    region.assign_advice(|| "done", advice[4], row, || done)?;
+
+                    let next =
+                        link.value()
+                            .zip(digest.value())
+                            .zip(done)
+                            .map(
+                                |((link, digest), done)| {
+                                    if done == F::one() {
+                                        *link
+                                    } else {
+                                        *digest
+                                    }
+                                },
+                            );
+                    let remaining = left.value().copied() - Value::known(F::one()) + done;
+                    steps.push((link, digest));
+                    link = region.assign_advice(|| "link", advice[0], row + 1, || next)?;
+                    left = region.assign_advice(|| "left", advice[2], row + 1, || remaining)?;
+                }
+
+                // more than MAX steps don't fit
+                region.constrain_constant(left.cell(), F::zero())?;
+                Ok((steps, link))
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon);
+        for (step, (link, digest)) in steps.iter().enumerate() {
+            let hashed = poseidon.hash(
+                layouter.namespace(|| format!("step {}", step)),
+                std::slice::from_ref(link),
+            )?;
+            layouter.assign_region(
+                || "digest",
+                |mut region| {
+                    let hashed = hashed.copy_advice(|| "digest", &mut region, advice[1], 0)?;
+                    region.constrain_equal(hashed.cell(), digest.cell())
+                },
+            )?;
+        }
+
+        layouter.constrain_instance(out.cell(), config.instance, 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 9;
+    const MAX: usize = 4;
+
+    #[test]
+    fn test_hash_chain() {
+        let seed = Fp::from(42);
+        let circuit = HashChainCircuit::<Fp, MAX>::new(seed);
+
+        assert_eq!(hash_chain(seed, 0), seed);
+        for n in 0..=MAX {
+            let out = hash_chain(seed, n);
+            let instance = vec![vec![Fp::from(n as u64), out]];
+            let prover = MockProver::run(K, &circuit, instance).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // one step short, another seed, and more steps than fit
+        let out = hash_chain(seed, 3);
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![vec![Fp::from(2), out]],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+        let other = HashChainCircuit::<Fp, MAX>::new(seed + Fp::one());
+        assert_unsatisfied_with(
+            K,
+            &other,
+            vec![vec![Fp::from(3), out]],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+        let n = MAX + 1;
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![vec![Fp::from(n as u64), hash_chain(seed, n)]],
+            &[
+                Failure::Copy("A0"),
+                Failure::Copy("A2"),
+                Failure::Copy("F3"),
+                Failure::Copy("I0"),
+            ],
+        );
+    }
+
+    // Every step is a permutation's worth of rows whatever n is, so the
+    // circuit grows with MAX.
+    #[test]
+    fn test_hash_chain_scaling() {
+        use crate::stats::min_k_for;
+
+        let seed = Fp::from(42);
+        let instance = |n: usize| vec![vec![Fp::from(n as u64), hash_chain(seed, n)]];
+        let k1 = min_k_for(&HashChainCircuit::<Fp, 1>::new(seed), &instance(1)).unwrap();
+        let k8 = min_k_for(&HashChainCircuit::<Fp, 8>::new(seed), &instance(1)).unwrap();
+        let k16 = min_k_for(&HashChainCircuit::<Fp, 16>::new(seed), &instance(1)).unwrap();
+        assert_eq!((k1, k8, k16), (7, 10, 11));
+    }
+}
@@ -0,0 +1,275 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::gadgets::{
+    compare::{CompareChip, CompareConfig},
+    range_check::RangeCheckChip,
+    tables::TableRegistry,
+};
+
+pub const NUM_BUCKETS: usize = 4;
+pub const VALUE_BYTES: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct HistogramConfig {
+    pub below: [Column<Advice>; NUM_BUCKETS - 1],
+    pub bucket: [Column<Advice>; NUM_BUCKETS],
+    pub count: [Column<Advice>; NUM_BUCKETS],
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+    pub compare: CompareConfig<VALUE_BYTES>,
+    pub tables: TableRegistry,
+}
+
+// Proves the public bucket counts of private values. Bucket j holds the values
+// in [b_j, b_{j+1}), with b_0 = 0 and b_NUM_BUCKETS = infinity; the public
+// boundaries b_1 < b_2 < ... have to be increasing. The instance column is
+// `[b_1, .., b_{NUM_BUCKETS - 1}, count_0, .., count_{NUM_BUCKETS - 1}]`.
+#[derive(Default)]
+pub struct HistogramCircuit<F> {
+    pub values: Vec<Value<F>>,
+}
+
+impl<F: FieldExt> HistogramCircuit<F> {
+    pub fn new(values: &[u64]) -> Self {
+        Self {
+            values: values.iter().map(|v| Value::known(F::from(*v))).collect(),
+        }
+    }
+}
+
+pub fn histogram(values: &[u64], boundaries: &[u64; NUM_BUCKETS - 1]) -> [u64; NUM_BUCKETS] {
+    let mut counts = [0; NUM_BUCKETS];
+    for v in values {
+        counts[boundaries.iter().filter(|b| *b <= v).count()] += 1;
+    }
+    counts
+}
+
+pub fn histogram_instance<F: FieldExt>(
+    boundaries: &[u64; NUM_BUCKETS - 1],
+    counts: &[u64; NUM_BUCKETS],
+) -> Vec<F> {
+    boundaries
+        .iter()
+        .chain(counts.iter())
+        .map(|v| F::from(*v))
+        .collect()
+}
+
+impl<F: FieldExt> Circuit<F> for HistogramCircuit<F> {
+    type Config = HistogramConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            values: vec![Value::unknown(); self.values.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let below = [(); NUM_BUCKETS - 1].map(|_| meta.advice_column());
+        let bucket = [(); NUM_BUCKETS].map(|_| meta.advice_column());
+        let count = [(); NUM_BUCKETS].map(|_| meta.advice_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+        let selector = meta.selector();
+
+        meta.enable_equality(instance);
+        meta.enable_constant(constants);
+        for column in below.iter().chain(count.iter()) {
+            meta.enable_equality(*column);
+        }
+
+        meta.create_gate("bucket", |meta| {
+            //
+            // below[0..3]       | bucket[0..4]      | count[0..4]        | selector
+            //   lt_1 .. lt_3       s_0 .. s_3          0 .. 0                1
+            //   ...                                    ...                   1
+            //                                          count_0 .. count_3
+            //
+            // lt_k says whether the value is below b_k. With lt_0 = 0 and
+            // lt_4 = 1 the bucket selectors s_j = lt_{j+1} - lt_j sum to 1, and
+            // being boolean makes them one-hot.
+            let s = meta.query_selector(selector);
+            let one = Expression::Constant(F::one());
+
+            let mut lt = vec![Expression::Constant(F::zero())];
+            for column in below {
+                lt.push(meta.query_advice(column, Rotation::cur()));
+            }
+            lt.push(one.clone());
+
+            let mut constraints = vec![];
+            for j in 0..NUM_BUCKETS {
+                let b = meta.query_advice(bucket[j], Rotation::cur());
+                let acc = meta.query_advice(count[j], Rotation::cur());
+                let acc_next = meta.query_advice(count[j], Rotation::next());
+                constraints.push(s.clone() * (lt[j + 1].clone() - lt[j].clone() - b.clone()));
+                constraints.push(s.clone() * b.clone() * (one.clone() - b.clone()));
+                constraints.push(s.clone() * (acc + b - acc_next));
+            }
+            constraints
+        });
+
+        let mut tables = TableRegistry::default();
+        let bytes = tables.bytes(meta);
+        let range = RangeCheckChip::configure(meta, [count[0], count[1]], bytes);
+        let compare = CompareChip::configure(meta, [count[0], count[1], count[2], count[3]], range);
+
+        HistogramConfig {
+            below,
+            bucket,
+            count,
+            selector,
+            instance,
+            compare,
+            tables,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let range = RangeCheckChip::construct(config.compare.range.clone());
+        config.tables.load(&mut layouter)?;
+
+        let boundaries = layouter.assign_region(
+            || "boundaries",
+            |mut region| {
+                (0..NUM_BUCKETS - 1)
+                    .map(|k| {
+                        region.assign_advice_from_instance(
+                            || "boundary",
+                            config.instance,
+                            k,
+                            config.below[k],
+                            0,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+        for b in boundaries.iter() {
+            range.range_check::<VALUE_BYTES>(layouter.namespace(|| "boundary"), b)?;
+        }
+
+        let compare = CompareChip::construct(config.compare.clone());
+        let mut below = vec![];
+        for v in self.values.iter() {
+            let v = range.witness_checked::<VALUE_BYTES>(layouter.namespace(|| "value"), *v)?;
+            let mut lt = vec![];
+            for b in boundaries.iter() {
+                lt.push(compare.less_than(layouter.namespace(|| "value < boundary"), &v, b)?);
+            }
+            below.push(lt);
+        }
+
+        let counts = layouter.assign_region(
+            || "histogram",
+            |mut region| {
+                let mut counts = config
+                    .count
+                    .iter()
+                    .map(|column| {
+                        region.assign_advice_from_constant(|| "count", *column, 0, F::zero())
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                for (offset, lt) in below.iter().enumerate() {
+                    config.selector.enable(&mut region, offset)?;
+
+                    let mut lt_values = vec![Value::known(F::zero())];
+                    for (k, cell) in lt.iter().enumerate() {
+                        let cell =
+                            cell.copy_advice(|| "lt", &mut region, config.below[k], offset)?;
+                        lt_values.push(cell.value().copied());
+                    }
+                    lt_values.push(Value::known(F::one()));
+
+                    for j in 0..NUM_BUCKETS {
+                        let b = lt_values[j + 1] - lt_values[j];
+                        region.assign_advice(|| "bucket", config.bucket[j], offset, || b)?;
+                        let acc = counts[j].value().copied() + b;
+                        counts[j] = region.assign_advice(
+                            || "count",
+                            config.count[j],
+                            offset + 1,
+                            || acc,
+                        )?;
+                    }
+                }
+
+                Ok(counts)
+            },
+        )?;
+
+        for (j, count) in counts.iter().enumerate() {
+            layouter.constrain_instance(count.cell(), config.instance, NUM_BUCKETS - 1 + j)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 10;
+
+    #[test]
+    fn test_histogram() {
+        let values = [3, 17, 42, 99, 100, 250, 0, 10, 1000];
+        let boundaries = [10, 100, 500];
+        let circuit = HistogramCircuit::<Fp>::new(&values);
+
+        let counts = histogram(&values, &boundaries);
+        assert_eq!(counts, [2, 4, 2, 1]);
+
+        let prover =
+            MockProver::run(K, &circuit, vec![histogram_instance(&boundaries, &counts)]).unwrap();
+        prover.assert_satisfied();
+
+        // moving a value to the next bucket, or dropping one
+        for (counts, failures) in [
+            (
+                [2, 3, 3, 1],
+                &[
+                    Failure::Copy("A8"),
+                    Failure::Copy("A9"),
+                    Failure::Copy("I0"),
+                ][..],
+            ),
+            ([2, 4, 2, 0], &[Failure::Copy("A10"), Failure::Copy("I0")]),
+        ] {
+            let public_input = histogram_instance(&boundaries, &counts);
+            assert_unsatisfied_with(K, &circuit, vec![public_input], failures);
+        }
+    }
+
+    #[test]
+    fn test_histogram_unsorted_boundaries() {
+        let values = [5, 50];
+        let boundaries = [100, 10, 500];
+        let circuit = HistogramCircuit::<Fp>::new(&values);
+
+        // 50 is below 100 but not below 10, which isn't a bucket at all
+        let counts = histogram(&values, &boundaries);
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![histogram_instance(&boundaries, &counts)],
+            &[
+                Failure::Copy("A7"),
+                Failure::Copy("A8"),
+                Failure::Copy("A9"),
+                Failure::Copy("I0"),
+                Failure::Gate("bucket"),
+            ],
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use crate::gadgets::{
+    range_check::RangeCheckChip,
+    sha256::{pad, sha256, Sha256Chip, Sha256Config, BLOCK_BYTES},
+    tables::TableRegistry,
+    uint32::{AssignedU32, U32Chip},
+};
+
+const IPAD: u32 = 0x3636_3636;
+const OPAD: u32 = 0x5c5c_5c5c;
+
+// Messages have to pad to one block, so the circuit runs a fixed four
+// compressions.
+pub const MAX_MESSAGE_BYTES: usize = BLOCK_BYTES - 9;
+
+// K0 from RFC 2104: the key zero padded to a block, or hashed first if it is
+// longer than one.
+pub fn key_block(key: &[u8]) -> [u32; 16] {
+    let mut k0 = [0; BLOCK_BYTES];
+    match key.len() > BLOCK_BYTES {
+        true => k0[..32].copy_from_slice(&sha256(key)),
+        false => k0[..key.len()].copy_from_slice(key),
+    }
+    std::array::from_fn(|i| u32::from_be_bytes(k0[4 * i..4 * i + 4].try_into().unwrap()))
+}
+
+pub fn hmac(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let k0: Vec<u8> = key_block(key)
+        .iter()
+        .flat_map(|w| w.to_be_bytes())
+        .collect();
+    let xor = |pad: u8| k0.iter().map(|b| b ^ pad).collect::<Vec<u8>>();
+
+    let inner = sha256(&[xor(0x36), message.to_vec()].concat());
+    sha256(&[xor(0x5c), inner.to_vec()].concat())
+}
+
+// The instance column: the message's padded block, then the tag, as big endian
+// words.
+pub fn hmac_instance<F: FieldExt>(message: &[u8], tag: &[u8; 32]) -> Vec<F> {
+    assert!(message.len() <= MAX_MESSAGE_BYTES);
+    let block = pad(&[[0; BLOCK_BYTES].to_vec(), message.to_vec()].concat())[1];
+    block
+        .into_iter()
+        .chain(
+            tag.chunks(4)
+                .map(|chunk| u32::from_be_bytes(chunk.try_into().unwrap())),
+        )
+        .map(|w| F::from(w as u64))
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct HmacConfig {
+    pub instance: Column<Instance>,
+    pub sha256: Sha256Config,
+    pub tables: TableRegistry,
+}
+
+// Proves knowledge of a key that MACs the public message to the public tag
+// with HMAC-SHA256. The witness is K0, the block the key becomes, so keys
+// longer than a block are hashed before they get here.
+#[derive(Default)]
+pub struct HmacCircuit<F> {
+    pub key: [Value<F>; 16],
+}
+
+impl<F: FieldExt> HmacCircuit<F> {
+    pub fn new(key: &[u8]) -> Self {
+        Self {
+            key: key_block(key).map(|w| Value::known(F::from(w as u64))),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for HmacCircuit<F> {
+    type Config = HmacConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 6].map(|_| meta.advice_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(instance);
+
+        let mut tables = TableRegistry::default();
+        let bytes = tables.bytes(meta);
+        let range = RangeCheckChip::configure(meta, [advice[4], advice[5]], bytes);
+        let sha256 = Sha256Chip::configure(meta, advice, constants, range);
+
+        HmacConfig {
+            instance,
+            sha256,
+            tables,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.tables.load(&mut layouter)?;
+        let u32 = U32Chip::construct(config.sha256.u32.clone());
+        let sha256 = Sha256Chip::construct(config.sha256.clone());
+
+        let mut key = vec![];
+        for word in self.key.iter() {
+            let word = word.map(|w| w.get_lower_128() as u32);
+            key.push(u32.witness(layouter.namespace(|| "key"), word)?);
+        }
+        let mut message = vec![];
+        for row in 0..16 {
+            let cell = layouter.assign_region(
+                || "message",
+                |mut region| {
+                    region.assign_advice_from_instance(
+                        || "word",
+                        config.instance,
+                        row,
+                        config.sha256.u32.advice[0],
+                        0,
+                    )
+                },
+            )?;
+            message.push(u32.check(layouter.namespace(|| "word"), cell)?);
+        }
+
+        let mut keyed = |pad: u32| -> Result<[AssignedU32<F>; 16], Error> {
+            let pad = u32.constant(layouter.namespace(|| "pad"), pad)?;
+            let mut block = vec![];
+            for word in key.iter() {
+                block.push(u32.xor(layouter.namespace(|| "key ^ pad"), word, &pad)?);
+            }
+            Ok(block.try_into().unwrap())
+        };
+        let (inner_key, outer_key) = (keyed(IPAD)?, keyed(OPAD)?);
+
+        let iv = sha256.iv(layouter.namespace(|| "iv"))?;
+        let h = sha256.compress(layouter.namespace(|| "inner key"), &iv, &inner_key)?;
+        let inner = sha256.compress(
+            layouter.namespace(|| "message"),
+            &h,
+            &message.try_into().unwrap(),
+        )?;
+
+        // the inner digest padded after a block of key: a 1 bit, zeros, and
+        // 768 bits of length
+        let mut block = inner.to_vec();
+        for word in [0x8000_0000, 0, 0, 0, 0, 0, 0, (BLOCK_BYTES as u32 + 32) * 8] {
+            block.push(u32.constant(layouter.namespace(|| "padding"), word)?);
+        }
+        let h = sha256.compress(layouter.namespace(|| "outer key"), &iv, &outer_key)?;
+        let tag = sha256.compress(
+            layouter.namespace(|| "inner digest"),
+            &h,
+            &block.try_into().unwrap(),
+        )?;
+
+        for (i, word) in tag.iter().enumerate() {
+            layouter.constrain_instance(word.cell(), config.instance, 16 + i)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 18;
+
+    fn hex(tag: &[u8]) -> String {
+        tag.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_hmac() {
+        // RFC 4231 test cases 2 and 6
+        assert_eq!(
+            hex(&hmac(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            hex(&hmac(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn test_hmac_circuit() {
+        let _guard = crate::testing::heavy_test();
+        let (key, message) = (b"Jefe", b"what do ya want for nothing?");
+        let tag = hmac(key, message);
+        let circuit = HmacCircuit::<Fp>::new(key);
+
+        let prover = MockProver::run(K, &circuit, vec![hmac_instance(message, &tag)]).unwrap();
+        prover.assert_satisfied();
+
+        // the tag of another message under the same key
+        let other = hmac(key, b"what do ya want for nothing!");
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![hmac_instance(message, &other)],
+            &[Failure::Copy("A2"), Failure::Copy("I0")],
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::gadgets::{
+    is_equal::{IsEqualChip, IsEqualConfig},
+    poseidon::{self, PoseidonChip},
+    range_check::RangeCheckChip,
+    sort::{SortChip, SortConfig},
+    tables::TableRegistry,
+};
+
+#[derive(Debug, Clone)]
+pub struct KthSmallestConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 6],
+    pub q_select: Selector,
+    pub instance: Column<Instance>,
+    pub sort: SortConfig<F>,
+    pub is_equal: IsEqualConfig,
+    pub tables: TableRegistry,
+}
+
+// Proves the public value is the k-th smallest (counting from 0) of private
+// values committed to as `hash(values || salt)`. The instance column is
+// `[commitment, k, value]`.
+#[derive(Default)]
+pub struct KthSmallestCircuit<F> {
+    pub values: Vec<Value<F>>,
+    pub salt: Value<F>,
+}
+
+impl<F: FieldExt> KthSmallestCircuit<F> {
+    pub fn new(values: &[u64], salt: u64) -> Self {
+        Self {
+            values: values.iter().map(|v| Value::known(F::from(*v))).collect(),
+            salt: Value::known(F::from(salt)),
+        }
+    }
+}
+
+pub fn commit<F: FieldExt>(values: &[u64], salt: u64) -> F {
+    let mut message: Vec<F> = values.iter().map(|v| F::from(*v)).collect();
+    message.push(F::from(salt));
+    poseidon::hash(&message)
+}
+
+pub fn kth_smallest(values: &[u64], k: usize) -> u64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    sorted[k]
+}
+
+impl<F: FieldExt> Circuit<F> for KthSmallestCircuit<F> {
+    type Config = KthSmallestConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            values: vec![Value::unknown(); self.values.len()],
+            salt: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 6].map(|_| meta.advice_column());
+        let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+        let q_select = meta.selector();
+
+        meta.enable_equality(instance);
+
+        meta.create_gate("select", |meta| {
+            //
+            // advice[0] | advice[1] | advice[2] | advice[3] | q_select
+            //   e_0         y_0         0           0            1
+            //   e_1         y_1         acc_1       count_1      1
+            //   ...
+            //                           acc_n       count_n
+            //
+            // e_i says whether i == k, so acc_n = y_k as long as count_n = 1
+            let s = meta.query_selector(q_select);
+            let [e, y, acc, count] =
+                [0, 1, 2, 3].map(|i| meta.query_advice(advice[i], Rotation::cur()));
+            let acc_next = meta.query_advice(advice[2], Rotation::next());
+            let count_next = meta.query_advice(advice[3], Rotation::next());
+            vec![
+                s.clone() * (acc + e.clone() * y - acc_next),
+                s * (count + e - count_next),
+            ]
+        });
+
+        let mut tables = TableRegistry::default();
+        let bytes = tables.bytes(meta);
+        let range = RangeCheckChip::configure(meta, [advice[4], advice[5]], bytes);
+        let poseidon =
+            PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+        let sort = SortChip::configure(meta, advice, range, poseidon);
+        let is_equal = IsEqualChip::configure(meta, [advice[0], advice[1], advice[2], advice[3]]);
+
+        KthSmallestConfig {
+            advice,
+            q_select,
+            instance,
+            sort,
+            is_equal,
+            tables,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.tables.load(&mut layouter)?;
+
+        let (values, salt, k) = layouter.assign_region(
+            || "inputs",
+            |mut region| {
+                let values = self
+                    .values
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, v)| {
+                        region.assign_advice(|| "value", config.advice[0], offset, || *v)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let salt = region.assign_advice(|| "salt", config.advice[1], 0, || self.salt)?;
+                let k = region.assign_advice_from_instance(
+                    || "k",
+                    config.instance,
+                    1,
+                    config.advice[2],
+                    0,
+                )?;
+                Ok((values, salt, k))
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.sort.multiset.poseidon.clone());
+        let mut message = values.clone();
+        message.push(salt);
+        let commitment = poseidon.hash(layouter.namespace(|| "commitment"), &message)?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 0)?;
+
+        let sort = SortChip::construct(config.sort.clone());
+        let sorted = sort.sort(layouter.namespace(|| "sort"), &values)?;
+
+        let is_equal = IsEqualChip::construct(config.is_equal.clone());
+        let mut hits = vec![];
+        for i in 0..sorted.len() {
+            let index = layouter.assign_region(
+                || "index",
+                |mut region| {
+                    region.assign_advice_from_constant(
+                        || "index",
+                        config.advice[0],
+                        0,
+                        F::from(i as u64),
+                    )
+                },
+            )?;
+            hits.push(is_equal.is_equal(layouter.namespace(|| "index == k"), &index, &k)?);
+        }
+
+        let selected = layouter.assign_region(
+            || "select",
+            |mut region| {
+                let n = sorted.len();
+                let mut acc =
+                    region.assign_advice_from_constant(|| "acc", config.advice[2], 0, F::zero())?;
+                let mut count = region.assign_advice_from_constant(
+                    || "count",
+                    config.advice[3],
+                    0,
+                    F::zero(),
+                )?;
+
+                for offset in 0..n {
+                    config.q_select.enable(&mut region, offset)?;
+
+                    let e =
+                        hits[offset].copy_advice(|| "e", &mut region, config.advice[0], offset)?;
+                    let y = sorted[offset].copy_advice(
+                        || "y",
+                        &mut region,
+                        config.advice[1],
+                        offset,
+                    )?;
+
+                    let acc_next = acc.value().copied() + e.value().copied() * y.value();
+                    let count_next = count.value().copied() + e.value();
+                    acc = region.assign_advice(
+                        || "acc",
+                        config.advice[2],
+                        offset + 1,
+                        || acc_next,
+                    )?;
+                    count = region.assign_advice(
+                        || "count",
+                        config.advice[3],
+                        offset + 1,
+                        || count_next,
+                    )?;
+                }
+
+                // k has to be in range for exactly one index to match
+                region.constrain_constant(count.cell(), F::one())?;
+
+                Ok(acc)
+            },
+        )?;
+        layouter.constrain_instance(selected.cell(), config.instance, 2)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 11;
+
+    #[test]
+    fn test_kth_smallest() {
+        let _guard = crate::testing::heavy_test();
+        let values = [31, 4, 15, 9, 26];
+        let salt = 0xc0ffee;
+        let commitment = commit::<Fp>(&values, salt);
+        let circuit = KthSmallestCircuit::<Fp>::new(&values, salt);
+
+        for k in 0..values.len() {
+            let value = kth_smallest(&values, k);
+            let public_input = vec![commitment, Fp::from(k as u64), Fp::from(value)];
+            let prover = MockProver::run(K, &circuit, vec![public_input]).unwrap();
+            prover.assert_satisfied();
+        }
+
+        let wrong_value = [Failure::Copy("A2"), Failure::Copy("I0")];
+        for (k, value, failures) in [
+            // wrong rank
+            (1, 4, &wrong_value[..]),
+            // not one of the values
+            (2, 14, &wrong_value),
+            // k past the end, with value 0 from no index matching, which
+            // leaves the count of matches off the constant one
+            (5, 0, &[Failure::Copy("A3"), Failure::Copy("F3")]),
+        ] {
+            let public_input = vec![commitment, Fp::from(k), Fp::from(value)];
+            assert_unsatisfied_with(K, &circuit, vec![public_input], failures);
+        }
+
+        // values that don't match the commitment
+        let other = KthSmallestCircuit::<Fp>::new(&[31, 4, 15, 9, 25], salt);
+        let public_input = vec![commitment, Fp::from(0), Fp::from(4)];
+        assert_unsatisfied_with(
+            K,
+            &other,
+            vec![public_input],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+    }
+}
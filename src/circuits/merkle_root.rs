@@ -0,0 +1,129 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use crate::gadgets::{
+    merkle::{MerkleChip, MerkleConfig},
+    poseidon::{self, PoseidonChip},
+};
+
+#[derive(Debug, Clone)]
+pub struct MerkleRootConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 5],
+    pub instance: Column<Instance>,
+    pub merkle: MerkleConfig<F>,
+}
+
+// Computes the Poseidon Merkle root of private leaves entirely in-circuit and
+// exposes it as the only public input. Unlike a path check this commits to
+// every leaf, so the root can stand in for the whole set in later proofs.
+pub struct MerkleRootCircuit<F, const LEAVES: usize> {
+    pub leaves: [Value<F>; LEAVES],
+}
+
+impl<F: FieldExt, const LEAVES: usize> MerkleRootCircuit<F, LEAVES> {
+    pub fn new(leaves: [F; LEAVES]) -> Self {
+        Self {
+            leaves: leaves.map(Value::known),
+        }
+    }
+}
+
+impl<F: FieldExt, const LEAVES: usize> Default for MerkleRootCircuit<F, LEAVES> {
+    fn default() -> Self {
+        Self {
+            leaves: [Value::unknown(); LEAVES],
+        }
+    }
+}
+
+impl<F: FieldExt, const LEAVES: usize> Circuit<F> for MerkleRootCircuit<F, LEAVES> {
+    type Config = MerkleRootConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 5].map(|_| meta.advice_column());
+        let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(instance);
+
+        let poseidon =
+            PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+        let merkle = MerkleChip::configure(meta, advice, poseidon);
+
+        MerkleRootConfig {
+            advice,
+            instance,
+            merkle,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let leaves = layouter.assign_region(
+            || "leaves",
+            |mut region| {
+                self.leaves
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, leaf)| {
+                        region.assign_advice(|| "leaf", config.advice[0], offset, || *leaf)
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+
+        let leaves: [_; LEAVES] = leaves.try_into().unwrap();
+
+        let merkle = MerkleChip::construct(config.merkle);
+        let root = merkle.tree_root(layouter.namespace(|| "tree"), &leaves)?;
+        layouter.constrain_instance(root.cell(), config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use crate::gadgets::merkle::merkle_root;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[test]
+    fn test_merkle_root() {
+        let k = 10;
+
+        let mut leaves: [Fp; 8] = std::array::from_fn(|i| Fp::from((i * i + 1) as u64));
+        let root = merkle_root(&leaves);
+
+        let circuit = MerkleRootCircuit::new(leaves);
+        let prover = MockProver::run(k, &circuit, vec![vec![root]]).unwrap();
+        prover.assert_satisfied();
+
+        // changing any one leaf changes the root
+        leaves[3] += Fp::one();
+        let circuit = MerkleRootCircuit::new(leaves);
+        assert_unsatisfied_with(
+            k,
+            &circuit,
+            vec![vec![root]],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+    }
+
+    #[test]
+    fn test_merkle_root_single_leaf() {
+        let k = 4;
+
+        let leaf = Fp::from(7);
+        let circuit = MerkleRootCircuit::new([leaf]);
+        let prover = MockProver::run(k, &circuit, vec![vec![leaf]]).unwrap();
+        prover.assert_satisfied();
+    }
+}
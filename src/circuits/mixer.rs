@@ -0,0 +1,333 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use crate::gadgets::{
+    merkle::{merkle_path, merkle_root, MerkleChip, MerkleConfig},
+    poseidon::{self, PoseidonChip},
+};
+
+pub fn commitment<F: FieldExt>(secret: F, nullifier: F) -> F {
+    poseidon::hash(&[secret, nullifier])
+}
+
+pub fn nullifier_hash<F: FieldExt>(nullifier: F) -> F {
+    poseidon::hash(&[nullifier])
+}
+
+#[derive(Debug, Clone)]
+pub struct MixerConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 5],
+    pub instance: Column<Instance>,
+    pub merkle: MerkleConfig<F>,
+}
+
+fn configure<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> MixerConfig<F> {
+    let advice = [(); 5].map(|_| meta.advice_column());
+    let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+    let constants = meta.fixed_column();
+    let instance = meta.instance_column();
+
+    meta.enable_equality(instance);
+
+    let poseidon = PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+    MixerConfig {
+        advice,
+        instance,
+        merkle: MerkleChip::configure(meta, advice, poseidon),
+    }
+}
+
+// The pool's side of a Tornado-style mixer: the tree of note commitments,
+// filled left to right with empty leaves being zero, and the nullifier hashes
+// already withdrawn.
+#[derive(Debug, Clone)]
+pub struct Pool<F, const DEPTH: usize> {
+    pub leaves: Vec<F>,
+    pub next: usize,
+    spent: Vec<F>,
+}
+
+impl<F: FieldExt, const DEPTH: usize> Default for Pool<F, DEPTH> {
+    fn default() -> Self {
+        Self {
+            leaves: vec![F::zero(); 1 << DEPTH],
+            next: 0,
+            spent: vec![],
+        }
+    }
+}
+
+impl<F: FieldExt, const DEPTH: usize> Pool<F, DEPTH> {
+    pub fn root(&self) -> F {
+        merkle_root(&self.leaves)
+    }
+
+    fn path(&self, index: usize) -> [F; DEPTH] {
+        merkle_path(&self.leaves, index).try_into().unwrap()
+    }
+
+    // Inserts a note at the next free leaf, returning the deposit circuit and
+    // its public input.
+    pub fn deposit(&mut self, commitment: F) -> (DepositCircuit<F, DEPTH>, Vec<F>) {
+        assert!(self.next < self.leaves.len(), "pool is full");
+        let (index, old_root) = (self.next, self.root());
+        let circuit = DepositCircuit::new(self.path(index), index as u64);
+        self.leaves[index] = commitment;
+        self.next += 1;
+        (circuit, vec![commitment, old_root, self.root()])
+    }
+
+    // The withdraw circuit for the note at `index`.
+    pub fn withdraw(&self, secret: F, nullifier: F, index: usize) -> WithdrawCircuit<F, DEPTH> {
+        WithdrawCircuit::new(secret, nullifier, self.path(index), index as u64)
+    }
+
+    // Records a withdrawal's nullifier hash, returning false if that note was
+    // already withdrawn. The proof still has to verify.
+    pub fn spend(&mut self, nullifier_hash: F) -> bool {
+        if self.spent.contains(&nullifier_hash) {
+            return false;
+        }
+        self.spent.push(nullifier_hash);
+        true
+    }
+}
+
+// Inserts a note commitment into the pool's tree. The instance column is
+// `[commitment, old_root, new_root]`; the leaf it goes into has to be empty
+// in the old tree, so a deposit can't overwrite someone else's note.
+pub struct DepositCircuit<F, const DEPTH: usize> {
+    pub path: [Value<F>; DEPTH],
+    pub index: Value<u64>,
+}
+
+impl<F: FieldExt, const DEPTH: usize> DepositCircuit<F, DEPTH> {
+    pub fn new(path: [F; DEPTH], index: u64) -> Self {
+        Self {
+            path: path.map(Value::known),
+            index: Value::known(index),
+        }
+    }
+}
+
+impl<F: FieldExt, const DEPTH: usize> Default for DepositCircuit<F, DEPTH> {
+    fn default() -> Self {
+        Self {
+            path: [Value::unknown(); DEPTH],
+            index: Value::unknown(),
+        }
+    }
+}
+
+impl<F: FieldExt, const DEPTH: usize> Circuit<F> for DepositCircuit<F, DEPTH> {
+    type Config = MixerConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let (empty, commitment) = layouter.assign_region(
+            || "leaves",
+            |mut region| {
+                let empty = region.assign_advice_from_constant(
+                    || "empty",
+                    config.advice[0],
+                    0,
+                    F::zero(),
+                )?;
+                let commitment = region.assign_advice_from_instance(
+                    || "commitment",
+                    config.instance,
+                    0,
+                    config.advice[1],
+                    0,
+                )?;
+                Ok((empty, commitment))
+            },
+        )?;
+
+        let merkle = MerkleChip::construct(config.merkle);
+        let (old_root, new_root) = merkle.update(
+            layouter.namespace(|| "insert"),
+            &empty,
+            &commitment,
+            &self.path,
+            self.index,
+        )?;
+        layouter.constrain_instance(old_root.cell(), config.instance, 1)?;
+        layouter.constrain_instance(new_root.cell(), config.instance, 2)
+    }
+}
+
+// Withdraws a note without saying which one: proves `H(secret, nullifier)` is
+// a leaf of the tree with the public root and reveals `H(nullifier)`, which
+// the pool records so the note can't be withdrawn twice. The instance column
+// is `[root, nullifier_hash, recipient]`.
+pub struct WithdrawCircuit<F, const DEPTH: usize> {
+    pub secret: Value<F>,
+    pub nullifier: Value<F>,
+    pub path: [Value<F>; DEPTH],
+    pub index: Value<u64>,
+}
+
+impl<F: FieldExt, const DEPTH: usize> WithdrawCircuit<F, DEPTH> {
+    pub fn new(secret: F, nullifier: F, path: [F; DEPTH], index: u64) -> Self {
+        Self {
+            secret: Value::known(secret),
+            nullifier: Value::known(nullifier),
+            path: path.map(Value::known),
+            index: Value::known(index),
+        }
+    }
+}
+
+impl<F: FieldExt, const DEPTH: usize> Default for WithdrawCircuit<F, DEPTH> {
+    fn default() -> Self {
+        Self {
+            secret: Value::unknown(),
+            nullifier: Value::unknown(),
+            path: [Value::unknown(); DEPTH],
+            index: Value::unknown(),
+        }
+    }
+}
+
+impl<F: FieldExt, const DEPTH: usize> Circuit<F> for WithdrawCircuit<F, DEPTH> {
+    type Config = MixerConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let (secret, nullifier) = layouter.assign_region(
+            || "note",
+            |mut region| {
+                let secret =
+                    region.assign_advice(|| "secret", config.advice[0], 0, || self.secret)?;
+                let nullifier =
+                    region.assign_advice(|| "nullifier", config.advice[1], 0, || self.nullifier)?;
+                // The recipient isn't used by any constraint; being part of
+                // the instance is enough to bind the proof to it, so nobody
+                // can take the proof and send the funds elsewhere.
+                region.assign_advice_from_instance(
+                    || "recipient",
+                    config.instance,
+                    2,
+                    config.advice[2],
+                    0,
+                )?;
+                Ok((secret, nullifier))
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.merkle.poseidon.clone());
+        let commitment = poseidon.hash(
+            layouter.namespace(|| "commitment"),
+            &[secret, nullifier.clone()],
+        )?;
+        let nullifier_hash =
+            poseidon.hash(layouter.namespace(|| "nullifier hash"), &[nullifier])?;
+        layouter.constrain_instance(nullifier_hash.cell(), config.instance, 1)?;
+
+        let merkle = MerkleChip::construct(config.merkle);
+        let root = merkle.compute_root(
+            layouter.namespace(|| "membership"),
+            &commitment,
+            &self.path,
+            self.index,
+        )?;
+        layouter.constrain_instance(root.cell(), config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 10;
+    const DEPTH: usize = 3;
+
+    fn verify<C: Circuit<Fp>>(circuit: &C, public_input: Vec<Fp>) -> bool {
+        let prover = MockProver::run(K, circuit, vec![public_input]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_mixer() {
+        let mut pool = Pool::<Fp, DEPTH>::default();
+        let notes: Vec<(Fp, Fp)> = (0..3)
+            .map(|i| (Fp::from(0x5ec2e7 + i), Fp::from(0x2e11 + i)))
+            .collect();
+        for (secret, nullifier) in notes.iter() {
+            let (circuit, public_input) = pool.deposit(commitment(*secret, *nullifier));
+            assert!(verify(&circuit, public_input));
+        }
+
+        let (secret, nullifier) = notes[1];
+        let circuit = pool.withdraw(secret, nullifier, 1);
+        let recipient = Fp::from(0xbeef);
+        let public_input = vec![pool.root(), nullifier_hash(nullifier), recipient];
+        assert!(verify(&circuit, public_input));
+        assert!(pool.spend(nullifier_hash(nullifier)));
+        // the same note again
+        assert!(!pool.spend(nullifier_hash(nullifier)));
+
+        // another note's nullifier hash, and a secret that isn't the note's
+        let other = nullifier_hash(notes[0].1);
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![vec![pool.root(), other, recipient]],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+        let circuit = pool.withdraw(secret + Fp::one(), nullifier, 1);
+        let public_input = vec![pool.root(), nullifier_hash(nullifier), recipient];
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![public_input],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+    }
+
+    #[test]
+    fn test_deposit_into_occupied_leaf() {
+        let mut pool = Pool::<Fp, DEPTH>::default();
+        pool.deposit(commitment(Fp::from(1), Fp::from(2)));
+        let old_root = pool.root();
+
+        // a second note going into leaf 0 instead of leaf 1
+        let note = commitment(Fp::from(3), Fp::from(4));
+        let circuit = DepositCircuit::new(pool.path(0), 0);
+        let mut leaves = pool.leaves.clone();
+        leaves[0] = note;
+        let public_input = vec![note, old_root, merkle_root(&leaves)];
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![public_input],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+    }
+}
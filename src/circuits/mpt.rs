@@ -0,0 +1,413 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use crate::gadgets::{
+    keccak::{keccak256, KeccakChip, KeccakConfig},
+    rlp::{decode, RlpChip, RlpConfig, RlpShape},
+    tables::TableRegistry,
+};
+
+pub const KEY_NIBBLES: usize = 64;
+
+pub fn nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter().flat_map(|b| [b >> 4, b & 0xf]).collect()
+}
+
+// Hex prefix encoding: a flag nibble saying whether this is a leaf and whether
+// the path is odd, padded to a byte when it's even.
+pub fn compact(path: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = 2 * leaf as u8 + path.len() as u8 % 2;
+    let mut nibbles = vec![flag];
+    if path.len().is_multiple_of(2) {
+        nibbles.push(0);
+    }
+    nibbles.extend(path);
+    nibbles.chunks(2).map(|n| n[0] << 4 | n[1]).collect()
+}
+
+// Everything about a node that decides where its bytes are: which of its
+// children a branch has and which one the key goes through, or how many
+// nibbles of the key an extension or leaf covers and how long the leaf's
+// value is.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeShape {
+    Branch { children: [bool; 16], nibble: u8 },
+    Extension { path: usize },
+    Leaf { path: usize, value: usize },
+}
+
+impl NodeShape {
+    // The node's RLP shape. Its fields are a branch's children then its value,
+    // or the path then the child or the value. A path of one byte is below 0x80
+    // and so is a value of one, being an RLP item itself, so both encode as
+    // themselves.
+    pub fn rlp(&self) -> RlpShape {
+        let bytes = |len: usize| match len {
+            1 => RlpShape::Byte,
+            _ => RlpShape::String(len),
+        };
+        RlpShape::List(match self {
+            NodeShape::Branch { children, .. } => children
+                .iter()
+                .map(|child| RlpShape::String(if *child { 32 } else { 0 }))
+                .chain(Some(RlpShape::String(0)))
+                .collect(),
+            NodeShape::Extension { path } => vec![bytes(path / 2 + 1), RlpShape::String(32)],
+            NodeShape::Leaf { path, value } => vec![bytes(path / 2 + 1), bytes(*value)],
+        })
+    }
+}
+
+// A compact path's nibbles, in order: the flag and padding are fixed by the
+// shape and the rest are the key's, from nibble `start` on.
+enum Nibble {
+    Const(u8),
+    Key(usize),
+}
+
+fn compact_nibbles(start: usize, len: usize, leaf: bool) -> Vec<Nibble> {
+    let mut nibbles = vec![Nibble::Const(2 * leaf as u8 + len as u8 % 2)];
+    if len.is_multiple_of(2) {
+        nibbles.push(Nibble::Const(0));
+    }
+    nibbles.extend((start..start + len).map(Nibble::Key));
+    nibbles
+}
+
+// Walks `proof`, root first, down the path of `key`, checking each node hashes
+// to the one its parent points to, and returns their shapes.
+fn walk(key: &[u8; 32], proof: &[Vec<u8>]) -> Vec<NodeShape> {
+    let key = nibbles(key);
+    let mut depth = 0;
+    let mut shapes = vec![];
+    for (i, node) in proof.iter().enumerate() {
+        let (rlp, items) = decode(node);
+        let next = proof.get(i + 1).map(|node| keccak256(node).to_vec());
+        let shape = match items.len() {
+            17 => {
+                let nibble = key[depth];
+                assert_eq!(Some(&items[nibble as usize]), next.as_ref());
+                assert!(items[16].is_empty());
+                depth += 1;
+                NodeShape::Branch {
+                    children: std::array::from_fn(|i| !items[i].is_empty()),
+                    nibble,
+                }
+            }
+            2 => {
+                let nibbles = nibbles(&items[0]);
+                let (leaf, odd) = (nibbles[0] >= 2, nibbles[0] % 2 == 1);
+                let path = &nibbles[2 - odd as usize..];
+                assert_eq!(path, &key[depth..depth + path.len()]);
+                depth += path.len();
+                match leaf {
+                    true => {
+                        assert_eq!((depth, next), (KEY_NIBBLES, None));
+                        NodeShape::Leaf {
+                            path: path.len(),
+                            value: items[1].len(),
+                        }
+                    }
+                    false => {
+                        assert_eq!(Some(&items[1]), next.as_ref());
+                        NodeShape::Extension { path: path.len() }
+                    }
+                }
+            }
+            _ => panic!("not a trie node, or one with embedded nodes"),
+        };
+
+        assert_eq!(shape.rlp(), rlp, "unsupported encoding");
+        shapes.push(shape);
+    }
+    assert_eq!(depth, KEY_NIBBLES, "proofs of absence aren't supported");
+    shapes
+}
+
+// The instance column: the state root, the key and the value, all as bytes.
+pub fn mpt_instance<F: FieldExt>(root: &[u8; 32], key: &[u8; 32], value: &[u8]) -> Vec<F> {
+    root.iter()
+        .chain(key)
+        .chain(value)
+        .map(|b| F::from(*b as u64))
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct MptConfig {
+    pub instance: Column<Instance>,
+    pub keccak: KeccakConfig,
+    pub rlp: RlpConfig,
+    pub tables: TableRegistry,
+}
+
+// Proves that a Merkle-Patricia trie with the public root maps the public key
+// to the public value, given the proof's nodes as the witness. The key is the
+// trie path itself, so for a state or storage proof it is the Keccak hash of
+// the address or slot, which the verifier can compute. The nodes' shapes are
+// part of the circuit, which fixes where every byte sits so the RLP headers are
+// constants and the path can be walked at synthesis time. The circuit checks
+// each node hashes to the child its parent names, that branches are taken on
+// the key's nibbles and extension and leaf paths spell out the rest of it, and
+// that the leaf holds the value. Only proofs of inclusion with nodes that are
+// hashed rather than embedded are supported.
+#[derive(Default)]
+pub struct MptCircuit<F> {
+    pub shapes: Vec<NodeShape>,
+    pub nodes: Vec<Vec<Value<F>>>,
+}
+
+impl<F: FieldExt> MptCircuit<F> {
+    pub fn new(key: &[u8; 32], proof: &[Vec<u8>]) -> Self {
+        Self {
+            shapes: walk(key, proof),
+            nodes: proof
+                .iter()
+                .map(|node| {
+                    node.iter()
+                        .map(|b| Value::known(F::from(*b as u64)))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for MptCircuit<F> {
+    type Config = MptConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            shapes: self.shapes.clone(),
+            nodes: self
+                .nodes
+                .iter()
+                .map(|node| vec![Value::unknown(); node.len()])
+                .collect(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 20].map(|_| meta.advice_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(instance);
+
+        let mut tables = TableRegistry::default();
+        let bytes = tables.bytes(meta);
+        let low = tables.range(meta, 0, 0x7f);
+
+        MptConfig {
+            instance,
+            keccak: KeccakChip::configure(meta, advice, constants),
+            rlp: RlpChip::configure(meta, [advice[0], advice[1]], constants, bytes, low),
+            tables,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.tables.load(&mut layouter)?;
+        let chip = KeccakChip::construct(config.keccak.clone());
+        let rlp = RlpChip::construct(config.rlp.clone());
+        let advice = config.keccak.advice;
+
+        let (key, zero, one) = layouter.assign_region(
+            || "key",
+            |mut region| {
+                let zero = region.assign_advice_from_constant(|| "0", advice[10], 0, F::zero())?;
+                let one = region.assign_advice_from_constant(|| "1", advice[11], 0, F::one())?;
+                let mut key = vec![];
+                for offset in 0..32 {
+                    let byte = region.assign_advice_from_instance(
+                        || "key",
+                        config.instance,
+                        32 + offset,
+                        advice[9],
+                        offset,
+                    )?;
+                    key.push(chip.decompose(&mut region, offset, &byte)?);
+                }
+                Ok((key, zero, one))
+            },
+        )?;
+        // a nibble's bits, least significant first, the high nibble of each
+        // byte coming first in the path
+        let key_nibble = |i: usize| match i % 2 {
+            0 => &key[i / 2][4..],
+            _ => &key[i / 2][..4],
+        };
+        let const_nibble = |c: u8| -> Vec<&AssignedCell<F, F>> {
+            (0..4)
+                .map(|i| if (c >> i) & 1 == 1 { &one } else { &zero })
+                .collect()
+        };
+
+        let mut depths = vec![];
+        let mut depth = 0;
+        for shape in self.shapes.iter() {
+            depths.push(depth);
+            depth += match shape {
+                NodeShape::Branch { .. } => 1,
+                NodeShape::Extension { path } | NodeShape::Leaf { path, .. } => *path,
+            };
+        }
+
+        // from the leaf up, so each node can take its child's hash
+        let mut child: Option<[AssignedCell<F, F>; 32]> = None;
+        for (i, shape) in self.shapes.iter().enumerate().rev() {
+            let node = rlp.decode(
+                layouter.namespace(|| format!("node {}", i)),
+                &shape.rlp(),
+                &self.nodes[i],
+            )?;
+            let fields = &node.fields;
+            layouter.assign_region(
+                || format!("walk node {}", i),
+                |mut region| {
+                    if let Some(child) = &child {
+                        let field = match shape {
+                            NodeShape::Branch { nibble, .. } => &fields[*nibble as usize],
+                            _ => &fields[1],
+                        };
+                        for (offset, (byte, expected)) in field.iter().zip(child).enumerate() {
+                            let byte =
+                                byte.copy_advice(|| "child", &mut region, advice[9], offset)?;
+                            region.constrain_equal(byte.cell(), expected.cell())?;
+                        }
+                    }
+
+                    let (path, leaf) = match shape {
+                        NodeShape::Branch { nibble, .. } => {
+                            for (bit, c) in key_nibble(depths[i]).iter().zip(const_nibble(*nibble))
+                            {
+                                region.constrain_equal(bit.cell(), c.cell())?;
+                            }
+                            return Ok(());
+                        }
+                        NodeShape::Extension { path } => (*path, false),
+                        NodeShape::Leaf { path, .. } => (*path, true),
+                    };
+                    let mut nibbles = vec![];
+                    for (offset, byte) in fields[0].iter().enumerate() {
+                        let bits = chip.decompose(&mut region, offset, byte)?;
+                        nibbles.push(bits[4..].to_vec());
+                        nibbles.push(bits[..4].to_vec());
+                    }
+                    for (bits, nibble) in nibbles.iter().zip(compact_nibbles(depths[i], path, leaf))
+                    {
+                        let expected: Vec<_> = match nibble {
+                            Nibble::Const(c) => const_nibble(c),
+                            Nibble::Key(j) => key_nibble(j).iter().collect(),
+                        };
+                        for (bit, expected) in bits.iter().zip(expected) {
+                            region.constrain_equal(bit.cell(), expected.cell())?;
+                        }
+                    }
+                    Ok(())
+                },
+            )?;
+
+            if let NodeShape::Leaf { .. } = shape {
+                for (j, byte) in fields[1].iter().enumerate() {
+                    layouter.constrain_instance(byte.cell(), config.instance, 64 + j)?;
+                }
+            }
+            child = Some(chip.hash(
+                layouter.namespace(|| format!("hash node {}", i)),
+                &node.encoded,
+            )?);
+        }
+
+        for (i, byte) in child.unwrap().iter().enumerate() {
+            layouter.constrain_instance(byte.cell(), config.instance, i)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use crate::gadgets::rlp::{rlp_list, rlp_string};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 16;
+
+    fn leaf(key: &[u8; 32], depth: usize, value: &[u8]) -> Vec<u8> {
+        let path = compact(&nibbles(key)[depth..], true);
+        rlp_list(&[rlp_string(&path), rlp_string(value)])
+    }
+
+    // An extension over the shared first nibble, a branch on the second, and
+    // a leaf under each side. Returns the root and the proof of the first key.
+    fn trie(keys: [&[u8; 32]; 2], values: [&[u8]; 2]) -> ([u8; 32], Vec<Vec<u8>>) {
+        let leaves = [0, 1].map(|i| leaf(keys[i], 2, values[i]));
+        let mut children = vec![rlp_string(&[]); 17];
+        for (key, leaf) in keys.iter().zip(&leaves) {
+            children[nibbles(*key)[1] as usize] = rlp_string(&keccak256(leaf));
+        }
+        let branch = rlp_list(&children);
+        let path = compact(&nibbles(keys[0])[..1], false);
+        let extension = rlp_list(&[rlp_string(&path), rlp_string(&keccak256(&branch))]);
+        (
+            keccak256(&extension),
+            vec![extension, branch, leaves[0].clone()],
+        )
+    }
+
+    #[test]
+    fn test_encoding() {
+        assert_eq!(compact(&[1, 2, 3], false), [0x11, 0x23]);
+        assert_eq!(
+            compact(&[0, 15, 1, 12, 11, 8], true),
+            [0x20, 0x0f, 0x1c, 0xb8]
+        );
+
+        let node = NodeShape::Leaf { path: 1, value: 40 };
+        let (key, value) = ([0x4a; 32], [0xab; 40]);
+        let encoded = rlp_list(&[
+            rlp_string(&compact(&nibbles(&key)[63..], true)),
+            rlp_string(&value),
+        ]);
+        assert_eq!(decode(&encoded).0, node.rlp());
+    }
+
+    #[test]
+    fn test_mpt() {
+        let _guard = crate::testing::heavy_test();
+        let key: [u8; 32] = std::array::from_fn(|i| 0x12 + i as u8);
+        let mut other = key;
+        other[0] = 0x15;
+        let value = [0xab; 40];
+        let (root, proof) = trie(
+            [&key, &other],
+            [&value, b"another value, with a different length"],
+        );
+
+        let circuit = MptCircuit::<Fp>::new(&key, &proof);
+        let prover = MockProver::run(K, &circuit, vec![mpt_instance(&root, &key, &value)]).unwrap();
+        prover.assert_satisfied();
+
+        let mut wrong = value;
+        wrong[39] ^= 1;
+        let instance = mpt_instance(&root, &key, &wrong);
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![instance],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+
+        // a key that only differs in the part the leaf's path covers
+        let mut wrong = key;
+        wrong[31] ^= 0x10;
+        let instance = mpt_instance(&root, &wrong, &value);
+        assert_unsatisfied_with(K, &circuit, vec![instance], &[Failure::Copy("A5")]);
+    }
+}
@@ -0,0 +1,147 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+use std::marker::PhantomData;
+
+use crate::gadgets::recurrence::{linear_recurrence, RecurrenceChip, RecurrenceConfig};
+
+#[derive(Debug, Clone)]
+pub struct PadovanConfig {
+    pub recurrence: RecurrenceConfig<3>,
+    pub instance: Column<Instance>,
+}
+
+// Proves P(n) for P(n) = P(n - 2) + P(n - 3) from public P(0), P(1) and P(2),
+// on the recurrence chip with c = [0, 1, 1]: the gate reaches back three rows
+// and skips the one just above. The instance column is
+// `[P(0), P(1), P(2), P(n)]`, the Padovan numbers themselves starting
+// `[1, 1, 1]` and the Perrin numbers `[3, 0, 2]`.
+#[derive(Default)]
+pub struct PadovanCircuit<F> {
+    pub n: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F> PadovanCircuit<F> {
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            _marker: PhantomData,
+        }
+    }
+}
+
+fn coefficients<F: FieldExt>() -> [F; 3] {
+    [F::zero(), F::one(), F::one()]
+}
+
+// P(0) to P(n) on the host.
+pub fn padovan<F: FieldExt>(start: [F; 3], n: usize) -> Vec<F> {
+    linear_recurrence(&coefficients(), &start, n)
+}
+
+pub fn padovan_instance<F: FieldExt>(start: [u64; 3], n: usize) -> Vec<F> {
+    let start = start.map(F::from);
+    let mut instance = start.to_vec();
+    instance.push(padovan(start, n)[n]);
+    instance
+}
+
+impl<F: FieldExt> Circuit<F> for PadovanCircuit<F> {
+    type Config = PadovanConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.n)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let x = meta.advice_column();
+        let coefficients = [(); 3].map(|_| meta.fixed_column());
+        let instance = meta.instance_column();
+
+        meta.enable_equality(instance);
+
+        PadovanConfig {
+            recurrence: RecurrenceChip::configure(meta, x, coefficients),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let x = config.recurrence.x;
+        let start = layouter.assign_region(
+            || "start",
+            |mut region| {
+                let cells = (0..3)
+                    .map(|row| {
+                        region.assign_advice_from_instance(|| "start", config.instance, row, x, row)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(cells.try_into().unwrap())
+            },
+        )?;
+
+        let chip = RecurrenceChip::construct(config.recurrence);
+        let out = chip.assign(
+            layouter.namespace(|| "padovan"),
+            coefficients(),
+            &start,
+            self.n,
+        )?;
+        layouter.constrain_instance(out.cell(), config.instance, 3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        explain::{assert_unsatisfied_with, Failure},
+        testing::assert_rejects_near_misses,
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 5;
+
+    #[test]
+    fn test_padovan() {
+        let expected = [1, 1, 1, 2, 2, 3, 4, 5, 7, 9, 12, 16, 21].map(Fp::from);
+        assert_eq!(padovan([1, 1, 1].map(Fp::from), 12), expected);
+        let perrin = [3, 0, 2, 3, 2, 5, 5, 7, 10, 12, 17].map(Fp::from);
+        assert_eq!(padovan([3, 0, 2].map(Fp::from), 10), perrin);
+
+        for start in [[1, 1, 1], [3, 0, 2]] {
+            for n in [0, 2, 3, 12, 20] {
+                let circuit = PadovanCircuit::<Fp>::new(n);
+                let instance = padovan_instance(start, n);
+                let prover = MockProver::run(K, &circuit, vec![instance]).unwrap();
+                prover.assert_satisfied();
+            }
+        }
+
+        // the Padovan number with the Perrin start, and the Perrin number
+        // before
+        let circuit = PadovanCircuit::<Fp>::new(12);
+        for out in [21, 22] {
+            assert_unsatisfied_with(
+                K,
+                &circuit,
+                vec![[3, 0, 2, out].map(Fp::from).to_vec()],
+                &[Failure::Copy("A0"), Failure::Copy("I0")],
+            );
+        }
+    }
+
+    #[test]
+    fn test_near_misses() {
+        assert_rejects_near_misses(
+            K,
+            &PadovanCircuit::<Fp>::new(15),
+            vec![padovan_instance([1, 1, 1], 15)],
+            &[],
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use halo2_proofs::{circuit::*, pasta::pallas, plonk::*};
+
+use crate::gadgets::pedersen::{PedersenChip, PedersenConfig};
+
+#[derive(Debug, Clone)]
+pub struct PedersenOpeningConfig {
+    pub instance: Column<Instance>,
+    pub pedersen: PedersenConfig,
+}
+
+// Opens a Pedersen commitment to a public value without revealing the blinding
+// factor, so the commitment stays hiding for anything else it was used in. The
+// instance column is `[x, y, value]` with (x, y) the commitment point.
+#[derive(Default)]
+pub struct PedersenOpeningCircuit {
+    pub value: Value<pallas::Base>,
+    pub blinding: Value<pallas::Scalar>,
+}
+
+impl PedersenOpeningCircuit {
+    pub fn new(value: u64, blinding: pallas::Scalar) -> Self {
+        Self {
+            value: Value::known(pallas::Base::from(value)),
+            blinding: Value::known(blinding),
+        }
+    }
+}
+
+impl Circuit<pallas::Base> for PedersenOpeningCircuit {
+    type Config = PedersenOpeningConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        let advice = [(); 6].map(|_| meta.advice_column());
+        let fixed = [(); 5].map(|_| meta.fixed_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(instance);
+
+        PedersenOpeningConfig {
+            instance,
+            pedersen: PedersenChip::configure(meta, advice, fixed, constants),
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+    ) -> Result<(), Error> {
+        let value = layouter.assign_region(
+            || "value",
+            |mut region| {
+                region.assign_advice_from_instance(
+                    || "value",
+                    config.instance,
+                    2,
+                    config.pedersen.advice[0],
+                    0,
+                )
+            },
+        )?;
+
+        let pedersen = PedersenChip::construct(config.pedersen);
+        let commitment = pedersen.commit(layouter.namespace(|| "commit"), &value, self.blinding)?;
+        layouter.constrain_instance(commitment.x.cell(), config.instance, 0)?;
+        layouter.constrain_instance(commitment.y.cell(), config.instance, 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use crate::gadgets::pedersen::commit;
+    use halo2_proofs::{arithmetic::CurveAffine, dev::MockProver};
+
+    fn instance(commitment: pallas::Affine, value: u64) -> Vec<pallas::Base> {
+        let coordinates = commitment.coordinates().unwrap();
+        vec![
+            *coordinates.x(),
+            *coordinates.y(),
+            pallas::Base::from(value),
+        ]
+    }
+
+    #[test]
+    fn test_pedersen_opening() {
+        let k = 9;
+
+        let blinding = pallas::Scalar::from(0x5a17) * pallas::Scalar::from(u64::MAX);
+        let commitment = commit(250_000, blinding);
+        let circuit = PedersenOpeningCircuit::new(250_000, blinding);
+
+        let prover = MockProver::run(k, &circuit, vec![instance(commitment, 250_000)]).unwrap();
+        prover.assert_satisfied();
+
+        // claiming the commitment opens to some other value
+        assert_unsatisfied_with(
+            k,
+            &circuit,
+            vec![instance(commitment, 250_001)],
+            &[
+                Failure::Copy("A1"),
+                Failure::Copy("A2"),
+                Failure::Copy("I0"),
+            ],
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+use std::marker::PhantomData;
+
+use crate::gadgets::recurrence::{linear_recurrence, RecurrenceChip, RecurrenceConfig};
+
+#[derive(Debug, Clone)]
+pub struct PellConfig {
+    pub recurrence: RecurrenceConfig<2>,
+    pub instance: Column<Instance>,
+}
+
+// Proves P(n) for the Pell numbers, P(n) = 2 * P(n - 1) + P(n - 2) from P(0) = 0
+// and P(1) = 1, on the recurrence chip with c = [2, 1]. The start is fixed, so
+// the instance column is only `[P(n)]`.
+#[derive(Default)]
+pub struct PellCircuit<F> {
+    pub n: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F> PellCircuit<F> {
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            _marker: PhantomData,
+        }
+    }
+}
+
+// c_1 and c_2, in the order the recurrence chip takes them.
+fn coefficients<F: FieldExt>() -> [F; 2] {
+    [F::from(2), F::one()]
+}
+
+// P(0) to P(n) on the host.
+pub fn pell<F: FieldExt>(n: usize) -> Vec<F> {
+    linear_recurrence(&coefficients(), &[F::zero(), F::one()], n)
+}
+
+pub fn pell_instance<F: FieldExt>(n: usize) -> Vec<F> {
+    vec![pell(n)[n]]
+}
+
+impl<F: FieldExt> Circuit<F> for PellCircuit<F> {
+    type Config = PellConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.n)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let x = meta.advice_column();
+        let coefficients = [(); 2].map(|_| meta.fixed_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(instance);
+        meta.enable_constant(constants);
+
+        PellConfig {
+            recurrence: RecurrenceChip::configure(meta, x, coefficients),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let x = config.recurrence.x;
+        let start = layouter.assign_region(
+            || "start",
+            |mut region| {
+                Ok([
+                    region.assign_advice_from_constant(|| "P(0)", x, 0, F::zero())?,
+                    region.assign_advice_from_constant(|| "P(1)", x, 1, F::one())?,
+                ])
+            },
+        )?;
+
+        let chip = RecurrenceChip::construct(config.recurrence);
+        let out = chip.assign(
+            layouter.namespace(|| "pell"),
+            coefficients(),
+            &start,
+            self.n,
+        )?;
+        layouter.constrain_instance(out.cell(), config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        explain::{assert_unsatisfied_with, Failure},
+        testing::assert_rejects_near_misses,
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 5;
+
+    #[test]
+    fn test_pell() {
+        let expected = [0, 1, 2, 5, 12, 29, 70, 169, 408, 985, 2378].map(Fp::from);
+        assert_eq!(pell::<Fp>(10), expected);
+
+        for n in [0, 1, 2, 10, 20] {
+            let circuit = PellCircuit::<Fp>::new(n);
+            let prover = MockProver::run(K, &circuit, vec![pell_instance(n)]).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // the Fibonacci number and the Pell number before
+        let circuit = PellCircuit::<Fp>::new(10);
+        for out in [55, 985] {
+            assert_unsatisfied_with(
+                K,
+                &circuit,
+                vec![vec![Fp::from(out)]],
+                &[Failure::Copy("A0"), Failure::Copy("I0")],
+            );
+        }
+    }
+
+    #[test]
+    fn test_near_misses() {
+        assert_rejects_near_misses(K, &PellCircuit::<Fp>::new(12), vec![pell_instance(12)], &[]);
+    }
+}
@@ -0,0 +1,196 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use crate::gadgets::{
+    poseidon::{self, PoseidonChip},
+    range_check::RangeCheckChip,
+    sort::{SortChip, SortConfig},
+    tables::TableRegistry,
+};
+
+pub use super::kth_smallest::commit;
+
+#[derive(Debug, Clone)]
+pub struct PercentileConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 6],
+    pub instance: Column<Instance>,
+    pub sort: SortConfig<F>,
+    pub tables: TableRegistry,
+}
+
+// Proves the public value is the p-th percentile of private values committed
+// to as `hash(values || salt)`, using the nearest-rank definition. The median
+// is the 50th percentile, which for an even count is the lower middle value.
+// The instance column is `[commitment, value]`; p and the number of values
+// are part of the circuit.
+#[derive(Default)]
+pub struct PercentileCircuit<F> {
+    pub values: Vec<Value<F>>,
+    pub salt: Value<F>,
+    pub percentile: u64,
+}
+
+impl<F: FieldExt> PercentileCircuit<F> {
+    pub fn new(values: &[u64], salt: u64, percentile: u64) -> Self {
+        assert!(percentile <= 100);
+        Self {
+            values: values.iter().map(|v| Value::known(F::from(*v))).collect(),
+            salt: Value::known(F::from(salt)),
+            percentile,
+        }
+    }
+
+    pub fn median(values: &[u64], salt: u64) -> Self {
+        Self::new(values, salt, 50)
+    }
+}
+
+// Index into the sorted values of the nearest-rank p-th percentile.
+pub fn rank(len: usize, percentile: u64) -> usize {
+    let rank = (percentile as usize * len).div_ceil(100);
+    rank.max(1) - 1
+}
+
+pub fn percentile(values: &[u64], percentile: u64) -> u64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    sorted[rank(values.len(), percentile)]
+}
+
+impl<F: FieldExt> Circuit<F> for PercentileCircuit<F> {
+    type Config = PercentileConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            values: vec![Value::unknown(); self.values.len()],
+            salt: Value::unknown(),
+            percentile: self.percentile,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 6].map(|_| meta.advice_column());
+        let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(instance);
+
+        let mut tables = TableRegistry::default();
+        let bytes = tables.bytes(meta);
+        let range = RangeCheckChip::configure(meta, [advice[4], advice[5]], bytes);
+        let poseidon =
+            PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+        let sort = SortChip::configure(meta, advice, range, poseidon);
+
+        PercentileConfig {
+            advice,
+            instance,
+            sort,
+            tables,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.tables.load(&mut layouter)?;
+
+        let (values, salt) = layouter.assign_region(
+            || "inputs",
+            |mut region| {
+                let values = self
+                    .values
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, v)| {
+                        region.assign_advice(|| "value", config.advice[0], offset, || *v)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let salt = region.assign_advice(|| "salt", config.advice[1], 0, || self.salt)?;
+                Ok((values, salt))
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.sort.multiset.poseidon.clone());
+        let mut message = values.clone();
+        message.push(salt);
+        let commitment = poseidon.hash(layouter.namespace(|| "commitment"), &message)?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 0)?;
+
+        let sort = SortChip::construct(config.sort);
+        let sorted = sort.sort(layouter.namespace(|| "sort"), &values)?;
+
+        let index = rank(sorted.len(), self.percentile);
+        layouter.constrain_instance(sorted[index].cell(), config.instance, 1)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 11;
+
+    #[test]
+    fn test_rank() {
+        assert_eq!(rank(5, 0), 0);
+        assert_eq!(rank(5, 50), 2);
+        assert_eq!(rank(4, 50), 1);
+        assert_eq!(rank(5, 100), 4);
+        assert_eq!(rank(10, 90), 8);
+        assert_eq!(rank(10, 91), 9);
+    }
+
+    #[test]
+    fn test_percentile() {
+        let _guard = crate::testing::heavy_test();
+        let values = [52_000, 48_500, 61_000, 39_000, 75_250, 58_000];
+        let salt = 0x5a1a;
+        let commitment = commit::<Fp>(&values, salt);
+
+        for p in [0, 25, 50, 90, 100] {
+            let circuit = PercentileCircuit::<Fp>::new(&values, salt, p);
+            let expected = percentile(&values, p);
+
+            let prover =
+                MockProver::run(K, &circuit, vec![vec![commitment, Fp::from(expected)]]).unwrap();
+            prover.assert_satisfied();
+
+            assert_unsatisfied_with(
+                K,
+                &circuit,
+                vec![vec![commitment, Fp::from(expected + 1)]],
+                &[Failure::Copy("A1"), Failure::Copy("I0")],
+            );
+        }
+    }
+
+    #[test]
+    fn test_median() {
+        let _guard = crate::testing::heavy_test();
+        let values = [7, 3, 9, 1, 5];
+        let salt = 1;
+        let circuit = PercentileCircuit::<Fp>::median(&values, salt);
+
+        let public_input = vec![commit(&values, salt), Fp::from(5)];
+        let prover = MockProver::run(K, &circuit, vec![public_input]).unwrap();
+        prover.assert_satisfied();
+
+        // same median, but these values don't open the commitment
+        let other = PercentileCircuit::<Fp>::median(&[8, 3, 9, 1, 5], salt);
+        let public_input = vec![commit(&values, salt), Fp::from(5)];
+        assert_unsatisfied_with(
+            K,
+            &other,
+            vec![public_input],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use crate::gadgets::poseidon::{PoseidonChip, PoseidonConfig, PoseidonParams, WIDTH};
+
+#[derive(Debug, Clone)]
+pub struct PermutationChainConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; WIDTH],
+    pub instance: Column<Instance>,
+    pub poseidon: PoseidonConfig<F>,
+}
+
+// Proves N Poseidon permutations applied one after another to a private
+// state, exposing the state after every EVERY-th of them: the instance column
+// is the WIDTH words of each checkpoint in turn, the last being the final
+// state, so N has to be a multiple of EVERY.
+//
+// A checkpoint pins down where the chain is at that point, so a long chain
+// can be proved as checkpoint-to-checkpoint segments that each start from the
+// previous one's public state. Each permutation is its own region, with its
+// input copied from the last one's output.
+pub struct PermutationChainCircuit<F, const N: usize, const EVERY: usize> {
+    pub state: Value<[F; WIDTH]>,
+}
+
+impl<F: FieldExt, const N: usize, const EVERY: usize> PermutationChainCircuit<F, N, EVERY> {
+    pub fn new(state: [F; WIDTH]) -> Self {
+        Self {
+            state: Value::known(state),
+        }
+    }
+}
+
+impl<F: FieldExt, const N: usize, const EVERY: usize> Default
+    for PermutationChainCircuit<F, N, EVERY>
+{
+    fn default() -> Self {
+        Self {
+            state: Value::unknown(),
+        }
+    }
+}
+
+// The state after each of `n` permutations of `state`.
+pub fn permutation_chain<F: FieldExt>(state: [F; WIDTH], n: usize) -> Vec<[F; WIDTH]> {
+    let params = PoseidonParams::new();
+    let mut states = vec![];
+    let mut state = state;
+    for _ in 0..n {
+        params.permute(&mut state);
+        states.push(state);
+    }
+    states
+}
+
+// The checkpoints the circuit exposes, flattened into its instance column.
+pub fn checkpoints<F: FieldExt>(state: [F; WIDTH], n: usize, every: usize) -> Vec<F> {
+    permutation_chain(state, n)
+        .into_iter()
+        .skip(every - 1)
+        .step_by(every)
+        .flatten()
+        .collect()
+}
+
+impl<F: FieldExt, const N: usize, const EVERY: usize> Circuit<F>
+    for PermutationChainCircuit<F, N, EVERY>
+{
+    type Config = PermutationChainConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        assert!(
+            EVERY > 0 && N.is_multiple_of(EVERY),
+            "the last permutation has to be a checkpoint"
+        );
+        let advice = [(); WIDTH].map(|_| meta.advice_column());
+        let rc = [(); WIDTH].map(|_| meta.fixed_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(instance);
+
+        PermutationChainConfig {
+            advice,
+            instance,
+            poseidon: PoseidonChip::configure(meta, advice, rc, constants),
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let mut state = layouter.assign_region(
+            || "initial state",
+            |mut region| {
+                let words = self.state.transpose_array();
+                let mut cells = vec![];
+                for (column, word) in config.advice.iter().zip(words) {
+                    cells.push(region.assign_advice(|| "word", *column, 0, || word)?);
+                }
+                Ok(cells.try_into().unwrap())
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon);
+        for step in 0..N {
+            state = poseidon.permute(layouter.namespace(|| format!("step {}", step)), &state)?;
+
+            if (step + 1).is_multiple_of(EVERY) {
+                let checkpoint = (step + 1) / EVERY - 1;
+                for (i, word) in state.iter().enumerate() {
+                    layouter.constrain_instance(
+                        word.cell(),
+                        config.instance,
+                        WIDTH * checkpoint + i,
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        explain::{assert_unsatisfied_with, Failure},
+        testing::assert_rejects_near_misses,
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 9;
+
+    fn start() -> [Fp; WIDTH] {
+        [1, 2, 3].map(Fp::from)
+    }
+
+    #[test]
+    fn test_permutation_chain() {
+        let states = permutation_chain(start(), 6);
+        assert_eq!(states.len(), 6);
+        assert_eq!(checkpoints(start(), 6, 2).len(), 3 * WIDTH);
+        assert_eq!(&checkpoints(start(), 6, 2)[..WIDTH], &states[1]);
+        assert_eq!(checkpoints(start(), 6, 6), states[5]);
+
+        let circuit = PermutationChainCircuit::<Fp, 6, 2>::new(start());
+        let prover = MockProver::run(K, &circuit, vec![checkpoints(start(), 6, 2)]).unwrap();
+        prover.assert_satisfied();
+        let circuit = PermutationChainCircuit::<Fp, 6, 3>::new(start());
+        let prover = MockProver::run(K, &circuit, vec![checkpoints(start(), 6, 3)]).unwrap();
+        prover.assert_satisfied();
+
+        // every checkpoint word is tied to the chain
+        let circuit = PermutationChainCircuit::<Fp, 6, 2>::new(start());
+        for i in 0..3 * WIDTH {
+            let mut instance = checkpoints(start(), 6, 2);
+            instance[i] += Fp::one();
+            let prover = MockProver::run(K, &circuit, vec![instance]).unwrap();
+            assert!(prover.verify().is_err(), "word {}", i);
+        }
+
+        // the checkpoints of another start
+        let other = [1, 2, 4].map(Fp::from);
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![checkpoints(other, 6, 2)],
+            &[
+                Failure::Copy("A0"),
+                Failure::Copy("A1"),
+                Failure::Copy("A2"),
+                Failure::Copy("I0"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_near_misses() {
+        let _guard = crate::testing::heavy_test();
+        assert_rejects_near_misses(
+            K,
+            &PermutationChainCircuit::<Fp, 4, 2>::new(start()),
+            vec![checkpoints(start(), 4, 2)],
+            &[],
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+use std::marker::PhantomData;
+
+use crate::gadgets::{hash::HashInstructions, tables::TableRegistry};
+
+#[derive(Debug, Clone)]
+pub struct PreimageConfig<C> {
+    pub hash: C,
+    pub instance: Column<Instance>,
+    pub tables: TableRegistry,
+}
+
+// Proves knowledge of an N element message hashing to the public digest, with
+// any chip that implements `HashInstructions`. The instance column is the
+// digest, however many elements the hash gives: 32 bytes for Keccak,
+// eight words for SHA-256 and one field element for Poseidon.
+//
+// Nothing here knows which hash it is: the chip picks its own columns and
+// tables, and only the message and digest go through the circuit.
+pub struct PreimageCircuit<F, H, const N: usize> {
+    pub message: [Value<F>; N],
+    _marker: PhantomData<H>,
+}
+
+impl<F: FieldExt, H, const N: usize> PreimageCircuit<F, H, N> {
+    pub fn new(message: [F; N]) -> Self {
+        Self {
+            message: message.map(Value::known),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F, H, const N: usize> Default for PreimageCircuit<F, H, N> {
+    fn default() -> Self {
+        Self {
+            message: [(); N].map(|_| Value::unknown()),
+            _marker: PhantomData,
+        }
+    }
+}
+
+pub fn preimage_instance<F: FieldExt, H: HashInstructions<F>>(message: &[F]) -> Vec<F> {
+    H::digest(message)
+}
+
+impl<F: FieldExt, H: HashInstructions<F>, const N: usize> Circuit<F> for PreimageCircuit<F, H, N> {
+    type Config = PreimageConfig<H::Config>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        let mut tables = TableRegistry::default();
+        PreimageConfig {
+            hash: H::configure(meta, &mut tables),
+            instance,
+            tables,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.tables.load(&mut layouter)?;
+        let chip = H::construct(config.hash.clone());
+
+        let message = layouter.assign_region(
+            || "message",
+            |mut region| {
+                let column = H::message_column(&config.hash);
+                self.message
+                    .iter()
+                    .enumerate()
+                    .map(|(row, value)| region.assign_advice(|| "message", column, row, || *value))
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+
+        let digest = chip.hash(layouter.namespace(|| "hash"), &message)?;
+        for (row, cell) in digest.iter().enumerate() {
+            layouter.constrain_instance(cell.cell(), config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        explain::{assert_unsatisfied_with, Failure},
+        gadgets::{keccak::KeccakChip, poseidon::PoseidonChip, sha256::Sha256Chip},
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    // The message against its digest, and against one with the first element
+    // off by one.
+    fn check<H: HashInstructions<Fp>, const N: usize>(k: u32, message: [u64; N]) {
+        let message = message.map(Fp::from);
+        let circuit = PreimageCircuit::<Fp, H, N>::new(message);
+        let mut instance = preimage_instance::<Fp, H>(&message);
+        let prover = MockProver::run(k, &circuit, vec![instance.clone()]).unwrap();
+        prover.assert_satisfied();
+
+        instance[0] += Fp::one();
+        let prover = MockProver::run(k, &circuit, vec![instance]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_poseidon() {
+        check::<PoseidonChip<Fp>, 3>(8, [1, 2, 3]);
+        check::<PoseidonChip<Fp>, 1>(8, [0]);
+
+        // the digest of another message
+        let circuit = PreimageCircuit::<Fp, PoseidonChip<Fp>, 2>::new([Fp::one(), Fp::zero()]);
+        let instance = preimage_instance::<Fp, PoseidonChip<Fp>>(&[Fp::zero(), Fp::one()]);
+        assert_unsatisfied_with(
+            8,
+            &circuit,
+            vec![instance],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+    }
+
+    #[test]
+    fn test_keccak() {
+        let _guard = crate::testing::heavy_test();
+        check::<KeccakChip<Fp>, 3>(14, b"abc".map(u64::from));
+
+        // not a byte, even with the digest of its low byte
+        let circuit = PreimageCircuit::<Fp, KeccakChip<Fp>, 1>::new([Fp::from(0x161)]);
+        let instance = preimage_instance::<Fp, KeccakChip<Fp>>(&[Fp::from(0x61)]);
+        let prover = MockProver::run(14, &circuit, vec![instance]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_sha256() {
+        let _guard = crate::testing::heavy_test();
+        check::<Sha256Chip<Fp>, 2>(16, [0x6162_6364, 0x6566_6768]);
+    }
+}
@@ -0,0 +1,434 @@
+use halo2_proofs::{circuit::*, pasta::pallas, plonk::*, poly::Rotation};
+
+use crate::gadgets::{
+    merkle::{merkle_path, merkle_root, MerkleChip, MerkleConfig},
+    pedersen::{EccPoint, PedersenChip},
+    poseidon::{self, PoseidonChip},
+    range_check::{RangeCheckChip, RangeCheckConfig},
+    schnorr::{coordinates, SchnorrChip, SchnorrConfig, Signature},
+    tables::TableRegistry,
+};
+
+// Balances and amounts are u64s.
+pub const BALANCE_BYTES: usize = 8;
+
+// Balances and nonces are kept as field elements, so an overdraft still has a
+// witness and it's the circuit that turns it down.
+#[derive(Debug, Clone, Copy)]
+pub struct Account {
+    pub public_key: pallas::Affine,
+    pub balance: pallas::Base,
+    pub nonce: pallas::Base,
+}
+
+impl Account {
+    pub fn new(public_key: pallas::Affine, balance: u64) -> Self {
+        Self {
+            public_key,
+            balance: pallas::Base::from(balance),
+            nonce: pallas::Base::zero(),
+        }
+    }
+
+    pub fn leaf(&self) -> pallas::Base {
+        let (x, y) = coordinates(self.public_key);
+        poseidon::hash(&[x, y, self.balance, self.nonce])
+    }
+}
+
+// What the sender signs: the receiver's key, the amount and the sender's
+// nonce, which keeps a signed transfer from being replayed.
+pub fn transfer_message(to: pallas::Affine, amount: u64, nonce: pallas::Base) -> pallas::Base {
+    let (x, y) = coordinates(to);
+    poseidon::hash(&[x, y, pallas::Base::from(amount), nonce])
+}
+
+// Both accounts as they were right before their own update, with their paths
+// in the tree at that point.
+#[derive(Debug, Clone, Copy)]
+pub struct TransferWitness<const DEPTH: usize> {
+    pub sender: Account,
+    pub sender_index: u64,
+    pub sender_path: [pallas::Base; DEPTH],
+    pub receiver: Account,
+    pub receiver_index: u64,
+    pub receiver_path: [pallas::Base; DEPTH],
+    pub amount: u64,
+    pub signature: Signature,
+}
+
+// The accounts behind the rollup's state root, one per leaf.
+#[derive(Debug, Clone)]
+pub struct Rollup<const DEPTH: usize> {
+    pub accounts: Vec<Account>,
+}
+
+impl<const DEPTH: usize> Rollup<DEPTH> {
+    pub fn new(accounts: Vec<Account>) -> Self {
+        assert_eq!(accounts.len(), 1 << DEPTH);
+        Self { accounts }
+    }
+
+    fn leaves(&self) -> Vec<pallas::Base> {
+        self.accounts.iter().map(Account::leaf).collect()
+    }
+
+    fn path(&self, index: usize) -> [pallas::Base; DEPTH] {
+        merkle_path(&self.leaves(), index).try_into().unwrap()
+    }
+
+    pub fn root(&self) -> pallas::Base {
+        merkle_root(&self.leaves())
+    }
+
+    // The message `from` has to sign to send `amount` to `to` next.
+    pub fn message(&self, from: usize, to: usize, amount: u64) -> pallas::Base {
+        transfer_message(
+            self.accounts[to].public_key,
+            amount,
+            self.accounts[from].nonce,
+        )
+    }
+
+    // Applies a transfer without checking it and returns the circuit's
+    // witness for it.
+    pub fn apply(
+        &mut self,
+        from: usize,
+        to: usize,
+        amount: u64,
+        signature: Signature,
+    ) -> TransferWitness<DEPTH> {
+        let (sender, sender_path) = (self.accounts[from], self.path(from));
+        self.accounts[from].balance -= pallas::Base::from(amount);
+        self.accounts[from].nonce += pallas::Base::one();
+
+        let (receiver, receiver_path) = (self.accounts[to], self.path(to));
+        self.accounts[to].balance += pallas::Base::from(amount);
+
+        TransferWitness {
+            sender,
+            sender_index: from as u64,
+            sender_path,
+            receiver,
+            receiver_index: to as u64,
+            receiver_path,
+            amount,
+            signature,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RollupConfig {
+    pub advice: [Column<Advice>; 11],
+    pub instance: Column<Instance>,
+    pub q_transfer: Selector,
+    pub schnorr: SchnorrConfig,
+    pub merkle: MerkleConfig<pallas::Base>,
+    pub range: RangeCheckConfig,
+    pub tables: TableRegistry,
+}
+
+// Applies a batch of `N` signed transfers to a Poseidon Merkle tree of
+// accounts, with leaves H(key.x, key.y, balance, nonce). The instance column is
+// `[old root, new root]`; the transfers themselves stay private.
+//
+// Each transfer checks the sender's Schnorr signature on the receiver's key,
+// the amount and the sender's nonce, bumps that nonce, and moves the amount
+// between the balances, range checking the amount and both new balances to 64
+// bits so nothing can go negative or wrap. Balances already in the tree are
+// taken to be 64 bits, which holds as long as the genesis tree's are. The
+// sender's leaf is updated first and the receiver's in the tree that gives,
+// so paying yourself works out too.
+pub struct RollupCircuit<const N: usize, const DEPTH: usize> {
+    pub transfers: [Value<TransferWitness<DEPTH>>; N],
+}
+
+impl<const N: usize, const DEPTH: usize> RollupCircuit<N, DEPTH> {
+    pub fn new(transfers: [TransferWitness<DEPTH>; N]) -> Self {
+        Self {
+            transfers: transfers.map(Value::known),
+        }
+    }
+}
+
+impl<const N: usize, const DEPTH: usize> Default for RollupCircuit<N, DEPTH> {
+    fn default() -> Self {
+        Self {
+            transfers: [Value::unknown(); N],
+        }
+    }
+}
+
+impl<const N: usize, const DEPTH: usize> Circuit<pallas::Base> for RollupCircuit<N, DEPTH> {
+    type Config = RollupConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        let advice = [(); 11].map(|_| meta.advice_column());
+        let fixed = [(); 5].map(|_| meta.fixed_column());
+        let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+        let q_transfer = meta.selector();
+
+        meta.enable_equality(instance);
+
+        let pedersen = PedersenChip::configure(
+            meta,
+            [
+                advice[0], advice[1], advice[2], advice[3], advice[4], advice[5],
+            ],
+            fixed,
+            constants,
+        );
+        let poseidon =
+            PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+        let schnorr = SchnorrChip::configure(meta, advice, pedersen, poseidon.clone());
+        let merkle = MerkleChip::configure(
+            meta,
+            [advice[0], advice[1], advice[2], advice[3], advice[4]],
+            poseidon,
+        );
+        let mut tables = TableRegistry::default();
+        let bytes = tables.bytes(meta);
+        let range = RangeCheckChip::configure(meta, [advice[9], advice[10]], bytes);
+
+        meta.create_gate("transfer", |meta| {
+            //
+            // amount | from | from' | to | to' | nonce | nonce' | to's nonce | q_transfer
+            //                                                                     1
+            //
+            // the receiver's nonce rides along unconstrained, for its leaf
+            let s = meta.query_selector(q_transfer);
+            let [amount, from, from_new, to, to_new, nonce, nonce_new] =
+                [0, 1, 2, 3, 4, 5, 6].map(|i| meta.query_advice(advice[i], Rotation::cur()));
+            vec![
+                s.clone() * (from - amount.clone() - from_new),
+                s.clone() * (to + amount - to_new),
+                s * (nonce + Expression::Constant(pallas::Base::one()) - nonce_new),
+            ]
+        });
+
+        RollupConfig {
+            advice,
+            instance,
+            q_transfer,
+            schnorr,
+            merkle,
+            range,
+            tables,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+    ) -> Result<(), Error> {
+        const { assert!(N > 0) };
+        config.tables.load(&mut layouter)?;
+        let schnorr = SchnorrChip::construct(config.schnorr.clone());
+        let poseidon = PoseidonChip::construct(config.schnorr.poseidon.clone());
+        let merkle = MerkleChip::construct(config.merkle.clone());
+        let range = RangeCheckChip::construct(config.range.clone());
+        let advice = config.advice;
+
+        let mut root: Option<AssignedCell<pallas::Base, pallas::Base>> = None;
+        for (i, tx) in self.transfers.iter().enumerate() {
+            let mut layouter = layouter.namespace(|| format!("transfer {}", i));
+            let sender_key = schnorr.witness_point(
+                layouter.namespace(|| "sender key"),
+                tx.map(|tx| tx.sender.public_key),
+            )?;
+            let receiver_key = schnorr.witness_point(
+                layouter.namespace(|| "receiver key"),
+                tx.map(|tx| tx.receiver.public_key),
+            )?;
+
+            let [amount, from, from_new, to, to_new, nonce, nonce_new, receiver_nonce] = layouter
+                .assign_region(
+                || "balances",
+                |mut region| {
+                    config.q_transfer.enable(&mut region, 0)?;
+                    let amount = tx.map(|tx| pallas::Base::from(tx.amount));
+                    let from = tx.map(|tx| tx.sender.balance);
+                    let to = tx.map(|tx| tx.receiver.balance);
+                    let nonce = tx.map(|tx| tx.sender.nonce);
+                    let values = [
+                        amount,
+                        from,
+                        from - amount,
+                        to,
+                        to + amount,
+                        nonce,
+                        nonce + Value::known(pallas::Base::one()),
+                        tx.map(|tx| tx.receiver.nonce),
+                    ];
+                    let mut cells = vec![];
+                    for (column, value) in advice.iter().zip(values) {
+                        cells.push(region.assign_advice(|| "value", *column, 0, || value)?);
+                    }
+                    Ok(cells.try_into().unwrap())
+                },
+            )?;
+            for cell in [&amount, &from_new, &to_new] {
+                range.range_check::<BALANCE_BYTES>(layouter.namespace(|| "u64"), cell)?;
+            }
+
+            let message = poseidon.hash(
+                layouter.namespace(|| "message"),
+                &[
+                    receiver_key.x.clone(),
+                    receiver_key.y.clone(),
+                    amount,
+                    nonce.clone(),
+                ],
+            )?;
+            schnorr.verify(
+                layouter.namespace(|| "signature"),
+                &sender_key,
+                &message,
+                tx.map(|tx| tx.signature),
+            )?;
+
+            let mut leaf = |key: &EccPoint, balance, nonce| {
+                poseidon.hash(
+                    layouter.namespace(|| "leaf"),
+                    &[key.x.clone(), key.y.clone(), balance, nonce],
+                )
+            };
+            let sender_old = leaf(&sender_key, from, nonce)?;
+            let sender_new = leaf(&sender_key, from_new, nonce_new)?;
+            let receiver_old = leaf(&receiver_key, to, receiver_nonce.clone())?;
+            let receiver_new = leaf(&receiver_key, to_new, receiver_nonce)?;
+
+            let (before, sent) = merkle.update(
+                layouter.namespace(|| "sender"),
+                &sender_old,
+                &sender_new,
+                &tx.map(|tx| tx.sender_path).transpose_array(),
+                tx.map(|tx| tx.sender_index),
+            )?;
+            let (received, after) = merkle.update(
+                layouter.namespace(|| "receiver"),
+                &receiver_old,
+                &receiver_new,
+                &tx.map(|tx| tx.receiver_path).transpose_array(),
+                tx.map(|tx| tx.receiver_index),
+            )?;
+
+            layouter.assign_region(
+                || "chain roots",
+                |mut region| {
+                    let sent = sent.copy_advice(|| "sent", &mut region, advice[0], 0)?;
+                    region.constrain_equal(sent.cell(), received.cell())?;
+                    if let Some(root) = &root {
+                        let root = root.copy_advice(|| "root", &mut region, advice[1], 0)?;
+                        region.constrain_equal(root.cell(), before.cell())?;
+                    }
+                    Ok(())
+                },
+            )?;
+            if root.is_none() {
+                layouter.constrain_instance(before.cell(), config.instance, 0)?;
+            }
+            root = Some(after);
+        }
+
+        layouter.constrain_instance(root.unwrap().cell(), config.instance, 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use crate::gadgets::schnorr::{public_key, sign};
+    use halo2_proofs::{arithmetic::FieldExt, dev::MockProver};
+
+    const K: u32 = 14;
+    const DEPTH: usize = 2;
+
+    fn secret(i: usize) -> pallas::Scalar {
+        pallas::Scalar::from(0x5ec2e7 + i as u64)
+    }
+
+    fn genesis() -> Rollup<DEPTH> {
+        Rollup::new(
+            (0..1 << DEPTH)
+                .map(|i| Account::new(public_key(secret(i)), 100))
+                .collect(),
+        )
+    }
+
+    // Signs and applies `from` paying `amount` to `to`.
+    fn pay(
+        rollup: &mut Rollup<DEPTH>,
+        from: usize,
+        to: usize,
+        amount: u64,
+    ) -> TransferWitness<DEPTH> {
+        let message = rollup.message(from, to, amount);
+        // a signing nonce that changes with the message
+        let nonce = pallas::Scalar::from(message.get_lower_128() as u64);
+        let signature = sign(secret(from), message, nonce);
+        rollup.apply(from, to, amount, signature)
+    }
+
+    fn verify(circuit: &RollupCircuit<2, DEPTH>, old: pallas::Base, new: pallas::Base) -> bool {
+        let prover = MockProver::run(K, circuit, vec![vec![old, new]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_rollup() {
+        let _guard = crate::testing::heavy_test();
+        let mut rollup = genesis();
+        let old = rollup.root();
+        let transfers = [pay(&mut rollup, 0, 3, 30), pay(&mut rollup, 3, 3, 130)];
+        let new = rollup.root();
+        assert_eq!(rollup.accounts[3].balance, pallas::Base::from(130));
+
+        let circuit = RollupCircuit::new(transfers);
+        assert!(verify(&circuit, old, new));
+        // not the roots the batch goes between
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![vec![old, old]],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![vec![new, new]],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+
+        // a signature over another amount
+        let mut forged = transfers;
+        forged[0].signature = pay(&mut genesis(), 0, 3, 31).signature;
+        assert_unsatisfied_with(
+            K,
+            &RollupCircuit::new(forged),
+            vec![vec![old, new]],
+            &[Failure::Copy("A0"), Failure::Copy("A1")],
+        );
+
+        // more than the sender has, even though it's signed
+        let mut rollup = genesis();
+        let transfers = [pay(&mut rollup, 1, 2, 60), pay(&mut rollup, 1, 2, 60)];
+        assert_unsatisfied_with(
+            K,
+            &RollupCircuit::new(transfers),
+            vec![vec![old, rollup.root()]],
+            &[Failure::Copy("A0"), Failure::Copy("A9")],
+        );
+    }
+}
@@ -0,0 +1,308 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+use std::collections::VecDeque;
+
+use crate::gadgets::{
+    merkle::{MerkleChip, MerkleConfig},
+    nullifier::{self, NullifierChip, NullifierConfig},
+    poseidon::{self, PoseidonChip},
+};
+
+#[derive(Debug, Clone)]
+pub struct SemaphoreConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 5],
+    pub instance: Column<Instance>,
+    pub merkle: MerkleConfig<F>,
+    pub nullifier: NullifierConfig<F>,
+}
+
+// Semaphore-style anonymous signalling. Proves the prover's identity
+// commitment is a leaf of the group tree with the public root, and derives a
+// nullifier that is the same every time this identity signals under the same
+// external nullifier, without revealing which leaf it is. The instance column
+// is `[root, nullifier_hash, external_nullifier, signal_hash]`.
+//
+//   identity_commitment = H(H(identity_nullifier, identity_trapdoor))
+//   nullifier_hash      = H(identity_nullifier, external_nullifier)
+pub struct SemaphoreCircuit<F, const DEPTH: usize> {
+    pub identity_nullifier: Value<F>,
+    pub identity_trapdoor: Value<F>,
+    pub path: [Value<F>; DEPTH],
+    pub index: Value<u64>,
+}
+
+impl<F: FieldExt, const DEPTH: usize> SemaphoreCircuit<F, DEPTH> {
+    pub fn new(identity_nullifier: F, identity_trapdoor: F, path: [F; DEPTH], index: u64) -> Self {
+        Self {
+            identity_nullifier: Value::known(identity_nullifier),
+            identity_trapdoor: Value::known(identity_trapdoor),
+            path: path.map(Value::known),
+            index: Value::known(index),
+        }
+    }
+}
+
+impl<F: FieldExt, const DEPTH: usize> Default for SemaphoreCircuit<F, DEPTH> {
+    fn default() -> Self {
+        Self {
+            identity_nullifier: Value::unknown(),
+            identity_trapdoor: Value::unknown(),
+            path: [Value::unknown(); DEPTH],
+            index: Value::unknown(),
+        }
+    }
+}
+
+pub fn identity_commitment<F: FieldExt>(identity_nullifier: F, identity_trapdoor: F) -> F {
+    let secret = poseidon::hash(&[identity_nullifier, identity_trapdoor]);
+    poseidon::hash(&[secret])
+}
+
+pub fn nullifier_hash<F: FieldExt>(external_nullifier: F, identity_nullifier: F) -> F {
+    nullifier::derive(None, identity_nullifier, external_nullifier)
+}
+
+// The group roots a verifier accepts proofs against. Members joining change
+// the root while proofs made against the old one are still on their way, so
+// the last SIZE roots all count. Rotating is a single `&mut` call, so a
+// history shared behind a lock never has the new root without the oldest one
+// dropped.
+#[derive(Debug, Clone)]
+pub struct RootHistory<F, const SIZE: usize> {
+    roots: VecDeque<F>,
+}
+
+impl<F: FieldExt, const SIZE: usize> RootHistory<F, SIZE> {
+    pub fn new(root: F) -> Self {
+        const { assert!(SIZE > 0) };
+        Self {
+            roots: VecDeque::from([root]),
+        }
+    }
+
+    pub fn current(&self) -> F {
+        *self.roots.back().unwrap()
+    }
+
+    // Makes `root` the current root, returning the one that stops being
+    // accepted, if any.
+    pub fn rotate(&mut self, root: F) -> Option<F> {
+        self.roots.push_back(root);
+        if self.roots.len() > SIZE {
+            self.roots.pop_front()
+        } else {
+            None
+        }
+    }
+
+    // Whether a proof's public input is against a recent root. The proof
+    // still has to verify.
+    pub fn accepts(&self, public_input: &[F]) -> bool {
+        public_input
+            .first()
+            .is_some_and(|root| self.roots.contains(root))
+    }
+}
+
+impl<F: FieldExt, const DEPTH: usize> Circuit<F> for SemaphoreCircuit<F, DEPTH> {
+    type Config = SemaphoreConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 5].map(|_| meta.advice_column());
+        let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(instance);
+
+        let poseidon =
+            PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+        let nullifier = NullifierChip::configure(meta, poseidon.clone(), instance, None);
+        let merkle = MerkleChip::configure(meta, advice, poseidon);
+
+        SemaphoreConfig {
+            advice,
+            instance,
+            merkle,
+            nullifier,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let (identity_nullifier, identity_trapdoor, external_nullifier) = layouter.assign_region(
+            || "inputs",
+            |mut region| {
+                let identity_nullifier = region.assign_advice(
+                    || "identity nullifier",
+                    config.advice[0],
+                    0,
+                    || self.identity_nullifier,
+                )?;
+                let identity_trapdoor = region.assign_advice(
+                    || "identity trapdoor",
+                    config.advice[1],
+                    0,
+                    || self.identity_trapdoor,
+                )?;
+                let external_nullifier = region.assign_advice_from_instance(
+                    || "external nullifier",
+                    config.instance,
+                    2,
+                    config.advice[2],
+                    0,
+                )?;
+                // The signal hash isn't used by any constraint; being part of
+                // the instance is enough to bind the proof to it.
+                region.assign_advice_from_instance(
+                    || "signal hash",
+                    config.instance,
+                    3,
+                    config.advice[3],
+                    0,
+                )?;
+                Ok((identity_nullifier, identity_trapdoor, external_nullifier))
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.merkle.poseidon.clone());
+        let secret = poseidon.hash(
+            layouter.namespace(|| "identity secret"),
+            &[identity_nullifier.clone(), identity_trapdoor],
+        )?;
+        let commitment = poseidon.hash(layouter.namespace(|| "identity commitment"), &[secret])?;
+
+        let merkle = MerkleChip::construct(config.merkle.clone());
+        let root = merkle.compute_root(
+            layouter.namespace(|| "membership"),
+            &commitment,
+            &self.path,
+            self.index,
+        )?;
+        layouter.constrain_instance(root.cell(), config.instance, 0)?;
+
+        let nullifier = NullifierChip::construct(config.nullifier);
+        nullifier.derive_public(
+            layouter.namespace(|| "nullifier hash"),
+            &identity_nullifier,
+            &external_nullifier,
+            1,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use crate::gadgets::merkle::{merkle_path, merkle_root};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 10;
+    const DEPTH: usize = 3;
+
+    #[test]
+    fn test_semaphore() {
+        let identities: Vec<(Fp, Fp)> = (0..1 << DEPTH)
+            .map(|i| (Fp::from(1000 + i), Fp::from(2000 + i)))
+            .collect();
+        let leaves: Vec<Fp> = identities
+            .iter()
+            .map(|(n, t)| identity_commitment(*n, *t))
+            .collect();
+        let root = merkle_root(&leaves);
+
+        let index = 6;
+        let (identity_nullifier, identity_trapdoor) = identities[index];
+        let path: [Fp; DEPTH] = merkle_path(&leaves, index).try_into().unwrap();
+        let circuit =
+            SemaphoreCircuit::new(identity_nullifier, identity_trapdoor, path, index as u64);
+
+        let external_nullifier = Fp::from(0xe1ec7);
+        let signal_hash = Fp::from(42);
+        let nullifier = nullifier_hash(external_nullifier, identity_nullifier);
+
+        let public_input = vec![root, nullifier, external_nullifier, signal_hash];
+        let prover = MockProver::run(K, &circuit, vec![public_input]).unwrap();
+        prover.assert_satisfied();
+
+        // a fresh nullifier for the same external nullifier, to signal twice
+        let public_input = vec![root, nullifier + Fp::one(), external_nullifier, signal_hash];
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![public_input],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+
+        // a different group
+        let public_input = vec![root + Fp::one(), nullifier, external_nullifier, signal_hash];
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![public_input],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+    }
+
+    #[test]
+    fn test_semaphore_non_member() {
+        let leaves: Vec<Fp> = (0..1 << DEPTH)
+            .map(|i| identity_commitment(Fp::from(1000 + i), Fp::from(2000 + i)))
+            .collect();
+        let root = merkle_root(&leaves);
+
+        // an identity that isn't in the group, using some member's path
+        let (identity_nullifier, identity_trapdoor) = (Fp::from(1), Fp::from(2));
+        let path: [Fp; DEPTH] = merkle_path(&leaves, 3).try_into().unwrap();
+        let circuit = SemaphoreCircuit::new(identity_nullifier, identity_trapdoor, path, 3);
+
+        let external_nullifier = Fp::from(0xe1ec7);
+        let nullifier = nullifier_hash(external_nullifier, identity_nullifier);
+        let public_input = vec![root, nullifier, external_nullifier, Fp::zero()];
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![public_input],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+    }
+
+    #[test]
+    fn test_root_history() {
+        let mut leaves: Vec<Fp> = (0..1 << DEPTH).map(|i| Fp::from(i + 1)).collect();
+        let (identity_nullifier, identity_trapdoor) = (Fp::from(1000), Fp::from(2000));
+        leaves[0] = identity_commitment(identity_nullifier, identity_trapdoor);
+        let root = merkle_root(&leaves);
+
+        let path: [Fp; DEPTH] = merkle_path(&leaves, 0).try_into().unwrap();
+        let circuit = SemaphoreCircuit::new(identity_nullifier, identity_trapdoor, path, 0);
+        let external_nullifier = Fp::from(0xe1ec7);
+        let nullifier = nullifier_hash(external_nullifier, identity_nullifier);
+        let public_input = vec![root, nullifier, external_nullifier, Fp::zero()];
+        let prover = MockProver::run(K, &circuit, vec![public_input.clone()]).unwrap();
+        prover.assert_satisfied();
+
+        // two more members join, and the proof against the first root is
+        // accepted until it falls out of the history
+        let mut history = RootHistory::<Fp, 2>::new(root);
+        assert!(history.accepts(&public_input));
+        leaves[1] = identity_commitment(Fp::from(1001), Fp::from(2001));
+        assert_eq!(history.rotate(merkle_root(&leaves)), None);
+        assert!(history.accepts(&public_input));
+        leaves[2] = identity_commitment(Fp::from(1002), Fp::from(2002));
+        assert_eq!(history.rotate(merkle_root(&leaves)), Some(root));
+        assert_eq!(history.current(), merkle_root(&leaves));
+        assert!(!history.accepts(&public_input));
+        assert!(!history.accepts(&[]));
+    }
+}
@@ -0,0 +1,353 @@
+use halo2_gadgets::{
+    ecc::{
+        chip::{
+            find_zs_and_us, BaseFieldElem, FixedPoint, FullScalar, ShortScalar, H, NUM_WINDOWS,
+            NUM_WINDOWS_SHORT,
+        },
+        FixedPoints,
+    },
+    sinsemilla::{
+        chip::{SinsemillaChip, SinsemillaConfig},
+        merkle::{
+            chip::{MerkleChip, MerkleConfig},
+            MerklePath,
+        },
+        primitives::{self as sinsemilla, HashDomain, Q_PERSONALIZATION},
+        CommitDomains, HashDomains,
+    },
+    utilities::{i2lebsp, lookup_range_check::LookupRangeCheckConfig, UtilitiesInstructions},
+};
+use halo2_proofs::{
+    arithmetic::CurveExt,
+    circuit::*,
+    pasta::{
+        group::{ff::PrimeField, Curve},
+        pallas, Fp,
+    },
+    plonk::*,
+};
+use std::sync::OnceLock;
+
+// Orchard's, so the roots are the ones Orchard's note commitment tree has.
+pub const PERSONALIZATION: &str = "z.cash:Orchard-MerkleCRH";
+
+// Bits of each child going into a layer's hash.
+const BASE_BITS: usize = 255;
+
+// The hash domain the Merkle chip hashes layers in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleCrh;
+
+impl HashDomains<pallas::Affine> for MerkleCrh {
+    fn Q(&self) -> pallas::Affine {
+        // what `HashDomain::new` hashes to, which it only hands out to tests
+        pallas::Point::hash_to_curve(Q_PERSONALIZATION)(PERSONALIZATION.as_bytes()).to_affine()
+    }
+}
+
+// The Sinsemilla chip is also generic over the fixed bases Orchard commits to
+// notes with, and over a commitment domain whose blinding base is one of them.
+// A Merkle path multiplies by none of them, but the types still have to be
+// real fixed points, so all three kinds of scalar get R, the base
+// SinsemillaCommit would blind with in the MerkleCRH domain. Its window tables
+// are only worked out if something asks for them.
+fn r() -> pallas::Affine {
+    pallas::Point::hash_to_curve(&format!("{}-r", PERSONALIZATION))(&[]).to_affine()
+}
+
+fn zs_and_us(windows: usize) -> &'static [(u64, [pallas::Base; H])] {
+    static FULL: OnceLock<Vec<(u64, [pallas::Base; H])>> = OnceLock::new();
+    static SHORT: OnceLock<Vec<(u64, [pallas::Base; H])>> = OnceLock::new();
+    let table = if windows == NUM_WINDOWS_SHORT {
+        &SHORT
+    } else {
+        &FULL
+    };
+    table.get_or_init(|| find_zs_and_us(r(), windows).unwrap())
+}
+
+fn us(windows: usize) -> Vec<[[u8; 32]; H]> {
+    zs_and_us(windows)
+        .iter()
+        .map(|(_, us)| us.map(|u| u.to_repr()))
+        .collect()
+}
+
+fn zs(windows: usize) -> Vec<u64> {
+    zs_and_us(windows).iter().map(|(z, _)| *z).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FullWidth;
+
+impl FixedPoint<pallas::Affine> for FullWidth {
+    type FixedScalarKind = FullScalar;
+
+    fn generator(&self) -> pallas::Affine {
+        r()
+    }
+
+    fn u(&self) -> Vec<[[u8; 32]; H]> {
+        us(NUM_WINDOWS)
+    }
+
+    fn z(&self) -> Vec<u64> {
+        zs(NUM_WINDOWS)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Short;
+
+impl FixedPoint<pallas::Affine> for Short {
+    type FixedScalarKind = ShortScalar;
+
+    fn generator(&self) -> pallas::Affine {
+        r()
+    }
+
+    fn u(&self) -> Vec<[[u8; 32]; H]> {
+        us(NUM_WINDOWS_SHORT)
+    }
+
+    fn z(&self) -> Vec<u64> {
+        zs(NUM_WINDOWS_SHORT)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BaseField;
+
+impl FixedPoint<pallas::Affine> for BaseField {
+    type FixedScalarKind = BaseFieldElem;
+
+    fn generator(&self) -> pallas::Affine {
+        r()
+    }
+
+    fn u(&self) -> Vec<[[u8; 32]; H]> {
+        us(NUM_WINDOWS)
+    }
+
+    fn z(&self) -> Vec<u64> {
+        zs(NUM_WINDOWS)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleFixedBases;
+
+impl FixedPoints<pallas::Affine> for MerkleFixedBases {
+    type FullScalar = FullWidth;
+    type ShortScalar = Short;
+    type Base = BaseField;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleCommitDomain;
+
+impl CommitDomains<pallas::Affine, MerkleFixedBases, MerkleCrh> for MerkleCommitDomain {
+    fn r(&self) -> FullWidth {
+        FullWidth
+    }
+
+    fn hash_domain(&self) -> MerkleCrh {
+        MerkleCrh
+    }
+}
+
+type Merkle = MerkleChip<MerkleCrh, MerkleCommitDomain, MerkleFixedBases>;
+// a path of DEPTH siblings hashed by one chip
+type Path<const DEPTH: usize> =
+    MerklePath<pallas::Affine, Merkle, DEPTH, { sinsemilla::K }, { sinsemilla::C }, 1>;
+
+#[derive(Debug, Clone)]
+pub struct SinsemillaMerkleConfig {
+    pub advice: [Column<Advice>; 5],
+    pub instance: Column<Instance>,
+    pub sinsemilla: SinsemillaConfig<MerkleCrh, MerkleCommitDomain, MerkleFixedBases>,
+    pub merkle: MerkleConfig<MerkleCrh, MerkleCommitDomain, MerkleFixedBases>,
+}
+
+// Proves a private leaf is at a private position in the tree with the public
+// root, with halo2_gadgets' Sinsemilla Merkle chip rather than one of the
+// crate's own, as an example of building on an external gadget crate. The
+// instance column is `[root]`.
+//
+// Each layer is MerkleCRH from Orchard: Sinsemilla over the layer's index in
+// 10 bits, then the left and right children in 255 bits each, 52 lookups into
+// the chip's 2^10 row table of generators, which is also why K can't be below
+// 11. The gadget hashes with only one chip here; Orchard gives it two, side by
+// side, to halve the rows.
+#[derive(Default)]
+pub struct SinsemillaMerkleCircuit<const DEPTH: usize> {
+    pub leaf: Value<Fp>,
+    pub pos: Value<u32>,
+    pub path: Value<[Fp; DEPTH]>,
+}
+
+impl<const DEPTH: usize> SinsemillaMerkleCircuit<DEPTH> {
+    pub fn new(leaf: Fp, pos: u32, path: [Fp; DEPTH]) -> Self {
+        Self {
+            leaf: Value::known(leaf),
+            pos: Value::known(pos),
+            path: Value::known(path),
+        }
+    }
+}
+
+fn bits(x: Fp) -> impl Iterator<Item = bool> {
+    let repr = x.to_repr();
+    (0..BASE_BITS).map(move |i| (repr[i / 8] >> (i % 8)) & 1 == 1)
+}
+
+// MerkleCRH on the host, `layer` counting up from the leaves.
+pub fn merkle_crh(layer: usize, left: Fp, right: Fp) -> Fp {
+    HashDomain::new(PERSONALIZATION)
+        .hash(
+            i2lebsp::<{ sinsemilla::K }>(layer as u64)
+                .into_iter()
+                .chain(bits(left))
+                .chain(bits(right)),
+        )
+        .unwrap()
+}
+
+// The root of the tree with `leaf` at `pos`, `path` holding the siblings from
+// the leaf up.
+pub fn sinsemilla_merkle_root<const DEPTH: usize>(leaf: Fp, pos: u32, path: [Fp; DEPTH]) -> Fp {
+    path.iter()
+        .enumerate()
+        .fold(leaf, |node, (layer, sibling)| match (pos >> layer) & 1 {
+            0 => merkle_crh(layer, node, *sibling),
+            _ => merkle_crh(layer, *sibling, node),
+        })
+}
+
+impl<const DEPTH: usize> Circuit<Fp> for SinsemillaMerkleCircuit<DEPTH> {
+    type Config = SinsemillaMerkleConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let advice = [(); 5].map(|_| meta.advice_column());
+        let constants = meta.fixed_column();
+        let fixed_y_q = meta.fixed_column();
+        let instance = meta.instance_column();
+        // the table of generators, which the range checks share
+        let lookup = (
+            meta.lookup_table_column(),
+            meta.lookup_table_column(),
+            meta.lookup_table_column(),
+        );
+
+        meta.enable_equality(instance);
+        meta.enable_constant(constants);
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        let range_check = LookupRangeCheckConfig::configure(meta, advice[4], lookup.0);
+        let sinsemilla =
+            SinsemillaChip::configure(meta, advice, advice[2], fixed_y_q, lookup, range_check);
+        let merkle = MerkleChip::configure(meta, sinsemilla.clone());
+
+        SinsemillaMerkleConfig {
+            advice,
+            instance,
+            sinsemilla,
+            merkle,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        SinsemillaChip::load(config.sinsemilla.clone(), &mut layouter)?;
+        let chip = Merkle::construct(config.merkle.clone());
+
+        let leaf = chip.load_private(layouter.namespace(|| "leaf"), config.advice[0], self.leaf)?;
+        let path = Path::<DEPTH>::construct([chip], MerkleCrh, self.pos, self.path);
+        let root = path.calculate_root(layouter.namespace(|| "root"), leaf)?;
+
+        layouter.constrain_instance(root.cell(), config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::group::prime::PrimeCurveAffine};
+
+    const K: u32 = 11;
+    const DEPTH: usize = 4;
+
+    fn path() -> [Fp; DEPTH] {
+        [3, 1, 4, 1].map(Fp::from)
+    }
+
+    #[test]
+    fn test_sinsemilla_merkle() {
+        let leaf = Fp::from(42);
+        for pos in [0, 5, 15] {
+            let root = sinsemilla_merkle_root(leaf, pos, path());
+            let circuit = SinsemillaMerkleCircuit::new(leaf, pos, path());
+            let prover = MockProver::run(K, &circuit, vec![vec![root]]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    // Every fixed base is MerkleCRH's R. Its window tables take minutes to
+    // find in a debug build, so they're left to halo2_gadgets' own tests.
+    #[test]
+    fn test_fixed_bases() {
+        assert_ne!(r(), pallas::Affine::identity());
+        assert_eq!(MerkleCommitDomain.r().generator(), r());
+        assert_eq!(Short.generator(), r());
+        assert_eq!(BaseField.generator(), r());
+        assert_eq!(MerkleCommitDomain.hash_domain(), MerkleCrh);
+    }
+
+    #[test]
+    fn test_wrong_root() {
+        let leaf = Fp::from(42);
+        let root = sinsemilla_merkle_root(leaf, 5, path());
+
+        // another leaf, another position, and a sibling changed
+        let mut changed = path();
+        changed[2] += Fp::one();
+        for circuit in [
+            SinsemillaMerkleCircuit::new(leaf + Fp::one(), 5, path()),
+            SinsemillaMerkleCircuit::new(leaf, 4, path()),
+            SinsemillaMerkleCircuit::new(leaf, 5, changed),
+        ] {
+            let prover = MockProver::run(K, &circuit, vec![vec![root]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[cfg(feature = "dev-graph")]
+    #[test]
+    fn plot_sinsemilla_merkle() {
+        use crate::layout::{render_layout, Format, LayoutOptions};
+
+        let options = LayoutOptions {
+            title: Some("Sinsemilla Merkle Layout".to_string()),
+            labels: false,
+            ..LayoutOptions::default()
+        };
+        render_layout(
+            &SinsemillaMerkleCircuit::<DEPTH>::default(),
+            K,
+            "sinsemilla-merkle-layout.png",
+            Format::Png,
+            &options,
+        )
+        .unwrap();
+    }
+}
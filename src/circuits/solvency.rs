@@ -0,0 +1,213 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::gadgets::{
+    compare::{CompareChip, CompareConfig},
+    poseidon::{self, PoseidonChip, PoseidonConfig},
+    range_check::RangeCheckChip,
+    tables::TableRegistry,
+};
+
+pub const BALANCE_BYTES: usize = 8;
+// Room for the sum of up to 2^32 balances.
+pub const TOTAL_BYTES: usize = 12;
+
+#[derive(Debug, Clone)]
+pub struct SolvencyConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 4],
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+    pub compare: CompareConfig<TOTAL_BYTES>,
+    pub poseidon: PoseidonConfig<F>,
+    pub tables: TableRegistry,
+}
+
+// Proves the private balances committed to as `hash(balances || salt)` add up
+// to at least the public total liabilities. Every balance is range checked, so
+// a huge "negative" balance can't wrap the sum around. The instance column is
+// `[commitment, liabilities]`.
+#[derive(Default)]
+pub struct SolvencyCircuit<F> {
+    pub balances: Vec<Value<F>>,
+    pub salt: Value<F>,
+}
+
+impl<F: FieldExt> SolvencyCircuit<F> {
+    pub fn new(balances: &[u64], salt: u64) -> Self {
+        Self {
+            balances: balances.iter().map(|v| Value::known(F::from(*v))).collect(),
+            salt: Value::known(F::from(salt)),
+        }
+    }
+}
+
+pub fn commit<F: FieldExt>(balances: &[u64], salt: u64) -> F {
+    let mut message: Vec<F> = balances.iter().map(|v| F::from(*v)).collect();
+    message.push(F::from(salt));
+    poseidon::hash(&message)
+}
+
+impl<F: FieldExt> Circuit<F> for SolvencyCircuit<F> {
+    type Config = SolvencyConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            balances: vec![Value::unknown(); self.balances.len()],
+            salt: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+        let selector = meta.selector();
+
+        meta.enable_equality(instance);
+
+        meta.create_gate("running sum", |meta| {
+            //
+            // advice[0] | advice[1] | selector
+            //   b_0         0            1
+            //   b_1         acc_1        1
+            //   ...
+            //               total
+            //
+            let s = meta.query_selector(selector);
+            let balance = meta.query_advice(advice[0], Rotation::cur());
+            let acc = meta.query_advice(advice[1], Rotation::cur());
+            let acc_next = meta.query_advice(advice[1], Rotation::next());
+            vec![s * (acc + balance - acc_next)]
+        });
+
+        let mut tables = TableRegistry::default();
+        let bytes = tables.bytes(meta);
+        let range = RangeCheckChip::configure(meta, [advice[2], advice[3]], bytes);
+        let compare = CompareChip::configure(meta, advice, range);
+        let poseidon =
+            PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+
+        SolvencyConfig {
+            advice,
+            selector,
+            instance,
+            compare,
+            poseidon,
+            tables,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let range = RangeCheckChip::construct(config.compare.range.clone());
+        config.tables.load(&mut layouter)?;
+
+        let mut balances = vec![];
+        for balance in self.balances.iter() {
+            balances.push(
+                range
+                    .witness_checked::<BALANCE_BYTES>(layouter.namespace(|| "balance"), *balance)?,
+            );
+        }
+
+        let (salt, liabilities) = layouter.assign_region(
+            || "inputs",
+            |mut region| {
+                let salt = region.assign_advice(|| "salt", config.advice[0], 0, || self.salt)?;
+                let liabilities = region.assign_advice_from_instance(
+                    || "liabilities",
+                    config.instance,
+                    1,
+                    config.advice[1],
+                    0,
+                )?;
+                Ok((salt, liabilities))
+            },
+        )?;
+        range.range_check::<TOTAL_BYTES>(layouter.namespace(|| "liabilities"), &liabilities)?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon.clone());
+        let mut message = balances.clone();
+        message.push(salt);
+        let commitment = poseidon.hash(layouter.namespace(|| "commitment"), &message)?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 0)?;
+
+        let total = layouter.assign_region(
+            || "total assets",
+            |mut region| {
+                let mut acc =
+                    region.assign_advice_from_constant(|| "acc", config.advice[1], 0, F::zero())?;
+                for (offset, balance) in balances.iter().enumerate() {
+                    config.selector.enable(&mut region, offset)?;
+                    let balance =
+                        balance.copy_advice(|| "balance", &mut region, config.advice[0], offset)?;
+                    let next = acc.value().copied() + balance.value();
+                    acc = region.assign_advice(|| "acc", config.advice[1], offset + 1, || next)?;
+                }
+                Ok(acc)
+            },
+        )?;
+
+        let compare = CompareChip::construct(config.compare);
+        compare.assert_le(
+            layouter.namespace(|| "liabilities <= assets"),
+            &liabilities,
+            &total,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 11;
+
+    #[test]
+    fn test_solvency() {
+        let _guard = crate::testing::heavy_test();
+        let balances = [1_500_000, 250_000, u64::MAX, 0, 42];
+        let salt = 0x5011;
+        let commitment = commit::<Fp>(&balances, salt);
+        let circuit = SolvencyCircuit::<Fp>::new(&balances, salt);
+
+        let assets: u128 = balances.iter().map(|b| *b as u128).sum();
+
+        for (liabilities, ok) in [
+            (0, true),
+            (1_000_000, true),
+            (assets, true),
+            (assets + 1, false),
+        ] {
+            let public_input = vec![commitment, Fp::from_u128(liabilities)];
+            let prover = MockProver::run(K, &circuit, vec![public_input]).unwrap();
+            assert_eq!(prover.verify().is_ok(), ok, "liabilities {}", liabilities);
+        }
+    }
+
+    #[test]
+    fn test_solvency_negative_balance() {
+        let _guard = crate::testing::heavy_test();
+        // -1 is p - 1 in the field, which only the balance range check rejects
+        let salt = 0x5011;
+        let circuit = SolvencyCircuit::<Fp> {
+            balances: vec![Value::known(Fp::from(100)), Value::known(-Fp::one())],
+            salt: Value::known(Fp::from(salt)),
+        };
+        let commitment = poseidon::hash(&[Fp::from(100), -Fp::one(), Fp::from(salt)]);
+
+        let public_input = vec![commitment, Fp::from(99)];
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![public_input],
+            &[Failure::Copy("A1"), Failure::Copy("A2")],
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::gadgets::{
+    distinct::{DistinctChip, DistinctConfig},
+    range_table::RangeTableConfig,
+};
+
+#[derive(Debug, Clone)]
+pub struct SudokuConfig {
+    pub cells: [Column<Advice>; 9],
+    pub givens: [Column<Advice>; 9],
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+    pub range: RangeTableConfig,
+    pub distinct: DistinctConfig,
+}
+
+// Proves knowledge of a solved grid that agrees with the public puzzle. The
+// puzzle is the instance column, row-major, with 0 marking an empty square.
+#[derive(Default)]
+pub struct SudokuCircuit<F> {
+    pub solution: [[Value<F>; 9]; 9],
+}
+
+impl<F: FieldExt> SudokuCircuit<F> {
+    pub fn new(solution: [[u64; 9]; 9]) -> Self {
+        Self {
+            solution: solution.map(|row| row.map(|v| Value::known(F::from(v)))),
+        }
+    }
+}
+
+pub fn puzzle_instance<F: FieldExt>(puzzle: &[[u64; 9]; 9]) -> Vec<F> {
+    puzzle.iter().flatten().map(|v| F::from(*v)).collect()
+}
+
+impl<F: FieldExt> Circuit<F> for SudokuCircuit<F> {
+    type Config = SudokuConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let cells = [(); 9].map(|_| meta.advice_column());
+        let givens = [(); 9].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        // used by a lookup, so it can't be a simple selector
+        let selector = meta.complex_selector();
+
+        meta.enable_equality(instance);
+        for column in cells.iter().chain(givens.iter()) {
+            meta.enable_equality(*column);
+        }
+
+        let range = RangeTableConfig::configure(meta, 1, 9);
+        for column in cells {
+            range.lookup(meta, selector, column);
+        }
+
+        meta.create_gate("given", |meta| {
+            //
+            // cells[0..9] | givens[0..9] | selector
+            //   x_0 .. x_8   g_0 .. g_8       s
+            //
+            // an empty square has g = 0, otherwise x has to equal g
+            let s = meta.query_selector(selector);
+            (0..9)
+                .map(|i| {
+                    let x = meta.query_advice(cells[i], Rotation::cur());
+                    let g = meta.query_advice(givens[i], Rotation::cur());
+                    s.clone() * g.clone() * (g - x)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        // the pairwise checks live in their own regions, so they can share the
+        // grid columns
+        let distinct = DistinctChip::configure(meta, [cells[0], cells[1], cells[2]]);
+
+        SudokuConfig {
+            cells,
+            givens,
+            selector,
+            instance,
+            range,
+            distinct,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.range.load(&mut layouter)?;
+
+        let grid = layouter.assign_region(
+            || "grid",
+            |mut region| {
+                let mut grid = vec![];
+                for (row, values) in self.solution.iter().enumerate() {
+                    config.selector.enable(&mut region, row)?;
+
+                    let mut cells = vec![];
+                    for (col, value) in values.iter().enumerate() {
+                        region.assign_advice_from_instance(
+                            || "given",
+                            config.instance,
+                            row * 9 + col,
+                            config.givens[col],
+                            row,
+                        )?;
+                        cells.push(region.assign_advice(
+                            || "cell",
+                            config.cells[col],
+                            row,
+                            || *value,
+                        )?);
+                    }
+                    grid.push(cells);
+                }
+                Ok(grid)
+            },
+        )?;
+
+        let chip = DistinctChip::construct(config.distinct);
+
+        for i in 0..9 {
+            let row = grid[i].clone();
+            let col = grid.iter().map(|r| r[i].clone()).collect::<Vec<_>>();
+            let square = (0..9)
+                .map(|j| grid[(i / 3) * 3 + j / 3][(i % 3) * 3 + j % 3].clone())
+                .collect::<Vec<_>>();
+
+            chip.assign(layouter.namespace(|| format!("row {}", i)), &row)?;
+            chip.assign(layouter.namespace(|| format!("col {}", i)), &col)?;
+            chip.assign(layouter.namespace(|| format!("box {}", i)), &square)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 11;
+
+    const PUZZLE: [[u64; 9]; 9] = [
+        [5, 3, 0, 0, 7, 0, 0, 0, 0],
+        [6, 0, 0, 1, 9, 5, 0, 0, 0],
+        [0, 9, 8, 0, 0, 0, 0, 6, 0],
+        [8, 0, 0, 0, 6, 0, 0, 0, 3],
+        [4, 0, 0, 8, 0, 3, 0, 0, 1],
+        [7, 0, 0, 0, 2, 0, 0, 0, 6],
+        [0, 6, 0, 0, 0, 0, 2, 8, 0],
+        [0, 0, 0, 4, 1, 9, 0, 0, 5],
+        [0, 0, 0, 0, 8, 0, 0, 7, 9],
+    ];
+
+    const SOLUTION: [[u64; 9]; 9] = [
+        [5, 3, 4, 6, 7, 8, 9, 1, 2],
+        [6, 7, 2, 1, 9, 5, 3, 4, 8],
+        [1, 9, 8, 3, 4, 2, 5, 6, 7],
+        [8, 5, 9, 7, 6, 1, 4, 2, 3],
+        [4, 2, 6, 8, 5, 3, 7, 9, 1],
+        [7, 1, 3, 9, 2, 4, 8, 5, 6],
+        [9, 6, 1, 5, 3, 7, 2, 8, 4],
+        [2, 8, 7, 4, 1, 9, 6, 3, 5],
+        [3, 4, 5, 2, 8, 6, 1, 7, 9],
+    ];
+
+    #[test]
+    fn test_sudoku() {
+        let _guard = crate::testing::heavy_test();
+        let circuit = SudokuCircuit::<Fp>::new(SOLUTION);
+
+        let prover = MockProver::run(K, &circuit, vec![puzzle_instance(&PUZZLE)]).unwrap();
+        prover.assert_satisfied();
+
+        // a solution to a different puzzle
+        let mut puzzle = PUZZLE;
+        puzzle[0][2] = 1;
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![puzzle_instance(&puzzle)],
+            &[Failure::Gate("given")],
+        );
+    }
+
+    #[test]
+    fn test_sudoku_duplicates() {
+        let _guard = crate::testing::heavy_test();
+        // swapping two empty squares keeps the row valid but breaks columns 2 and 3
+        let mut solution = SOLUTION;
+        solution[0].swap(2, 3);
+        let circuit = SudokuCircuit::<Fp>::new(solution);
+
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![puzzle_instance(&PUZZLE)],
+            &[Failure::Gate("not equal")],
+        );
+    }
+
+    #[test]
+    fn test_sudoku_out_of_range() {
+        let _guard = crate::testing::heavy_test();
+        // 2..=10 is still pairwise distinct everywhere, only the range check catches it
+        let solution = SOLUTION.map(|row| row.map(|v| v + 1));
+        let circuit = SudokuCircuit::<Fp>::new(solution);
+
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![puzzle_instance(&[[0; 9]; 9])],
+            &(0..9).map(Failure::Lookup).collect::<Vec<_>>(),
+        );
+    }
+}
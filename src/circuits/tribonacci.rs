@@ -0,0 +1,187 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone)]
+pub struct TribonacciConfig {
+    pub advice: [Column<Advice>; 3],
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+}
+
+// Proves T(n) for T(i) = T(i - 1) + T(i - 2) + T(i - 3) from public T(0),
+// T(1) and T(2). The instance column is `[T(0), T(1), T(2), T(n)]`.
+//
+// The table is laid out like example3's, three terms a row instead of two, so
+// each gate reaches back through the whole row above to make the next row.
+#[derive(Default)]
+pub struct TribonacciCircuit<F> {
+    pub n: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F> TribonacciCircuit<F> {
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            _marker: PhantomData,
+        }
+    }
+}
+
+// T(0) to T(n) on the host.
+pub fn tribonacci<F: FieldExt>(start: [F; 3], n: usize) -> Vec<F> {
+    let mut sequence = start.to_vec();
+    while sequence.len() <= n {
+        let next = sequence[sequence.len() - 3..]
+            .iter()
+            .fold(F::zero(), |acc, term| acc + term);
+        sequence.push(next);
+    }
+    sequence.truncate(n + 1);
+    sequence
+}
+
+pub fn tribonacci_instance<F: FieldExt>(start: [u64; 3], n: usize) -> Vec<F> {
+    let start = start.map(F::from);
+    let mut instance = start.to_vec();
+    instance.push(tribonacci(start, n)[n]);
+    instance
+}
+
+impl<F: FieldExt> Circuit<F> for TribonacciCircuit<F> {
+    type Config = TribonacciConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.n)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let selector = meta.selector();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(instance);
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("tribonacci", |meta| {
+            //
+            // advice[0] | advice[1] | advice[2] | selector
+            //    a           b           c           1
+            //    d           e           f
+            //
+            let s = meta.query_selector(selector);
+            let [a, b, c] = advice.map(|column| meta.query_advice(column, Rotation::cur()));
+            let [d, e, f] = advice.map(|column| meta.query_advice(column, Rotation::next()));
+            vec![
+                s.clone() * (a + b.clone() + c.clone() - d.clone()),
+                s.clone() * (b + c.clone() + d.clone() - e.clone()),
+                s * (c + d + e - f),
+            ]
+        });
+
+        TribonacciConfig {
+            advice,
+            selector,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let rows = self.n / 3 + 1;
+
+        let out = layouter.assign_region(
+            || "entire tribonacci table",
+            |mut region| {
+                let start = config
+                    .advice
+                    .iter()
+                    .enumerate()
+                    .map(|(row, column)| {
+                        region.assign_advice_from_instance(
+                            || "start",
+                            config.instance,
+                            row,
+                            *column,
+                            0,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                // the whole table worked out on the host first
+                let table = start[0]
+                    .value()
+                    .zip(start[1].value())
+                    .zip(start[2].value())
+                    .map(|((a, b), c)| tribonacci([*a, *b, *c], 3 * rows - 1));
+
+                let mut cells = start;
+                for row in 1..rows {
+                    config.selector.enable(&mut region, row - 1)?;
+                    for (i, column) in config.advice.iter().enumerate() {
+                        let value = table.as_ref().map(|table| table[3 * row + i]);
+                        cells.push(region.assign_advice(|| "term", *column, row, || value)?);
+                    }
+                }
+
+                Ok(cells.swap_remove(self.n))
+            },
+        )?;
+
+        layouter.constrain_instance(out.cell(), config.instance, 3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        explain::{assert_unsatisfied_with, Failure},
+        testing::assert_rejects_near_misses,
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 5;
+
+    #[test]
+    fn test_tribonacci() {
+        let expected = [0, 0, 1, 1, 2, 4, 7, 13, 24, 44, 81, 149].map(Fp::from);
+        assert_eq!(tribonacci([0, 0, 1].map(Fp::from), 11), expected);
+        assert_eq!(tribonacci([1, 2, 3].map(Fp::from), 1).len(), 2);
+
+        // the output in each column, and one of the public inputs
+        for n in [2, 9, 10, 11, 30] {
+            let circuit = TribonacciCircuit::<Fp>::new(n);
+            let instance = tribonacci_instance([0, 0, 1], n);
+            let prover = MockProver::run(K, &circuit, vec![instance]).unwrap();
+            prover.assert_satisfied();
+        }
+        assert_eq!(tribonacci_instance::<Fp>([0, 0, 1], 11)[3], Fp::from(149));
+
+        let circuit = TribonacciCircuit::<Fp>::new(10);
+        let mut instance = tribonacci_instance([0, 0, 1], 10);
+        instance[3] += Fp::one();
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![instance],
+            &[Failure::Copy("A1"), Failure::Copy("I0")],
+        );
+    }
+
+    #[test]
+    fn test_tampered_start() {
+        assert_rejects_near_misses(
+            K,
+            &TribonacciCircuit::<Fp>::new(20),
+            vec![tribonacci_instance([3, 1, 4], 20)],
+            &[],
+        );
+    }
+}
@@ -0,0 +1,316 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::gadgets::{
+    memory::{MemoryAccess, MemoryChip, MemoryConfig},
+    poseidon::{self, PoseidonChip},
+    range_check::RangeCheckChip,
+    tables::TableRegistry,
+};
+
+// A machine whose only state is 256 words of 32-bit memory, all zero to begin
+// with. Results that don't fit in 32 bits make the proof fail rather than wrap.
+#[derive(Debug, Clone, Copy)]
+pub enum Instruction {
+    // mem[addr] = value
+    Set(u8, u32),
+    // mem[dst] = mem[a] + mem[b]
+    Add(u8, u8, u8),
+    // mem[dst] = mem[a] * mem[b]
+    Mul(u8, u8, u8),
+    // makes mem[addr] the next public output
+    Output(u8),
+}
+
+#[derive(Debug, Clone)]
+pub struct VmConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 7],
+    pub q_add: Selector,
+    pub q_mul: Selector,
+    pub instance: Column<Instance>,
+    pub memory: MemoryConfig<F>,
+    pub tables: TableRegistry,
+}
+
+// Proves a public program produces the public outputs. Each instruction gets
+// its own region, and the memory accesses they make are checked all at once
+// by `MemoryChip` at the end, so the values read are free witnesses until
+// then. The instance column lists the outputs in the order they're made.
+#[derive(Default)]
+pub struct VmCircuit<F> {
+    pub program: Vec<Instruction>,
+    // what every read returns, in execution order
+    pub reads: Vec<Value<F>>,
+}
+
+// Runs the program, returning every value read and the outputs.
+pub fn run(program: &[Instruction]) -> (Vec<u64>, Vec<u64>) {
+    let mut memory = [0u64; 256];
+    let mut reads = vec![];
+    let mut outputs = vec![];
+    for instruction in program {
+        match *instruction {
+            Instruction::Set(addr, value) => memory[addr as usize] = value as u64,
+            Instruction::Add(dst, a, b) | Instruction::Mul(dst, a, b) => {
+                let (a, b) = (memory[a as usize], memory[b as usize]);
+                reads.extend([a, b]);
+                memory[dst as usize] = match instruction {
+                    Instruction::Add(..) => a + b,
+                    _ => a * b,
+                };
+            }
+            Instruction::Output(addr) => {
+                reads.push(memory[addr as usize]);
+                outputs.push(memory[addr as usize]);
+            }
+        }
+    }
+    (reads, outputs)
+}
+
+impl<F: FieldExt> VmCircuit<F> {
+    pub fn new(program: &[Instruction]) -> Self {
+        let (reads, _) = run(program);
+        Self {
+            program: program.to_vec(),
+            reads: reads.iter().map(|v| Value::known(F::from(*v))).collect(),
+        }
+    }
+}
+
+// Records an access whose value sits in row 0 of `column`, putting its address
+// and kind in the two rows below.
+fn access<F: FieldExt>(
+    region: &mut Region<'_, F>,
+    column: Column<Advice>,
+    value: AssignedCell<F, F>,
+    addr: u8,
+    is_write: bool,
+) -> Result<MemoryAccess<F>, Error> {
+    let addr = region.assign_advice_from_constant(|| "addr", column, 1, F::from(addr as u64))?;
+    let is_write =
+        region.assign_advice_from_constant(|| "is_write", column, 2, F::from(is_write as u64))?;
+    Ok(MemoryAccess {
+        addr,
+        is_write,
+        value,
+    })
+}
+
+impl<F: FieldExt> Circuit<F> for VmCircuit<F> {
+    type Config = VmConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            program: self.program.clone(),
+            reads: vec![Value::unknown(); self.reads.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 7].map(|_| meta.advice_column());
+        let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+        let q_add = meta.selector();
+        let q_mul = meta.selector();
+
+        meta.enable_equality(instance);
+
+        meta.create_gate("alu", |meta| {
+            //
+            // advice[0] | advice[1] | advice[2] | q_add | q_mul
+            //    a           b           c          1       0
+            //
+            let q_add = meta.query_selector(q_add);
+            let q_mul = meta.query_selector(q_mul);
+            let [a, b, c] = [0, 1, 2].map(|i| meta.query_advice(advice[i], Rotation::cur()));
+            vec![
+                q_add * (a.clone() + b.clone() - c.clone()),
+                q_mul * (a * b - c),
+            ]
+        });
+
+        let mut tables = TableRegistry::default();
+        let bytes = tables.bytes(meta);
+        let range = RangeCheckChip::configure(meta, [advice[5], advice[6]], bytes);
+        let poseidon =
+            PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+        let memory = MemoryChip::configure(meta, advice, range, poseidon);
+
+        VmConfig {
+            advice,
+            q_add,
+            q_mul,
+            instance,
+            memory,
+            tables,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.tables.load(&mut layouter)?;
+
+        let mut reads = self.reads.iter().copied();
+        let mut accesses = vec![];
+        let mut outputs = vec![];
+
+        for instruction in self.program.iter() {
+            let region_accesses = match *instruction {
+                Instruction::Set(addr, value) => layouter.assign_region(
+                    || "set",
+                    |mut region| {
+                        let value = region.assign_advice_from_constant(
+                            || "value",
+                            config.advice[0],
+                            0,
+                            F::from(value as u64),
+                        )?;
+                        Ok(vec![access(
+                            &mut region,
+                            config.advice[0],
+                            value,
+                            addr,
+                            true,
+                        )?])
+                    },
+                )?,
+                Instruction::Add(dst, a, b) | Instruction::Mul(dst, a, b) => {
+                    let (a_value, b_value) = (reads.next().unwrap(), reads.next().unwrap());
+                    let (selector, c_value) = match instruction {
+                        Instruction::Add(..) => (config.q_add, a_value + b_value),
+                        _ => (config.q_mul, a_value * b_value),
+                    };
+                    layouter.assign_region(
+                        || format!("{:?}", instruction),
+                        |mut region| {
+                            selector.enable(&mut region, 0)?;
+
+                            let [col_a, col_b, col_c] = [0, 1, 2].map(|i| config.advice[i]);
+                            let a_cell = region.assign_advice(|| "a", col_a, 0, || a_value)?;
+                            let b_cell = region.assign_advice(|| "b", col_b, 0, || b_value)?;
+                            let c_cell = region.assign_advice(|| "c", col_c, 0, || c_value)?;
+                            Ok(vec![
+                                access(&mut region, col_a, a_cell, a, false)?,
+                                access(&mut region, col_b, b_cell, b, false)?,
+                                access(&mut region, col_c, c_cell, dst, true)?,
+                            ])
+                        },
+                    )?
+                }
+                Instruction::Output(addr) => {
+                    let value = reads.next().unwrap();
+                    let region_accesses = layouter.assign_region(
+                        || "output",
+                        |mut region| {
+                            let value =
+                                region.assign_advice(|| "value", config.advice[0], 0, || value)?;
+                            Ok(vec![access(
+                                &mut region,
+                                config.advice[0],
+                                value,
+                                addr,
+                                false,
+                            )?])
+                        },
+                    )?;
+                    outputs.push(region_accesses[0].value.clone());
+                    region_accesses
+                }
+            };
+            accesses.extend(region_accesses);
+        }
+
+        let memory = MemoryChip::construct(config.memory);
+        memory.check(layouter.namespace(|| "memory"), &accesses)?;
+
+        for (row, output) in outputs.iter().enumerate() {
+            layouter.constrain_instance(output.cell(), config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+    use Instruction::*;
+
+    const K: u32 = 12;
+
+    // fib(2..=8) two at a time in addresses 0 and 1, then (fib(8) + 1) * fib(7)
+    const PROGRAM: [Instruction; 12] = [
+        Set(0, 1),
+        Set(1, 1),
+        Add(0, 0, 1),
+        Add(1, 0, 1),
+        Add(0, 0, 1),
+        Add(1, 0, 1),
+        Add(0, 0, 1),
+        Add(1, 0, 1),
+        Output(1),
+        Set(2, 1),
+        Add(2, 2, 1),
+        Mul(2, 2, 0),
+    ];
+
+    fn public_input(outputs: &[u64]) -> Vec<Vec<Fp>> {
+        vec![outputs.iter().map(|v| Fp::from(*v)).collect()]
+    }
+
+    #[test]
+    fn test_vm() {
+        let _guard = crate::testing::heavy_test();
+        let mut program = PROGRAM.to_vec();
+        program.push(Output(2));
+        let (_, outputs) = run(&program);
+        assert_eq!(outputs, [21, 22 * 13]);
+
+        let circuit = VmCircuit::<Fp>::new(&program);
+        let prover = MockProver::run(K, &circuit, public_input(&outputs)).unwrap();
+        prover.assert_satisfied();
+
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            public_input(&[21, 22 * 13 + 1]),
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+
+        // a read of address 0 returning what it held before the last write
+        let mut reads = run(&program).0;
+        reads[2] = 1;
+        let circuit = VmCircuit::<Fp> {
+            program: program.clone(),
+            reads: reads.iter().map(|v| Value::known(Fp::from(*v))).collect(),
+        };
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            public_input(&outputs),
+            &[Failure::Gate("memory consistency")],
+        );
+
+        // results have to fit in a word
+        let program = [Set(0, u32::MAX), Add(1, 0, 0), Output(1)];
+        let (_, outputs) = run(&program);
+        let circuit = VmCircuit::<Fp>::new(&program);
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            public_input(&outputs),
+            &[
+                Failure::Copy("A2"),
+                Failure::Copy("A5"),
+                Failure::Copy("I0"),
+                Failure::Gate("memory consistency"),
+            ],
+        );
+    }
+}
@@ -0,0 +1,339 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::gadgets::{
+    merkle::{MerkleChip, MerkleConfig},
+    nullifier::{self, NullifierChip, NullifierConfig},
+    poseidon::{self, PoseidonChip},
+};
+
+// Keeps voting nullifiers apart from any other use of the same secret.
+pub const DOMAIN: u64 = 0x766f7465;
+
+pub fn member<F: FieldExt>(secret: F) -> F {
+    poseidon::hash(&[secret])
+}
+
+pub fn ballot<F: FieldExt>(vote: bool, salt: F) -> F {
+    poseidon::hash(&[F::from(vote as u64), salt])
+}
+
+pub fn nullifier_hash<F: FieldExt>(secret: F, election: F) -> F {
+    nullifier::derive(Some(DOMAIN), secret, election)
+}
+
+#[derive(Debug, Clone)]
+pub struct VoteConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 5],
+    pub instance: Column<Instance>,
+    pub q_vote: Selector,
+    pub q_tally: Selector,
+    pub merkle: MerkleConfig<F>,
+    pub nullifier: NullifierConfig<F>,
+}
+
+fn configure<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> VoteConfig<F> {
+    let advice = [(); 5].map(|_| meta.advice_column());
+    let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+    let constants = meta.fixed_column();
+    let instance = meta.instance_column();
+    let q_vote = meta.selector();
+    let q_tally = meta.selector();
+
+    meta.enable_equality(instance);
+
+    let poseidon = PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+    let nullifier = NullifierChip::configure(meta, poseidon.clone(), instance, Some(DOMAIN));
+    let merkle = MerkleChip::configure(meta, advice, poseidon);
+
+    meta.create_gate("vote", |meta| {
+        //
+        // advice[3] | q_vote
+        //   vote        1
+        //
+        let s = meta.query_selector(q_vote);
+        let vote = meta.query_advice(advice[3], Rotation::cur());
+        vec![s * vote.clone() * (Expression::Constant(F::one()) - vote)]
+    });
+
+    meta.create_gate("tally", |meta| {
+        //
+        // advice[3] | advice[4] | q_tally
+        //   vote        acc          1
+        //               acc'
+        //
+        let s = meta.query_selector(q_tally);
+        let vote = meta.query_advice(advice[3], Rotation::cur());
+        let acc = meta.query_advice(advice[4], Rotation::cur());
+        let acc_next = meta.query_advice(advice[4], Rotation::next());
+        vec![s * (acc + vote - acc_next)]
+    });
+
+    VoteConfig {
+        advice,
+        instance,
+        q_vote,
+        q_tally,
+        merkle,
+        nullifier,
+    }
+}
+
+// Casts one ballot. Proves the voter's `H(secret)` is in the census tree, that
+// the ballot commits to a vote of 0 or 1, and derives the voter's nullifier for
+// this election, which the election records so nobody votes twice. The
+// instance column is `[census_root, nullifier_hash, election, ballot]` with
+// `ballot = H(vote, salt)`.
+pub struct BallotCircuit<F, const DEPTH: usize> {
+    pub secret: Value<F>,
+    pub vote: Value<F>,
+    pub salt: Value<F>,
+    pub path: [Value<F>; DEPTH],
+    pub index: Value<u64>,
+}
+
+impl<F: FieldExt, const DEPTH: usize> BallotCircuit<F, DEPTH> {
+    pub fn new(secret: F, vote: bool, salt: F, path: [F; DEPTH], index: u64) -> Self {
+        Self {
+            secret: Value::known(secret),
+            vote: Value::known(F::from(vote as u64)),
+            salt: Value::known(salt),
+            path: path.map(Value::known),
+            index: Value::known(index),
+        }
+    }
+}
+
+impl<F: FieldExt, const DEPTH: usize> Default for BallotCircuit<F, DEPTH> {
+    fn default() -> Self {
+        Self {
+            secret: Value::unknown(),
+            vote: Value::unknown(),
+            salt: Value::unknown(),
+            path: [Value::unknown(); DEPTH],
+            index: Value::unknown(),
+        }
+    }
+}
+
+impl<F: FieldExt, const DEPTH: usize> Circuit<F> for BallotCircuit<F, DEPTH> {
+    type Config = VoteConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let [secret, salt, election, vote] = layouter.assign_region(
+            || "inputs",
+            |mut region| {
+                config.q_vote.enable(&mut region, 0)?;
+                let secret =
+                    region.assign_advice(|| "secret", config.advice[0], 0, || self.secret)?;
+                let salt = region.assign_advice(|| "salt", config.advice[1], 0, || self.salt)?;
+                let election = region.assign_advice_from_instance(
+                    || "election",
+                    config.instance,
+                    2,
+                    config.advice[2],
+                    0,
+                )?;
+                let vote = region.assign_advice(|| "vote", config.advice[3], 0, || self.vote)?;
+                Ok([secret, salt, election, vote])
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.merkle.poseidon.clone());
+        let ballot = poseidon.hash(layouter.namespace(|| "ballot"), &[vote, salt])?;
+        layouter.constrain_instance(ballot.cell(), config.instance, 3)?;
+
+        let member = poseidon.hash(
+            layouter.namespace(|| "member"),
+            std::slice::from_ref(&secret),
+        )?;
+        let merkle = MerkleChip::construct(config.merkle.clone());
+        let root = merkle.compute_root(
+            layouter.namespace(|| "census"),
+            &member,
+            &self.path,
+            self.index,
+        )?;
+        layouter.constrain_instance(root.cell(), config.instance, 0)?;
+
+        let nullifier = NullifierChip::construct(config.nullifier);
+        nullifier.derive_public(
+            layouter.namespace(|| "nullifier hash"),
+            &secret,
+            &election,
+            1,
+        )?;
+        Ok(())
+    }
+}
+
+// Tallies `N` ballots. The tallier, who the voters open their ballots to,
+// proves every ballot opens to a vote of 0 or 1 and that the votes add up to
+// the public total. The instance column is `[ballot_0, .., ballot_{N-1},
+// total]`.
+pub struct TallyCircuit<F, const N: usize> {
+    pub votes: [Value<F>; N],
+    pub salts: [Value<F>; N],
+}
+
+impl<F: FieldExt, const N: usize> TallyCircuit<F, N> {
+    pub fn new(votes: [bool; N], salts: [F; N]) -> Self {
+        Self {
+            votes: votes.map(|vote| Value::known(F::from(vote as u64))),
+            salts: salts.map(Value::known),
+        }
+    }
+}
+
+impl<F: FieldExt, const N: usize> Default for TallyCircuit<F, N> {
+    fn default() -> Self {
+        Self {
+            votes: [Value::unknown(); N],
+            salts: [Value::unknown(); N],
+        }
+    }
+}
+
+impl<F: FieldExt, const N: usize> Circuit<F> for TallyCircuit<F, N> {
+    type Config = VoteConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let (votes, total) = layouter.assign_region(
+            || "tally",
+            |mut region| {
+                let mut acc =
+                    region.assign_advice_from_constant(|| "acc", config.advice[4], 0, F::zero())?;
+                let mut votes = vec![];
+                for (offset, vote) in self.votes.iter().enumerate() {
+                    config.q_vote.enable(&mut region, offset)?;
+                    config.q_tally.enable(&mut region, offset)?;
+                    let vote =
+                        region.assign_advice(|| "vote", config.advice[3], offset, || *vote)?;
+                    let sum = acc.value().copied() + vote.value();
+                    acc = region.assign_advice(|| "acc", config.advice[4], offset + 1, || sum)?;
+                    votes.push(vote);
+                }
+                Ok((votes, acc))
+            },
+        )?;
+        layouter.constrain_instance(total.cell(), config.instance, N)?;
+
+        let poseidon = PoseidonChip::construct(config.merkle.poseidon.clone());
+        for (i, (vote, salt)) in votes.into_iter().zip(self.salts.iter()).enumerate() {
+            let salt = layouter.assign_region(
+                || "salt",
+                |mut region| region.assign_advice(|| "salt", config.advice[1], 0, || *salt),
+            )?;
+            let ballot = poseidon.hash(layouter.namespace(|| "ballot"), &[vote, salt])?;
+            layouter.constrain_instance(ballot.cell(), config.instance, i)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use crate::gadgets::merkle::{merkle_path, merkle_root};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 10;
+    const DEPTH: usize = 3;
+
+    fn verify<C: Circuit<Fp>>(circuit: &C, public_input: Vec<Fp>) -> bool {
+        let prover = MockProver::run(K, circuit, vec![public_input]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_vote() {
+        let secrets: Vec<Fp> = (0..1 << DEPTH).map(|i| Fp::from(0x5ec2e7 + i)).collect();
+        let census: Vec<Fp> = secrets.iter().map(|s| member(*s)).collect();
+        let root = merkle_root(&census);
+        let election = Fp::from(2024);
+
+        let votes = [true, false, true, true, false];
+        let salts: [Fp; 5] = std::array::from_fn(|i| Fp::from(0x5a17 + i as u64));
+        let mut ballots = vec![];
+        for (index, (vote, salt)) in votes.iter().zip(salts.iter()).enumerate() {
+            let path: [Fp; DEPTH] = merkle_path(&census, index).try_into().unwrap();
+            let circuit = BallotCircuit::new(secrets[index], *vote, *salt, path, index as u64);
+            let ballot = ballot(*vote, *salt);
+            let nullifier = nullifier_hash(secrets[index], election);
+            assert!(verify(&circuit, vec![root, nullifier, election, ballot]));
+            ballots.push(ballot);
+        }
+
+        // a vote of 2, and someone outside the census
+        let path: [Fp; DEPTH] = merkle_path(&census, 0).try_into().unwrap();
+        let mut circuit = BallotCircuit::new(secrets[0], true, salts[0], path, 0);
+        circuit.vote = Value::known(Fp::from(2));
+        let public_input = vec![
+            root,
+            nullifier_hash(secrets[0], election),
+            election,
+            poseidon::hash(&[Fp::from(2), salts[0]]),
+        ];
+        assert_unsatisfied_with(K, &circuit, vec![public_input], &[Failure::Gate("vote")]);
+        let outsider = Fp::from(0xbad);
+        let circuit = BallotCircuit::new(outsider, true, salts[0], path, 0);
+        let public_input = vec![
+            root,
+            nullifier_hash(outsider, election),
+            election,
+            ballot(true, salts[0]),
+        ];
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![public_input],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+
+        let circuit = TallyCircuit::new(votes, salts);
+        let public_input = |total: u64| [ballots.clone(), vec![Fp::from(total)]].concat();
+        assert!(verify(&circuit, public_input(3)));
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![public_input(2)],
+            &[Failure::Copy("A4"), Failure::Copy("I0")],
+        );
+
+        // the tallier opening a ballot to the other vote
+        let mut flipped = votes;
+        flipped[1] = true;
+        assert_unsatisfied_with(
+            K,
+            &TallyCircuit::new(flipped, salts),
+            vec![public_input(4)],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+    }
+}
@@ -0,0 +1,311 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::gadgets::{
+    range_check::{RangeCheckChip, RangeCheckConfig},
+    tables::TableRegistry,
+};
+
+pub const VALUE_BYTES: usize = 4;
+pub const WEIGHT_BYTES: usize = 2;
+// The average and the tolerance are fixed point numbers with this many
+// fractional bits.
+pub const SCALE_BITS: u32 = 16;
+pub const AVERAGE_BYTES: usize = 8;
+// Wide enough for 2^SCALE_BITS * sum(w * v) with a few thousand values, so the
+// error bounds can't wrap around.
+pub const ERROR_BYTES: usize = 16;
+
+#[derive(Debug, Clone)]
+pub struct WeightedAverageConfig {
+    pub advice: [Column<Advice>; 6],
+    pub q_sum: Selector,
+    pub q_error: Selector,
+    pub instance: Column<Instance>,
+    pub range: RangeCheckConfig,
+    pub tables: TableRegistry,
+}
+
+// Proves the public fixed point `average` is within `tolerance` (in the same
+// units) of the exact weighted average of private values, sum(w * v) / sum(w),
+// for public weights:
+//
+//   |2^SCALE_BITS * sum(w * v) - average * sum(w)| <= tolerance * sum(w)
+//
+// The instance column is `[average, tolerance, w_0, w_1, ...]`.
+#[derive(Default)]
+pub struct WeightedAverageCircuit<F> {
+    pub values: Vec<Value<F>>,
+}
+
+impl<F: FieldExt> WeightedAverageCircuit<F> {
+    pub fn new(values: &[u64]) -> Self {
+        Self {
+            values: values.iter().map(|v| Value::known(F::from(*v))).collect(),
+        }
+    }
+}
+
+// The weighted average rounded to the nearest fixed point number, which is
+// always within a tolerance of 1.
+pub fn weighted_average(values: &[u64], weights: &[u64]) -> u64 {
+    let total: u128 = values
+        .iter()
+        .zip(weights)
+        .map(|(v, w)| *v as u128 * *w as u128)
+        .sum();
+    let weight: u128 = weights.iter().map(|w| *w as u128).sum();
+    (((total << SCALE_BITS) + weight / 2) / weight) as u64
+}
+
+pub fn weighted_average_instance<F: FieldExt>(
+    average: u64,
+    tolerance: u64,
+    weights: &[u64],
+) -> Vec<F> {
+    [average, tolerance]
+        .iter()
+        .chain(weights.iter())
+        .map(|v| F::from(*v))
+        .collect()
+}
+
+impl<F: FieldExt> Circuit<F> for WeightedAverageCircuit<F> {
+    type Config = WeightedAverageConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            values: vec![Value::unknown(); self.values.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 6].map(|_| meta.advice_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+        let q_sum = meta.selector();
+        let q_error = meta.selector();
+
+        meta.enable_equality(instance);
+        meta.enable_constant(constants);
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("weighted sum", |meta| {
+            //
+            // v   | w   | acc_wv   | acc_w   | q_sum
+            // v_0   w_0   0          0          1
+            // v_1   w_1   acc_wv_1   acc_w_1    1
+            // ...
+            //             acc_wv_n   acc_w_n
+            //
+            let s = meta.query_selector(q_sum);
+            let [v, w, acc_wv, acc_w] =
+                [0, 1, 2, 3].map(|i| meta.query_advice(advice[i], Rotation::cur()));
+            let acc_wv_next = meta.query_advice(advice[2], Rotation::next());
+            let acc_w_next = meta.query_advice(advice[3], Rotation::next());
+            vec![
+                s.clone() * (acc_wv + w.clone() * v - acc_wv_next),
+                s * (acc_w + w - acc_w_next),
+            ]
+        });
+
+        meta.create_gate("error bound", |meta| {
+            //
+            // sum_wv | sum_w | average | tolerance | lo | hi | q_error
+            //
+            // lo = 2^SCALE_BITS * sum_wv - average * sum_w + tolerance * sum_w
+            // hi = 2 * tolerance * sum_w - lo
+            //
+            // both are range checked, so neither can be negative
+            let s = meta.query_selector(q_error);
+            let [sum_wv, sum_w, average, tolerance, lo, hi] =
+                advice.map(|column| meta.query_advice(column, Rotation::cur()));
+            let scale = Expression::Constant(F::from(1 << SCALE_BITS));
+            let two = Expression::Constant(F::from(2));
+            vec![
+                s.clone()
+                    * (scale * sum_wv - average * sum_w.clone()
+                        + tolerance.clone() * sum_w.clone()
+                        - lo.clone()),
+                s * (two * tolerance * sum_w - lo - hi),
+            ]
+        });
+
+        let mut tables = TableRegistry::default();
+        let bytes = tables.bytes(meta);
+        let range = RangeCheckChip::configure(meta, [advice[4], advice[5]], bytes);
+
+        WeightedAverageConfig {
+            advice,
+            q_sum,
+            q_error,
+            instance,
+            range,
+            tables,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let range = RangeCheckChip::construct(config.range.clone());
+        config.tables.load(&mut layouter)?;
+
+        let mut values = vec![];
+        for v in self.values.iter() {
+            values.push(range.witness_checked::<VALUE_BYTES>(layouter.namespace(|| "value"), *v)?);
+        }
+
+        let (average, tolerance, weights) = layouter.assign_region(
+            || "public inputs",
+            |mut region| {
+                let mut cells = vec![];
+                for row in 0..self.values.len() + 2 {
+                    cells.push(region.assign_advice_from_instance(
+                        || "public",
+                        config.instance,
+                        row,
+                        config.advice[0],
+                        row,
+                    )?);
+                }
+                let weights = cells.split_off(2);
+                Ok((cells[0].clone(), cells[1].clone(), weights))
+            },
+        )?;
+        range.range_check::<AVERAGE_BYTES>(layouter.namespace(|| "average"), &average)?;
+        range.range_check::<AVERAGE_BYTES>(layouter.namespace(|| "tolerance"), &tolerance)?;
+        for w in weights.iter() {
+            range.range_check::<WEIGHT_BYTES>(layouter.namespace(|| "weight"), w)?;
+        }
+
+        let (sum_wv, sum_w) = layouter.assign_region(
+            || "weighted sum",
+            |mut region| {
+                let mut acc_wv = region.assign_advice_from_constant(
+                    || "acc_wv",
+                    config.advice[2],
+                    0,
+                    F::zero(),
+                )?;
+                let mut acc_w = region.assign_advice_from_constant(
+                    || "acc_w",
+                    config.advice[3],
+                    0,
+                    F::zero(),
+                )?;
+
+                for (offset, (v, w)) in values.iter().zip(weights.iter()).enumerate() {
+                    config.q_sum.enable(&mut region, offset)?;
+
+                    let v = v.copy_advice(|| "v", &mut region, config.advice[0], offset)?;
+                    let w = w.copy_advice(|| "w", &mut region, config.advice[1], offset)?;
+
+                    let acc_wv_next = acc_wv.value().copied() + w.value().copied() * v.value();
+                    let acc_w_next = acc_w.value().copied() + w.value();
+                    acc_wv = region.assign_advice(
+                        || "acc_wv",
+                        config.advice[2],
+                        offset + 1,
+                        || acc_wv_next,
+                    )?;
+                    acc_w = region.assign_advice(
+                        || "acc_w",
+                        config.advice[3],
+                        offset + 1,
+                        || acc_w_next,
+                    )?;
+                }
+
+                Ok((acc_wv, acc_w))
+            },
+        )?;
+
+        let (lo, hi) = layouter.assign_region(
+            || "error bound",
+            |mut region| {
+                config.q_error.enable(&mut region, 0)?;
+
+                let cells = [&sum_wv, &sum_w, &average, &tolerance]
+                    .iter()
+                    .enumerate()
+                    .map(|(i, cell)| {
+                        cell.copy_advice(|| "operand", &mut region, config.advice[i], 0)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let [sum_wv, sum_w, average, tolerance] =
+                    [0, 1, 2, 3].map(|i| cells[i].value().copied());
+
+                let scale = Value::known(F::from(1 << SCALE_BITS));
+                let lo = scale * sum_wv - average * sum_w + tolerance * sum_w;
+                let hi = Value::known(F::from(2)) * tolerance * sum_w - lo;
+
+                let lo = region.assign_advice(|| "lo", config.advice[4], 0, || lo)?;
+                let hi = region.assign_advice(|| "hi", config.advice[5], 0, || hi)?;
+                Ok((lo, hi))
+            },
+        )?;
+        range.range_check::<ERROR_BYTES>(layouter.namespace(|| "lo"), &lo)?;
+        range.range_check::<ERROR_BYTES>(layouter.namespace(|| "hi"), &hi)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 9;
+
+    #[test]
+    fn test_weighted_average() {
+        // (3 * 90 + 2 * 76 + 5 * 82) / 10 = 83.2, which has no exact fixed point form
+        let values = [90, 76, 82];
+        let weights = [3, 2, 5];
+        let circuit = WeightedAverageCircuit::<Fp>::new(&values);
+
+        let floor = (832 << SCALE_BITS) / 10;
+        let rounded = weighted_average(&values, &weights);
+        assert_eq!(rounded, floor);
+
+        let both = vec![Failure::Copy("A4"), Failure::Copy("A5")];
+        for (average, tolerance, failures) in [
+            (rounded, 1, vec![]),
+            (floor + 1, 1, vec![]),
+            (83 << SCALE_BITS, 1 << (SCALE_BITS - 2), vec![]),
+            // An error outside the tolerance wraps one of the bounds around
+            // the field, and its range check can't put it back together.
+            (83 << SCALE_BITS, 1 << (SCALE_BITS - 3), both.clone()),
+            (rounded + 2, 1, vec![Failure::Copy("A4")]),
+            (rounded - 2, 1, both.clone()),
+            (rounded, 0, both),
+        ] {
+            let public_input = weighted_average_instance(average, tolerance, &weights);
+            if failures.is_empty() {
+                let prover = MockProver::run(K, &circuit, vec![public_input]).unwrap();
+                prover.assert_satisfied();
+            } else {
+                assert_unsatisfied_with(K, &circuit, vec![public_input], &failures);
+            }
+        }
+    }
+
+    #[test]
+    fn test_weighted_average_exact() {
+        let values = [10, 20, 30, 40];
+        let weights = [1, 1, 1, 1];
+        let circuit = WeightedAverageCircuit::<Fp>::new(&values);
+
+        // 25 is exact, so no tolerance is needed
+        let public_input = weighted_average_instance(25 << SCALE_BITS, 0, &weights);
+        let prover = MockProver::run(K, &circuit, vec![public_input]).unwrap();
+        prover.assert_satisfied();
+    }
+}
@@ -0,0 +1,270 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::gadgets::{
+    is_equal::{IsEqualChip, IsEqualConfig},
+    poseidon::{self, PoseidonChip, PoseidonConfig},
+    range_table::RangeTableConfig,
+};
+
+pub const WORD_LEN: usize = 5;
+
+pub const GRAY: u64 = 0;
+pub const YELLOW: u64 = 1;
+pub const GREEN: u64 = 2;
+
+#[derive(Debug, Clone)]
+pub struct WordleConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 7],
+    pub q_letters: Selector,
+    pub q_feedback: Selector,
+    pub instance: Column<Instance>,
+    pub letters: RangeTableConfig,
+    pub is_equal: IsEqualConfig,
+    pub poseidon: PoseidonConfig<F>,
+}
+
+// Proves the feedback for a public guess against a secret word the prover has
+// committed to as `hash(letters || salt)`. The instance column is the
+// commitment, then the guess letters, then the feedback for each of them.
+//
+// Feedback is per letter: GREEN if the secret has it at the same position,
+// YELLOW if it has it anywhere else, GRAY otherwise. Repeated letters are not
+// rationed, so a guess with a letter twice can get YELLOW for both.
+#[derive(Default)]
+pub struct WordleCircuit<F> {
+    pub secret: [Value<F>; WORD_LEN],
+    pub salt: Value<F>,
+}
+
+impl<F: FieldExt> WordleCircuit<F> {
+    pub fn new(secret: &[u8; WORD_LEN], salt: u64) -> Self {
+        Self {
+            secret: secret.map(|l| Value::known(F::from(l as u64))),
+            salt: Value::known(F::from(salt)),
+        }
+    }
+}
+
+pub fn commit<F: FieldExt>(secret: &[u8; WORD_LEN], salt: u64) -> F {
+    let mut message: Vec<F> = secret.iter().map(|l| F::from(*l as u64)).collect();
+    message.push(F::from(salt));
+    poseidon::hash(&message)
+}
+
+pub fn feedback(secret: &[u8; WORD_LEN], guess: &[u8; WORD_LEN]) -> [u64; WORD_LEN] {
+    let mut feedback = [GRAY; WORD_LEN];
+    for (i, l) in guess.iter().enumerate() {
+        if secret[i] == *l {
+            feedback[i] = GREEN;
+        } else if secret.contains(l) {
+            feedback[i] = YELLOW;
+        }
+    }
+    feedback
+}
+
+pub fn wordle_instance<F: FieldExt>(
+    commitment: F,
+    guess: &[u8; WORD_LEN],
+    feedback: &[u64; WORD_LEN],
+) -> Vec<F> {
+    let mut instance = vec![commitment];
+    instance.extend(guess.iter().map(|l| F::from(*l as u64)));
+    instance.extend(feedback.iter().map(|f| F::from(*f)));
+    instance
+}
+
+impl<F: FieldExt> Circuit<F> for WordleCircuit<F> {
+    type Config = WordleConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 7].map(|_| meta.advice_column());
+        let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+        // used by a lookup, so it can't be a simple selector
+        let q_letters = meta.complex_selector();
+        let q_feedback = meta.selector();
+
+        meta.enable_equality(instance);
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        let letters = RangeTableConfig::configure(meta, b'a' as u64, b'z' as u64);
+        for column in advice.iter().take(WORD_LEN) {
+            letters.lookup(meta, q_letters, *column);
+        }
+
+        meta.create_gate("feedback", |meta| {
+            //
+            // advice[0] | advice[1..6] | advice[6] | q_feedback
+            //   green      e_0 .. e_4      f             1
+            //
+            // e_j says whether the guess letter is the secret's j-th letter,
+            // green is a copy of e_i. The product is 0 iff some e_j is 1.
+            let s = meta.query_selector(q_feedback);
+            let green = meta.query_advice(advice[0], Rotation::cur());
+            let f = meta.query_advice(advice[6], Rotation::cur());
+            let one = Expression::Constant(F::one());
+            let absent = (1..=WORD_LEN)
+                .map(|j| one.clone() - meta.query_advice(advice[j], Rotation::cur()))
+                .reduce(|acc, e| acc * e)
+                .unwrap();
+            vec![s * (green + one - absent - f)]
+        });
+
+        let is_equal = IsEqualChip::configure(meta, [advice[0], advice[1], advice[2], advice[3]]);
+        let poseidon =
+            PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+
+        WordleConfig {
+            advice,
+            q_letters,
+            q_feedback,
+            instance,
+            letters,
+            is_equal,
+            poseidon,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.letters.load(&mut layouter)?;
+
+        let (secret, salt) = layouter.assign_region(
+            || "secret",
+            |mut region| {
+                config.q_letters.enable(&mut region, 0)?;
+                let mut secret = vec![];
+                for (i, l) in self.secret.iter().enumerate() {
+                    secret.push(region.assign_advice(|| "letter", config.advice[i], 0, || *l)?);
+                }
+                let salt =
+                    region.assign_advice(|| "salt", config.advice[WORD_LEN], 0, || self.salt)?;
+                Ok((secret, salt))
+            },
+        )?;
+
+        let guess = layouter.assign_region(
+            || "guess",
+            |mut region| {
+                (0..WORD_LEN)
+                    .map(|i| {
+                        region.assign_advice_from_instance(
+                            || "letter",
+                            config.instance,
+                            1 + i,
+                            config.advice[i],
+                            0,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon.clone());
+        let mut message = secret.clone();
+        message.push(salt);
+        let commitment = poseidon.hash(layouter.namespace(|| "commitment"), &message)?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 0)?;
+
+        let is_equal = IsEqualChip::construct(config.is_equal.clone());
+        for (i, g) in guess.iter().enumerate() {
+            let mut matches = vec![];
+            for s in secret.iter() {
+                matches.push(is_equal.is_equal(layouter.namespace(|| "guess == secret"), g, s)?);
+            }
+
+            let f = layouter.assign_region(
+                || "feedback",
+                |mut region| {
+                    config.q_feedback.enable(&mut region, 0)?;
+                    matches[i].copy_advice(|| "green", &mut region, config.advice[0], 0)?;
+                    let mut absent = Value::known(F::one());
+                    for (j, e) in matches.iter().enumerate() {
+                        let e = e.copy_advice(|| "match", &mut region, config.advice[1 + j], 0)?;
+                        absent = absent * (Value::known(F::one()) - e.value());
+                    }
+                    let f = matches[i].value().copied() + Value::known(F::one()) - absent;
+                    region.assign_advice(|| "feedback", config.advice[6], 0, || f)
+                },
+            )?;
+            layouter.constrain_instance(f.cell(), config.instance, 1 + WORD_LEN + i)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[test]
+    fn test_feedback() {
+        assert_eq!(
+            feedback(b"crane", b"caper"),
+            [GREEN, YELLOW, GRAY, YELLOW, YELLOW]
+        );
+        assert_eq!(feedback(b"crane", b"crane"), [GREEN; WORD_LEN]);
+        assert_eq!(feedback(b"crane", b"moist"), [GRAY; WORD_LEN]);
+    }
+
+    #[test]
+    fn test_wordle() {
+        let k = 9;
+
+        let secret = b"crane";
+        let salt = 0x5eed;
+        let commitment = commit::<Fp>(secret, salt);
+        let circuit = WordleCircuit::<Fp>::new(secret, salt);
+
+        for guess in [b"caper", b"crane", b"moist", b"nacre"] {
+            let public_input = wordle_instance(commitment, guess, &feedback(secret, guess));
+            let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // lying about the feedback
+        let public_input =
+            wordle_instance(commitment, b"caper", &[GREEN, GRAY, GRAY, YELLOW, YELLOW]);
+        assert_unsatisfied_with(
+            k,
+            &circuit,
+            vec![public_input],
+            &[Failure::Copy("A6"), Failure::Copy("I0")],
+        );
+
+        // answering for a different secret than the committed one
+        let other = WordleCircuit::<Fp>::new(b"crone", salt);
+        let public_input = wordle_instance(commitment, b"crone", &[GREEN; WORD_LEN]);
+        assert_unsatisfied_with(
+            k,
+            &other,
+            vec![public_input],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+
+        // the secret has to be lowercase letters
+        let upper = WordleCircuit::<Fp>::new(b"CRANE", salt);
+        let public_input = wordle_instance(commit(b"CRANE", salt), b"moist", &[GRAY; WORD_LEN]);
+        assert_unsatisfied_with(
+            k,
+            &upper,
+            vec![public_input],
+            &(0..5).map(Failure::Lookup).collect::<Vec<_>>(),
+        );
+    }
+}
@@ -0,0 +1,730 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::gadgets::{
+    memory::{MemoryAccess, MemoryChip, MemoryConfig},
+    poseidon::{self, PoseidonChip},
+    range_check::RangeCheckChip,
+    tables::TableRegistry,
+};
+
+pub const REGISTERS: usize = 4;
+
+// is_add, is_mul, is_jmpz and is_halt, then one-hot dst, a and b register
+// selectors
+const OPS: usize = 4;
+pub const FLAGS: usize = OPS + 3 * REGISTERS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    // r[dst] = r[a] + r[b]
+    Add(usize, usize, usize),
+    // r[dst] = r[a] * r[b]
+    Mul(usize, usize, usize),
+    // jumps to target if r[a] == 0, falls through otherwise
+    Jmpz(usize, usize),
+    // stops the machine with exit code r[a]
+    Halt(usize),
+}
+
+impl Instruction {
+    // The instruction word: a nonzero opcode and three 2-bit register fields.
+    // Jump targets are kept in a word of their own.
+    pub fn code(&self) -> u64 {
+        let (opcode, dst, a, b) = match *self {
+            Instruction::Add(dst, a, b) => (1, dst, a, b),
+            Instruction::Mul(dst, a, b) => (2, dst, a, b),
+            Instruction::Jmpz(a, _) => (3, 0, a, 0),
+            Instruction::Halt(a) => (4, 0, a, 0),
+        };
+        ((opcode * 4 + dst as u64) * 4 + a as u64) * 4 + b as u64
+    }
+
+    pub fn target(&self) -> u64 {
+        match *self {
+            Instruction::Jmpz(_, target) => target as u64,
+            _ => 0,
+        }
+    }
+
+    fn flags(&self) -> [bool; FLAGS] {
+        let mut flags = [false; FLAGS];
+        let (op, dst, a, b) = match *self {
+            Instruction::Add(dst, a, b) => (0, Some(dst), a, Some(b)),
+            Instruction::Mul(dst, a, b) => (1, Some(dst), a, Some(b)),
+            Instruction::Jmpz(a, _) => (2, None, a, None),
+            Instruction::Halt(a) => (3, None, a, None),
+        };
+        flags[op] = true;
+        if let Some(dst) = dst {
+            flags[OPS + dst] = true;
+        }
+        flags[OPS + REGISTERS + a] = true;
+        if let Some(b) = b {
+            flags[OPS + 2 * REGISTERS + b] = true;
+        }
+        flags
+    }
+
+    // Every instruction word there is, jump targets aside.
+    fn all() -> Vec<Self> {
+        let mut all = vec![];
+        for dst in 0..REGISTERS {
+            for a in 0..REGISTERS {
+                for b in 0..REGISTERS {
+                    all.extend([Instruction::Add(dst, a, b), Instruction::Mul(dst, a, b)]);
+                }
+            }
+        }
+        all.extend((0..REGISTERS).map(|a| Instruction::Jmpz(a, 0)));
+        all.extend((0..REGISTERS).map(Instruction::Halt));
+        all
+    }
+}
+
+// Maps every valid instruction word to its flags. The first row is all zeros,
+// including `valid`, for rows that aren't decoding anything.
+#[derive(Debug, Clone)]
+pub struct DecodeTableConfig {
+    pub code: TableColumn,
+    pub flags: [TableColumn; FLAGS],
+    pub valid: TableColumn,
+}
+
+impl DecodeTableConfig {
+    pub fn configure<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        DecodeTableConfig {
+            code: meta.lookup_table_column(),
+            flags: [(); FLAGS].map(|_| meta.lookup_table_column()),
+            valid: meta.lookup_table_column(),
+        }
+    }
+
+    // `selector` has to be a complex selector.
+    pub fn lookup<F: FieldExt>(
+        &self,
+        meta: &mut ConstraintSystem<F>,
+        selector: Selector,
+        code: Column<Advice>,
+        flags: [Column<Advice>; FLAGS],
+    ) {
+        let table = self.clone();
+        meta.lookup(|meta| {
+            let q = meta.query_selector(selector);
+            let code = meta.query_advice(code, Rotation::cur());
+            let mut lookups = vec![(q.clone() * code, table.code)];
+            for (column, table) in flags.iter().zip(table.flags) {
+                let flag = meta.query_advice(*column, Rotation::cur());
+                lookups.push((q.clone() * flag, table));
+            }
+            lookups.push((q, table.valid));
+            lookups
+        });
+    }
+
+    pub fn load<F: FieldExt>(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "decode",
+            |mut table| {
+                let rows = std::iter::once((0, [false; FLAGS], false)).chain(
+                    Instruction::all()
+                        .into_iter()
+                        .map(|i| (i.code(), i.flags(), true)),
+                );
+                for (offset, (code, flags, valid)) in rows.enumerate() {
+                    table.assign_cell(
+                        || "code",
+                        self.code,
+                        offset,
+                        || Value::known(F::from(code)),
+                    )?;
+                    for (column, flag) in self.flags.iter().zip(flags) {
+                        table.assign_cell(
+                            || "flag",
+                            *column,
+                            offset,
+                            || Value::known(F::from(flag as u64)),
+                        )?;
+                    }
+                    table.assign_cell(
+                        || "valid",
+                        self.valid,
+                        offset,
+                        || Value::known(F::from(valid as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ZkVmConfig<F: FieldExt> {
+    pub pc: Column<Advice>,
+    pub registers: [Column<Advice>; REGISTERS],
+    pub halted: Column<Advice>,
+    // code, target, addr_code, addr_target, pc_slack
+    pub fetch: [Column<Advice>; 5],
+    pub last_pc: Column<Fixed>,
+    // a, b, result, inv, zero
+    pub alu: [Column<Advice>; 5],
+    pub flags: [Column<Advice>; FLAGS],
+    pub q_step: Selector,
+    pub instance: Column<Instance>,
+    pub decode: DecodeTableConfig,
+    pub memory: MemoryConfig<F>,
+    pub tables: TableRegistry,
+}
+
+// Proves that a private program started on the public registers halts within
+// STEPS steps with the public exit code, without revealing the program beyond
+// a Poseidon hash of its words. The instance column is
+// `[commitment, r_0..r_3, exit code]`.
+//
+// The program is loaded into memory as (code, target) word pairs at addresses
+// 2·pc and 2·pc + 1, and every step reads its instruction back out, so
+// `MemoryChip` ties the instructions executed to the committed ones. The
+// instruction word is decoded by a lookup into flags that pick the operands and
+// destination, one row per step, and pc is range checked against the program
+// length before each fetch. Halt sets a flag that freezes pc and the registers
+// for the rest of the steps, and the flag has to be set after the last one.
+#[derive(Default)]
+pub struct ZkVmCircuit<const STEPS: usize> {
+    pub program: Vec<Value<Instruction>>,
+}
+
+impl<const STEPS: usize> ZkVmCircuit<STEPS> {
+    pub fn new(program: &[Instruction]) -> Self {
+        Self {
+            program: program.iter().map(|i| Value::known(*i)).collect(),
+        }
+    }
+}
+
+fn words(program: &[Instruction]) -> Vec<u64> {
+    program
+        .iter()
+        .flat_map(|i| [i.code(), i.target()])
+        .collect()
+}
+
+pub fn commit<F: FieldExt>(program: &[Instruction]) -> F {
+    let message: Vec<F> = words(program).into_iter().map(F::from).collect();
+    poseidon::hash(&message)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct State<F> {
+    pub pc: usize,
+    pub registers: [F; REGISTERS],
+    pub halted: bool,
+}
+
+// The state before each step and after the last one. A pc past the end of the
+// program leaves the machine stuck where it is, which the circuit rejects.
+pub fn execute<F: FieldExt>(
+    program: &[Instruction],
+    registers: [F; REGISTERS],
+    steps: usize,
+) -> Vec<State<F>> {
+    let mut state = State {
+        pc: 0,
+        registers,
+        halted: false,
+    };
+    let mut trace = vec![state];
+    for _ in 0..steps {
+        let pc = state.pc;
+        let r = &mut state.registers;
+        state.pc = match program.get(pc) {
+            Some(Instruction::Add(dst, a, b)) => {
+                r[*dst] = r[*a] + r[*b];
+                pc + 1
+            }
+            Some(Instruction::Mul(dst, a, b)) => {
+                r[*dst] = r[*a] * r[*b];
+                pc + 1
+            }
+            Some(Instruction::Jmpz(a, target)) if r[*a] == F::zero() => *target,
+            Some(Instruction::Jmpz(..)) => pc + 1,
+            Some(Instruction::Halt(_)) => {
+                state.halted = true;
+                pc
+            }
+            None => pc,
+        };
+        trace.push(state);
+    }
+    trace
+}
+
+// The exit code, if the program halts within `steps` steps.
+pub fn run<F: FieldExt>(
+    program: &[Instruction],
+    registers: [F; REGISTERS],
+    steps: usize,
+) -> Option<F> {
+    let last = execute(program, registers, steps)[steps];
+    match program.get(last.pc) {
+        Some(Instruction::Halt(a)) if last.halted => Some(last.registers[*a]),
+        _ => None,
+    }
+}
+
+impl<F: FieldExt, const STEPS: usize> Circuit<F> for ZkVmCircuit<STEPS> {
+    type Config = ZkVmConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            program: vec![Value::unknown(); self.program.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let col_pc = meta.advice_column();
+        let col_registers = [(); REGISTERS].map(|_| meta.advice_column());
+        let col_halted = meta.advice_column();
+        let fetch = [(); 5].map(|_| meta.advice_column());
+        let last_pc = meta.fixed_column();
+        let alu = [(); 5].map(|_| meta.advice_column());
+        let flags = [(); FLAGS].map(|_| meta.advice_column());
+        let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+        let constants = meta.fixed_column();
+        let instance = meta.instance_column();
+        // used by a lookup, so it can't be a simple selector
+        let q_step = meta.complex_selector();
+
+        meta.enable_equality(instance);
+        meta.enable_equality(col_pc);
+        meta.enable_equality(col_halted);
+        for column in col_registers.into_iter().chain(fetch).chain(alu) {
+            meta.enable_equality(column);
+        }
+
+        let [col_code, col_target, col_addr_code, col_addr_target, col_pc_slack] = fetch;
+        let [col_a, col_b, col_result, col_inv, col_zero] = alu;
+
+        let decode = DecodeTableConfig::configure(meta);
+        decode.lookup(meta, q_step, col_code, flags);
+
+        meta.create_gate("step", |meta| {
+            //
+            // pc   | r_0..r_3 | halted | fetch | alu | flags | last_pc | q_step
+            // pc_0   r_0        0        ...     ...   ...     L - 1      1
+            // pc_1   r_1        h_1      ...     ...   ...     L - 1      1
+            // ...
+            // pc_T   r_T        1
+            //
+            // fetch holds the instruction words at pc, their addresses 2·pc and
+            // 2·pc + 1, and L - 1 - pc, which is range checked. alu holds a and
+            // b, the operand registers picked by the flags, the result, and
+            // whether a is 0 for jmpz along with its inverse.
+            let s = meta.query_selector(q_step);
+            let pc = meta.query_advice(col_pc, Rotation::cur());
+            let pc_next = meta.query_advice(col_pc, Rotation::next());
+            let registers = col_registers.map(|c| meta.query_advice(c, Rotation::cur()));
+            let registers_next = col_registers.map(|c| meta.query_advice(c, Rotation::next()));
+            let halted = meta.query_advice(col_halted, Rotation::cur());
+            let halted_next = meta.query_advice(col_halted, Rotation::next());
+            let pc_slack = meta.query_advice(col_pc_slack, Rotation::cur());
+            let last_pc = meta.query_fixed(last_pc, Rotation::cur());
+            let [target, addr_code, addr_target, a, b, result, inv, zero] = [
+                col_target,
+                col_addr_code,
+                col_addr_target,
+                col_a,
+                col_b,
+                col_result,
+                col_inv,
+                col_zero,
+            ]
+            .map(|c| meta.query_advice(c, Rotation::cur()));
+            let flags = flags.map(|c| meta.query_advice(c, Rotation::cur()));
+            let [is_add, is_mul, is_jmpz, is_halt] = [0, 1, 2, 3].map(|i| flags[i].clone());
+            let select = |offset: usize| {
+                (0..REGISTERS).fold(Expression::Constant(F::zero()), |acc, i| {
+                    acc + flags[offset + i].clone() * registers[i].clone()
+                })
+            };
+
+            let one = Expression::Constant(F::one());
+            let two = Expression::Constant(F::from(2));
+            let mut constraints = vec![
+                s.clone() * (last_pc - pc.clone() - pc_slack),
+                s.clone() * (two * pc.clone() - addr_code.clone()),
+                s.clone() * (addr_code + one.clone() - addr_target),
+                s.clone() * (select(OPS + REGISTERS) - a.clone()),
+                s.clone() * (select(OPS + 2 * REGISTERS) - b.clone()),
+                s.clone()
+                    * (is_add * (a.clone() + b.clone()) + is_mul * a.clone() * b - result.clone()),
+                s.clone() * a.clone() * zero.clone(),
+                s.clone() * (a * inv + zero.clone() - one.clone()),
+                s.clone()
+                    * (pc.clone() + one.clone() - is_halt.clone()
+                        + is_jmpz * zero * (target - pc.clone() - one.clone())
+                        - pc_next.clone()),
+                // halted is set by halt and never cleared, and once it's set
+                // nothing changes
+                s.clone() * (halted.clone() + is_halt * (one - halted.clone()) - halted_next),
+                s.clone() * halted.clone() * (pc_next - pc),
+            ];
+            for i in 0..REGISTERS {
+                constraints.push(
+                    s.clone()
+                        * (registers[i].clone()
+                            + flags[OPS + i].clone() * (result.clone() - registers[i].clone())
+                            - registers_next[i].clone()),
+                );
+                constraints.push(
+                    s.clone() * halted.clone() * (registers_next[i].clone() - registers[i].clone()),
+                );
+            }
+            constraints
+        });
+
+        let mut tables = TableRegistry::default();
+        let bytes = tables.bytes(meta);
+        let range = RangeCheckChip::configure(meta, [col_a, col_b], bytes);
+        let [r0, r1, r2, r3] = col_registers;
+        let poseidon = PoseidonChip::configure(meta, [col_pc, r0, r1], rc, constants);
+        let memory_advice = [col_pc, r0, r1, r2, r3, col_code, col_target];
+        let memory = MemoryChip::configure(meta, memory_advice, range, poseidon);
+
+        ZkVmConfig {
+            pc: col_pc,
+            registers: col_registers,
+            halted: col_halted,
+            fetch,
+            last_pc,
+            alu,
+            flags,
+            q_step,
+            instance,
+            decode,
+            memory,
+            tables,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        // 2·pc + 1 has to fit in a byte-sized memory address
+        assert!(!self.program.is_empty() && self.program.len() <= 128);
+
+        config.tables.load(&mut layouter)?;
+        config.decode.load(&mut layouter)?;
+
+        let (col_pc, col_registers) = (config.pc, config.registers);
+        let (fetch, alu) = (config.fetch, config.alu);
+        let [col_code, col_target, col_addr_code, col_addr_target, col_pc_slack] = fetch;
+        let [col_a, col_b, col_result, col_inv, col_zero] = alu;
+
+        let program: Value<Vec<Instruction>> = self.program.iter().copied().collect();
+        let program_words = program
+            .as_ref()
+            .map(|program| words(program))
+            .transpose_vec(2 * self.program.len());
+
+        let (mut accesses, words, read) = layouter.assign_region(
+            || "program",
+            |mut region| {
+                let mut accesses = vec![];
+                let mut words = vec![];
+                for (offset, word) in program_words.iter().enumerate() {
+                    let addr = region.assign_advice_from_constant(
+                        || "addr",
+                        col_addr_code,
+                        offset,
+                        F::from(offset as u64),
+                    )?;
+                    let is_write = region.assign_advice_from_constant(
+                        || "is_write",
+                        col_addr_target,
+                        offset,
+                        F::one(),
+                    )?;
+                    let value =
+                        region.assign_advice(|| "word", col_code, offset, || word.map(F::from))?;
+                    words.push(value.clone());
+                    accesses.push(MemoryAccess {
+                        addr,
+                        is_write,
+                        value,
+                    });
+                }
+                let read = region.assign_advice_from_constant(|| "read", col_zero, 0, F::zero())?;
+                Ok((accesses, words, read))
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.memory.sort.multiset.poseidon.clone());
+        let commitment = poseidon.hash(layouter.namespace(|| "commitment"), &words)?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 0)?;
+
+        let last_pc = F::from(self.program.len() as u64 - 1);
+        let (steps, slacks, exit_code) = layouter.assign_region(
+            || "execution",
+            |mut region| {
+                region.assign_advice_from_constant(|| "pc", col_pc, 0, F::zero())?;
+                region.assign_advice_from_constant(|| "halted", config.halted, 0, F::zero())?;
+                let mut inputs = vec![];
+                for (i, column) in col_registers.iter().enumerate() {
+                    inputs.push(region.assign_advice_from_instance(
+                        || "input",
+                        config.instance,
+                        1 + i,
+                        *column,
+                        0,
+                    )?);
+                }
+
+                let inputs: Value<Vec<F>> = inputs.iter().map(|c| c.value().copied()).collect();
+                let trace = program
+                    .as_ref()
+                    .zip(inputs)
+                    .map(|(program, inputs)| execute(program, inputs.try_into().unwrap(), STEPS))
+                    .transpose_vec(STEPS + 1);
+
+                let mut steps = vec![];
+                let mut slacks = vec![];
+                let mut exit_code = None;
+                for (offset, state) in trace.iter().take(STEPS).enumerate() {
+                    config.q_step.enable(&mut region, offset)?;
+
+                    let state = *state;
+                    // a pc past the end fails the range check whatever gets
+                    // decoded here
+                    let instruction = program.as_ref().zip(state).map(|(program, state)| {
+                        program
+                            .get(state.pc)
+                            .copied()
+                            .unwrap_or(Instruction::Halt(0))
+                    });
+                    let registers = state.map(|state| state.registers);
+                    let flags = instruction.map(|i| i.flags());
+                    let select = |offset: usize| {
+                        flags.zip(registers).map(|(flags, registers)| {
+                            (0..REGISTERS).fold(F::zero(), |acc, i| {
+                                if flags[offset + i] {
+                                    acc + registers[i]
+                                } else {
+                                    acc
+                                }
+                            })
+                        })
+                    };
+                    let (a, b) = (select(OPS + REGISTERS), select(OPS + 2 * REGISTERS));
+                    let result = flags.zip(a.zip(b)).map(|(flags, (a, b))| {
+                        if flags[0] {
+                            a + b
+                        } else if flags[1] {
+                            a * b
+                        } else {
+                            F::zero()
+                        }
+                    });
+                    let inv = a.map(|a| a.invert().unwrap_or(F::zero()));
+                    let zero = a.map(|a| F::from((a == F::zero()) as u64));
+                    let pc = state.map(|state| F::from(state.pc as u64));
+
+                    if offset > 0 {
+                        region.assign_advice(|| "pc", col_pc, offset, || pc)?;
+                        for (i, column) in col_registers.iter().enumerate() {
+                            let r = registers.map(|registers| registers[i]);
+                            region.assign_advice(|| "r", *column, offset, || r)?;
+                        }
+                        let halted = state.map(|state| F::from(state.halted as u64));
+                        region.assign_advice(|| "halted", config.halted, offset, || halted)?;
+                    }
+                    region.assign_fixed(
+                        || "last_pc",
+                        config.last_pc,
+                        offset,
+                        || Value::known(last_pc),
+                    )?;
+                    slacks.push(region.assign_advice(
+                        || "pc_slack",
+                        col_pc_slack,
+                        offset,
+                        || Value::known(last_pc) - pc,
+                    )?);
+                    let code = region.assign_advice(
+                        || "code",
+                        col_code,
+                        offset,
+                        || instruction.map(|i| F::from(i.code())),
+                    )?;
+                    let target = region.assign_advice(
+                        || "target",
+                        col_target,
+                        offset,
+                        || instruction.map(|i| F::from(i.target())),
+                    )?;
+                    let addr_code = region.assign_advice(
+                        || "addr_code",
+                        col_addr_code,
+                        offset,
+                        || pc * Value::known(F::from(2)),
+                    )?;
+                    let addr_target = region.assign_advice(
+                        || "addr_target",
+                        col_addr_target,
+                        offset,
+                        || pc * Value::known(F::from(2)) + Value::known(F::one()),
+                    )?;
+                    exit_code = Some(region.assign_advice(|| "a", col_a, offset, || a)?);
+                    region.assign_advice(|| "b", col_b, offset, || b)?;
+                    region.assign_advice(|| "result", col_result, offset, || result)?;
+                    region.assign_advice(|| "inv", col_inv, offset, || inv)?;
+                    region.assign_advice(|| "zero", col_zero, offset, || zero)?;
+                    for (k, column) in config.flags.iter().enumerate() {
+                        let flag = flags.map(|flags| F::from(flags[k] as u64));
+                        region.assign_advice(|| "flag", *column, offset, || flag)?;
+                    }
+
+                    steps.push([(addr_code, code), (addr_target, target)]);
+                }
+
+                let last = trace[STEPS];
+                region.assign_advice(
+                    || "pc",
+                    col_pc,
+                    STEPS,
+                    || last.map(|state| F::from(state.pc as u64)),
+                )?;
+                for (i, column) in col_registers.iter().enumerate() {
+                    let r = last.map(|state| state.registers[i]);
+                    region.assign_advice(|| "r", *column, STEPS, || r)?;
+                }
+                let halted = region.assign_advice(
+                    || "halted",
+                    config.halted,
+                    STEPS,
+                    || last.map(|state| F::from(state.halted as u64)),
+                )?;
+                region.constrain_constant(halted.cell(), F::one())?;
+
+                // once halted nothing changes, so the last step is the halt
+                // and its a is the exit code
+                Ok((steps, slacks, exit_code.unwrap()))
+            },
+        )?;
+
+        // Together with the 1-byte addresses, this keeps pc within
+        // 0..=last_pc. A pc that wrapped below 0 would have an address far
+        // too big.
+        let range = RangeCheckChip::construct(config.memory.sort.compare.range.clone());
+        for slack in slacks.iter() {
+            range.range_check::<1>(layouter.namespace(|| "pc"), slack)?;
+        }
+
+        for (addr, value) in steps.into_iter().flatten() {
+            accesses.push(MemoryAccess {
+                addr,
+                is_write: read.clone(),
+                value,
+            });
+        }
+        let memory = MemoryChip::construct(config.memory);
+        memory.check(layouter.namespace(|| "fetch"), &accesses)?;
+
+        layouter.constrain_instance(exit_code.cell(), config.instance, 1 + REGISTERS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{assert_unsatisfied_with, Failure};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+    use Instruction::*;
+
+    const K: u32 = 13;
+    const STEPS: usize = 16;
+
+    // x^(2^n) by squaring, with r0 = x, r1 = 0, r2 = n and r3 = -1
+    const PROGRAM: [Instruction; 5] = [Jmpz(2, 4), Mul(0, 0, 0), Add(2, 2, 3), Jmpz(1, 0), Halt(0)];
+
+    fn instance(program: &[Instruction], inputs: [Fp; REGISTERS], exit_code: Fp) -> Vec<Vec<Fp>> {
+        let mut public_input = vec![commit(program)];
+        public_input.extend(inputs);
+        public_input.push(exit_code);
+        vec![public_input]
+    }
+
+    fn inputs(n: u64) -> [Fp; REGISTERS] {
+        [Fp::from(3), Fp::zero(), Fp::from(n), -Fp::one()]
+    }
+
+    #[test]
+    fn test_zkvm() {
+        let _guard = crate::testing::heavy_test();
+        let exit_code = run(&PROGRAM, inputs(3), STEPS).unwrap();
+        assert_eq!(exit_code, Fp::from(6561));
+        let public_input = instance(&PROGRAM, inputs(3), exit_code);
+
+        let circuit = ZkVmCircuit::<STEPS>::new(&PROGRAM);
+        let prover = MockProver::run(K, &circuit, public_input.clone()).unwrap();
+        prover.assert_satisfied();
+
+        // a wrong exit code
+        let wrong = instance(&PROGRAM, inputs(3), exit_code + Fp::one());
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            wrong,
+            &[Failure::Copy("A11"), Failure::Copy("I0")],
+        );
+
+        // running a program other than the committed one
+        let mut other = PROGRAM;
+        other[1] = Add(0, 0, 0);
+        let circuit = ZkVmCircuit::<STEPS>::new(&other);
+        let mut claimed = instance(&other, inputs(3), run(&other, inputs(3), STEPS).unwrap());
+        claimed[0][0] = public_input[0][0];
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            claimed,
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+
+        // four squarings take 18 steps
+        assert_eq!(run(&PROGRAM, inputs(4), STEPS), None);
+        assert!(run(&PROGRAM, inputs(4), 18).is_some());
+        let circuit = ZkVmCircuit::<STEPS>::new(&PROGRAM);
+        let claimed = instance(&PROGRAM, inputs(4), Fp::from(3).pow(&[16, 0, 0, 0]));
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            claimed,
+            &[
+                Failure::Copy("A11"),
+                Failure::Copy("A5"),
+                Failure::Copy("F4"),
+                Failure::Copy("I0"),
+            ],
+        );
+
+        // jumping past the end of the program
+        let program = [Jmpz(1, 7), Halt(0)];
+        let circuit = ZkVmCircuit::<STEPS>::new(&program);
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            instance(&program, inputs(3), Fp::from(3)),
+            &[
+                Failure::Copy("A10"),
+                Failure::Copy("A11"),
+                Failure::Copy("A5"),
+                Failure::Copy("F4"),
+                Failure::Gate("memory consistency"),
+                Failure::Gate("step"),
+            ],
+        );
+    }
+}
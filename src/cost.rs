@@ -0,0 +1,188 @@
+use halo2_proofs::{
+    circuit::Layouter,
+    dev::CircuitCost,
+    pasta::{Eq, Fp},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+use serde::Serialize;
+use std::fmt;
+
+// What a proof of a circuit costs, from halo2's cost model rather than from
+// proving it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CostReport {
+    pub k: u32,
+    pub max_degree: usize,
+    pub advice_columns: usize,
+    pub lookups: usize,
+    pub permutation_columns: usize,
+    // proof size in bytes for one instance, and what each further instance
+    // proved alongside it adds
+    pub proof_size: usize,
+    pub marginal_proof_size: usize,
+    // curve points and field elements in a one-instance proof
+    pub commitments: usize,
+    pub evaluations: usize,
+    // distinct sets of points the multiopen argument opens at
+    pub point_sets: usize,
+    // The verifier's work is dominated by one multiscalar multiplication over
+    // every commitment in the proof plus the 2^k generators the IPA folds;
+    // this is the number of points in it.
+    pub verifier_msm: usize,
+}
+
+// Reads `name: N` out of a cost model's or constraint system's debug output,
+// which is the only place this halo2 version exposes its counts.
+pub(crate) fn read(debug: &str, name: &str) -> usize {
+    let pattern = format!("{}: ", name);
+    let start = debug.find(&pattern).unwrap() + pattern.len();
+    debug[start..]
+        .split(|c: char| !c.is_ascii_digit())
+        .next()
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+// Adds up every `name: N` in a cost model's debug output.
+fn sum(debug: &str, name: &str) -> usize {
+    let pattern = format!("{}: ", name);
+    debug
+        .match_indices(&pattern)
+        .map(|(i, _)| read(&debug[i..], name))
+        .sum()
+}
+
+// `CircuitCost` only has its counts in its debug output, and only derives
+// `Debug` for circuits that have it, which none of the examples do.
+struct Measured<C>(C);
+
+impl<C> fmt::Debug for Measured<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Measured")
+    }
+}
+
+impl<C: Circuit<Fp>> Circuit<Fp> for Measured<C> {
+    type Config = C::Config;
+    type FloorPlanner = C::FloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        self.0.synthesize(config, layouter)
+    }
+}
+
+// The cost of proving `circuit` at `k`. Panics if the circuit doesn't fit in
+// 2^k rows, as `CircuitCost::measure` does.
+pub fn cost_report<C: Circuit<Fp>>(circuit: C, k: u32) -> CostReport {
+    let cost = CircuitCost::<Eq, _>::measure(k as usize, &Measured(circuit));
+    let model = format!("{:?}", cost);
+    let proof = format!("{:?}", cost.proof_size(1));
+    let commitments = sum(&proof, "commitments");
+
+    CostReport {
+        k,
+        max_degree: read(&model, "max_deg"),
+        advice_columns: read(&model, "advice_columns"),
+        lookups: read(&model, "lookups"),
+        permutation_columns: read(&model, "permutation_cols"),
+        proof_size: cost.proof_size(1).into(),
+        marginal_proof_size: cost.marginal_proof_size().into(),
+        commitments,
+        evaluations: sum(&proof, "evaluations"),
+        point_sets: read(&model, "point_sets"),
+        verifier_msm: commitments + (1 << k),
+    }
+}
+
+impl CostReport {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+impl fmt::Display for CostReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "k                    {}", self.k)?;
+        writeln!(f, "max degree           {}", self.max_degree)?;
+        writeln!(
+            f,
+            "columns              {} advice, {} in the permutation",
+            self.advice_columns, self.permutation_columns
+        )?;
+        writeln!(f, "lookups              {}", self.lookups)?;
+        writeln!(
+            f,
+            "proof size           {} B, +{} B per extra instance",
+            self.proof_size, self.marginal_proof_size
+        )?;
+        writeln!(
+            f,
+            "proof contents       {} commitments, {} evaluations",
+            self.commitments, self.evaluations
+        )?;
+        writeln!(f, "multiopen point sets {}", self.point_sets)?;
+        write!(f, "verifier MSM         {} points", self.verifier_msm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        batch::prove_many,
+        example1, example2, example3,
+        fibo::{FiboLayout, FiboPublicInputs},
+    };
+    use halo2_proofs::{
+        circuit::Value,
+        plonk::{keygen_pk, keygen_vk},
+        poly::commitment::Params,
+    };
+
+    #[test]
+    fn test_cost_report() {
+        let _guard = crate::testing::heavy_test();
+        let inputs = FiboPublicInputs::new(Fp::from(1), Fp::from(1));
+        let circuit = example1::MyCircuit::<Fp> {
+            a: Value::known(inputs.a),
+            b: Value::known(inputs.b),
+        };
+        let report = cost_report(circuit.without_witnesses(), 4);
+        assert_eq!(report.advice_columns, 3);
+        assert_eq!(report.lookups, 0);
+        // three advice columns and the instance column
+        assert_eq!(report.permutation_columns, 4);
+        assert_eq!(report.verifier_msm, report.commitments + 16);
+
+        // the model is an estimate, but no smaller than the real proof and
+        // off by no more than a couple of points
+        let params = Params::new(4);
+        let vk = keygen_vk(&params, &circuit).unwrap();
+        let pk = keygen_pk(&params, vk, &circuit).unwrap();
+        let instances = [inputs.to_instances(&FiboLayout::single())];
+        let proof = prove_many(&params, &pk, &[circuit], &instances);
+        assert!(proof.len() <= report.proof_size);
+        assert!(report.proof_size - proof.len() <= 64);
+
+        // fewer advice columns, fewer commitments
+        let example2 = cost_report(example2::MyCircuit::<Fp>::default(), 4);
+        let example3 = cost_report(example3::MyCircuit::<Fp>::default(), 4);
+        assert_eq!(example2.advice_columns, 1);
+        assert_eq!(example3.advice_columns, 2);
+        assert!(example2.commitments < example3.commitments);
+        assert!(example3.commitments < report.commitments);
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["proof_size"], report.proof_size);
+        assert!(report.to_string().contains("3 advice"));
+    }
+}
@@ -0,0 +1,98 @@
+// The three Fibonacci layouts prove the same statement, F[OUT_TERM] from a
+// and b, so given the same public inputs they have to agree on every one: all
+// accept the honest instance, all reject the same near misses of it, and all
+// accept exactly the one output. A refactor that moves one chip's output to
+// another term, or reads a and b from the wrong rows, shows up here as the
+// layouts disagreeing.
+
+use halo2_proofs::pasta::Fp;
+
+use crate::{
+    batch::Instance,
+    fibo::{FiboLayout, FiboPublicInputs, OUT_TERM},
+    spec::{Backend, CircuitSpec, FiboCircuit, Variant},
+    testing::near_misses,
+};
+
+const K: u32 = 4;
+const VARIANTS: [Variant; 3] = [Variant::Example1, Variant::Example2, Variant::Example3];
+
+fn circuits(inputs: &FiboPublicInputs<Fp>) -> Vec<FiboCircuit> {
+    VARIANTS
+        .iter()
+        .map(|variant| {
+            let spec = CircuitSpec {
+                variant: *variant,
+                k: Some(K),
+                steps: OUT_TERM,
+                backend: Backend::Ipa,
+            };
+            spec.build(inputs).unwrap().0
+        })
+        .collect()
+}
+
+// Whether each layout accepts `instance`, checking they all agree.
+#[track_caller]
+fn accepted(circuits: &[FiboCircuit], instance: &Instance, note: &str) -> bool {
+    let accepted: Vec<_> = circuits
+        .iter()
+        .map(|circuit| circuit.mock(K, instance).is_ok())
+        .collect();
+    assert!(
+        accepted.iter().all(|a| *a == accepted[0]),
+        "{}: {:?} accepted {:?}",
+        note,
+        VARIANTS,
+        accepted
+    );
+    accepted[0]
+}
+
+fn terms(a: Fp, b: Fp) -> Vec<Fp> {
+    let mut terms = vec![a, b];
+    while terms.len() < OUT_TERM + 2 {
+        terms.push(terms[terms.len() - 2] + terms[terms.len() - 1]);
+    }
+    terms
+}
+
+#[test]
+fn test_layouts_agree() {
+    let big = Fp::from_raw([u64::MAX, 3, 0, 1 << 60]);
+    for (a, b) in [
+        (Fp::from(1), Fp::from(1)),
+        (Fp::from(0), Fp::from(0)),
+        (Fp::from(0), Fp::from(1)),
+        (Fp::from(2), Fp::from(3)),
+        (-Fp::one(), Fp::from(1)),
+        (-Fp::one(), -Fp::one()),
+        (Fp::from(u64::MAX), Fp::from(7)),
+        (big, -big),
+    ] {
+        let inputs = FiboPublicInputs::new(a, b);
+        let circuits = circuits(&inputs);
+        let instance = inputs.to_instances(&FiboLayout::single());
+        let name = format!("a = {:?}, b = {:?}", a, b);
+        assert!(accepted(&circuits, &instance, &name), "{}: rejected", name);
+
+        for (note, instance) in near_misses(&instance) {
+            let note = format!("{}, {}", name, note);
+            assert!(!accepted(&circuits, &instance, &note), "{}: accepted", note);
+        }
+
+        // every other term as the output, which is where an off-by-one in
+        // one of the tables would land
+        for (n, out) in terms(a, b).into_iter().enumerate() {
+            let claimed = FiboPublicInputs { out, ..inputs };
+            let instance = claimed.to_instances(&FiboLayout::single());
+            let note = format!("{}, out = F[{}]", name, n);
+            assert_eq!(
+                accepted(&circuits, &instance, &note),
+                out == inputs.out,
+                "{}",
+                note
+            );
+        }
+    }
+}
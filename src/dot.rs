@@ -0,0 +1,199 @@
+use halo2_proofs::{
+    dev::{CircuitGates, MockProver},
+    pasta::Fp,
+    plonk::{Circuit, ConstraintSystem},
+};
+use std::collections::BTreeMap;
+
+use crate::{batch::Instance, cost::read};
+
+// Columns by the names `CircuitGates` gives them, `A0`, `F0`, `I0` and `S0`,
+// in the order the permutation argument would list them.
+fn columns(pinned: &str) -> Vec<String> {
+    let mut columns = vec![];
+    for (prefix, name) in [
+        ("A", "num_advice_columns"),
+        ("F", "num_fixed_columns"),
+        ("I", "num_instance_columns"),
+        ("S", "num_selectors"),
+    ] {
+        columns.extend((0..read(pinned, name)).map(|i| format!("{}{}", prefix, i)));
+    }
+    columns
+}
+
+// `Column { index: 0, column_type: Advice }, ..` as `A0, ..`.
+fn parse_columns(list: &str) -> Vec<String> {
+    list.split("Column { ")
+        .skip(1)
+        .map(|column| {
+            let index = read(column, "index");
+            let prefix = match column.split("column_type: ").nth(1) {
+                Some(t) if t.starts_with("Advice") => "A",
+                Some(t) if t.starts_with("Fixed") => "F",
+                _ => "I",
+            };
+            format!("{}{}", prefix, index)
+        })
+        .collect()
+}
+
+// The columns in the permutation argument, from a pinned constraint system.
+fn equality_columns(pinned: &str) -> Vec<String> {
+    let start = pinned.find("permutation: Argument { columns: [").unwrap();
+    let end = start + pinned[start..].find(']').unwrap();
+    parse_columns(&pinned[start..end])
+}
+
+// How many cells each pair of equality columns has tied together, from the
+// permutation a mock prover run built. Each cell points at the next cell in
+// its copy cycle; every one that points into another column counts.
+fn copies(debug: &str) -> BTreeMap<(String, String), usize> {
+    let start = debug.find("permutation: Assembly { columns: [").unwrap();
+    let assembly = &debug[start..];
+    let columns = parse_columns(&assembly[..assembly.find(']').unwrap()]);
+    let mapping = &assembly[assembly.find("mapping: [").unwrap() + "mapping: [".len()..];
+    let mapping = &mapping[..mapping.find("]]").unwrap() + 1];
+
+    let mut copies = BTreeMap::new();
+    for (from, cells) in mapping.split("], [").enumerate() {
+        for cell in cells.split("), (") {
+            let cell = cell.trim_matches(|c| "[]() ".contains(c));
+            let to: usize = cell.split(", ").next().unwrap().parse().unwrap();
+            if to != from {
+                let pair = if from < to { (from, to) } else { (to, from) };
+                let pair = (columns[pair.0].clone(), columns[pair.1].clone());
+                *copies.entry(pair).or_insert(0) += 1;
+            }
+        }
+    }
+    copies
+}
+
+fn graph<C: Circuit<Fp>>(copies: &BTreeMap<(String, String), usize>) -> String {
+    let mut cs = ConstraintSystem::<Fp>::default();
+    C::configure(&mut cs);
+    let pinned = format!("{:?}", cs.pinned());
+    let equality = equality_columns(&pinned);
+
+    let mut out = String::from("digraph circuit {\n  rankdir=LR;\n");
+    for column in columns(&pinned) {
+        let (kind, shape) = match &column[..1] {
+            "A" => ("advice", "box"),
+            "F" => ("fixed", "box"),
+            "I" => ("instance", "box"),
+            _ => ("selector", "diamond"),
+        };
+        // equality-enabled columns get a double border
+        let peripheries = if equality.contains(&column) { 2 } else { 1 };
+        out.push_str(&format!(
+            "  {} [label=\"{} {}\", shape={}, peripheries={}];\n",
+            column,
+            kind,
+            &column[1..],
+            shape,
+            peripheries
+        ));
+    }
+
+    // One node per gate, with an edge to each column its constraints query,
+    // labelled with the rotations.
+    let csv = CircuitGates::collect::<Fp, C>().queries_to_csv();
+    let mut lines = csv.lines();
+    let header: Vec<&str> = lines.next().unwrap().split(',').collect();
+    let queries = &header[..header.len() - 1];
+    let mut gates: Vec<(String, BTreeMap<String, Vec<String>>)> = vec![];
+    for line in lines {
+        let fields: Vec<&str> = line.splitn(queries.len() + 1, ',').collect();
+        let gate = fields[queries.len()].split('/').next().unwrap().to_string();
+        if gates.last().map(|(name, _)| name) != Some(&gate) {
+            gates.push((gate, BTreeMap::new()));
+        }
+        let used = &mut gates.last_mut().unwrap().1;
+        for (query, flag) in queries.iter().zip(&fields) {
+            if *flag == "1" {
+                let (column, rotation) = query.split_once('@').unwrap_or((query, ""));
+                let rotations = used.entry(column.to_string()).or_default();
+                if !rotation.is_empty() && !rotations.iter().any(|r| r == rotation) {
+                    rotations.push(rotation.to_string());
+                }
+            }
+        }
+    }
+    for (i, (name, used)) in gates.iter().enumerate() {
+        out.push_str(&format!("  gate{} [label={:?}, shape=ellipse];\n", i, name));
+        for (column, rotations) in used {
+            // selectors aren't queried at a rotation
+            if rotations.is_empty() {
+                out.push_str(&format!("  gate{} -> {};\n", i, column));
+            } else {
+                out.push_str(&format!(
+                    "  gate{} -> {} [label=\"{}\"];\n",
+                    i,
+                    column,
+                    rotations.join(", ")
+                ));
+            }
+        }
+    }
+
+    for ((a, b), n) in copies {
+        out.push_str(&format!(
+            "  {} -> {} [dir=none, style=dashed, color=red, label=\"{}\"];\n",
+            a, b, n
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+// `C`'s columns, its gates with the columns they query, and which columns
+// have equality enabled, as a Graphviz graph.
+pub fn dot_graph<C: Circuit<Fp>>() -> String {
+    graph::<C>(&BTreeMap::new())
+}
+
+// `dot_graph`, plus a dashed edge between every two columns that `circuit`
+// copies cells between when the mock prover runs it at `k`, labelled with how
+// many cells are involved.
+pub fn dot_graph_with_copies<C: Circuit<Fp>>(
+    k: u32,
+    circuit: &C,
+    instance: Instance,
+) -> Result<String, String> {
+    let prover = MockProver::run(k, circuit, instance).map_err(|e| format!("{:?}", e))?;
+    Ok(graph::<C>(&copies(&format!("{:?}", prover))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        example1, example2,
+        fibo::{FiboLayout, FiboPublicInputs},
+    };
+
+    #[test]
+    fn test_dot_graph() {
+        let dot = dot_graph::<example1::MyCircuit<Fp>>();
+        assert!(dot.starts_with("digraph circuit {"));
+        // three equality-enabled advice columns, the instance column and
+        // the add gate's selector
+        assert_eq!(dot.matches("peripheries=2").count(), 4);
+        assert!(dot.contains("S0 [label=\"selector 0\", shape=diamond, peripheries=1]"));
+        assert!(dot.contains("gate0 [label=\"add\", shape=ellipse]"));
+        for column in ["A0", "A1", "A2"] {
+            assert!(dot.contains(&format!("gate0 -> {} [label=\"0\"]", column)));
+        }
+        assert!(dot.contains("gate0 -> S0;"));
+        assert!(!dot.contains("dashed"));
+
+        // example2 reads a and b from the instance column and exposes out
+        let instance =
+            FiboPublicInputs::new(Fp::from(1), Fp::from(1)).to_instances(&FiboLayout::single());
+        let dot =
+            dot_graph_with_copies(4, &example2::MyCircuit::<Fp>::default(), instance).unwrap();
+        assert!(dot.contains("gate0 -> A0 [label=\"0, 1, 2\"]"));
+        assert!(dot.contains("A0 -> I0 [dir=none, style=dashed, color=red, label=\"6\"]"));
+    }
+}
@@ -0,0 +1,119 @@
+// Calldata for an on-chain verifier, in the layout the verifiers generated by
+// snark-verifier read: every instance value as a 32-byte big-endian word,
+// column by column, then the proof bytes as they are.
+//
+// halo2 at this revision only proves with IPA over the Pasta curves, which no
+// EVM verifier checks, so nothing here produces calldata a contract accepts
+// yet. The encoding is generic over the field, so proofs made with KZG over
+// bn256 encode the same way.
+
+use halo2_proofs::arithmetic::FieldExt;
+
+const WORD: usize = 32;
+
+fn word<F: FieldExt>(value: &F) -> [u8; WORD] {
+    let repr = value.to_repr();
+    let mut word = [0; WORD];
+    word.copy_from_slice(repr.as_ref());
+    // field elements are little-endian, EVM words big-endian
+    word.reverse();
+    word
+}
+
+// The calldata for `proof` of `instances`.
+pub fn encode_calldata<F: FieldExt>(instances: &[Vec<F>], proof: &[u8]) -> Vec<u8> {
+    let mut calldata: Vec<u8> = instances.iter().flatten().flat_map(word).collect();
+    calldata.extend_from_slice(proof);
+    calldata
+}
+
+// Splits calldata from `encode_calldata` back into its instance columns, of
+// `rows` rows each, and the proof.
+pub fn decode_calldata<F: FieldExt>(
+    calldata: &[u8],
+    rows: &[usize],
+) -> Result<(Vec<Vec<F>>, Vec<u8>), String> {
+    let words: usize = rows.iter().sum();
+    if calldata.len() < words * WORD {
+        return Err(format!(
+            "expected {} instance words, found {} bytes",
+            words,
+            calldata.len()
+        ));
+    }
+    let (values, proof) = calldata.split_at(words * WORD);
+
+    let mut values = values.chunks(WORD).map(|chunk| {
+        let mut repr = F::Repr::default();
+        repr.as_mut().copy_from_slice(chunk);
+        repr.as_mut().reverse();
+        Option::from(F::from_repr(repr))
+            .ok_or_else(|| format!("0x{} isn't a field element", hex(chunk)))
+    });
+    let instances = rows
+        .iter()
+        .map(|n| values.by_ref().take(*n).collect())
+        .collect::<Result<_, _>>()?;
+    Ok((instances, proof.to_vec()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fibo::{FiboLayout, FiboPublicInputs},
+        testing::non_canonical_encodings,
+    };
+    use halo2_proofs::pasta::Fp;
+
+    #[test]
+    fn test_calldata() {
+        let inputs = FiboPublicInputs::new(Fp::from(1), -Fp::one());
+        let layout = FiboLayout::split();
+        let instances = inputs.to_instances(&layout);
+        let proof = [7u8; 100];
+
+        let calldata = encode_calldata(&instances, &proof);
+        assert_eq!(calldata.len(), 3 * WORD + proof.len());
+        // a = 1 as a big-endian word
+        assert_eq!(calldata[..WORD - 1], [0; WORD - 1]);
+        assert_eq!(calldata[WORD - 1], 1);
+        // b = p - 1
+        assert_eq!(
+            hex(&calldata[WORD..2 * WORD]),
+            "40000000000000000000000000000000224698fc094cf91b992d30ed00000000"
+        );
+        assert_eq!(
+            decode_calldata(&calldata, &layout.rows()),
+            Ok((instances.clone(), proof.to_vec()))
+        );
+
+        // each value written as itself plus a multiple of the modulus
+        for (i, value) in instances.iter().flatten().enumerate() {
+            for (note, mut repr) in non_canonical_encodings(value) {
+                repr.reverse();
+                let mut calldata = calldata.clone();
+                calldata[i * WORD..(i + 1) * WORD].copy_from_slice(&repr);
+                assert!(
+                    decode_calldata::<Fp>(&calldata, &layout.rows()).is_err(),
+                    "row {}: {}",
+                    i,
+                    note
+                );
+            }
+        }
+
+        // no proof at all, too little calldata, and a word past the modulus
+        let empty = encode_calldata(&instances, &[]);
+        assert_eq!(
+            decode_calldata::<Fp>(&empty, &layout.rows()),
+            Ok((instances, vec![]))
+        );
+        assert!(decode_calldata::<Fp>(&empty[1..], &layout.rows()).is_err());
+        assert!(decode_calldata::<Fp>(&[0xff; WORD], &[1]).is_err());
+    }
+}
@@ -2,24 +2,27 @@ use std::marker::PhantomData;
 
 use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
 
+use crate::fibo::{FiboLayout, InstanceCell};
+
 #[derive(Debug, Clone)]
 struct ACell<F: FieldExt>(AssignedCell<F, F>);
 
 #[derive(Debug, Clone)]
-struct FiboConfig {
+pub struct FiboConfig<const N: usize> {
     pub advice: [Column<Advice>; 3],
     pub selector: Selector,
-    pub instance: Column<Instance>,
+    pub instance: [Column<Instance>; N],
+    pub layout: FiboLayout,
 }
 
 #[derive(Debug, Clone)]
-struct FiboChip<F: FieldExt> {
-    config: FiboConfig,
+struct FiboChip<F: FieldExt, const N: usize> {
+    config: FiboConfig<N>,
     _marker: PhantomData<F>,
 }
 
-impl<F: FieldExt> FiboChip<F> {
-    pub fn construct(config: FiboConfig) -> Self {
+impl<F: FieldExt, const N: usize> FiboChip<F, N> {
+    pub fn construct(config: FiboConfig<N>) -> Self {
         Self {
             config,
             _marker: PhantomData,
@@ -29,8 +32,10 @@ impl<F: FieldExt> FiboChip<F> {
     pub fn configure(
         meta: &mut ConstraintSystem<F>,
         advice: [Column<Advice>; 3],
-        instance: Column<Instance>,
-    ) -> FiboConfig {
+        instance: [Column<Instance>; N],
+        layout: FiboLayout,
+    ) -> FiboConfig<N> {
+        assert!(layout.columns() <= N, "layout needs more instance columns");
         let col_a = advice[0];
         let col_b = advice[1];
         let col_c = advice[2];
@@ -39,7 +44,9 @@ impl<F: FieldExt> FiboChip<F> {
         meta.enable_equality(col_a);
         meta.enable_equality(col_b);
         meta.enable_equality(col_c);
-        meta.enable_equality(instance);
+        for column in instance {
+            meta.enable_equality(column);
+        }
 
         meta.create_gate("add", |meta| {
             //
@@ -57,6 +64,7 @@ impl<F: FieldExt> FiboChip<F> {
             advice: [col_a, col_b, col_c],
             selector,
             instance,
+            layout,
         }
     }
 
@@ -122,20 +130,22 @@ impl<F: FieldExt> FiboChip<F> {
         &self,
         mut layouter: impl Layouter<F>,
         cell: &ACell<F>,
-        row: usize,
+        at: InstanceCell,
     ) -> Result<(), Error> {
-        layouter.constrain_instance(cell.0.cell(), self.config.instance, row)
+        layouter.constrain_instance(cell.0.cell(), self.config.instance[at.column], at.row)
     }
 }
 
+// With one instance column it holds `[a, b, out]`; with more, the output goes
+// in a column of its own.
 #[derive(Default)]
-struct MyCircuit<F> {
+pub struct MyCircuit<F, const N: usize = 1> {
     pub a: Value<F>,
     pub b: Value<F>,
 }
 
-impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
-    type Config = FiboConfig;
+impl<F: FieldExt, const N: usize> Circuit<F> for MyCircuit<F, N> {
+    type Config = FiboConfig<N>;
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
@@ -146,8 +156,9 @@ impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
         let col_a = meta.advice_column();
         let col_b = meta.advice_column();
         let col_c = meta.advice_column();
-        let instance = meta.instance_column();
-        FiboChip::configure(meta, [col_a, col_b, col_c], instance)
+        let instance = [(); N].map(|_| meta.instance_column());
+        let layout = if N == 1 { FiboLayout::single() } else { FiboLayout::split() };
+        FiboChip::configure(meta, [col_a, col_b, col_c], instance, layout)
     }
 
     fn synthesize(
@@ -155,13 +166,14 @@ impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
         config: Self::Config,
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
+        let layout = config.layout;
         let chip = FiboChip::construct(config);
 
         let (prev_a, mut prev_b, mut prev_c) =
             chip.assign_first_row(layouter.namespace(|| "first row"), self.a, self.b)?;
 
-        chip.expose_public(layouter.namespace(|| "private a"), &prev_a, 0)?;
-        chip.expose_public(layouter.namespace(|| "private b"), &prev_b, 1)?;
+        chip.expose_public(layouter.namespace(|| "private a"), &prev_a, layout.a)?;
+        chip.expose_public(layouter.namespace(|| "private b"), &prev_b, layout.b)?;
 
         for _i in 3..10 {
             let c_cell = chip.assign_row(layouter.namespace(|| "next row"), &prev_b, &prev_c)?;
@@ -169,7 +181,7 @@ impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
             prev_c = c_cell;
         }
 
-        chip.expose_public(layouter.namespace(|| "out"), &prev_c, 2)?;
+        chip.expose_public(layouter.namespace(|| "out"), &prev_c, layout.out)?;
 
         Ok(())
     }
@@ -178,6 +190,10 @@ impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
 #[cfg(test)]
 mod tests {
     use super::MyCircuit;
+    use crate::{
+        explain::{assert_unsatisfied_with, Failure},
+        fibo::{FiboLayout, FiboPublicInputs},
+    };
     use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
 
     #[test]
@@ -188,37 +204,62 @@ mod tests {
         let b = Fp::from(1); // F[1]
         let out = Fp::from(55); // F[9]
 
-        let circuit = MyCircuit {
+        let circuit = MyCircuit::<Fp> {
             a: Value::known(a),
             b: Value::known(b),
         };
 
-        let mut public_input = vec![a, b, out];
+        let public_inputs = FiboPublicInputs::new(a, b);
+        assert_eq!(public_inputs.out, out);
+        let mut public_input = public_inputs.to_instances(&FiboLayout::single());
+
+        let prover = MockProver::run(k, &circuit, public_input.clone()).unwrap();
+        prover.assert_satisfied();
+
+        // the output no longer matches the cell it's copied from
+        public_input[0][2] += Fp::one();
+        assert_unsatisfied_with(
+            k,
+            &circuit,
+            public_input,
+            &[Failure::Copy("A2"), Failure::Copy("I0")],
+        );
+    }
+
+    #[test]
+    fn test_example1_split_instance() {
+        let k = 4;
 
-        let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
+        let public_inputs = FiboPublicInputs::new(Fp::from(1), Fp::from(1));
+        let public_input = public_inputs.to_instances(&FiboLayout::split());
+        let circuit = MyCircuit::<Fp, 2> {
+            a: Value::known(public_inputs.a),
+            b: Value::known(public_inputs.b),
+        };
+
+        let prover = MockProver::run(k, &circuit, public_input).unwrap();
         prover.assert_satisfied();
 
-        public_input[2] += Fp::one();
-        let _prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
-        // uncomment the following line and the assert will fail
-        // _prover.assert_satisfied();
+        // the single column layout doesn't fit
+        let mut public_input = public_inputs.to_instances(&FiboLayout::single());
+        public_input.push(vec![]);
+        let prover = MockProver::run(k, &circuit, public_input).unwrap();
+        assert!(prover.verify().is_err());
     }
 
     #[cfg(feature = "dev-graph")]
     #[test]
     fn plot_fibo1() {
-        use plotters::prelude::*;
-
-        let root = BitMapBackend::new("fib-1-layout.png", (1024, 3096)).into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let root = root.titled("Fib 1 Layout", ("sans-serif", 60)).unwrap();
+        use crate::layout::{render_layout, Format, LayoutOptions};
 
         let circuit = MyCircuit::<Fp> {
             a: Value::unknown(),
             b: Value::unknown(),
         };
-        halo2_proofs::dev::CircuitLayout::default()
-            .render(4, &circuit, &root)
-            .unwrap();
+        let options = LayoutOptions {
+            title: Some("Fib 1 Layout".to_string()),
+            ..LayoutOptions::default()
+        };
+        render_layout(&circuit, 4, "fib-1-layout.png", Format::Png, &options).unwrap();
     }
 }
\ No newline at end of file
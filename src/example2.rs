@@ -1,24 +1,27 @@
 use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
 use std::marker::PhantomData;
 
+use crate::fibo::{fibonacci_sequence, FiboLayout, InstanceCell};
+
 #[derive(Debug, Clone)]
 struct ACell<F: FieldExt>(AssignedCell<F, F>);
 
 #[derive(Debug, Clone)]
-struct FiboConfig {
+pub struct FiboConfig<const N: usize> {
     advice: Column<Advice>,
     selector: Selector,
-    instance: Column<Instance>,
+    instance: [Column<Instance>; N],
+    layout: FiboLayout,
 }
 
 #[derive(Debug, Clone)]
-struct FiboChip<F: FieldExt> {
-    config: FiboConfig,
+struct FiboChip<F: FieldExt, const N: usize> {
+    config: FiboConfig<N>,
     _marker: PhantomData<F>,
 }
 
-impl<F: FieldExt> FiboChip<F> {
-    pub fn construct(config: FiboConfig) -> Self {
+impl<F: FieldExt, const N: usize> FiboChip<F, N> {
+    pub fn construct(config: FiboConfig<N>) -> Self {
         Self {
             config,
             _marker: PhantomData,
@@ -28,13 +31,17 @@ impl<F: FieldExt> FiboChip<F> {
     pub fn configure(
         meta: &mut ConstraintSystem<F>,
         advice: Column<Advice>,
-        instance: Column<Instance>,
-    ) -> FiboConfig {
+        instance: [Column<Instance>; N],
+        layout: FiboLayout,
+    ) -> FiboConfig<N> {
+        assert!(layout.columns() <= N, "layout needs more instance columns");
         let selector = meta.selector();
 
         // copy constraint を追加するために enable_equality で有効化する必要がある
         meta.enable_equality(advice);
-        meta.enable_equality(instance);
+        for column in instance {
+            meta.enable_equality(column);
+        }
 
         meta.create_gate("add", |meta| {
             //
@@ -54,6 +61,7 @@ impl<F: FieldExt> FiboChip<F> {
             advice,
             selector,
             instance,
+            layout,
         }
     }
 
@@ -61,28 +69,37 @@ impl<F: FieldExt> FiboChip<F> {
         &self,
         mut layouter: impl Layouter<F>,
         nrows: usize,
-    ) -> Result<AssignedCell<F, F>, Error> {
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
         layouter.assign_region(
             || "entire fibonacci table",
             |mut region| {
                 self.config.selector.enable(&mut region, 0)?;
                 self.config.selector.enable(&mut region, 1)?;
 
-                let mut a_cell = region.assign_advice_from_instance(
+                let (a, b) = (self.config.layout.a, self.config.layout.b);
+                let a_cell = region.assign_advice_from_instance(
                     || "1",
-                    self.config.instance,
-                    0,
+                    self.config.instance[a.column],
+                    a.row,
                     self.config.advice,
                     0,
                 )?;
-                let mut b_cell = region.assign_advice_from_instance(
+                let b_cell = region.assign_advice_from_instance(
                     || "1",
-                    self.config.instance,
-                    1,
+                    self.config.instance[b.column],
+                    b.row,
                     self.config.advice,
                     1,
                 )?;
 
+                // the whole table worked out on the host first, then assigned
+                // row by row
+                let table = a_cell
+                    .value()
+                    .zip(b_cell.value())
+                    .map(|(a, b)| fibonacci_sequence(*a, *b, nrows));
+                let mut cells = vec![a_cell, b_cell];
+
                 // 2 <= row <= 9
                 for row in 2..nrows {
 
@@ -92,18 +109,15 @@ impl<F: FieldExt> FiboChip<F> {
                         self.config.selector.enable(&mut region, row)?;
                     }
 
-                    let c_cell = region.assign_advice(
+                    cells.push(region.assign_advice(
                         || "advice",
                         self.config.advice,
                         row,
-                        || a_cell.value().copied() + b_cell.value(),
-                    )?;
-
-                    a_cell = b_cell;
-                    b_cell = c_cell;
+                        || table.as_ref().map(|table| table[row]),
+                    )?);
                 }
 
-                Ok(b_cell)
+                Ok(cells)
             },
         )
     }
@@ -112,50 +126,104 @@ impl<F: FieldExt> FiboChip<F> {
         &self,
         mut layouter: impl Layouter<F>,
         cell: AssignedCell<F, F>,
-        row: usize,
+        at: InstanceCell,
     ) -> Result<(), Error> {
         // cell が instance の row で指定されところと一致する constraint を作成
-        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+        layouter.constrain_instance(cell.cell(), self.config.instance[at.column], at.row)
+    }
+
+    // Every term against its row of the first instance column, for
+    // `FiboLayout::trace`.
+    pub fn expose_trace(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cells: &[AssignedCell<F, F>],
+    ) -> Result<(), Error> {
+        for (row, cell) in cells.iter().enumerate() {
+            layouter.constrain_instance(cell.cell(), self.config.instance[0], row)?;
+        }
+        Ok(())
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use halo2_proofs::{dev::MockProver, pasta::Fp};
+// With one instance column it holds `[a, b, out]`; with more, the output goes
+// in a column of its own.
+#[derive(Default)]
+pub struct MyCircuit<F, const N: usize = 1>(PhantomData<F>);
 
-    #[derive(Default)]
-    struct MyCircuit<F>(PhantomData<F>);
+impl<F: FieldExt, const N: usize> Circuit<F> for MyCircuit<F, N> {
+    type Config = FiboConfig<N>;
+    type FloorPlanner = SimpleFloorPlanner;
 
-    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
-        type Config = FiboConfig;
-        type FloorPlanner = SimpleFloorPlanner;
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
 
-        fn without_witnesses(&self) -> Self {
-            Self::default()
-        }
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = meta.advice_column();
+        let instance = [(); N].map(|_| meta.instance_column());
+        let layout = if N == 1 { FiboLayout::single() } else { FiboLayout::split() };
+        FiboChip::configure(meta, advice, instance, layout)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let layout = config.layout;
+        let chip = FiboChip::construct(config);
+
+        let mut cells = chip.assign(layouter.namespace(|| "entire table"), 10)?;
 
-        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-            let advice = meta.advice_column();
-            let instance = meta.instance_column();
-            FiboChip::configure(meta, advice, instance)
+        if layout.trace {
+            chip.expose_trace(layouter.namespace(|| "trace"), &cells)?;
+        } else {
+            let out_cell = cells.pop().unwrap();
+            chip.expose_public(layouter.namespace(|| "out"), out_cell, layout.out)?;
         }
 
-        fn synthesize(
-            &self,
-            config: Self::Config,
-            mut layouter: impl Layouter<F>,
-        ) -> Result<(), Error> {
-            let chip = FiboChip::construct(config);
+        Ok(())
+    }
+}
 
-            let out_cell = chip.assign(layouter.namespace(|| "entire table"), 10)?;
+// MyCircuit with every term public, `[F[0], .., F[9]]` down one instance
+// column.
+#[derive(Default)]
+pub struct TraceCircuit<F>(PhantomData<F>);
 
-            chip.expose_public(layouter.namespace(|| "out"), out_cell, 2)?;
+impl<F: FieldExt> Circuit<F> for TraceCircuit<F> {
+    type Config = FiboConfig<1>;
+    type FloorPlanner = SimpleFloorPlanner;
 
-            Ok(())
-        }
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = meta.advice_column();
+        let instance = [meta.instance_column()];
+        FiboChip::configure(meta, advice, instance, FiboLayout::trace())
     }
 
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        MyCircuit::<F, 1>(PhantomData).synthesize(config, layouter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        explain::{assert_unsatisfied_with, Failure},
+        fibo::FiboPublicInputs,
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
     #[test]
     fn test_example2() {
         let k = 4;
@@ -164,30 +232,81 @@ mod tests {
         let b = Fp::from(1); // F[1]
         let out = Fp::from(55); // F[9]
 
-        let circuit = MyCircuit(PhantomData);
+        let circuit = MyCircuit::<Fp>(PhantomData);
+
+        let public_inputs = FiboPublicInputs::new(a, b);
+        assert_eq!(public_inputs.out, out);
+        let mut public_input = public_inputs.to_instances(&FiboLayout::single());
+
+        let prover = MockProver::run(k, &circuit, public_input.clone()).unwrap();
+        prover.assert_satisfied();
+
+        // the output no longer matches the cell it's copied from
+        public_input[0][2] += Fp::one();
+        assert_unsatisfied_with(
+            k,
+            &circuit,
+            public_input,
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+    }
+
+    #[test]
+    fn test_example2_split_instance() {
+        let k = 4;
+
+        let public_inputs = FiboPublicInputs::new(Fp::from(1), Fp::from(1));
+        let mut public_input = public_inputs.to_instances(&FiboLayout::split());
+        let circuit = MyCircuit::<Fp, 2>(PhantomData);
+
+        let prover = MockProver::run(k, &circuit, public_input.clone()).unwrap();
+        prover.assert_satisfied();
 
-        let mut public_input = vec![a, b, out];
+        public_input[1][0] += Fp::one();
+        assert_unsatisfied_with(
+            k,
+            &circuit,
+            public_input,
+            &[Failure::Copy("A0"), Failure::Copy("I1")],
+        );
+    }
 
-        let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
+    #[test]
+    fn test_example2_trace() {
+        let k = 4;
+
+        let public_inputs = FiboPublicInputs::new(Fp::from(1), Fp::from(1));
+        let public_input = public_inputs.to_instances(&FiboLayout::trace());
+        assert_eq!(public_input[0].len(), 10);
+        let circuit = TraceCircuit::<Fp>(PhantomData);
+
+        let prover = MockProver::run(k, &circuit, public_input.clone()).unwrap();
         prover.assert_satisfied();
 
-        public_input[2] += Fp::one();
-        let _prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
-        // uncomment the following line and the assert will fail
-        // _prover.assert_satisfied();
+        // a or b changes every term after them, and any other term stops
+        // matching its cell
+        for row in 0..10 {
+            let mut tampered = public_input.clone();
+            tampered[0][row] += Fp::one();
+            assert_unsatisfied_with(
+                k,
+                &circuit,
+                tampered,
+                &[Failure::Copy("A0"), Failure::Copy("I0")],
+            );
+        }
     }
 
     #[cfg(feature = "dev-graph")]
     #[test]
     fn plot_fibo2() {
-        use plotters::prelude::*;
-        let root = BitMapBackend::new("fib-2-layout.png", (1024, 3096)).into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let root = root.titled("Fib 2 Layout", ("sans-serif", 60)).unwrap();
+        use crate::layout::{render_layout, Format, LayoutOptions};
 
         let circuit = MyCircuit::<Fp>(PhantomData);
-        halo2_proofs::dev::CircuitLayout::default()
-            .render(4, &circuit, &root)
-            .unwrap();
+        let options = LayoutOptions {
+            title: Some("Fib 2 Layout".to_string()),
+            ..LayoutOptions::default()
+        };
+        render_layout(&circuit, 4, "fib-2-layout.png", Format::Png, &options).unwrap();
     }
 }
\ No newline at end of file
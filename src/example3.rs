@@ -1,24 +1,27 @@
 use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
 use std::marker::PhantomData;
 
+use crate::fibo::{fibonacci_sequence, FiboLayout, InstanceCell};
+
 #[derive(Debug, Clone)]
 struct ACell<F: FieldExt>(AssignedCell<F, F>);
 
 #[derive(Debug, Clone)]
-struct FiboConfig {
-    advice: [Column<Advice>; 2],
-    selector: Selector,
-    instance: Column<Instance>,
+pub struct FiboConfig<const N: usize> {
+    pub advice: [Column<Advice>; 2],
+    pub selector: Selector,
+    pub instance: [Column<Instance>; N],
+    pub layout: FiboLayout,
 }
 
 #[derive(Debug, Clone)]
-struct FiboChip<F: FieldExt> {
-    config: FiboConfig,
+struct FiboChip<F: FieldExt, const N: usize> {
+    config: FiboConfig<N>,
     _marker: PhantomData<F>,
 }
 
-impl<F: FieldExt> FiboChip<F> {
-    pub fn construct(config: FiboConfig) -> Self {
+impl<F: FieldExt, const N: usize> FiboChip<F, N> {
+    pub fn construct(config: FiboConfig<N>) -> Self {
         Self {
             config,
             _marker: PhantomData,
@@ -28,8 +31,10 @@ impl<F: FieldExt> FiboChip<F> {
     pub fn configure(
         meta: &mut ConstraintSystem<F>,
         advice: [Column<Advice>; 2],
-        instance: Column<Instance>,
-    ) -> FiboConfig {
+        instance: [Column<Instance>; N],
+        layout: FiboLayout,
+    ) -> FiboConfig<N> {
+        assert!(layout.columns() <= N, "layout needs more instance columns");
         let col_a = advice[0];
         let col_b = advice[1];
         let selector = meta.selector();
@@ -37,7 +42,9 @@ impl<F: FieldExt> FiboChip<F> {
         // copy constraint を追加するために enable_equality で有効化する必要がある
         meta.enable_equality(col_a);
         meta.enable_equality(col_b);
-        meta.enable_equality(instance);
+        for column in instance {
+            meta.enable_equality(column);
+        }
 
         meta.create_gate("add1", |meta| {
             //
@@ -56,6 +63,7 @@ impl<F: FieldExt> FiboChip<F> {
             advice: [col_a, col_b],
             selector,
             instance,
+            layout,
         }
     }
 
@@ -70,58 +78,50 @@ impl<F: FieldExt> FiboChip<F> {
 
                 self.config.selector.enable(&mut region, 0)?;
 
-                let mut a_cell = region.assign_advice_from_instance(
+                let (a, b) = (self.config.layout.a, self.config.layout.b);
+                let a_cell = region.assign_advice_from_instance(
                     || "1",
-                    self.config.instance,
-                    0,
+                    self.config.instance[a.column],
+                    a.row,
                     self.config.advice[0],
                     0,
                 )?;
 
                 let mut b_cell = region.assign_advice_from_instance(
                     || "1",
-                    self.config.instance,
-                    0,
+                    self.config.instance[b.column],
+                    b.row,
                     self.config.advice[1],
                     0,
                 )?;
 
-                self.config.selector.enable(&mut region, 1)?;
+                // the whole table worked out on the host first, two terms a
+                // row, then assigned row by row
+                let table = a_cell
+                    .value()
+                    .zip(b_cell.value())
+                    .map(|(a, b)| fibonacci_sequence(*a, *b, 2 * nrows));
 
-                a_cell = region.assign_advice(
-                    || "advice",
-                    self.config.advice[0],
-                    1,
-                    || a_cell.value().copied() + b_cell.value().copied(),
-                )?;
-
-                b_cell = region.assign_advice(
-                    || "advice",
-                    self.config.advice[1],
-                    1,
-                    || a_cell.value().copied() + b_cell.value().copied(),
-                )?;
-
-                for row in 2.. nrows {
+                for row in 1..nrows {
                     if row < nrows-1 {
                         self.config.selector.enable(&mut region, row)?;
                     }
 
-                    a_cell = region.assign_advice(
+                    region.assign_advice(
                         || "advice",
                         self.config.advice[0],
                         row,
-                        || a_cell.value().copied() + b_cell.value().copied(),
+                        || table.as_ref().map(|table| table[2 * row]),
                     )?;
 
                     b_cell = region.assign_advice(
                         || "advice",
                         self.config.advice[1],
                         row,
-                        || a_cell.value().copied() + b_cell.value().copied(),
+                        || table.as_ref().map(|table| table[2 * row + 1]),
                     )?;
 
-                }       
+                }
 
                 Ok(b_cell)
             },
@@ -132,49 +132,57 @@ impl<F: FieldExt> FiboChip<F> {
         &self,
         mut layouter: impl Layouter<F>,
         cell: AssignedCell<F, F>,
-        row: usize,
+        at: InstanceCell,
     ) -> Result<(), Error> {
-        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+        layouter.constrain_instance(cell.cell(), self.config.instance[at.column], at.row)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use halo2_proofs::{dev::MockProver, pasta::Fp};
-
-    #[derive(Default)]
-    struct MyCircuit<F>(PhantomData<F>);
+// With one instance column it holds `[a, b, out]`; with more, the output goes
+// in a column of its own.
+#[derive(Default)]
+pub struct MyCircuit<F, const N: usize = 1>(PhantomData<F>);
 
-    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
-        type Config = FiboConfig;
-        type FloorPlanner = SimpleFloorPlanner;
+impl<F: FieldExt, const N: usize> Circuit<F> for MyCircuit<F, N> {
+    type Config = FiboConfig<N>;
+    type FloorPlanner = SimpleFloorPlanner;
 
-        fn without_witnesses(&self) -> Self {
-            Self::default()
-        }
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
 
-        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-            let col_a = meta.advice_column();
-            let col_b = meta.advice_column();
-            let instance = meta.instance_column();
-            FiboChip::configure(meta, [col_a, col_b], instance)
-        }
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let col_a = meta.advice_column();
+        let col_b = meta.advice_column();
+        let instance = [(); N].map(|_| meta.instance_column());
+        let layout = if N == 1 { FiboLayout::single() } else { FiboLayout::split() };
+        FiboChip::configure(meta, [col_a, col_b], instance, layout)
+    }
 
-        fn synthesize(
-            &self,
-            config: Self::Config,
-            mut layouter: impl Layouter<F>,
-        ) -> Result<(), Error> {
-            let chip = FiboChip::construct(config);
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let out = config.layout.out;
+        let chip = FiboChip::construct(config);
 
-            let out_cell = chip.assign(layouter.namespace(|| "entire table"), 5)?;
+        let out_cell = chip.assign(layouter.namespace(|| "entire table"), 5)?;
 
-            chip.expose_public(layouter.namespace(|| "out"), out_cell, 2)?;
+        chip.expose_public(layouter.namespace(|| "out"), out_cell, out)?;
 
-            Ok(())
-        }
+        Ok(())
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        explain::{assert_unsatisfied_with, Failure},
+        fibo::FiboPublicInputs,
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
 
     #[test]
     fn test_example3() {
@@ -184,30 +192,67 @@ mod tests {
         let b = Fp::from(1); // F[1]
         let out = Fp::from(55); // F[9]
 
-        let circuit = MyCircuit(PhantomData);
+        let circuit = MyCircuit::<Fp>(PhantomData);
+
+        let public_inputs = FiboPublicInputs::new(a, b);
+        assert_eq!(public_inputs.out, out);
+        let mut public_input = public_inputs.to_instances(&FiboLayout::single());
+
+        let prover = MockProver::run(k, &circuit, public_input.clone()).unwrap();
+        prover.assert_satisfied();
+
+        // the output no longer matches the cell it's copied from
+        public_input[0][2] += Fp::one();
+        assert_unsatisfied_with(
+            k,
+            &circuit,
+            public_input,
+            &[Failure::Copy("A1"), Failure::Copy("I0")],
+        );
+    }
+
+    // b comes from row 1 of the instance; with a = b, reading it from row 0
+    // as well would still pass
+    #[test]
+    fn test_example3_distinct_start() {
+        let public_inputs = FiboPublicInputs::new(Fp::from(2), Fp::from(3));
+        assert_eq!(public_inputs.out, Fp::from(144));
+        let public_input = public_inputs.to_instances(&FiboLayout::single());
+
+        let prover = MockProver::run(4, &MyCircuit::<Fp>(PhantomData), public_input).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_example3_split_instance() {
+        let k = 4;
 
-        let mut public_input = vec![a, b, out];
+        let public_inputs = FiboPublicInputs::new(Fp::from(1), Fp::from(1));
+        let mut public_input = public_inputs.to_instances(&FiboLayout::split());
+        let circuit = MyCircuit::<Fp, 2>(PhantomData);
 
-        let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
+        let prover = MockProver::run(k, &circuit, public_input.clone()).unwrap();
         prover.assert_satisfied();
 
-        // public_input[2] += Fp::one();
-        // let _prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
-        // uncomment the following line and the assert will fail
-        // _prover.assert_satisfied();
+        public_input[1][0] += Fp::one();
+        assert_unsatisfied_with(
+            k,
+            &circuit,
+            public_input,
+            &[Failure::Copy("A1"), Failure::Copy("I1")],
+        );
     }
 
     #[cfg(feature = "dev-graph")]
     #[test]
     fn plot_fibo3() {
-        use plotters::prelude::*;
-        let root = BitMapBackend::new("fib-3-layout.png", (1024, 3096)).into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let root = root.titled("Fib 3 Layout", ("sans-serif", 60)).unwrap();
+        use crate::layout::{render_layout, Format, LayoutOptions};
 
         let circuit = MyCircuit::<Fp>(PhantomData);
-        halo2_proofs::dev::CircuitLayout::default()
-            .render(4, &circuit, &root)
-            .unwrap();
+        let options = LayoutOptions {
+            title: Some("Fib 3 Layout".to_string()),
+            ..LayoutOptions::default()
+        };
+        render_layout(&circuit, 4, "fib-3-layout.png", Format::Png, &options).unwrap();
     }
 }
\ No newline at end of file
@@ -0,0 +1,283 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use crate::fibo::{fibonacci_sequence, FiboLayout, InstanceCell};
+
+#[derive(Debug, Clone)]
+pub struct FiboConfig<const N: usize> {
+    advice: Column<Advice>,
+    selector: Selector,
+    instance: [Column<Instance>; N],
+    layout: FiboLayout,
+}
+
+#[derive(Debug, Clone)]
+struct FiboChip<F: FieldExt, const N: usize> {
+    config: FiboConfig<N>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const N: usize> FiboChip<F, N> {
+    pub fn construct(config: FiboConfig<N>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: Column<Advice>,
+        instance: [Column<Instance>; N],
+        layout: FiboLayout,
+    ) -> FiboConfig<N> {
+        assert!(layout.columns() <= N, "layout needs more instance columns");
+        let selector = meta.selector();
+
+        meta.enable_equality(advice);
+        for column in instance {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("add", |meta| {
+            //
+            // advice | selector
+            //   a    |
+            //   b    |
+            //   c    |   s
+            //
+            // example2's gate, looking back from the row it makes instead of
+            // forward from the first row it adds
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(advice, Rotation(-2));
+            let b = meta.query_advice(advice, Rotation::prev());
+            let c = meta.query_advice(advice, Rotation::cur());
+            vec![s * (a + b - c)]
+        });
+
+        FiboConfig {
+            advice,
+            selector,
+            instance,
+            layout,
+        }
+    }
+
+    pub fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        nrows: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        // every row but the two a and b are in
+        self.assign_enabled(layouter, nrows, 2..nrows)
+    }
+
+    // `assign` with the selector on for the rows in `enabled`.
+    fn assign_enabled(
+        &self,
+        mut layouter: impl Layouter<F>,
+        nrows: usize,
+        enabled: Range<usize>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "entire fibonacci table",
+            |mut region| {
+                for row in enabled.clone() {
+                    self.config.selector.enable(&mut region, row)?;
+                }
+
+                let (a, b) = (self.config.layout.a, self.config.layout.b);
+                let a_cell = region.assign_advice_from_instance(
+                    || "1",
+                    self.config.instance[a.column],
+                    a.row,
+                    self.config.advice,
+                    0,
+                )?;
+                let mut b_cell = region.assign_advice_from_instance(
+                    || "1",
+                    self.config.instance[b.column],
+                    b.row,
+                    self.config.advice,
+                    1,
+                )?;
+
+                let table = a_cell
+                    .value()
+                    .zip(b_cell.value())
+                    .map(|(a, b)| fibonacci_sequence(*a, *b, nrows));
+
+                for row in 2..nrows {
+                    b_cell = region.assign_advice(
+                        || "advice",
+                        self.config.advice,
+                        row,
+                        || table.as_ref().map(|table| table[row]),
+                    )?;
+                }
+
+                Ok(b_cell)
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: AssignedCell<F, F>,
+        at: InstanceCell,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance[at.column], at.row)
+    }
+}
+
+// example2's single column table, with the gate written with `Rotation::prev()`.
+// With one instance column it holds `[a, b, out]`; with more, the output goes
+// in a column of its own.
+#[derive(Default)]
+pub struct MyCircuit<F, const N: usize = 1>(PhantomData<F>);
+
+impl<F: FieldExt, const N: usize> Circuit<F> for MyCircuit<F, N> {
+    type Config = FiboConfig<N>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = meta.advice_column();
+        let instance = [(); N].map(|_| meta.instance_column());
+        let layout = if N == 1 {
+            FiboLayout::single()
+        } else {
+            FiboLayout::split()
+        };
+        FiboChip::configure(meta, advice, instance, layout)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let out = config.layout.out;
+        let chip = FiboChip::construct(config);
+
+        let out_cell = chip.assign(layouter.namespace(|| "entire table"), 10)?;
+
+        chip.expose_public(layouter.namespace(|| "out"), out_cell, out)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        explain::{assert_unsatisfied_with, Failure},
+        fibo::FiboPublicInputs,
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[test]
+    fn test_example4() {
+        let k = 4;
+
+        let public_inputs = FiboPublicInputs::new(Fp::from(1), Fp::from(1));
+        assert_eq!(public_inputs.out, Fp::from(55));
+        let mut public_input = public_inputs.to_instances(&FiboLayout::single());
+
+        let circuit = MyCircuit::<Fp>(PhantomData);
+        let prover = MockProver::run(k, &circuit, public_input.clone()).unwrap();
+        prover.assert_satisfied();
+
+        // the output no longer matches the cell it's copied from
+        public_input[0][2] += Fp::one();
+        assert_unsatisfied_with(
+            k,
+            &circuit,
+            public_input,
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+    }
+
+    #[test]
+    fn test_example4_split_instance() {
+        let k = 4;
+
+        let public_inputs = FiboPublicInputs::new(Fp::from(1), Fp::from(1));
+        let mut public_input = public_inputs.to_instances(&FiboLayout::split());
+        let circuit = MyCircuit::<Fp, 2>(PhantomData);
+
+        let prover = MockProver::run(k, &circuit, public_input.clone()).unwrap();
+        prover.assert_satisfied();
+
+        public_input[1][0] += Fp::one();
+        assert_unsatisfied_with(
+            k,
+            &circuit,
+            public_input,
+            &[Failure::Copy("A0"), Failure::Copy("I1")],
+        );
+    }
+
+    // MyCircuit with the selector on for other rows than `assign` picks.
+    struct Enabled(Range<usize>);
+
+    impl Circuit<Fp> for Enabled {
+        type Config = FiboConfig<1>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self(self.0.clone())
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            MyCircuit::<Fp>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let out = config.layout.out;
+            let chip = FiboChip::construct(config);
+            let out_cell =
+                chip.assign_enabled(layouter.namespace(|| "table"), 10, self.0.clone())?;
+            chip.expose_public(layouter.namespace(|| "out"), out_cell, out)
+        }
+    }
+
+    // example2 turns its selector on for rows 0 to 7, the rows a sum starts
+    // on, and the two rows after the last one read the rows below. Looking
+    // back, it's on for rows 2 to 9 instead, the rows a sum ends on.
+    #[test]
+    fn test_enabled_rows() {
+        let k = 4;
+        let instance =
+            FiboPublicInputs::new(Fp::from(1), Fp::from(1)).to_instances(&FiboLayout::single());
+
+        let prover = MockProver::run(k, &Enabled(2..10), instance.clone()).unwrap();
+        prover.assert_satisfied();
+
+        // On row 0 or 1 the gate reads rows -2 and -1, which wrap around to
+        // the last two rows of the column: nothing is assigned there, and the
+        // prover fills them with random blinding values.
+        assert_unsatisfied_with(
+            k,
+            &Enabled(0..10),
+            instance.clone(),
+            &[Failure::Unassigned("add"), Failure::Poisoned("add")],
+        );
+
+        // Off for rows 8 and 9, nothing says the output is the sum of the
+        // two terms before it. The honest table still passes, which is why
+        // a range like that is easy to miss.
+        let prover = MockProver::run(k, &Enabled(2..8), instance).unwrap();
+        prover.assert_satisfied();
+    }
+}
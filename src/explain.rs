@@ -0,0 +1,430 @@
+use halo2_proofs::{
+    dev::{FailureLocation, MockProver, VerifyFailure},
+    pasta::{group::ff::PrimeField, Fp},
+    plonk::Circuit,
+};
+
+use crate::cost::read;
+
+// `Column('Advice', 0)` as `A0`.
+fn short_column(column: &str) -> String {
+    let column = column.trim_start_matches("Column(");
+    let prefix = &column[1..2];
+    let index = column
+        .split(", ")
+        .nth(1)
+        .unwrap()
+        .split(|c: char| !c.is_ascii_digit())
+        .next()
+        .unwrap();
+    format!("{}{}", prefix, index)
+}
+
+// A cell as `A0@1`, with its name after it if it has one.
+fn short_cell(cell: &str) -> String {
+    let (column, rotation) = cell.split_once(")@").unwrap();
+    format!("{}@{}", short_column(column), rotation)
+}
+
+// Reads back a value as the mock prover prints it: `0`, `1`, `-1` or hex.
+fn parse_value(value: &str) -> Option<Fp> {
+    match value {
+        "0" => Some(Fp::zero()),
+        "1" => Some(Fp::one()),
+        "-1" => Some(-Fp::one()),
+        _ => {
+            let hex = value.strip_prefix("0x")?;
+            if hex.is_empty() || hex.len() > 64 {
+                return None;
+            }
+            let hex = format!("{:0>64}", hex);
+            let mut repr = [0u8; 32];
+            for (i, byte) in repr.iter_mut().rev().enumerate() {
+                *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+            }
+            Option::from(Fp::from_repr(repr))
+        }
+    }
+}
+
+// The mock prover's own notation for a value, so the table reads the same as
+// its cells.
+fn format_value(value: Fp) -> String {
+    if value == Fp::zero() {
+        "0".to_string()
+    } else if value == Fp::one() {
+        "1".to_string()
+    } else if value == -Fp::one() {
+        "-1".to_string()
+    } else {
+        format!("0x{}", format!("{:?}", value)[2..].trim_start_matches('0'))
+    }
+}
+
+// The region and the row in the whole circuit a failure is at. Regions only
+// have their first row in the prover's debug output, in order.
+fn locate(debug: &str, location: &FailureLocation) -> (String, String) {
+    match location {
+        FailureLocation::InRegion { region, offset } => {
+            let region = region.to_string();
+            let index: usize = region["Region ".len()..]
+                .split(' ')
+                .next()
+                .unwrap()
+                .parse()
+                .unwrap();
+            let row = debug
+                .match_indices("rows: ")
+                .nth(index)
+                .and_then(|(i, _)| debug[i..].strip_prefix("rows: Some(("))
+                .map(|rows| {
+                    let first: usize = rows[..rows.find(',').unwrap()].parse().unwrap();
+                    (first + offset).to_string()
+                })
+                .unwrap_or_else(|| format!("offset {}", offset));
+            let name = region["Region ".len()..].to_string();
+            (name, row)
+        }
+        FailureLocation::OutsideRegion { row } => ("-".to_string(), row.to_string()),
+    }
+}
+
+// `add` out of `Gate 0 ('add')` or `Constraint 0 in gate 0 ('add')`.
+fn gate_name(gate: &str) -> &str {
+    gate[gate.rfind("('").unwrap() + 2..].trim_end_matches("')")
+}
+
+// The `add` gates in the Fibonacci examples constrain one cell to the sum of
+// two others. Read in row order, and column order within a row, the sum is
+// the last of the three.
+fn fibonacci_step(gate: &str, cells: &[(String, String)]) -> Option<String> {
+    if !gate.starts_with("add") || cells.len() != 3 {
+        return None;
+    }
+    let mut cells = cells.to_vec();
+    cells.sort_by_key(|(cell, _)| {
+        let (column, rotation) = cell.split_once('@').unwrap();
+        let rotation: i32 = rotation
+            .trim_end_matches(|c: char| !c.is_ascii_digit())
+            .parse()
+            .unwrap();
+        (rotation, column.to_string())
+    });
+    let a = parse_value(&cells[0].1)?;
+    let b = parse_value(&cells[1].1)?;
+    Some(format!(
+        "{} + {} = {}, got {}",
+        cells[0].0,
+        cells[1].0,
+        format_value(a + b),
+        cells[2].1
+    ))
+}
+
+// One line of the table: what failed, where, and the cells involved.
+fn explain(debug: &str, failure: &VerifyFailure) -> [String; 5] {
+    match failure {
+        VerifyFailure::ConstraintNotSatisfied {
+            constraint,
+            location,
+            cell_values,
+        } => {
+            let constraint = constraint.to_string();
+            let gate = gate_name(&constraint);
+            let (region, row) = locate(debug, location);
+            let cells: Vec<(String, String)> = cell_values
+                .iter()
+                .map(|(cell, value)| (short_cell(&cell.to_string()), value.clone()))
+                .collect();
+            let step = fibonacci_step(gate, &cells).unwrap_or_default();
+            let cells = cells
+                .iter()
+                .map(|(cell, value)| format!("{} = {}", cell, value))
+                .collect::<Vec<_>>()
+                .join(", ");
+            [format!("gate '{}'", gate), region, row, cells, step]
+        }
+        VerifyFailure::Permutation { column, location } => {
+            let (region, row) = locate(debug, location);
+            [
+                "copy".to_string(),
+                region,
+                row,
+                short_column(&column.to_string()),
+                "the cell it's copied to".to_string(),
+            ]
+        }
+        VerifyFailure::Lookup {
+            lookup_index,
+            location,
+        } => {
+            let (region, row) = locate(debug, location);
+            [
+                format!("lookup {}", lookup_index),
+                region,
+                row,
+                String::new(),
+                "a row of the table".to_string(),
+            ]
+        }
+        VerifyFailure::CellNotAssigned {
+            gate,
+            region,
+            gate_offset,
+            column,
+            offset,
+        } => {
+            // `Column { index: 0, column_type: Advice }`
+            let column = format!("{:?}", column);
+            let column = format!(
+                "{}{}",
+                &column[column.find("column_type: ").unwrap() + "column_type: ".len()..][..1],
+                read(&column, "index")
+            );
+            [
+                gate.to_string(),
+                region.to_string()["Region ".len()..].to_string(),
+                format!("offset {}", gate_offset),
+                format!("{} at offset {}", column, offset),
+                "an assigned cell".to_string(),
+            ]
+        }
+        VerifyFailure::ConstraintPoisoned { constraint } => [
+            constraint.to_string(),
+            "-".to_string(),
+            "-".to_string(),
+            String::new(),
+            "a selector off on the unusable rows".to_string(),
+        ],
+    }
+}
+
+fn table(prover: &MockProver<Fp>, failures: &[VerifyFailure]) -> String {
+    let debug = format!("{:?}", prover);
+    let header = ["failure", "region", "row", "cells", "expected"].map(String::from);
+    let rows: Vec<[String; 5]> = std::iter::once(header)
+        .chain(failures.iter().map(|failure| explain(&debug, failure)))
+        .collect();
+    let mut widths = [0; 5];
+    for row in &rows {
+        for (width, field) in widths.iter_mut().zip(row) {
+            *width = (*width).max(field.len());
+        }
+    }
+
+    let mut table = format!("{} constraint failures:\n", failures.len());
+    for row in &rows {
+        let fields: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(field, width)| format!("{:width$}", field, width = width))
+            .collect();
+        table.push_str(fields.join(" | ").trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+// Checks the mock prover's constraints, and if any fail, prints a table of
+// which gate failed in which region and on which row, with the cells it read.
+// For the Fibonacci gates it also says what the sum should have been. The
+// table is returned as the error too.
+pub fn check_or_explain(prover: &MockProver<Fp>) -> Result<(), String> {
+    let failures = match prover.verify() {
+        Ok(()) => return Ok(()),
+        Err(failures) => failures,
+    };
+    let table = table(prover, &failures);
+    eprint!("{}", table);
+    Err(table)
+}
+
+// Why a circuit was rejected, at the level a negative test cares about.
+// Columns are named as in `check_or_explain`'s table, `A0`, `I0` and so on.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Failure {
+    // a constraint in the named gate
+    Gate(&'static str),
+    // a copy constraint on a cell in the column
+    Copy(&'static str),
+    // the lookup at this index in the constraint system
+    Lookup(usize),
+    // the named gate read a cell nothing was assigned to
+    Unassigned(&'static str),
+    // the named gate is on in the blinding rows
+    Poisoned(&'static str),
+}
+
+// Whether a rejection matches an expected `Failure`; the gate and column names
+// halo2 hands back aren't `'static`, so they're compared as text.
+fn matches(failure: &VerifyFailure, expected: &Failure) -> bool {
+    match (failure, expected) {
+        (VerifyFailure::ConstraintNotSatisfied { constraint, .. }, Failure::Gate(gate))
+        | (VerifyFailure::ConstraintPoisoned { constraint }, Failure::Poisoned(gate)) => {
+            gate_name(&constraint.to_string()) == *gate
+        }
+        (VerifyFailure::Permutation { column, .. }, Failure::Copy(name)) => {
+            short_column(&column.to_string()) == *name
+        }
+        (VerifyFailure::Lookup { lookup_index, .. }, Failure::Lookup(index)) => {
+            lookup_index == index
+        }
+        (VerifyFailure::CellNotAssigned { gate, .. }, Failure::Unassigned(name)) => {
+            gate_name(&gate.to_string()) == *name
+        }
+        _ => false,
+    }
+}
+
+// Asserts the mock prover rejects `circuit` with `instances` at `k`, and for
+// exactly the reasons in `expected`: every failure is one of them, and each
+// of them happens at least once. A circuit that doesn't synthesize at all
+// fails the assertion too, since that's a different bug.
+#[track_caller]
+pub fn assert_unsatisfied_with<C: Circuit<Fp>>(
+    k: u32,
+    circuit: &C,
+    instances: Vec<Vec<Fp>>,
+    expected: &[Failure],
+) {
+    let prover = MockProver::run(k, circuit, instances)
+        .unwrap_or_else(|e| panic!("the circuit didn't synthesize: {:?}", e));
+    let failures = match prover.verify() {
+        Ok(()) => panic!("the circuit was satisfied, expected {:?}", expected),
+        Err(failures) => failures,
+    };
+    let unexpected = failures
+        .iter()
+        .any(|failure| !expected.iter().any(|e| matches(failure, e)));
+    let missing: Vec<&Failure> = expected
+        .iter()
+        .filter(|e| !failures.iter().any(|failure| matches(failure, e)))
+        .collect();
+    if unexpected || !missing.is_empty() {
+        panic!(
+            "expected {:?}, missing {:?}, got\n{}",
+            expected,
+            missing,
+            table(&prover, &failures)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        example1,
+        fibo::{FiboLayout, FiboPublicInputs},
+    };
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector},
+        poly::Rotation,
+    };
+
+    // One Fibonacci step with a wrong sum, 3 + 5 = 9.
+    #[derive(Default)]
+    struct WrongStep;
+
+    impl Circuit<Fp> for WrongStep {
+        type Config = ([Column<Advice>; 3], Selector);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let selector = meta.selector();
+            meta.create_gate("add", |meta| {
+                let s = meta.query_selector(selector);
+                let a = meta.query_advice(advice[0], Rotation::cur());
+                let b = meta.query_advice(advice[1], Rotation::cur());
+                let c = meta.query_advice(advice[2], Rotation::cur());
+                vec![s * (a + b - c)]
+            });
+            (advice, selector)
+        }
+
+        fn synthesize(
+            &self,
+            (advice, selector): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            // pushes the step down a row
+            layouter.assign_region(
+                || "first",
+                |mut region| {
+                    region.assign_advice(|| "pad", advice[0], 0, || Value::known(Fp::zero()))
+                },
+            )?;
+            layouter.assign_region(
+                || "step",
+                |mut region| {
+                    selector.enable(&mut region, 0)?;
+                    for (column, value) in advice.iter().zip([3, 5, 9]) {
+                        region.assign_advice(
+                            || "v",
+                            *column,
+                            0,
+                            || Value::known(Fp::from(value)),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_check_or_explain() {
+        let prover = MockProver::run(4, &WrongStep, vec![]).unwrap();
+        let table = check_or_explain(&prover).unwrap_err();
+        assert!(table.starts_with("1 constraint failures:\n"));
+        let line = table.lines().nth(2).unwrap();
+        assert!(line.starts_with("gate 'add' | 1 ('step') | 1   |"));
+        assert!(line.contains("A0@0 = 0x3, A1@0 = 0x5, A2@0 = 0x9"));
+        assert!(line.ends_with("A0@0 + A1@0 = 0x8, got 0x9"));
+
+        // a wrong output only shows up where it's copied
+        let inputs = FiboPublicInputs::new(Fp::from(1), Fp::from(1));
+        let circuit = example1::MyCircuit::<Fp> {
+            a: Value::known(inputs.a),
+            b: Value::known(inputs.b),
+        };
+        let mut instance = inputs.to_instances(&FiboLayout::single());
+        let prover = MockProver::run(4, &circuit, instance.clone()).unwrap();
+        assert_eq!(check_or_explain(&prover), Ok(()));
+        instance[0][2] += Fp::one();
+        let prover = MockProver::run(4, &circuit, instance).unwrap();
+        let table = check_or_explain(&prover).unwrap_err();
+        assert!(table.contains("copy"));
+        assert!(table.contains("copy    | -              | 2   | I0"));
+
+        assert_eq!(parse_value(&format_value(Fp::from(55))), Some(Fp::from(55)));
+        assert_eq!(parse_value(&format_value(-Fp::from(2))), Some(-Fp::from(2)));
+        assert_eq!(parse_value("0x"), None);
+    }
+
+    #[test]
+    fn test_assert_unsatisfied_with() {
+        assert_unsatisfied_with(4, &WrongStep, vec![], &[Failure::Gate("add")]);
+    }
+
+    #[test]
+    #[should_panic(expected = "missing [Copy(\"A2\")]")]
+    fn test_assert_unsatisfied_with_other_reason() {
+        assert_unsatisfied_with(
+            4,
+            &WrongStep,
+            vec![],
+            &[Failure::Gate("add"), Failure::Copy("A2")],
+        );
+    }
+}
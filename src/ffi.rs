@@ -0,0 +1,171 @@
+// A C interface to proving and verifying example1, for embedding the prover
+// in applications that aren't written in Rust. It's only compiled with the
+// `ffi` feature, and the fibo-ffi crate in ffi/ builds it into libfibo.so and
+// libfibo.a. The declarations are in include/fibo.h, generated with
+//
+//     cbindgen --config cbindgen.toml --output include/fibo.h
+//
+// Every function returns a `FiboStatus`. A proof is handed out as a
+// `FiboProof` owning a buffer Rust allocated, which has to go back through
+// `fibo_free_proof`. Panics are caught at the boundary rather than unwinding
+// into C.
+
+use std::{panic::catch_unwind, ptr, slice};
+
+use crate::{fibo::OUT_TERM, io::FiboInput};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FiboStatus {
+    Ok = 0,
+    // the proof doesn't verify
+    Invalid = 1,
+    // a null pointer, a run the circuit isn't laid out for, or an output
+    // that doesn't fit in 64 bits
+    BadInput = 2,
+    // something panicked on the way
+    Internal = 3,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct FiboProof {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl FiboProof {
+    fn empty() -> Self {
+        Self {
+            data: ptr::null_mut(),
+            len: 0,
+        }
+    }
+}
+
+// Proves F[n] from `a` and `b`, writing the proof to `proof`. `n` has to be
+// the term the circuit is laid out for, 9. `proof` has to be null or point to
+// a `FiboProof` that can be written to.
+#[allow(clippy::missing_safety_doc)]
+#[no_mangle]
+pub unsafe extern "C" fn fibo_prove(a: u64, b: u64, n: usize, proof: *mut FiboProof) -> FiboStatus {
+    let Some(proof) = proof.as_mut() else {
+        return FiboStatus::BadInput;
+    };
+    *proof = FiboProof::empty();
+    let Ok(input) = FiboInput::new(a, b, n) else {
+        return FiboStatus::BadInput;
+    };
+    match catch_unwind(|| input.prove()) {
+        Ok(Ok(bytes)) => {
+            let bytes = Box::into_raw(bytes.into_boxed_slice());
+            *proof = FiboProof {
+                len: bytes.len(),
+                data: bytes as *mut u8,
+            };
+            FiboStatus::Ok
+        }
+        Ok(Err(_)) => FiboStatus::BadInput,
+        Err(_) => FiboStatus::Internal,
+    }
+}
+
+// Checks `len` bytes of proof at `proof` against the public inputs `a`, `b`
+// and `out`. `proof` has to point to `len` readable bytes.
+#[allow(clippy::missing_safety_doc)]
+#[no_mangle]
+pub unsafe extern "C" fn fibo_verify(
+    proof: *const u8,
+    len: usize,
+    a: u64,
+    b: u64,
+    out: u64,
+) -> FiboStatus {
+    if proof.is_null() {
+        return FiboStatus::BadInput;
+    }
+    let proof = slice::from_raw_parts(proof, len);
+    let input = FiboInput {
+        a,
+        b,
+        out,
+        n_steps: OUT_TERM,
+    };
+    match catch_unwind(|| input.verify(proof)) {
+        Ok(Ok(true)) => FiboStatus::Ok,
+        Ok(Ok(false)) => FiboStatus::Invalid,
+        Ok(Err(_)) => FiboStatus::BadInput,
+        Err(_) => FiboStatus::Internal,
+    }
+}
+
+// Frees a proof from `fibo_prove` and empties it, so freeing it twice is
+// harmless. `proof` has to be null or point to a `FiboProof` filled in by
+// `fibo_prove`.
+#[allow(clippy::missing_safety_doc)]
+#[no_mangle]
+pub unsafe extern "C" fn fibo_free_proof(proof: *mut FiboProof) {
+    let Some(proof) = proof.as_mut() else {
+        return;
+    };
+    if !proof.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            proof.data, proof.len,
+        )));
+    }
+    *proof = FiboProof::empty();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Through function pointers with the C ABI, the way a C caller sees them.
+    #[test]
+    fn test_ffi_round_trip() {
+        let _guard = crate::testing::heavy_test();
+        let prove: unsafe extern "C" fn(u64, u64, usize, *mut FiboProof) -> FiboStatus = fibo_prove;
+        let verify: unsafe extern "C" fn(*const u8, usize, u64, u64, u64) -> FiboStatus =
+            fibo_verify;
+        let free: unsafe extern "C" fn(*mut FiboProof) = fibo_free_proof;
+
+        unsafe {
+            let mut proof = FiboProof::empty();
+            assert_eq!(prove(2, 3, 9, &mut proof), FiboStatus::Ok);
+            assert!(proof.len > 0);
+            assert_eq!(verify(proof.data, proof.len, 2, 3, 144), FiboStatus::Ok);
+            assert_eq!(
+                verify(proof.data, proof.len, 2, 3, 145),
+                FiboStatus::Invalid
+            );
+            assert_eq!(
+                verify(proof.data, proof.len - 1, 2, 3, 144),
+                FiboStatus::Invalid
+            );
+            free(&mut proof);
+            assert!(proof.data.is_null());
+            free(&mut proof);
+
+            assert_eq!(prove(2, 3, 10, &mut proof), FiboStatus::BadInput);
+            assert!(proof.data.is_null());
+            assert_eq!(prove(2, 3, 9, ptr::null_mut()), FiboStatus::BadInput);
+            assert_eq!(verify(ptr::null(), 0, 2, 3, 144), FiboStatus::BadInput);
+        }
+    }
+
+    // The header has to declare what the module exports.
+    #[test]
+    fn test_header() {
+        let header = include_str!("../include/fibo.h");
+        let source = include_str!("ffi.rs");
+        let exported: Vec<_> = source
+            .lines()
+            .filter_map(|line| line.strip_prefix("pub unsafe extern \"C\" fn "))
+            .map(|line| &line[..line.find('(').unwrap()])
+            .collect();
+        assert_eq!(exported, ["fibo_prove", "fibo_verify", "fibo_free_proof"]);
+        for name in exported.iter().chain(&["FiboStatus", "FiboProof"]) {
+            assert!(header.contains(name), "{} isn't in include/fibo.h", name);
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use halo2_proofs::arithmetic::FieldExt;
+
+// Where one of a Fibonacci chip's public inputs lives: which of the instance
+// columns handed to the chip, and which row of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstanceCell {
+    pub column: usize,
+    pub row: usize,
+}
+
+// The named public inputs of the Fibonacci chips, the two initial values and
+// the output, mapped onto the chip's instance columns. Circuits that already
+// have an instance layout of their own pick where these go instead of the
+// chip hard-coding `[a, b, out]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FiboLayout {
+    pub a: InstanceCell,
+    pub b: InstanceCell,
+    pub out: InstanceCell,
+    // Every term from F[0] to F[OUT_TERM] is public, in the first column's
+    // rows 0 to OUT_TERM, and a, b and out are its first, second and last.
+    pub trace: bool,
+}
+
+impl FiboLayout {
+    // `[a, b, out]` down a single column.
+    pub fn single() -> Self {
+        Self {
+            a: InstanceCell { column: 0, row: 0 },
+            b: InstanceCell { column: 0, row: 1 },
+            out: InstanceCell { column: 0, row: 2 },
+            trace: false,
+        }
+    }
+
+    // `[a, b]` in the first column and `[out]` in the second.
+    pub fn split() -> Self {
+        Self {
+            out: InstanceCell { column: 1, row: 0 },
+            ..Self::single()
+        }
+    }
+
+    // `[F[0], .., F[OUT_TERM]]` down a single column, so the verifier sees
+    // the whole sequence.
+    pub fn trace() -> Self {
+        Self {
+            out: InstanceCell {
+                column: 0,
+                row: OUT_TERM,
+            },
+            trace: true,
+            ..Self::single()
+        }
+    }
+
+    // `a`, `b` and `out`, in that order.
+    pub fn cells(&self) -> [InstanceCell; 3] {
+        [self.a, self.b, self.out]
+    }
+
+    // How many instance columns the layout needs.
+    pub fn columns(&self) -> usize {
+        self.cells()
+            .iter()
+            .map(|cell| cell.column + 1)
+            .max()
+            .unwrap()
+    }
+
+    // How many rows each instance column needs.
+    pub fn rows(&self) -> Vec<usize> {
+        let mut rows = vec![0; self.columns()];
+        for cell in self.cells() {
+            rows[cell.column] = rows[cell.column].max(cell.row + 1);
+        }
+        rows
+    }
+}
+
+impl Default for FiboLayout {
+    fn default() -> Self {
+        Self::single()
+    }
+}
+
+// The examples all prove the tenth term, F[9], from F[0] = a and F[1] = b.
+pub const OUT_TERM: usize = 9;
+
+// F[0], .., F[n - 1] from F[0] = a and F[1] = b, for the chips to assign a
+// table from in one go rather than adding up cells as they go.
+pub fn fibonacci_sequence<F: FieldExt>(a: F, b: F, n: usize) -> Vec<F> {
+    let mut sequence = vec![a, b];
+    while sequence.len() < n {
+        sequence.push(sequence[sequence.len() - 2] + sequence[sequence.len() - 1]);
+    }
+    sequence.truncate(n);
+    sequence
+}
+
+// The Fibonacci examples' public inputs by name, so they can't be put in the
+// instance columns in the wrong order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FiboPublicInputs<F> {
+    pub a: F,
+    pub b: F,
+    pub out: F,
+}
+
+impl<F: FieldExt> FiboPublicInputs<F> {
+    // The inputs of a run from `a` and `b`, with the output it ends on.
+    pub fn new(a: F, b: F) -> Self {
+        let (_, out) = (1..OUT_TERM).fold((a, b), |(a, b), _| (b, a + b));
+        Self { a, b, out }
+    }
+
+    // Checks `out` really is F[OUT_TERM] for `a` and `b`, which catches
+    // swapped inputs before a proof fails on them.
+    pub fn validate(&self) -> Result<(), String> {
+        if Self::new(self.a, self.b).out != self.out {
+            return Err(format!("out is not F[{}] from a and b", OUT_TERM));
+        }
+        Ok(())
+    }
+
+    // The instance columns for `layout`, with every cell the layout doesn't
+    // name left zero.
+    pub fn to_instances(&self, layout: &FiboLayout) -> Vec<Vec<F>> {
+        let mut instances: Vec<_> = layout.rows().iter().map(|n| vec![F::zero(); *n]).collect();
+        for (cell, value) in layout.cells().into_iter().zip([self.a, self.b, self.out]) {
+            instances[cell.column][cell.row] = value;
+        }
+        if layout.trace {
+            instances[0] = fibonacci_sequence(self.a, self.b, OUT_TERM + 1);
+        }
+        instances
+    }
+
+    // Reads the inputs back out of instance columns laid out as `layout`, and
+    // validates them.
+    pub fn from_instances(instances: &[Vec<F>], layout: &FiboLayout) -> Result<Self, String> {
+        if instances.len() != layout.columns() {
+            return Err(format!(
+                "expected {} instance columns, found {}",
+                layout.columns(),
+                instances.len()
+            ));
+        }
+        for (column, (values, rows)) in instances.iter().zip(layout.rows()).enumerate() {
+            if values.len() != rows {
+                return Err(format!(
+                    "expected {} rows in instance column {}, found {}",
+                    rows,
+                    column,
+                    values.len()
+                ));
+            }
+        }
+
+        let [a, b, out] = layout.cells().map(|cell| instances[cell.column][cell.row]);
+        let inputs = Self { a, b, out };
+        inputs.validate()?;
+        if layout.trace && instances[0] != fibonacci_sequence(a, b, OUT_TERM + 1) {
+            return Err("instance column 0 isn't the sequence from a and b".to_string());
+        }
+        Ok(inputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::pasta::Fp;
+
+    #[test]
+    fn test_public_inputs() {
+        let inputs = FiboPublicInputs::new(Fp::from(1), Fp::from(1));
+        assert_eq!(inputs.out, Fp::from(55));
+        assert_eq!(
+            fibonacci_sequence(Fp::from(1), Fp::from(1), OUT_TERM + 1).last(),
+            Some(&inputs.out)
+        );
+        assert_eq!(
+            fibonacci_sequence(Fp::from(1), Fp::from(2), 1),
+            [Fp::from(1)]
+        );
+
+        for layout in [
+            FiboLayout::single(),
+            FiboLayout::split(),
+            FiboLayout::trace(),
+        ] {
+            let instances = inputs.to_instances(&layout);
+            assert_eq!(
+                FiboPublicInputs::from_instances(&instances, &layout),
+                Ok(inputs)
+            );
+        }
+        assert_eq!(
+            inputs.to_instances(&FiboLayout::split()),
+            vec![vec![Fp::from(1), Fp::from(1)], vec![Fp::from(55)]]
+        );
+
+        // the wrong layout, and the output swapped in front
+        let instances = inputs.to_instances(&FiboLayout::single());
+        assert!(FiboPublicInputs::from_instances(&instances, &FiboLayout::split()).is_err());
+        let swapped = vec![vec![Fp::from(55), Fp::from(1), Fp::from(1)]];
+        assert!(FiboPublicInputs::from_instances(&swapped, &FiboLayout::single()).is_err());
+        assert!(FiboPublicInputs::<Fp>::from_instances(&[], &FiboLayout::single()).is_err());
+
+        // every term of the trace, and a wrong one in the middle
+        let mut trace = inputs.to_instances(&FiboLayout::trace());
+        assert_eq!(
+            trace,
+            vec![fibonacci_sequence(Fp::from(1), Fp::from(1), OUT_TERM + 1)]
+        );
+        trace[0][5] += Fp::one();
+        assert!(FiboPublicInputs::from_instances(&trace, &FiboLayout::trace()).is_err());
+    }
+}
@@ -0,0 +1,35 @@
+pub mod blake2b;
+pub mod chacha;
+pub mod compare;
+pub mod constants;
+pub mod convergent;
+pub mod coprime;
+pub mod dedup;
+pub mod distinct;
+pub mod dot_product;
+pub mod fixed_point;
+pub mod gf256;
+pub mod hash;
+pub mod is_equal;
+pub mod keccak;
+pub mod lucas;
+pub mod membership;
+pub mod memory;
+pub mod merkle;
+pub mod multiset;
+pub mod nullifier;
+pub mod pedersen;
+pub mod poly;
+pub mod poseidon;
+pub mod range_check;
+pub mod range_table;
+pub mod recurrence;
+pub mod rlp;
+pub mod sbox;
+pub mod schnorr;
+pub mod sha256;
+pub mod shuffle;
+pub mod sort;
+pub mod tables;
+pub mod uint32;
+pub mod uint64;
@@ -0,0 +1,289 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use super::{
+    range_check::RangeCheckConfig,
+    uint32::{AssignedU32, U32Chip, U32Config},
+};
+
+// "expand 32-byte k"
+pub const CONSTANTS: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+pub const DOUBLE_ROUNDS: usize = 10;
+
+// The columns, then the diagonals, of the 4x4 state.
+const QUARTER_ROUNDS: [[usize; 4]; 8] = [
+    [0, 4, 8, 12],
+    [1, 5, 9, 13],
+    [2, 6, 10, 14],
+    [3, 7, 11, 15],
+    [0, 5, 10, 15],
+    [1, 6, 11, 12],
+    [2, 7, 8, 13],
+    [3, 4, 9, 14],
+];
+
+pub fn quarter_round([mut a, mut b, mut c, mut d]: [u32; 4]) -> [u32; 4] {
+    a = a.wrapping_add(b);
+    d = (d ^ a).rotate_left(16);
+    c = c.wrapping_add(d);
+    b = (b ^ c).rotate_left(12);
+    a = a.wrapping_add(b);
+    d = (d ^ a).rotate_left(8);
+    c = c.wrapping_add(d);
+    b = (b ^ c).rotate_left(7);
+    [a, b, c, d]
+}
+
+// The block function's input, as laid out in RFC 8439 section 2.3.
+pub fn initial_state(key: [u32; 8], counter: u32, nonce: [u32; 3]) -> [u32; 16] {
+    let mut state = [0; 16];
+    state[..4].copy_from_slice(&CONSTANTS);
+    state[4..12].copy_from_slice(&key);
+    state[12] = counter;
+    state[13..].copy_from_slice(&nonce);
+    state
+}
+
+pub fn block(initial: [u32; 16]) -> [u32; 16] {
+    let mut state = initial;
+    for _ in 0..DOUBLE_ROUNDS {
+        for indices in QUARTER_ROUNDS {
+            let out = quarter_round(indices.map(|i| state[i]));
+            for (i, v) in indices.into_iter().zip(out) {
+                state[i] = v;
+            }
+        }
+    }
+    std::array::from_fn(|i| state[i].wrapping_add(initial[i]))
+}
+
+#[derive(Debug, Clone)]
+pub struct ChaChaConfig {
+    pub u32: U32Config,
+}
+
+// The ChaCha20 quarter round and block function, out of `U32Chip` adds, xors
+// and rotations. Rotations by 8 and 16 cost as much as any other, so a quarter
+// round is eight bit decompositions and a block a little over 20k rows.
+#[derive(Debug, Clone)]
+pub struct ChaChaChip<F: FieldExt> {
+    u32: U32Chip<F>,
+}
+
+impl<F: FieldExt> ChaChaChip<F> {
+    pub fn construct(config: ChaChaConfig) -> Self {
+        Self {
+            u32: U32Chip::construct(config.u32),
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 6],
+        range: RangeCheckConfig,
+    ) -> ChaChaConfig {
+        ChaChaConfig {
+            u32: U32Chip::configure(meta, advice, range),
+        }
+    }
+
+    // d ^= a and rotate left by n
+    fn xor_rotate(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        d: &AssignedU32<F>,
+        a: &AssignedU32<F>,
+        n: u32,
+    ) -> Result<AssignedU32<F>, Error> {
+        let x = self.u32.xor(layouter.namespace(|| "xor"), d, a)?;
+        self.u32
+            .rotate_right(layouter.namespace(|| format!("rotl {}", n)), &x, 32 - n)
+    }
+
+    pub fn quarter_round(
+        &self,
+        mut layouter: impl Layouter<F>,
+        [a, b, c, d]: [AssignedU32<F>; 4],
+    ) -> Result<[AssignedU32<F>; 4], Error> {
+        let u32 = &self.u32;
+        let a = u32.add(layouter.namespace(|| "a + b"), &a, &b)?;
+        let d = self.xor_rotate(&mut layouter, &d, &a, 16)?;
+        let c = u32.add(layouter.namespace(|| "c + d"), &c, &d)?;
+        let b = self.xor_rotate(&mut layouter, &b, &c, 12)?;
+        let a = u32.add(layouter.namespace(|| "a + b"), &a, &b)?;
+        let d = self.xor_rotate(&mut layouter, &d, &a, 8)?;
+        let c = u32.add(layouter.namespace(|| "c + d"), &c, &d)?;
+        let b = self.xor_rotate(&mut layouter, &b, &c, 7)?;
+        Ok([a, b, c, d])
+    }
+
+    // Twenty rounds over the state, then the input added back in.
+    pub fn block(
+        &self,
+        mut layouter: impl Layouter<F>,
+        initial: &[AssignedU32<F>; 16],
+    ) -> Result<[AssignedU32<F>; 16], Error> {
+        let mut state = initial.clone();
+        for round in 0..DOUBLE_ROUNDS {
+            for indices in QUARTER_ROUNDS {
+                let out = self.quarter_round(
+                    layouter.namespace(|| format!("double round {}", round)),
+                    indices.map(|i| state[i].clone()),
+                )?;
+                for (i, v) in indices.into_iter().zip(out) {
+                    state[i] = v;
+                }
+            }
+        }
+
+        let mut out = vec![];
+        for (v, initial) in state.iter().zip(initial.iter()) {
+            out.push(
+                self.u32
+                    .add(layouter.namespace(|| "add input"), v, initial)?,
+            );
+        }
+        Ok(out.try_into().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::{range_check::RangeCheckChip, range_table::RangeTableConfig};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    // Runs a quarter round, or a whole block, over words from the instance
+    // column, and exposes the result after them.
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        words: usize,
+        _marker: std::marker::PhantomData<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = (ChaChaConfig, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                words: self.words,
+                _marker: std::marker::PhantomData,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 6].map(|_| meta.advice_column());
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            let bytes = RangeTableConfig::configure(meta, 0, 255);
+            let range = RangeCheckChip::configure(meta, [advice[4], advice[5]], bytes);
+            (ChaChaChip::configure(meta, advice, range), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            RangeCheckChip::construct(config.u32.range.clone()).load(&mut layouter)?;
+            let u32 = U32Chip::construct(config.u32.clone());
+            let chip = ChaChaChip::construct(config.clone());
+
+            let mut words = vec![];
+            for row in 0..self.words {
+                let cell = layouter.assign_region(
+                    || "input",
+                    |mut region| {
+                        region.assign_advice_from_instance(
+                            || "word",
+                            instance,
+                            row,
+                            config.u32.advice[0],
+                            0,
+                        )
+                    },
+                )?;
+                words.push(u32.check(layouter.namespace(|| "word"), cell)?);
+            }
+
+            let out = match self.words {
+                4 => chip
+                    .quarter_round(
+                        layouter.namespace(|| "quarter round"),
+                        words.try_into().unwrap(),
+                    )?
+                    .to_vec(),
+                _ => chip
+                    .block(layouter.namespace(|| "block"), &words.try_into().unwrap())?
+                    .to_vec(),
+            };
+            for (row, word) in out.iter().enumerate() {
+                layouter.constrain_instance(word.cell(), instance, self.words + row)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn verify(k: u32, input: &[u32], output: &[u32]) -> bool {
+        let circuit = MyCircuit::<Fp> {
+            words: input.len(),
+            _marker: std::marker::PhantomData,
+        };
+        let instance = input
+            .iter()
+            .chain(output.iter())
+            .map(|w| Fp::from(*w as u64))
+            .collect();
+        let prover = MockProver::run(k, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_quarter_round() {
+        // RFC 8439 section 2.1.1
+        let input = [0x1111_1111, 0x0102_0304, 0x9b8d_6f43, 0x0123_4567];
+        let output = [0xea2a_92f4, 0xcb1c_f8ce, 0x4581_472e, 0x5881_c4bb];
+        assert_eq!(quarter_round(input), output);
+
+        assert!(verify(9, &input, &output));
+        let mut wrong = output;
+        wrong[3] ^= 1 << 31;
+        assert!(!verify(9, &input, &wrong));
+    }
+
+    #[test]
+    fn test_block() {
+        let _guard = crate::testing::heavy_test();
+        // RFC 8439 section 2.3.2
+        let key: [u32; 8] =
+            std::array::from_fn(|i| u32::from_le_bytes(std::array::from_fn(|j| (4 * i + j) as u8)));
+        let nonce = [0x0900_0000, 0x4a00_0000, 0];
+        let input = initial_state(key, 1, nonce);
+        let output = [
+            0xe4e7_f110,
+            0x1559_3bd1,
+            0x1fdd_0f50,
+            0xc471_20a3,
+            0xc7f4_d1c7,
+            0x0368_c033,
+            0x9aaa_2204,
+            0x4e6c_d4c3,
+            0x4664_82d2,
+            0x09aa_9f07,
+            0x05d7_c214,
+            0xa202_8bd9,
+            0xd19c_12b5,
+            0xb94e_16de,
+            0xe883_d0cb,
+            0x4e3c_50a2,
+        ];
+        assert_eq!(block(input), output);
+
+        assert!(verify(15, &input, &output));
+        let mut wrong = output;
+        wrong[0] = wrong[0].wrapping_add(1);
+        assert!(!verify(15, &input, &wrong));
+    }
+}
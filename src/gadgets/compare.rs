@@ -0,0 +1,222 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+use super::range_check::{RangeCheckChip, RangeCheckConfig};
+
+#[derive(Debug, Clone)]
+pub struct CompareConfig<const NUM_BYTES: usize> {
+    pub advice: [Column<Advice>; 4],
+    pub q_lt: Selector,
+    pub q_le: Selector,
+    pub range: RangeCheckConfig,
+}
+
+// Compares integers below 2^(8 * NUM_BYTES). The operands have to be range
+// checked to that size already, otherwise a difference can wrap around the
+// field and the comparison means nothing.
+#[derive(Debug, Clone)]
+pub struct CompareChip<F: FieldExt, const NUM_BYTES: usize> {
+    config: CompareConfig<NUM_BYTES>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const NUM_BYTES: usize> CompareChip<F, NUM_BYTES> {
+    pub fn construct(config: CompareConfig<NUM_BYTES>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+        range: RangeCheckConfig,
+    ) -> CompareConfig<NUM_BYTES> {
+        // the shift lt moves diff by is 2^(8 * NUM_BYTES) as a u128, which
+        // overflows at 16 bytes
+        const { assert!(NUM_BYTES > 0 && NUM_BYTES < 16) };
+        let [col_a, col_b, col_lt, col_diff] = advice;
+        let q_lt = meta.selector();
+        let q_le = meta.selector();
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        let shift = Expression::Constant(F::from_u128(1 << (8 * NUM_BYTES)));
+
+        meta.create_gate("less than", |meta| {
+            //
+            // col_a | col_b | col_lt | col_diff | q_lt
+            //   a       b       lt       diff       1
+            //
+            // diff = a - b + lt * 2^(8 * NUM_BYTES) fits in NUM_BYTES exactly
+            // when lt says whether a < b
+            let s = meta.query_selector(q_lt);
+            let a = meta.query_advice(col_a, Rotation::cur());
+            let b = meta.query_advice(col_b, Rotation::cur());
+            let lt = meta.query_advice(col_lt, Rotation::cur());
+            let diff = meta.query_advice(col_diff, Rotation::cur());
+            vec![
+                s.clone() * lt.clone() * (Expression::Constant(F::one()) - lt.clone()),
+                s * (a - b + lt * shift - diff),
+            ]
+        });
+
+        meta.create_gate("less or equal", |meta| {
+            let s = meta.query_selector(q_le);
+            let a = meta.query_advice(col_a, Rotation::cur());
+            let b = meta.query_advice(col_b, Rotation::cur());
+            let diff = meta.query_advice(col_diff, Rotation::cur());
+            vec![s * (b - a - diff)]
+        });
+
+        CompareConfig {
+            advice,
+            q_lt,
+            q_le,
+            range,
+        }
+    }
+
+    // Returns a cell holding 1 if a < b and 0 otherwise.
+    pub fn less_than(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        let shift = F::from_u128(1 << (8 * NUM_BYTES));
+
+        let (lt, diff) = layouter.assign_region(
+            || "less than",
+            |mut region| {
+                config.q_lt.enable(&mut region, 0)?;
+
+                let a = a.copy_advice(|| "a", &mut region, config.advice[0], 0)?;
+                let b = b.copy_advice(|| "b", &mut region, config.advice[1], 0)?;
+
+                let lt = a.value().zip(b.value()).map(|(a, b)| {
+                    if a.get_lower_128() < b.get_lower_128() {
+                        F::one()
+                    } else {
+                        F::zero()
+                    }
+                });
+                let diff = a.value().copied() - b.value() + lt * Value::known(shift);
+
+                let lt = region.assign_advice(|| "lt", config.advice[2], 0, || lt)?;
+                let diff = region.assign_advice(|| "diff", config.advice[3], 0, || diff)?;
+                Ok((lt, diff))
+            },
+        )?;
+
+        let range = RangeCheckChip::construct(config.range.clone());
+        range.range_check::<NUM_BYTES>(layouter.namespace(|| "diff"), &diff)?;
+
+        Ok(lt)
+    }
+
+    pub fn assert_le(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        let config = &self.config;
+
+        let diff = layouter.assign_region(
+            || "less or equal",
+            |mut region| {
+                config.q_le.enable(&mut region, 0)?;
+
+                let a = a.copy_advice(|| "a", &mut region, config.advice[0], 0)?;
+                let b = b.copy_advice(|| "b", &mut region, config.advice[1], 0)?;
+
+                let diff = b.value().copied() - a.value();
+                region.assign_advice(|| "diff", config.advice[3], 0, || diff)
+            },
+        )?;
+
+        let range = RangeCheckChip::construct(config.range.clone());
+        range.range_check::<NUM_BYTES>(layouter.namespace(|| "diff"), &diff)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::range_table::RangeTableConfig;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const NUM_BYTES: usize = 4;
+
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        a: Value<F>,
+        b: Value<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = (CompareConfig<NUM_BYTES>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 4].map(|_| meta.advice_column());
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            let bytes = RangeTableConfig::configure(meta, 0, 255);
+            let range = RangeCheckChip::configure(meta, [advice[0], advice[1]], bytes);
+            (CompareChip::configure(meta, advice, range), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let range = RangeCheckChip::construct(config.range.clone());
+            range.load(&mut layouter)?;
+
+            let a = range.witness_checked::<NUM_BYTES>(layouter.namespace(|| "a"), self.a)?;
+            let b = range.witness_checked::<NUM_BYTES>(layouter.namespace(|| "b"), self.b)?;
+
+            let chip = CompareChip::construct(config);
+            let lt = chip.less_than(layouter.namespace(|| "a < b"), &a, &b)?;
+            layouter.constrain_instance(lt.cell(), instance, 0)?;
+            chip.assert_le(layouter.namespace(|| "a <= b"), &a, &b)
+        }
+    }
+
+    #[test]
+    fn test_compare() {
+        let k = 9;
+
+        for (a, b, lt, ok) in [
+            (3u64, 4u64, 1u64, true),
+            (0, 0xffff_ffff, 1, true),
+            (4, 4, 0, true),
+            (3, 4, 0, false),
+            (4, 4, 1, false),
+            // a <= b fails
+            (5, 4, 0, false),
+            (0xffff_ffff, 0, 0, false),
+        ] {
+            let circuit = MyCircuit {
+                a: Value::known(Fp::from(a)),
+                b: Value::known(Fp::from(b)),
+            };
+            let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(lt)]]).unwrap();
+            assert_eq!(prover.verify().is_ok(), ok, "{} < {} -> {}", a, b, lt);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone)]
+pub struct ConstantsConfig {
+    pub value: Column<Advice>,
+    pub constant: Column<Fixed>,
+    pub selector: Selector,
+}
+
+// Circuit constants, like a recurrence's coefficients, kept in a fixed column
+// rather than written into a gate's expression, so one gate serves any of
+// them. The fixed column is part of the keys, so the constants are too.
+//
+// `load` puts each constant in the fixed column and a gate makes the advice
+// cell next to it equal, a row per constant with no copy constraint.
+// `constrain` goes the other way, through the permutation: the fixed column
+// also takes the layouter's constants, and a cell that must hold one is
+// copied to it.
+#[derive(Debug, Clone)]
+pub struct ConstantsChip<F: FieldExt> {
+    config: ConstantsConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> ConstantsChip<F> {
+    pub fn construct(config: ConstantsConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        value: Column<Advice>,
+        constant: Column<Fixed>,
+    ) -> ConstantsConfig {
+        let selector = meta.selector();
+
+        meta.enable_equality(value);
+        meta.enable_constant(constant);
+
+        meta.create_gate("constant", |meta| {
+            //
+            // value | constant | selector
+            //   v       c           1
+            //
+            let s = meta.query_selector(selector);
+            let v = meta.query_advice(value, Rotation::cur());
+            let c = meta.query_fixed(constant, Rotation::cur());
+            vec![s * (v - c)]
+        });
+
+        ConstantsConfig {
+            value,
+            constant,
+            selector,
+        }
+    }
+
+    // Advice cells holding `constants`, in order.
+    pub fn load(
+        &self,
+        mut layouter: impl Layouter<F>,
+        constants: &[F],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(
+            || "load constants",
+            |mut region| {
+                constants
+                    .iter()
+                    .enumerate()
+                    .map(|(row, c)| {
+                        config.selector.enable(&mut region, row)?;
+                        region.assign_fixed(|| "c", config.constant, row, || Value::known(*c))?;
+                        region.assign_advice(|| "v", config.value, row, || Value::known(*c))
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    // Constrains `cell` to hold `constant`.
+    pub fn constrain(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        constant: F,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "constrain constant",
+            |mut region| {
+                // in a cell of its own, since MockProver can't place a failure
+                // in a region with no cells
+                let cell = cell.copy_advice(|| "v", &mut region, self.config.value, 0)?;
+                region.constrain_constant(cell.cell(), constant)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        explain::{assert_unsatisfied_with, Failure},
+        gadgets::lucas::{lucas_sequence, Coefficients, LucasChip, LucasConfig, LucasParams},
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 5;
+
+    // x_n of the Lucas sequence for p, q, x_0 and x_1 loaded from the fixed
+    // column, checked against `expected` with no instance at all.
+    struct MyCircuit<F> {
+        constants: [F; 4],
+        n: usize,
+        expected: F,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = (ConstantsConfig, LucasConfig);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { ..*self }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 3].map(|_| meta.advice_column());
+            let constant = meta.fixed_column();
+            let constants = ConstantsChip::configure(meta, advice[0], constant);
+            let coefficients = Coefficients::Advice([advice[1], advice[2]]);
+            (
+                constants,
+                LucasChip::configure(meta, advice[0], coefficients),
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (constants, lucas): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let constants = ConstantsChip::construct(constants);
+            let [p, q, x0, x1]: [AssignedCell<F, F>; 4] = constants
+                .load(layouter.namespace(|| "constants"), &self.constants)?
+                .try_into()
+                .unwrap();
+
+            let lucas = LucasChip::construct(lucas);
+            let out = lucas.assign(
+                layouter.namespace(|| "sequence"),
+                &LucasParams::Cells(p, q),
+                &x0,
+                &x1,
+                self.n,
+            )?;
+            constants.constrain(layouter.namespace(|| "expected"), &out, self.expected)
+        }
+    }
+
+    #[test]
+    fn test_constants() {
+        // Pell numbers, p = 2 and q = -1 from 0 and 1
+        let constants = [Fp::from(2), -Fp::one(), Fp::zero(), Fp::one()];
+        let [p, q, x0, x1] = constants;
+        let expected = lucas_sequence(p, q, x0, x1, 10)[10];
+        assert_eq!(expected, Fp::from(2378));
+
+        let circuit = MyCircuit {
+            constants,
+            n: 10,
+            expected,
+        };
+        MockProver::run(K, &circuit, vec![])
+            .unwrap()
+            .assert_satisfied();
+
+        let circuit = MyCircuit {
+            expected: expected + Fp::one(),
+            ..circuit
+        };
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![],
+            &[Failure::Copy("A0"), Failure::Copy("F0")],
+        );
+    }
+}
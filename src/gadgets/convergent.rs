@@ -0,0 +1,124 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone)]
+pub struct ConvergentConfig {
+    pub advice: [Column<Advice>; 2],
+    pub quotient: Column<Fixed>,
+    pub selector: Selector,
+}
+
+// Convergents h_n / k_n of the continued fraction [a_0; a_1, a_2, ...]:
+//
+//   h_n = a_n * h_{n-1} + h_{n-2},  h_{-1} = 1, h_{-2} = 0
+//   k_n = a_n * k_{n-1} + k_{n-2},  k_{-1} = 0, k_{-2} = 1
+//
+// The partial quotients are circuit constants held in a fixed column.
+#[derive(Debug, Clone)]
+pub struct ConvergentChip<F: FieldExt> {
+    config: ConvergentConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> ConvergentChip<F> {
+    pub fn construct(config: ConvergentConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    // `constants` has to be a fixed column enabled with `enable_constant`.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 2],
+        quotient: Column<Fixed>,
+        constants: Column<Fixed>,
+    ) -> ConvergentConfig {
+        let [col_h, col_k] = advice;
+        let selector = meta.selector();
+
+        meta.enable_equality(col_h);
+        meta.enable_equality(col_k);
+        meta.enable_constant(constants);
+
+        meta.create_gate("convergent", |meta| {
+            //
+            // col_h   | col_k   | quotient | selector
+            //  h_{n-2}   k_{n-2}                s
+            //  h_{n-1}   k_{n-1}
+            //  h_n       k_n        a_n
+            //
+            let s = meta.query_selector(selector);
+            let a = meta.query_fixed(quotient, Rotation(2));
+            let h0 = meta.query_advice(col_h, Rotation::cur());
+            let h1 = meta.query_advice(col_h, Rotation::next());
+            let h2 = meta.query_advice(col_h, Rotation(2));
+            let k0 = meta.query_advice(col_k, Rotation::cur());
+            let k1 = meta.query_advice(col_k, Rotation::next());
+            let k2 = meta.query_advice(col_k, Rotation(2));
+            vec![
+                s.clone() * (a.clone() * h1 + h0 - h2),
+                s * (a * k1 + k0 - k2),
+            ]
+        });
+
+        ConvergentConfig {
+            advice,
+            quotient,
+            selector,
+        }
+    }
+
+    // Returns (h_n, k_n) for the last partial quotient given.
+    #[allow(clippy::type_complexity)]
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        quotients: &[u64],
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        assert!(!quotients.is_empty());
+
+        layouter.assign_region(
+            || "convergents",
+            |mut region| {
+                let [col_h, col_k] = self.config.advice;
+
+                let mut h = (
+                    region.assign_advice_from_constant(|| "h_-2", col_h, 0, F::zero())?,
+                    region.assign_advice_from_constant(|| "h_-1", col_h, 1, F::one())?,
+                );
+                let mut k = (
+                    region.assign_advice_from_constant(|| "k_-2", col_k, 0, F::one())?,
+                    region.assign_advice_from_constant(|| "k_-1", col_k, 1, F::zero())?,
+                );
+
+                for (i, a) in quotients.iter().enumerate() {
+                    let row = i + 2;
+                    let a = F::from(*a);
+
+                    self.config.selector.enable(&mut region, i)?;
+                    region.assign_fixed(|| "a", self.config.quotient, row, || Value::known(a))?;
+
+                    let h_cell = region.assign_advice(
+                        || "h",
+                        col_h,
+                        row,
+                        || Value::known(a) * h.1.value() + h.0.value(),
+                    )?;
+                    let k_cell = region.assign_advice(
+                        || "k",
+                        col_k,
+                        row,
+                        || Value::known(a) * k.1.value() + k.0.value(),
+                    )?;
+
+                    h = (h.1, h_cell);
+                    k = (k.1, k_cell);
+                }
+
+                Ok((h.1, k.1))
+            },
+        )
+    }
+}
@@ -0,0 +1,215 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+use super::range_check::{RangeCheckChip, RangeCheckConfig};
+
+// Operands and Bezout coefficients are all range checked to this many bytes, so
+// `u * p - v * q` can't wrap around the field modulus.
+pub const COPRIME_BYTES: usize = 8;
+
+#[derive(Debug, Clone)]
+pub struct CoprimeConfig {
+    pub advice: [Column<Advice>; 4],
+    pub selector: Selector,
+    pub range: RangeCheckConfig,
+}
+
+// Proves gcd(p, q) = 1 for integers p >= 1, q >= 0 by exhibiting non-negative
+// Bezout coefficients with u * p - v * q = 1.
+#[derive(Debug, Clone)]
+pub struct CoprimeChip<F: FieldExt> {
+    config: CoprimeConfig,
+    _marker: PhantomData<F>,
+}
+
+// Host-side Bezout coefficients (u, v) with u * p - v * q = 1, if p and q are coprime.
+pub fn bezout(p: u64, q: u64) -> Option<(u64, u64)> {
+    let (p, q) = (p as i128, q as i128);
+    if p == 0 {
+        return None;
+    }
+    if q == 0 {
+        return if p == 1 { Some((1, 0)) } else { None };
+    }
+
+    // extended euclid: p * x + q * y = g
+    let (mut old_r, mut r) = (p, q);
+    let (mut old_x, mut x) = (1i128, 0i128);
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_x, x) = (x, old_x - quotient * x);
+    }
+    if old_r != 1 {
+        return None;
+    }
+
+    // pick u in (0, q] so that v = (u * p - 1) / q lands in [0, p); u * p
+    // can reach 2^128, past i128
+    let mut u = old_x.rem_euclid(q) as u128;
+    if u == 0 {
+        u = q as u128;
+    }
+    let v = (u * p as u128 - 1) / q as u128;
+    Some((u as u64, v as u64))
+}
+
+impl<F: FieldExt> CoprimeChip<F> {
+    pub fn construct(config: CoprimeConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+        range: RangeCheckConfig,
+    ) -> CoprimeConfig {
+        let selector = meta.selector();
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("bezout", |meta| {
+            //
+            // p | q | u | v | selector
+            //
+            let s = meta.query_selector(selector);
+            let [p, q, u, v] = advice.map(|column| meta.query_advice(column, Rotation::cur()));
+            vec![s * (u * p - v * q - Expression::Constant(F::one()))]
+        });
+
+        CoprimeConfig {
+            advice,
+            selector,
+            range,
+        }
+    }
+
+    pub fn assert_coprime(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: &AssignedCell<F, F>,
+        q: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        let coefficients = p.value().zip(q.value()).map(|(p, q)| {
+            bezout(p.get_lower_128() as u64, q.get_lower_128() as u64).unwrap_or((0, 0))
+        });
+
+        let cells = layouter.assign_region(
+            || "bezout",
+            |mut region| {
+                self.config.selector.enable(&mut region, 0)?;
+
+                let p = p.copy_advice(|| "p", &mut region, self.config.advice[0], 0)?;
+                let q = q.copy_advice(|| "q", &mut region, self.config.advice[1], 0)?;
+                let u = region.assign_advice(
+                    || "u",
+                    self.config.advice[2],
+                    0,
+                    || coefficients.map(|(u, _)| F::from(u)),
+                )?;
+                let v = region.assign_advice(
+                    || "v",
+                    self.config.advice[3],
+                    0,
+                    || coefficients.map(|(_, v)| F::from(v)),
+                )?;
+
+                Ok([p, q, u, v])
+            },
+        )?;
+
+        let range = RangeCheckChip::construct(self.config.range.clone());
+        for cell in cells.iter() {
+            range.range_check::<COPRIME_BYTES>(layouter.namespace(|| "bezout operand"), cell)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::range_table::RangeTableConfig;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[test]
+    fn test_bezout() {
+        for (p, q) in [
+            (1, 0),
+            (1, 1),
+            (2, 1),
+            (144, 89),
+            (7, 12),
+            (u32::MAX as u64, 2),
+            (u64::MAX - 1, u64::MAX),
+            (u64::MAX, u64::MAX - 1),
+        ] {
+            let (u, v) = bezout(p, q).unwrap();
+            assert_eq!(u as u128 * p as u128 - v as u128 * q as u128, 1);
+        }
+        assert_eq!(bezout(6, 4), None);
+        assert_eq!(bezout(0, 1), None);
+    }
+
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        p: Value<F>,
+        q: Value<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = CoprimeConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 4].map(|_| meta.advice_column());
+            let bytes = RangeTableConfig::configure(meta, 0, 255);
+            let range = RangeCheckChip::configure(meta, [advice[0], advice[1]], bytes);
+            CoprimeChip::configure(meta, advice, range)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let range = RangeCheckChip::construct(config.range.clone());
+            range.load(&mut layouter)?;
+
+            let p = range.witness_checked::<COPRIME_BYTES>(layouter.namespace(|| "p"), self.p)?;
+            let q = range.witness_checked::<COPRIME_BYTES>(layouter.namespace(|| "q"), self.q)?;
+
+            let chip = CoprimeChip::construct(config);
+            chip.assert_coprime(layouter.namespace(|| "coprime"), &p, &q)
+        }
+    }
+
+    #[test]
+    fn test_coprime() {
+        let k = 9;
+
+        for (p, q, ok) in [
+            (144, 89, true),
+            (89, 144, true),
+            (6, 4, false),
+            (5, 0, false),
+        ] {
+            let circuit = MyCircuit {
+                p: Value::known(Fp::from(p)),
+                q: Value::known(Fp::from(q)),
+            };
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            assert_eq!(prover.verify().is_ok(), ok, "gcd({}, {})", p, q);
+        }
+    }
+}
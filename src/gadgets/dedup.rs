@@ -0,0 +1,188 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use super::{
+    distinct::{DistinctChip, DistinctConfig},
+    sort::{SortChip, SortConfig},
+};
+
+// How `DedupChip` proves its inputs are distinct.
+#[derive(Debug, Clone)]
+pub enum DedupStrategy<F: FieldExt> {
+    // A not-equal row for every pair: n (n - 1) / 2 rows and nothing else.
+    Pairwise,
+    // Sort first, then only neighbours need a not-equal row. The sort costs a
+    // Poseidon hash over 2n values and range checks, so this only wins for a
+    // few hundred values and up. Values have to fit in `sort::SORT_BYTES`.
+    Sorted(Box<SortConfig<F>>),
+}
+
+#[derive(Debug, Clone)]
+pub struct DedupConfig<F: FieldExt> {
+    pub distinct: DistinctConfig,
+    pub strategy: DedupStrategy<F>,
+}
+
+// Proves a list of assigned cells holds no duplicates, with the strategy picked
+// when the circuit is configured.
+#[derive(Debug, Clone)]
+pub struct DedupChip<F: FieldExt> {
+    config: DedupConfig<F>,
+}
+
+impl<F: FieldExt> DedupChip<F> {
+    pub fn construct(config: DedupConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+        strategy: DedupStrategy<F>,
+    ) -> DedupConfig<F> {
+        DedupConfig {
+            distinct: DistinctChip::configure(meta, advice),
+            strategy,
+        }
+    }
+
+    pub fn assert_distinct(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cells: &[AssignedCell<F, F>],
+    ) -> Result<(), Error> {
+        let distinct = DistinctChip::construct(self.config.distinct.clone());
+
+        match &self.config.strategy {
+            DedupStrategy::Pairwise => distinct.assign(layouter.namespace(|| "all pairs"), cells),
+            DedupStrategy::Sorted(sort) => {
+                let sort = SortChip::construct(sort.as_ref().clone());
+                let sorted = sort.sort(layouter.namespace(|| "sort"), cells)?;
+                let neighbours: Vec<_> = sorted
+                    .windows(2)
+                    .map(|pair| (pair[0].clone(), pair[1].clone()))
+                    .collect();
+                distinct.assign_pairs(layouter.namespace(|| "neighbours"), &neighbours)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::{
+        poseidon::{self, PoseidonChip},
+        range_check::RangeCheckChip,
+        range_table::RangeTableConfig,
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[derive(Default)]
+    struct MyCircuit<F, const SORTED: bool> {
+        values: Vec<Value<F>>,
+    }
+
+    impl<F: FieldExt, const SORTED: bool> Circuit<F> for MyCircuit<F, SORTED> {
+        type Config = DedupConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                values: vec![Value::unknown(); self.values.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 6].map(|_| meta.advice_column());
+            let strategy = if SORTED {
+                let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+                let constants = meta.fixed_column();
+                let bytes = RangeTableConfig::configure(meta, 0, 255);
+                let range = RangeCheckChip::configure(meta, [advice[4], advice[5]], bytes);
+                let poseidon =
+                    PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+                DedupStrategy::Sorted(Box::new(SortChip::configure(meta, advice, range, poseidon)))
+            } else {
+                DedupStrategy::Pairwise
+            };
+            DedupChip::configure(meta, [advice[0], advice[1], advice[2]], strategy)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            if let DedupStrategy::Sorted(sort) = &config.strategy {
+                RangeCheckChip::construct(sort.compare.range.clone()).load(&mut layouter)?;
+            }
+
+            let cells = layouter.assign_region(
+                || "values",
+                |mut region| {
+                    self.values
+                        .iter()
+                        .enumerate()
+                        .map(|(offset, v)| {
+                            region.assign_advice(
+                                || "value",
+                                config.distinct.advice[0],
+                                offset,
+                                || *v,
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                },
+            )?;
+
+            let chip = DedupChip::construct(config);
+            chip.assert_distinct(layouter.namespace(|| "dedup"), &cells)
+        }
+    }
+
+    fn circuit<const SORTED: bool>(values: &[u64]) -> MyCircuit<Fp, SORTED> {
+        MyCircuit {
+            values: values.iter().map(|v| Value::known(Fp::from(*v))).collect(),
+        }
+    }
+
+    // Smallest k the circuit fits in.
+    fn min_k<const SORTED: bool>(values: &[u64]) -> u32 {
+        let circuit = circuit::<SORTED>(values);
+        (4..=16)
+            .find(|k| MockProver::run(*k, &circuit, vec![]).is_ok())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_dedup() {
+        let k = 10;
+
+        let unique = [9, 4, 7, 1, 0, 12];
+        let duplicated = [9, 4, 7, 1, 4, 12];
+
+        let prover = MockProver::run(k, &circuit::<false>(&unique), vec![]).unwrap();
+        prover.assert_satisfied();
+        let prover = MockProver::run(k, &circuit::<true>(&unique), vec![]).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(k, &circuit::<false>(&duplicated), vec![]).unwrap();
+        assert!(prover.verify().is_err());
+        let prover = MockProver::run(k, &circuit::<true>(&duplicated), vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_dedup_cost() {
+        let _guard = crate::testing::heavy_test();
+        // Pairwise is quadratic and sorting is linear with a large constant, so
+        // the gap between them closes as the input grows.
+        let small: Vec<u64> = (0..8).collect();
+        let large: Vec<u64> = (0..48).collect();
+
+        let small_gap = min_k::<true>(&small) - min_k::<false>(&small);
+        let large_gap = min_k::<true>(&large) - min_k::<false>(&large);
+        assert!(min_k::<false>(&small) < min_k::<true>(&small));
+        assert!(large_gap < small_gap);
+    }
+}
@@ -0,0 +1,90 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone)]
+pub struct DistinctConfig {
+    pub advice: [Column<Advice>; 3],
+    pub selector: Selector,
+}
+
+// Proves that a list of assigned cells are pairwise distinct. Every pair gets a
+// row holding both values and the inverse of their difference, which only
+// exists when the difference is non-zero.
+#[derive(Debug, Clone)]
+pub struct DistinctChip<F: FieldExt> {
+    config: DistinctConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> DistinctChip<F> {
+    pub fn construct(config: DistinctConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+    ) -> DistinctConfig {
+        let [col_a, col_b, col_inv] = advice;
+        let selector = meta.selector();
+
+        meta.enable_equality(col_a);
+        meta.enable_equality(col_b);
+
+        meta.create_gate("not equal", |meta| {
+            //
+            // col_a | col_b | col_inv | selector
+            //   a      b       inv        s
+            //
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(col_a, Rotation::cur());
+            let b = meta.query_advice(col_b, Rotation::cur());
+            let inv = meta.query_advice(col_inv, Rotation::cur());
+            vec![s * ((a - b) * inv - Expression::Constant(F::one()))]
+        });
+
+        DistinctConfig { advice, selector }
+    }
+
+    pub fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        cells: &[AssignedCell<F, F>],
+    ) -> Result<(), Error> {
+        let mut pairs = vec![];
+        for i in 0..cells.len() {
+            for j in (i + 1)..cells.len() {
+                pairs.push((cells[i].clone(), cells[j].clone()));
+            }
+        }
+        self.assign_pairs(layouter, &pairs)
+    }
+
+    // Only the given pairs are checked, one row each.
+    #[allow(clippy::type_complexity)]
+    pub fn assign_pairs(
+        &self,
+        mut layouter: impl Layouter<F>,
+        pairs: &[(AssignedCell<F, F>, AssignedCell<F, F>)],
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "pairwise distinct",
+            |mut region| {
+                for (offset, (a, b)) in pairs.iter().enumerate() {
+                    self.config.selector.enable(&mut region, offset)?;
+
+                    let a = a.copy_advice(|| "a", &mut region, self.config.advice[0], offset)?;
+                    let b = b.copy_advice(|| "b", &mut region, self.config.advice[1], offset)?;
+
+                    let inv = (a.value().copied() - b.value().copied())
+                        .map(|diff| diff.invert().unwrap_or(F::zero()));
+                    region.assign_advice(|| "inv", self.config.advice[2], offset, || inv)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
@@ -0,0 +1,183 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone)]
+pub struct DotProductConfig {
+    pub advice: [Column<Advice>; 3],
+    pub selector: Selector,
+}
+
+// Computes sum(a_i * b_i) over two equally long lists of cells, one row per
+// term, with a running sum that starts at a fixed zero.
+#[derive(Debug, Clone)]
+pub struct DotProductChip<F: FieldExt> {
+    config: DotProductConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> DotProductChip<F> {
+    pub fn construct(config: DotProductConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+        constants: Column<Fixed>,
+    ) -> DotProductConfig {
+        let [col_a, col_b, col_acc] = advice;
+        let selector = meta.selector();
+
+        meta.enable_constant(constants);
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("dot product", |meta| {
+            //
+            // a     | b     | acc     | selector
+            // a_0     b_0     0           1
+            // a_1     b_1     acc_1       1
+            // ...
+            // a_n-1   b_n-1   acc_n-1     1
+            //                 acc_n
+            //
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(col_a, Rotation::cur());
+            let b = meta.query_advice(col_b, Rotation::cur());
+            let acc = meta.query_advice(col_acc, Rotation::cur());
+            let acc_next = meta.query_advice(col_acc, Rotation::next());
+            vec![s * (acc + a * b - acc_next)]
+        });
+
+        DotProductConfig { advice, selector }
+    }
+
+    // Returns the cell holding sum(a_i * b_i).
+    pub fn dot_product(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &[AssignedCell<F, F>],
+        b: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert_eq!(a.len(), b.len());
+        let config = &self.config;
+        let [col_a, col_b, col_acc] = config.advice;
+
+        layouter.assign_region(
+            || "dot product",
+            |mut region| {
+                let mut acc =
+                    region.assign_advice_from_constant(|| "acc", col_acc, 0, F::zero())?;
+
+                for (offset, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+                    config.selector.enable(&mut region, offset)?;
+
+                    let a = a.copy_advice(|| "a", &mut region, col_a, offset)?;
+                    let b = b.copy_advice(|| "b", &mut region, col_b, offset)?;
+
+                    let acc_next = acc.value().copied() + a.value().copied() * b.value();
+                    acc = region.assign_advice(|| "acc", col_acc, offset + 1, || acc_next)?;
+                }
+
+                Ok(acc)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        a: Vec<Value<F>>,
+        b: Vec<Value<F>>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = (DotProductConfig, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                a: vec![Value::unknown(); self.a.len()],
+                b: vec![Value::unknown(); self.b.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 3].map(|_| meta.advice_column());
+            let constants = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            (DotProductChip::configure(meta, advice, constants), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let (a, b) = layouter.assign_region(
+                || "vectors",
+                |mut region| {
+                    let mut cells_a = vec![];
+                    let mut cells_b = vec![];
+                    for (offset, (a, b)) in self.a.iter().zip(self.b.iter()).enumerate() {
+                        cells_a.push(region.assign_advice(
+                            || "a",
+                            config.advice[0],
+                            offset,
+                            || *a,
+                        )?);
+                        cells_b.push(region.assign_advice(
+                            || "b",
+                            config.advice[1],
+                            offset,
+                            || *b,
+                        )?);
+                    }
+                    Ok((cells_a, cells_b))
+                },
+            )?;
+
+            let chip = DotProductChip::construct(config);
+            let sum = chip.dot_product(layouter.namespace(|| "a . b"), &a, &b)?;
+            layouter.constrain_instance(sum.cell(), instance, 0)
+        }
+    }
+
+    fn run(a: &[u64], b: &[u64], sum: Fp) -> bool {
+        let values = |list: &[u64]| list.iter().map(|v| Value::known(Fp::from(*v))).collect();
+        let circuit = MyCircuit {
+            a: values(a),
+            b: values(b),
+        };
+        let prover = MockProver::run(5, &circuit, vec![vec![sum]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_dot_product() {
+        let a = [1, 2, 3, 4];
+        let b = [5, 6, 7, 8];
+
+        assert!(run(&a, &b, Fp::from(70)));
+        assert!(!run(&a, &b, Fp::from(71)));
+
+        // the empty product is zero
+        assert!(run(&[], &[], Fp::zero()));
+
+        // products wrap around the field like any other arithmetic
+        let big = u64::MAX;
+        let expected = Fp::from(big) * Fp::from(big) * Fp::from(2);
+        assert!(run(&[big, big], &[big, big], expected));
+    }
+}
@@ -0,0 +1,438 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+use super::{
+    range_check::{RangeCheckChip, RangeCheckConfig},
+    range_table::RangeTableConfig,
+};
+
+#[derive(Debug, Clone)]
+pub struct FixedPointConfig<const FRAC_BITS: usize, const NUM_BYTES: usize> {
+    pub advice: [Column<Advice>; 4],
+    pub q_add: Selector,
+    pub q_sub: Selector,
+    pub q_mul: Selector,
+    pub q_floor: Selector,
+    pub q_rem: Selector,
+    pub q_bias: Selector,
+    pub range: RangeCheckConfig,
+    pub frac: RangeTableConfig,
+}
+
+// Signed fixed-point numbers: a cell holding x stands for x / 2^FRAC_BITS, with
+// negative x as p - |x|, and -2^(8 * NUM_BYTES - 1) <= x < 2^(8 * NUM_BYTES - 1).
+// Every cell the chip hands out is range checked to that, so a result that
+// overflows makes the proof fail rather than wrap around the field. `mul` and
+// `floor` round towards negative infinity, the same as `>>` on a signed integer,
+// so -1/512 becomes -1/256 and not 0.
+#[derive(Debug, Clone)]
+pub struct FixedPointChip<F: FieldExt, const FRAC_BITS: usize, const NUM_BYTES: usize> {
+    config: FixedPointConfig<FRAC_BITS, NUM_BYTES>,
+    _marker: PhantomData<F>,
+}
+
+// The field element for a signed integer.
+pub fn encode<F: FieldExt>(x: i128) -> F {
+    if x < 0 {
+        -F::from_u128(x.unsigned_abs())
+    } else {
+        F::from_u128(x as u128)
+    }
+}
+
+// The signed integer in a field element, which has to be within 2^126 of 0.
+pub fn decode<F: FieldExt>(x: F) -> i128 {
+    let bias = 1u128 << 126;
+    let biased = x + F::from_u128(bias);
+    assert!(F::from_u128(biased.get_lower_128()) == biased);
+    biased.get_lower_128() as i128 - bias as i128
+}
+
+pub fn mul<const FRAC_BITS: usize>(a: i128, b: i128) -> i128 {
+    (a * b) >> FRAC_BITS
+}
+
+pub fn floor<const FRAC_BITS: usize>(a: i128) -> i128 {
+    (a >> FRAC_BITS) << FRAC_BITS
+}
+
+impl<F: FieldExt, const FRAC_BITS: usize, const NUM_BYTES: usize>
+    FixedPointChip<F, FRAC_BITS, NUM_BYTES>
+{
+    pub fn construct(config: FixedPointConfig<FRAC_BITS, NUM_BYTES>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    // `frac` has to be the table of 0..2^FRAC_BITS.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+        range: RangeCheckConfig,
+        frac: RangeTableConfig,
+    ) -> FixedPointConfig<FRAC_BITS, NUM_BYTES> {
+        // products of two values have to stay far from wrapping around the
+        // field, and the biased values have to fit in the range check
+        const { assert!(NUM_BYTES > 0 && NUM_BYTES < 16) };
+        const { assert!(FRAC_BITS > 0 && FRAC_BITS < 8 * NUM_BYTES) };
+        assert!(frac.lo == 0 && frac.hi == (1 << FRAC_BITS) - 1);
+
+        let [col_a, col_b, col_c, col_rem] = advice;
+        let q_add = meta.selector();
+        let q_sub = meta.selector();
+        let q_mul = meta.selector();
+        let q_floor = meta.selector();
+        let q_rem = meta.complex_selector();
+        let q_bias = meta.selector();
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        frac.lookup(meta, q_rem, col_rem);
+
+        let one = F::from_u128(1 << FRAC_BITS);
+        let bias = F::from_u128(1 << (8 * NUM_BYTES - 1));
+
+        meta.create_gate("add", |meta| {
+            //
+            // col_a | col_b | col_c | col_rem | q_add | q_sub | q_mul | q_rem
+            //   a       b       c                1
+            //   a       b       c                        1
+            //   a       b       c       r                        1       1
+            //
+            let s = meta.query_selector(q_add);
+            let a = meta.query_advice(col_a, Rotation::cur());
+            let b = meta.query_advice(col_b, Rotation::cur());
+            let c = meta.query_advice(col_c, Rotation::cur());
+            vec![s * (a + b - c)]
+        });
+
+        meta.create_gate("sub", |meta| {
+            let s = meta.query_selector(q_sub);
+            let a = meta.query_advice(col_a, Rotation::cur());
+            let b = meta.query_advice(col_b, Rotation::cur());
+            let c = meta.query_advice(col_c, Rotation::cur());
+            vec![s * (a - b - c)]
+        });
+
+        meta.create_gate("mul", |meta| {
+            // a·b = c·2^FRAC_BITS + r, so c is a·b rounded down once r is
+            // looked up and c is range checked
+            let s = meta.query_selector(q_mul);
+            let a = meta.query_advice(col_a, Rotation::cur());
+            let b = meta.query_advice(col_b, Rotation::cur());
+            let c = meta.query_advice(col_c, Rotation::cur());
+            let rem = meta.query_advice(col_rem, Rotation::cur());
+            vec![s * (a * b - c * Expression::Constant(one) - rem)]
+        });
+
+        meta.create_gate("floor", |meta| {
+            //
+            // col_a | col_b | col_c | col_rem | q_floor | q_rem
+            //   a      int      c       r          1         1
+            //
+            // a = c + r with c = int·2^FRAC_BITS
+            let s = meta.query_selector(q_floor);
+            let a = meta.query_advice(col_a, Rotation::cur());
+            let int = meta.query_advice(col_b, Rotation::cur());
+            let c = meta.query_advice(col_c, Rotation::cur());
+            let rem = meta.query_advice(col_rem, Rotation::cur());
+            vec![
+                s.clone() * (a - c.clone() - rem),
+                s * (int * Expression::Constant(one) - c),
+            ]
+        });
+
+        meta.create_gate("bias", |meta| {
+            //
+            // col_a | col_b    | q_bias
+            //   x      biased      1
+            //
+            // biased = x + 2^(8 * NUM_BYTES - 1) fits in NUM_BYTES exactly
+            // when x is in range
+            let s = meta.query_selector(q_bias);
+            let x = meta.query_advice(col_a, Rotation::cur());
+            let biased = meta.query_advice(col_b, Rotation::cur());
+            vec![s * (x + Expression::Constant(bias) - biased)]
+        });
+
+        FixedPointConfig {
+            advice,
+            q_add,
+            q_sub,
+            q_mul,
+            q_floor,
+            q_rem,
+            q_bias,
+            range,
+            frac,
+        }
+    }
+
+    // Assigns a fresh value and range checks it.
+    pub fn witness(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let cell = layouter.assign_region(
+            || "witness",
+            |mut region| region.assign_advice(|| "value", self.config.advice[2], 0, || value),
+        )?;
+        self.range_check(layouter.namespace(|| "range check"), &cell)?;
+        Ok(cell)
+    }
+
+    pub fn add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let c = a.value().copied() + b.value();
+        self.op(layouter, "add", self.config.q_add, a, b, c, None)
+    }
+
+    pub fn sub(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let c = a.value().copied() - b.value();
+        self.op(layouter, "sub", self.config.q_sub, a, b, c, None)
+    }
+
+    pub fn mul(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let product = a
+            .value()
+            .zip(b.value())
+            .map(|(a, b)| decode(*a) * decode(*b));
+        let c = product.map(|p| encode::<F>(p >> FRAC_BITS));
+        let rem = product.map(|p| encode::<F>(p & ((1 << FRAC_BITS) - 1)));
+        self.op(layouter, "mul", self.config.q_mul, a, b, c, Some(rem))
+    }
+
+    // Rounds down to an integer, returning a cell with no fractional bits.
+    pub fn floor(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        let value = a.value().map(|a| decode(*a));
+
+        let (int, c) = layouter.assign_region(
+            || "floor",
+            |mut region| {
+                config.q_floor.enable(&mut region, 0)?;
+                config.q_rem.enable(&mut region, 0)?;
+
+                let [col_a, col_b, col_c, col_rem] = config.advice;
+                a.copy_advice(|| "a", &mut region, col_a, 0)?;
+                let int = region.assign_advice(
+                    || "int",
+                    col_b,
+                    0,
+                    || value.map(|a| encode::<F>(a >> FRAC_BITS)),
+                )?;
+                let c = region.assign_advice(
+                    || "c",
+                    col_c,
+                    0,
+                    || value.map(|a| encode::<F>(floor::<FRAC_BITS>(a))),
+                )?;
+                region.assign_advice(
+                    || "rem",
+                    col_rem,
+                    0,
+                    || value.map(|a| encode::<F>(a & ((1 << FRAC_BITS) - 1))),
+                )?;
+                Ok((int, c))
+            },
+        )?;
+
+        // c is the integer part times 2^FRAC_BITS only if the integer part
+        // can't wrap around the field
+        self.range_check(layouter.namespace(|| "int"), &int)?;
+        self.range_check(layouter.namespace(|| "c"), &c)?;
+        Ok(c)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn op(
+        &self,
+        mut layouter: impl Layouter<F>,
+        name: &str,
+        selector: Selector,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        c: Value<F>,
+        rem: Option<Value<F>>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+
+        let c = layouter.assign_region(
+            || name,
+            |mut region| {
+                selector.enable(&mut region, 0)?;
+
+                let [col_a, col_b, col_c, col_rem] = config.advice;
+                a.copy_advice(|| "a", &mut region, col_a, 0)?;
+                b.copy_advice(|| "b", &mut region, col_b, 0)?;
+                if let Some(rem) = rem {
+                    config.q_rem.enable(&mut region, 0)?;
+                    region.assign_advice(|| "rem", col_rem, 0, || rem)?;
+                }
+                region.assign_advice(|| "c", col_c, 0, || c)
+            },
+        )?;
+
+        self.range_check(layouter.namespace(|| "c"), &c)?;
+        Ok(c)
+    }
+
+    fn range_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        let config = &self.config;
+        let bias = Value::known(F::from_u128(1 << (8 * NUM_BYTES - 1)));
+
+        let biased = layouter.assign_region(
+            || "bias",
+            |mut region| {
+                config.q_bias.enable(&mut region, 0)?;
+                let cell = cell.copy_advice(|| "value", &mut region, config.advice[0], 0)?;
+                let biased = cell.value().copied() + bias;
+                region.assign_advice(|| "biased", config.advice[1], 0, || biased)
+            },
+        )?;
+
+        let range = RangeCheckChip::construct(config.range.clone());
+        range.range_check::<NUM_BYTES>(layouter.namespace(|| "biased"), &biased)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::tables::TableRegistry;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const FRAC_BITS: usize = 8;
+    const NUM_BYTES: usize = 4;
+
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        a: Value<F>,
+        b: Value<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = (
+            FixedPointConfig<FRAC_BITS, NUM_BYTES>,
+            TableRegistry,
+            Column<Instance>,
+        );
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 4].map(|_| meta.advice_column());
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            let mut tables = TableRegistry::default();
+            let bytes = tables.bytes(meta);
+            let frac = tables.range(meta, 0, (1 << FRAC_BITS) - 1);
+            let range = RangeCheckChip::configure(meta, [advice[2], advice[3]], bytes);
+            let config = FixedPointChip::configure(meta, advice, range, frac);
+            (config, tables, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, tables, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            tables.load(&mut layouter)?;
+
+            let chip = FixedPointChip::construct(config);
+            let a = chip.witness(layouter.namespace(|| "a"), self.a)?;
+            let b = chip.witness(layouter.namespace(|| "b"), self.b)?;
+
+            let results = [
+                chip.add(layouter.namespace(|| "a + b"), &a, &b)?,
+                chip.sub(layouter.namespace(|| "a - b"), &a, &b)?,
+                chip.mul(layouter.namespace(|| "a * b"), &a, &b)?,
+                chip.floor(layouter.namespace(|| "floor(a)"), &a)?,
+            ];
+            for (row, result) in results.iter().enumerate() {
+                layouter.constrain_instance(result.cell(), instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    // Runs the circuit on raw values, claiming the host results with the given
+    // change to one of them.
+    fn verify(a: i128, b: i128, tweak: Option<(usize, i128)>) -> bool {
+        let mut results = [a + b, a - b, mul::<FRAC_BITS>(a, b), floor::<FRAC_BITS>(a)];
+        if let Some((i, result)) = tweak {
+            results[i] = result;
+        }
+        let circuit = MyCircuit {
+            a: Value::known(encode(a)),
+            b: Value::known(encode(b)),
+        };
+        let instance = results.iter().map(|r| encode::<Fp>(*r)).collect();
+        let prover = MockProver::run(10, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_fixed_point() {
+        // 1.5 and -0.5
+        assert_eq!(mul::<FRAC_BITS>(384, -128), -192);
+        assert!(verify(384, -128, None));
+        // -1.5 rounds down to -2
+        assert_eq!(floor::<FRAC_BITS>(-384), -512);
+        assert!(verify(-384, 256, None));
+        assert!(!verify(-384, 256, Some((3, -256))));
+
+        // 1/256 · 1/2 rounds down to 0, and -1/256 · 1/2 to -1/256 rather
+        // than towards 0
+        assert_eq!(mul::<FRAC_BITS>(1, 128), 0);
+        assert_eq!(mul::<FRAC_BITS>(-1, 128), -1);
+        assert!(verify(1, 128, None));
+        assert!(verify(-1, 128, None));
+        assert!(!verify(-1, 128, Some((2, 0))));
+        // rounding up isn't allowed either
+        assert!(!verify(1, 128, Some((2, 1))));
+
+        // the largest and smallest values there are
+        let (max, min) = ((1 << 31) - 1, -(1 << 31));
+        assert!(verify(max, 0, None));
+        assert!(verify(min, 0, None));
+
+        // overflows
+        assert!(!verify(max, 1, None));
+        assert!(!verify(min, 1, None));
+        assert!(!verify(1 << 20, 1 << 20, None));
+        assert!(!verify(max + 1, 0, None));
+    }
+}
@@ -0,0 +1,289 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+// Multiplication in GF(2^8) modulo the AES polynomial x^8 + x^4 + x^3 + x + 1.
+pub fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 == 1 {
+            product ^= a;
+        }
+        let carry = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+#[derive(Debug, Clone)]
+pub struct Gf256Config {
+    pub advice: [Column<Advice>; 9],
+    pub q_xor: Selector,
+    pub q_xtime: Selector,
+    pub xor_table: [TableColumn; 3],
+    pub xtime_table: [TableColumn; 2],
+}
+
+// Arithmetic on bytes as elements of GF(2^8): addition is xor, which is looked
+// up a nibble at a time in a 256 row table of (x, y, x ^ y), and `xtime`
+// multiplies by x (i.e. 2) with one lookup. Decomposing into nibbles that are
+// in the table range checks the operands, so results are always bytes.
+#[derive(Debug, Clone)]
+pub struct Gf256Chip<F: FieldExt> {
+    config: Gf256Config,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Gf256Chip<F> {
+    pub fn construct(config: Gf256Config) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 9]) -> Gf256Config {
+        let q_xor = meta.complex_selector();
+        let q_xtime = meta.complex_selector();
+        let xor_table = [(); 3].map(|_| meta.lookup_table_column());
+        let xtime_table = [(); 2].map(|_| meta.lookup_table_column());
+
+        for column in &advice[..3] {
+            meta.enable_equality(*column);
+        }
+
+        //
+        // advice[0..3] | advice[3..6]        | advice[6..9]        | q_xor
+        //   a  b  c       a_hi  b_hi  c_hi      a_lo  b_lo  c_lo        1
+        //
+        // with c = a ^ b. Rows where q_xor is off look up (0, 0, 0).
+        for nibbles in [&advice[3..6], &advice[6..9]] {
+            meta.lookup(|meta| {
+                let q = meta.query_selector(q_xor);
+                nibbles
+                    .iter()
+                    .zip(xor_table)
+                    .map(|(column, table)| {
+                        let v = meta.query_advice(*column, Rotation::cur());
+                        (q.clone() * v, table)
+                    })
+                    .collect()
+            });
+        }
+
+        meta.create_gate("xor nibbles", |meta| {
+            let q = meta.query_selector(q_xor);
+            let sixteen = Expression::Constant(F::from(16));
+            (0..3)
+                .map(|i| {
+                    let byte = meta.query_advice(advice[i], Rotation::cur());
+                    let hi = meta.query_advice(advice[3 + i], Rotation::cur());
+                    let lo = meta.query_advice(advice[6 + i], Rotation::cur());
+                    q.clone() * (byte - hi * sixteen.clone() - lo)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        meta.lookup(|meta| {
+            //
+            // advice[0] | advice[2] | q_xtime
+            //     a       xtime(a)      1
+            //
+            // xtime(0) = 0, so rows where q_xtime is off look up (0, 0)
+            let q = meta.query_selector(q_xtime);
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let c = meta.query_advice(advice[2], Rotation::cur());
+            vec![(q.clone() * a, xtime_table[0]), (q * c, xtime_table[1])]
+        });
+
+        Gf256Config {
+            advice,
+            q_xor,
+            q_xtime,
+            xor_table,
+            xtime_table,
+        }
+    }
+
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        let config = &self.config;
+        let byte = |v: u8| Value::known(F::from(v as u64));
+
+        layouter.assign_table(
+            || "nibble xor",
+            |mut table| {
+                for offset in 0..256 {
+                    let (x, y) = ((offset >> 4) as u8, (offset & 0xf) as u8);
+                    table.assign_cell(|| "x", config.xor_table[0], offset, || byte(x))?;
+                    table.assign_cell(|| "y", config.xor_table[1], offset, || byte(y))?;
+                    table.assign_cell(|| "x ^ y", config.xor_table[2], offset, || byte(x ^ y))?;
+                }
+                Ok(())
+            },
+        )?;
+
+        layouter.assign_table(
+            || "xtime",
+            |mut table| {
+                for x in 0..=255u8 {
+                    let offset = x as usize;
+                    table.assign_cell(|| "x", config.xtime_table[0], offset, || byte(x))?;
+                    table.assign_cell(
+                        || "xtime(x)",
+                        config.xtime_table[1],
+                        offset,
+                        || byte(gf_mul(x, 2)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    // a + b, i.e. a ^ b.
+    pub fn xor(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        let byte = |cell: &AssignedCell<F, F>| cell.value().map(|v| v.get_lower_128() as u8);
+        let (x, y) = (byte(a), byte(b));
+        let z = x.zip(y).map(|(x, y)| x ^ y);
+
+        layouter.assign_region(
+            || "xor",
+            |mut region| {
+                config.q_xor.enable(&mut region, 0)?;
+                a.copy_advice(|| "a", &mut region, config.advice[0], 0)?;
+                b.copy_advice(|| "b", &mut region, config.advice[1], 0)?;
+                let c = region.assign_advice(
+                    || "c",
+                    config.advice[2],
+                    0,
+                    || z.map(|z| F::from(z as u64)),
+                )?;
+
+                for (i, v) in [x, y, z].into_iter().enumerate() {
+                    let hi = v.map(|v| F::from((v >> 4) as u64));
+                    let lo = v.map(|v| F::from((v & 0xf) as u64));
+                    region.assign_advice(|| "hi", config.advice[3 + i], 0, || hi)?;
+                    region.assign_advice(|| "lo", config.advice[6 + i], 0, || lo)?;
+                }
+                Ok(c)
+            },
+        )
+    }
+
+    // a·x, i.e. a·2.
+    pub fn xtime(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "xtime",
+            |mut region| {
+                config.q_xtime.enable(&mut region, 0)?;
+                a.copy_advice(|| "a", &mut region, config.advice[0], 0)?;
+                let c = a
+                    .value()
+                    .map(|v| F::from(gf_mul(v.get_lower_128() as u8, 2) as u64));
+                region.assign_advice(|| "xtime(a)", config.advice[2], 0, || c)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    // Proves (a ^ b, xtime(a)) for each pair.
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        pairs: Vec<(Value<F>, Value<F>)>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = (Gf256Config, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                pairs: vec![(Value::unknown(), Value::unknown()); self.pairs.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 9].map(|_| meta.advice_column());
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (Gf256Chip::configure(meta, advice), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = Gf256Chip::construct(config.clone());
+            chip.load(&mut layouter)?;
+
+            for (i, (a, b)) in self.pairs.iter().enumerate() {
+                let (a, b) = layouter.assign_region(
+                    || "inputs",
+                    |mut region| {
+                        let a = region.assign_advice(|| "a", config.advice[0], 0, || *a)?;
+                        let b = region.assign_advice(|| "b", config.advice[1], 0, || *b)?;
+                        Ok((a, b))
+                    },
+                )?;
+                let c = chip.xor(layouter.namespace(|| "a ^ b"), &a, &b)?;
+                let d = chip.xtime(layouter.namespace(|| "xtime(a)"), &a)?;
+                layouter.constrain_instance(c.cell(), instance, 2 * i)?;
+                layouter.constrain_instance(d.cell(), instance, 2 * i + 1)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn verify(pairs: &[(u64, u64)], results: &[(u64, u64)]) -> bool {
+        let circuit = MyCircuit {
+            pairs: pairs
+                .iter()
+                .map(|(a, b)| (Value::known(Fp::from(*a)), Value::known(Fp::from(*b))))
+                .collect(),
+        };
+        let instance = results
+            .iter()
+            .flat_map(|(c, d)| [Fp::from(*c), Fp::from(*d)])
+            .collect();
+        let prover = MockProver::run(9, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_gf256() {
+        // from FIPS-197
+        assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+        assert_eq!(gf_mul(0x57, 0x13), 0xfe);
+
+        let pairs = [(0x57, 0x83), (0xff, 0x0f), (0x80, 0x80), (0, 0xa5)];
+        let results = pairs.map(|(a, b)| (a ^ b, gf_mul(a as u8, 2) as u64));
+        assert_eq!(results[2], (0, 0x1b));
+        assert!(verify(&pairs, &results));
+
+        // a wrong xor, a wrong xtime, and operands that aren't bytes
+        assert!(!verify(&[(0x57, 0x83)], &[(0xd5, 0xae)]));
+        assert!(!verify(&[(0x57, 0x83)], &[(0xd4, 0xaf)]));
+        assert!(!verify(&[(0x100, 0x01)], &[(0x101, 0x00)]));
+        assert!(!verify(&[(0x01, 0x101)], &[(0x100, 0x02)]));
+    }
+}
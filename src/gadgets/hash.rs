@@ -0,0 +1,172 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+use std::fmt::Debug;
+
+use super::{
+    keccak::{keccak256, KeccakChip, KeccakConfig},
+    poseidon::{self, PoseidonChip, PoseidonConfig},
+    range_check::RangeCheckChip,
+    sha256::{pad, sha256, Sha256Chip, Sha256Config},
+    tables::TableRegistry,
+};
+
+// What a circuit needs from a hash chip to use it without knowing which one it
+// is: columns of its own, somewhere to witness the message, the hash itself,
+// and the same hash on the host. Messages and digests are field elements in
+// whatever units the hash works in, so bytes for Keccak, 32-bit words for
+// SHA-256 and field elements for Poseidon; the chip constrains the message to
+// those units itself.
+pub trait HashInstructions<F: FieldExt>: Sized {
+    type Config: Clone + Debug;
+
+    // Allocates the chip's columns, asking `tables` for any lookup tables so
+    // the circuit loads them.
+    fn configure(meta: &mut ConstraintSystem<F>, tables: &mut TableRegistry) -> Self::Config;
+
+    fn construct(config: Self::Config) -> Self;
+
+    // An equality enabled column the message can be witnessed in.
+    fn message_column(config: &Self::Config) -> Column<Advice>;
+
+    fn hash(
+        &self,
+        layouter: impl Layouter<F>,
+        message: &[AssignedCell<F, F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error>;
+
+    fn digest(message: &[F]) -> Vec<F>;
+}
+
+impl<F: FieldExt> HashInstructions<F> for PoseidonChip<F> {
+    type Config = PoseidonConfig<F>;
+
+    fn configure(meta: &mut ConstraintSystem<F>, _: &mut TableRegistry) -> Self::Config {
+        let state = [(); poseidon::WIDTH].map(|_| meta.advice_column());
+        let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+        let constants = meta.fixed_column();
+        PoseidonChip::configure(meta, state, rc, constants)
+    }
+
+    fn construct(config: Self::Config) -> Self {
+        PoseidonChip::construct(config)
+    }
+
+    fn message_column(config: &Self::Config) -> Column<Advice> {
+        config.state[0]
+    }
+
+    fn hash(
+        &self,
+        layouter: impl Layouter<F>,
+        message: &[AssignedCell<F, F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        Ok(vec![PoseidonChip::hash(self, layouter, message)?])
+    }
+
+    fn digest(message: &[F]) -> Vec<F> {
+        vec![poseidon::hash(message)]
+    }
+}
+
+impl<F: FieldExt> HashInstructions<F> for KeccakChip<F> {
+    type Config = KeccakConfig;
+
+    fn configure(meta: &mut ConstraintSystem<F>, _: &mut TableRegistry) -> Self::Config {
+        let advice = [(); 20].map(|_| meta.advice_column());
+        let constants = meta.fixed_column();
+        KeccakChip::configure(meta, advice, constants)
+    }
+
+    fn construct(config: Self::Config) -> Self {
+        KeccakChip::construct(config)
+    }
+
+    fn message_column(config: &Self::Config) -> Column<Advice> {
+        config.advice[0]
+    }
+
+    fn hash(
+        &self,
+        layouter: impl Layouter<F>,
+        message: &[AssignedCell<F, F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        Ok(KeccakChip::hash(self, layouter, message)?.to_vec())
+    }
+
+    // Panics on an element that isn't a byte.
+    fn digest(message: &[F]) -> Vec<F> {
+        let bytes: Vec<u8> = message
+            .iter()
+            .map(|b| u8::try_from(b.get_lower_128()).expect("not a byte"))
+            .collect();
+        keccak256(&bytes)
+            .iter()
+            .map(|b| F::from(*b as u64))
+            .collect()
+    }
+}
+
+// SHA-256 of big endian words, so the message is always a whole number of
+// words, padded with constants in the circuit.
+impl<F: FieldExt> HashInstructions<F> for Sha256Chip<F> {
+    type Config = Sha256Config;
+
+    fn configure(meta: &mut ConstraintSystem<F>, tables: &mut TableRegistry) -> Self::Config {
+        let advice = [(); 6].map(|_| meta.advice_column());
+        let constants = meta.fixed_column();
+        let bytes = tables.bytes(meta);
+        let range = RangeCheckChip::configure(meta, [advice[4], advice[5]], bytes);
+        Sha256Chip::configure(meta, advice, constants, range)
+    }
+
+    fn construct(config: Self::Config) -> Self {
+        Sha256Chip::construct(config)
+    }
+
+    fn message_column(config: &Self::Config) -> Column<Advice> {
+        config.u32.advice[0]
+    }
+
+    fn hash(
+        &self,
+        mut layouter: impl Layouter<F>,
+        message: &[AssignedCell<F, F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let u32 = self.u32();
+
+        let mut words = vec![];
+        for word in message {
+            words.push(u32.check(layouter.namespace(|| "word"), word.clone())?);
+        }
+        // only the padding depends on the length, not the message
+        let blocks = pad(&vec![0; 4 * message.len()]);
+        for word in blocks.iter().flatten().skip(message.len()) {
+            words.push(u32.constant(layouter.namespace(|| "padding"), *word)?);
+        }
+
+        let mut h = self.iv(layouter.namespace(|| "iv"))?;
+        for (i, block) in words.chunks(16).enumerate() {
+            h = self.compress(
+                layouter.namespace(|| format!("block {}", i)),
+                &h,
+                &block.to_vec().try_into().unwrap(),
+            )?;
+        }
+        Ok(h.iter().map(|word| word.inner().clone()).collect())
+    }
+
+    // Panics on an element that isn't a 32-bit word.
+    fn digest(message: &[F]) -> Vec<F> {
+        let bytes: Vec<u8> = message
+            .iter()
+            .flat_map(|w| {
+                u32::try_from(w.get_lower_128())
+                    .expect("not a word")
+                    .to_be_bytes()
+            })
+            .collect();
+        sha256(&bytes)
+            .chunks(4)
+            .map(|chunk| F::from(u32::from_be_bytes(chunk.try_into().unwrap()) as u64))
+            .collect()
+    }
+}
@@ -0,0 +1,150 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone)]
+pub struct IsEqualConfig {
+    pub advice: [Column<Advice>; 4],
+    pub selector: Selector,
+}
+
+// Outputs a boolean cell that is 1 when two cells hold the same value and 0
+// otherwise. The prover supplies the inverse of the difference when there is
+// one, which forces `out` to 0; a zero difference forces `out` to 1.
+#[derive(Debug, Clone)]
+pub struct IsEqualChip<F: FieldExt> {
+    config: IsEqualConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> IsEqualChip<F> {
+    pub fn construct(config: IsEqualConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 4]) -> IsEqualConfig {
+        let [col_a, col_b, col_inv, col_out] = advice;
+        let selector = meta.selector();
+
+        meta.enable_equality(col_a);
+        meta.enable_equality(col_b);
+        meta.enable_equality(col_out);
+
+        meta.create_gate("is equal", |meta| {
+            //
+            // col_a | col_b | col_inv | col_out | selector
+            //   a      b       inv       out         s
+            //
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(col_a, Rotation::cur());
+            let b = meta.query_advice(col_b, Rotation::cur());
+            let inv = meta.query_advice(col_inv, Rotation::cur());
+            let out = meta.query_advice(col_out, Rotation::cur());
+            let diff = a - b;
+            vec![
+                s.clone() * (out.clone() + diff.clone() * inv - Expression::Constant(F::one())),
+                s * diff * out,
+            ]
+        });
+
+        IsEqualConfig { advice, selector }
+    }
+
+    pub fn is_equal(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "is equal",
+            |mut region| {
+                self.config.selector.enable(&mut region, 0)?;
+
+                let a = a.copy_advice(|| "a", &mut region, self.config.advice[0], 0)?;
+                let b = b.copy_advice(|| "b", &mut region, self.config.advice[1], 0)?;
+
+                let diff = a.value().copied() - b.value().copied();
+                let inv = diff.map(|diff| diff.invert().unwrap_or(F::zero()));
+                let out = diff.map(|diff| {
+                    if diff == F::zero() {
+                        F::one()
+                    } else {
+                        F::zero()
+                    }
+                });
+
+                region.assign_advice(|| "inv", self.config.advice[2], 0, || inv)?;
+                region.assign_advice(|| "out", self.config.advice[3], 0, || out)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        a: Value<F>,
+        b: Value<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = (IsEqualConfig, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 4].map(|_| meta.advice_column());
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (IsEqualChip::configure(meta, advice), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let (a, b) = layouter.assign_region(
+                || "inputs",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", config.advice[0], 0, || self.a)?;
+                    let b = region.assign_advice(|| "b", config.advice[1], 0, || self.b)?;
+                    Ok((a, b))
+                },
+            )?;
+
+            let chip = IsEqualChip::construct(config);
+            let out = chip.is_equal(layouter.namespace(|| "a == b"), &a, &b)?;
+            layouter.constrain_instance(out.cell(), instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_is_equal() {
+        let k = 4;
+
+        for (a, b, out, ok) in [
+            (3, 3, 1, true),
+            (3, 4, 0, true),
+            (3, 3, 0, false),
+            (3, 4, 1, false),
+        ] {
+            let circuit = MyCircuit {
+                a: Value::known(Fp::from(a)),
+                b: Value::known(Fp::from(b)),
+            };
+            let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(out)]]).unwrap();
+            assert_eq!(prover.verify().is_ok(), ok, "{} == {} -> {}", a, b, out);
+        }
+    }
+}
@@ -0,0 +1,602 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+pub const RATE_BYTES: usize = 136;
+pub const ROUNDS: usize = 24;
+
+const STATE_BITS: usize = 1600;
+const RATE_BITS: usize = 8 * RATE_BYTES;
+
+pub const RC: [u64; ROUNDS] = [
+    0x0000_0000_0000_0001,
+    0x0000_0000_0000_8082,
+    0x8000_0000_0000_808a,
+    0x8000_0000_8000_8000,
+    0x0000_0000_0000_808b,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8009,
+    0x0000_0000_0000_008a,
+    0x0000_0000_0000_0088,
+    0x0000_0000_8000_8009,
+    0x0000_0000_8000_000a,
+    0x0000_0000_8000_808b,
+    0x8000_0000_0000_008b,
+    0x8000_0000_0000_8089,
+    0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002,
+    0x8000_0000_0000_0080,
+    0x0000_0000_0000_800a,
+    0x8000_0000_8000_000a,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8080,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8008,
+];
+
+// rho's rotation of lane x + 5y
+const ROTATIONS: [usize; 25] = [
+    0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56, 14,
+];
+
+pub fn keccak_f(a: &mut [u64; 25]) {
+    for rc in RC {
+        let c: [u64; 5] =
+            std::array::from_fn(|x| a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20]);
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                a[x + 5 * y] ^= d;
+            }
+        }
+
+        let mut b = [0; 25];
+        for x in 0..5 {
+            for y in 0..5 {
+                b[y + 5 * ((2 * x + 3 * y) % 5)] =
+                    a[x + 5 * y].rotate_left(ROTATIONS[x + 5 * y] as u32);
+            }
+        }
+        for x in 0..5 {
+            for y in 0..5 {
+                a[x + 5 * y] = b[x + 5 * y] ^ (!b[(x + 1) % 5 + 5 * y] & b[(x + 2) % 5 + 5 * y]);
+            }
+        }
+        a[0] ^= rc;
+    }
+}
+
+// Keccak's pad10*1 with the 0x01 domain byte Ethereum uses, rather than
+// SHA-3's 0x06.
+fn pad(message: &[u8]) -> Vec<u8> {
+    let mut padded = message.to_vec();
+    padded.push(0x01);
+    padded.resize(padded.len().div_ceil(RATE_BYTES) * RATE_BYTES, 0);
+    *padded.last_mut().unwrap() |= 0x80;
+    padded
+}
+
+pub fn keccak256(message: &[u8]) -> [u8; 32] {
+    let mut state = [0; 25];
+    for block in pad(message).chunks(RATE_BYTES) {
+        for (lane, chunk) in state.iter_mut().zip(block.chunks(8)) {
+            *lane ^= u64::from_le_bytes(chunk.try_into().unwrap());
+        }
+        keccak_f(&mut state);
+    }
+
+    let mut digest = [0; 32];
+    for (chunk, lane) in digest.chunks_mut(8).zip(state) {
+        chunk.copy_from_slice(&lane.to_le_bytes());
+    }
+    digest
+}
+
+fn xor<F: FieldExt>(a: Value<F>, b: Value<F>) -> Value<F> {
+    a.zip(b).map(|(a, b)| a + b - a * b * F::from(2))
+}
+
+// a ^ (!b & c)
+fn chi<F: FieldExt>(a: Value<F>, b: Value<F>, c: Value<F>) -> Value<F> {
+    xor(a, b.zip(c).map(|(b, c)| (F::one() - b) * c))
+}
+
+#[derive(Debug, Clone)]
+pub struct KeccakConfig {
+    pub advice: [Column<Advice>; 20],
+    pub rc: Column<Fixed>,
+    pub q_bits: Selector,
+    pub q_theta: Selector,
+    pub q_xor: Selector,
+    pub q_chi: Selector,
+}
+
+// Keccak-256 over the state's 1600 bits, one cell each. Working on bits makes
+// rho and pi free, since they only move bits around, so a round is 320 theta
+// rows, each the parity of the ten bits that go into one of its column sums,
+// and 320 rows of the rest, each xoring that in and applying chi and iota
+// across a row of five lanes. A permutation is a little over 15k rows.
+#[derive(Debug, Clone)]
+pub struct KeccakChip<F: FieldExt> {
+    config: KeccakConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> KeccakChip<F> {
+    pub fn construct(config: KeccakConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    // `constants` holds the padding and the empty capacity.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 20],
+        constants: Column<Fixed>,
+    ) -> KeccakConfig {
+        let rc = meta.fixed_column();
+        let q_bits = meta.selector();
+        let q_theta = meta.selector();
+        let q_xor = meta.selector();
+        let q_chi = meta.selector();
+
+        meta.enable_constant(constants);
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        let one = || Expression::Constant(F::one());
+        let two = || Expression::Constant(F::from(2));
+
+        meta.create_gate("bits", |meta| {
+            //
+            // advice[0] | advice[1] | ... | advice[8] | q_bits
+            //   byte        b_0       ...     b_7         1
+            //
+            let s = meta.query_selector(q_bits);
+            let byte = meta.query_advice(advice[0], Rotation::cur());
+            let bits: Vec<_> = (1..9)
+                .map(|i| meta.query_advice(advice[i], Rotation::cur()))
+                .collect();
+            let sum = bits
+                .iter()
+                .rev()
+                .fold(Expression::Constant(F::zero()), |acc, bit| {
+                    acc * two() + bit.clone()
+                });
+            bits.into_iter()
+                .map(|bit| s.clone() * bit.clone() * (one() - bit))
+                .chain(Some(s.clone() * (byte - sum)))
+                .collect::<Vec<_>>()
+        });
+
+        meta.create_gate("theta", |meta| {
+            //
+            // advice[0..10] | advice[10] | advice[11] | q_theta
+            //    c_0..c_9          d           h           1
+            //
+            // d is the parity of the c's and h the rest of their sum halved
+            let s = meta.query_selector(q_theta);
+            let sum = (0..10)
+                .map(|i| meta.query_advice(advice[i], Rotation::cur()))
+                .fold(Expression::Constant(F::zero()), |acc, c| acc + c);
+            let d = meta.query_advice(advice[10], Rotation::cur());
+            let h = meta.query_advice(advice[11], Rotation::cur());
+            let range = (0..6).fold(one(), |acc, i| {
+                acc * (h.clone() - Expression::Constant(F::from(i)))
+            });
+            vec![
+                s.clone() * d.clone() * (one() - d.clone()),
+                s.clone() * range,
+                s * (sum - d - h * two()),
+            ]
+        });
+
+        meta.create_gate("xor", |meta| {
+            //
+            // advice[0..5] | advice[5..10] | advice[10..15] | q_xor
+            //      a              d            b = a ^ d        1
+            //
+            let s = meta.query_selector(q_xor);
+            (0..5)
+                .map(|i| {
+                    let a = meta.query_advice(advice[i], Rotation::cur());
+                    let d = meta.query_advice(advice[5 + i], Rotation::cur());
+                    let b = meta.query_advice(advice[10 + i], Rotation::cur());
+                    s.clone() * (a.clone() + d.clone() - two() * a * d - b)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        meta.create_gate("chi", |meta| {
+            //
+            // advice[10..15] | advice[15..20] |  rc  | q_chi
+            //   b_0..b_4         out_0..out_4    rc      1
+            //
+            // out_x = b_x ^ (!b_x+1 & b_x+2), with rc xored into out_0
+            let s = meta.query_selector(q_chi);
+            let b: Vec<_> = (10..15)
+                .map(|i| meta.query_advice(advice[i], Rotation::cur()))
+                .collect();
+            let rc = meta.query_fixed(rc, Rotation::cur());
+            let xor = |a: Expression<F>, b: Expression<F>| a.clone() + b.clone() - two() * a * b;
+            (0..5)
+                .map(|x| {
+                    let out = meta.query_advice(advice[15 + x], Rotation::cur());
+                    let and = (one() - b[(x + 1) % 5].clone()) * b[(x + 2) % 5].clone();
+                    let chi = xor(b[x].clone(), and);
+                    let chi = match x {
+                        0 => xor(chi, rc.clone()),
+                        _ => chi,
+                    };
+                    s.clone() * (chi - out)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        KeccakConfig {
+            advice,
+            rc,
+            q_bits,
+            q_theta,
+            q_xor,
+            q_chi,
+        }
+    }
+
+    // Copies `byte` in at `offset` and splits it into bits, least significant
+    // first. The decomposition is also what range checks it.
+    pub fn decompose(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        byte: &AssignedCell<F, F>,
+    ) -> Result<[AssignedCell<F, F>; 8], Error> {
+        let advice = self.config.advice;
+        self.config.q_bits.enable(region, offset)?;
+        byte.copy_advice(|| "byte", region, advice[0], offset)?;
+
+        let mut bits = vec![];
+        for (i, column) in advice[1..9].iter().enumerate() {
+            let bit = byte
+                .value()
+                .map(|b| F::from((b.get_lower_128() >> i) as u64 & 1));
+            bits.push(region.assign_advice(|| "bit", *column, offset, || bit)?);
+        }
+        Ok(bits.try_into().unwrap())
+    }
+
+    fn compose(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        bits: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let advice = self.config.advice;
+        self.config.q_bits.enable(region, offset)?;
+        let mut byte = Value::known(F::zero());
+        for (i, bit) in bits.iter().enumerate() {
+            bit.copy_advice(|| "bit", region, advice[1 + i], offset)?;
+            byte = byte
+                .zip(bit.value())
+                .map(|(byte, bit)| byte + *bit * F::from(1 << i));
+        }
+        region.assign_advice(|| "byte", advice[0], offset, || byte)
+    }
+
+    // Xors the block into the state's rate, five bits a row.
+    fn absorb(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        state: &[AssignedCell<F, F>],
+        block: &[AssignedCell<F, F>],
+        zero: &AssignedCell<F, F>,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let config = &self.config;
+        let rate = layouter.assign_region(
+            || "absorb",
+            |mut region| {
+                let mut rate = vec![];
+                for (offset, i) in (0..RATE_BITS).step_by(5).enumerate() {
+                    config.q_xor.enable(&mut region, offset)?;
+                    for x in 0..5 {
+                        // the rate isn't a multiple of five, so the last row
+                        // runs into zeros
+                        let (a, d) = match i + x < RATE_BITS {
+                            true => (&state[i + x], &block[i + x]),
+                            false => (zero, zero),
+                        };
+                        let a = a.copy_advice(|| "a", &mut region, config.advice[x], offset)?;
+                        let d = d.copy_advice(|| "d", &mut region, config.advice[5 + x], offset)?;
+                        let b = region.assign_advice(
+                            || "b",
+                            config.advice[10 + x],
+                            offset,
+                            || xor(a.value().copied(), d.value().copied()),
+                        )?;
+                        rate.push(b);
+                    }
+                }
+                rate.truncate(RATE_BITS);
+                Ok(rate)
+            },
+        )?;
+        Ok(rate
+            .into_iter()
+            .chain(state[RATE_BITS..].to_vec())
+            .collect())
+    }
+
+    fn round(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &[AssignedCell<F, F>],
+        rc: u64,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let config = &self.config;
+        // bit z of lane x + 5y is a[64 (x + 5y) + z]
+        let d = layouter.assign_region(
+            || "theta",
+            |mut region| {
+                let mut d = vec![];
+                for x in 0..5 {
+                    for z in 0..64 {
+                        let offset = 64 * x + z;
+                        config.q_theta.enable(&mut region, offset)?;
+                        // column x - 1, and column x + 1 rotated by one
+                        let c = (0..5)
+                            .map(|y| &a[64 * ((x + 4) % 5 + 5 * y) + z])
+                            .chain((0..5).map(|y| &a[64 * ((x + 1) % 5 + 5 * y) + (z + 63) % 64]));
+
+                        let mut sum = Value::known(0);
+                        for (i, bit) in c.enumerate() {
+                            bit.copy_advice(|| "c", &mut region, config.advice[i], offset)?;
+                            sum = sum
+                                .zip(bit.value())
+                                .map(|(sum, bit)| sum + bit.get_lower_128() as u64);
+                        }
+                        d.push(region.assign_advice(
+                            || "d",
+                            config.advice[10],
+                            offset,
+                            || sum.map(|sum| F::from(sum & 1)),
+                        )?);
+                        region.assign_advice(
+                            || "h",
+                            config.advice[11],
+                            offset,
+                            || sum.map(|sum| F::from(sum >> 1)),
+                        )?;
+                    }
+                }
+                Ok(d)
+            },
+        )?;
+
+        layouter.assign_region(
+            || "rho pi chi iota",
+            |mut region| {
+                let mut out = vec![None; STATE_BITS];
+                for y in 0..5 {
+                    for z in 0..64 {
+                        let offset = 64 * y + z;
+                        config.q_xor.enable(&mut region, offset)?;
+                        config.q_chi.enable(&mut region, offset)?;
+
+                        let mut b = vec![];
+                        for x in 0..5 {
+                            // pi moves lane (3y + x, x) to (x, y), rho having
+                            // rotated it first
+                            let lane = (3 * y + x) % 5 + 5 * x;
+                            let z = (z + 64 - ROTATIONS[lane]) % 64;
+                            let a = a[64 * lane + z].copy_advice(
+                                || "a",
+                                &mut region,
+                                config.advice[x],
+                                offset,
+                            )?;
+                            let d = d[64 * ((3 * y + x) % 5) + z].copy_advice(
+                                || "d",
+                                &mut region,
+                                config.advice[5 + x],
+                                offset,
+                            )?;
+                            b.push(region.assign_advice(
+                                || "b",
+                                config.advice[10 + x],
+                                offset,
+                                || xor(a.value().copied(), d.value().copied()),
+                            )?);
+                        }
+
+                        let rc = match y {
+                            0 => F::from((rc >> z) & 1),
+                            _ => F::zero(),
+                        };
+                        region.assign_fixed(|| "rc", config.rc, offset, || Value::known(rc))?;
+                        for x in 0..5 {
+                            let [b0, b1, b2] =
+                                [x, (x + 1) % 5, (x + 2) % 5].map(|i| b[i].value().copied());
+                            let value = match x {
+                                0 => xor(chi(b0, b1, b2), Value::known(rc)),
+                                _ => chi(b0, b1, b2),
+                            };
+                            out[64 * (x + 5 * y) + z] = Some(region.assign_advice(
+                                || "out",
+                                config.advice[15 + x],
+                                offset,
+                                || value,
+                            )?);
+                        }
+                    }
+                }
+                Ok(out.into_iter().map(Option::unwrap).collect())
+            },
+        )
+    }
+
+    pub fn permute(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: &[AssignedCell<F, F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let mut state = state.to_vec();
+        for (i, rc) in RC.into_iter().enumerate() {
+            state = self.round(layouter.namespace(|| format!("round {}", i)), &state, rc)?;
+        }
+        Ok(state)
+    }
+
+    // Keccak-256 of `bytes`, which only need to be cells; splitting them into
+    // bits checks that they are bytes. The digest comes back as bytes too.
+    pub fn hash(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bytes: &[AssignedCell<F, F>],
+    ) -> Result<[AssignedCell<F, F>; 32], Error> {
+        let advice = self.config.advice;
+        let (mut bits, zero, one) = layouter.assign_region(
+            || "message bits",
+            |mut region| {
+                let zero = region.assign_advice_from_constant(|| "0", advice[9], 0, F::zero())?;
+                let one = region.assign_advice_from_constant(|| "1", advice[10], 0, F::one())?;
+                let mut bits = vec![];
+                for (offset, byte) in bytes.iter().enumerate() {
+                    bits.extend(self.decompose(&mut region, offset, byte)?);
+                }
+                Ok((bits, zero, one))
+            },
+        )?;
+        for byte in &pad(&vec![0; bytes.len()])[bytes.len()..] {
+            bits.extend((0..8).map(|i| match (byte >> i) & 1 {
+                1 => one.clone(),
+                _ => zero.clone(),
+            }));
+        }
+
+        let mut state: Vec<_> = std::iter::repeat_n(zero.clone(), STATE_BITS).collect();
+        for (i, block) in bits.chunks(RATE_BITS).enumerate() {
+            state = match i {
+                // nothing to xor into yet
+                0 => block.iter().chain(&state[RATE_BITS..]).cloned().collect(),
+                _ => self.absorb(&mut layouter, &state, block, &zero)?,
+            };
+            state = self.permute(layouter.namespace(|| format!("block {}", i)), &state)?;
+        }
+
+        let digest = layouter.assign_region(
+            || "digest",
+            |mut region| {
+                let mut digest = vec![];
+                for (offset, bits) in state[..256].chunks(8).enumerate() {
+                    digest.push(self.compose(&mut region, offset, bits)?);
+                }
+                Ok(digest)
+            },
+        )?;
+        Ok(digest.try_into().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    // Hashes `len` bytes from the instance column and exposes the digest after
+    // them.
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        len: usize,
+        _marker: PhantomData<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = (KeccakConfig, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                len: self.len,
+                _marker: PhantomData,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 20].map(|_| meta.advice_column());
+            let constants = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (KeccakChip::configure(meta, advice, constants), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = KeccakChip::construct(config.clone());
+            let bytes = layouter.assign_region(
+                || "message",
+                |mut region| {
+                    (0..self.len)
+                        .map(|row| {
+                            region.assign_advice_from_instance(
+                                || "byte",
+                                instance,
+                                row,
+                                config.advice[0],
+                                row,
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                },
+            )?;
+            let digest = chip.hash(layouter.namespace(|| "keccak"), &bytes)?;
+            for (i, byte) in digest.iter().enumerate() {
+                layouter.constrain_instance(byte.cell(), instance, self.len + i)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn verify(k: u32, message: &[u8], digest: [u8; 32]) -> bool {
+        let circuit = MyCircuit::<Fp> {
+            len: message.len(),
+            _marker: PhantomData,
+        };
+        let instance = message
+            .iter()
+            .chain(digest.iter())
+            .map(|b| Fp::from(*b as u64))
+            .collect();
+        let prover = MockProver::run(k, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_keccak256() {
+        let hex =
+            |digest: [u8; 32]| -> String { digest.iter().map(|b| format!("{:02x}", b)).collect() };
+        assert_eq!(
+            hex(keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            hex(keccak256(b"abc")),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+    }
+
+    #[test]
+    fn test_keccak_chip() {
+        let _guard = crate::testing::heavy_test();
+        assert!(verify(14, b"abc", keccak256(b"abc")));
+        assert!(!verify(14, b"abc", keccak256(b"abd")));
+
+        // a full block, which pushes the padding into a second one
+        let block = [0x5a; RATE_BYTES];
+        assert!(verify(15, &block, keccak256(&block)));
+    }
+}
@@ -0,0 +1,386 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+// Where a Lucas sequence's p and q come from. Fixed columns bake them into the
+// keys, so every sequence needs keys of its own; advice columns take them from
+// cells, like public inputs, so one set of keys proves any p and q.
+#[derive(Debug, Clone, Copy)]
+pub enum Coefficients {
+    Fixed([Column<Fixed>; 2]),
+    Advice([Column<Advice>; 2]),
+}
+
+// The values of p and q, for the kind of columns the chip was configured with.
+#[derive(Debug, Clone)]
+pub enum LucasParams<F: FieldExt> {
+    Constants(F, F),
+    Cells(AssignedCell<F, F>, AssignedCell<F, F>),
+}
+
+#[derive(Debug, Clone)]
+pub struct LucasConfig {
+    pub x: Column<Advice>,
+    pub coefficients: Coefficients,
+    pub selector: Selector,
+}
+
+// The sequence x_{n+1} = p * x_n - q * x_{n-1} from x_0 and x_1. The
+// Fibonacci examples are p = 1 and q = -1; the Lucas numbers start that same
+// recurrence from 2 and 1, and Pell numbers are p = 2, q = -1.
+#[derive(Debug, Clone)]
+pub struct LucasChip<F: FieldExt> {
+    config: LucasConfig,
+    _marker: PhantomData<F>,
+}
+
+// x_0 to x_n on the host.
+pub fn lucas_sequence<F: FieldExt>(p: F, q: F, x0: F, x1: F, n: usize) -> Vec<F> {
+    let mut sequence = vec![x0, x1];
+    while sequence.len() <= n {
+        let [prev, cur] = [sequence[sequence.len() - 2], sequence[sequence.len() - 1]];
+        sequence.push(p * cur - q * prev);
+    }
+    sequence.truncate(n + 1);
+    sequence
+}
+
+impl<F: FieldExt> LucasChip<F> {
+    pub fn construct(config: LucasConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        x: Column<Advice>,
+        coefficients: Coefficients,
+    ) -> LucasConfig {
+        let selector = meta.selector();
+
+        meta.enable_equality(x);
+        if let Coefficients::Advice(columns) = coefficients {
+            for column in columns {
+                meta.enable_equality(column);
+            }
+        }
+
+        meta.create_gate("lucas", |meta| {
+            //
+            // x    | p | q | selector
+            // x_0    p   q      1
+            // x_1    p   q      1
+            // ...
+            // x_n-2  p   q      1
+            // x_n-1
+            // x_n
+            //
+            let s = meta.query_selector(selector);
+            let [p, q] = match coefficients {
+                Coefficients::Fixed(columns) => {
+                    columns.map(|column| meta.query_fixed(column, Rotation::cur()))
+                }
+                Coefficients::Advice(columns) => {
+                    columns.map(|column| meta.query_advice(column, Rotation::cur()))
+                }
+            };
+            let prev = meta.query_advice(x, Rotation::cur());
+            let cur = meta.query_advice(x, Rotation::next());
+            let next = meta.query_advice(x, Rotation(2));
+            vec![s * (p * cur - q * prev - next)]
+        });
+
+        LucasConfig {
+            x,
+            coefficients,
+            selector,
+        }
+    }
+
+    // Returns the cell holding x_n, for n >= 1.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        params: &LucasParams<F>,
+        x0: &AssignedCell<F, F>,
+        x1: &AssignedCell<F, F>,
+        n: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        assert!(n >= 1, "the sequence starts from x_0 and x_1");
+
+        layouter.assign_region(
+            || "lucas",
+            |mut region| {
+                let mut prev = x0.copy_advice(|| "x_0", &mut region, config.x, 0)?;
+                let mut cur = x1.copy_advice(|| "x_1", &mut region, config.x, 1)?;
+
+                for row in 0..n - 1 {
+                    config.selector.enable(&mut region, row)?;
+
+                    let (p, q) = match (config.coefficients, params) {
+                        (Coefficients::Fixed([col_p, col_q]), LucasParams::Constants(p, q)) => {
+                            region.assign_fixed(|| "p", col_p, row, || Value::known(*p))?;
+                            region.assign_fixed(|| "q", col_q, row, || Value::known(*q))?;
+                            (Value::known(*p), Value::known(*q))
+                        }
+                        (Coefficients::Advice([col_p, col_q]), LucasParams::Cells(p, q)) => {
+                            let p = p.copy_advice(|| "p", &mut region, col_p, row)?;
+                            let q = q.copy_advice(|| "q", &mut region, col_q, row)?;
+                            (p.value().copied(), q.value().copied())
+                        }
+                        // constants for advice columns would be unconstrained,
+                        // and cells can't go in fixed columns
+                        _ => return Err(Error::Synthesis),
+                    };
+
+                    let next = p * cur.value() - q * prev.value();
+                    let next = region.assign_advice(|| "x", config.x, row + 2, || next)?;
+                    (prev, cur) = (cur, next);
+                }
+
+                Ok(cur)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        explain::{assert_unsatisfied_with, Failure},
+        fibo::{FiboLayout, FiboPublicInputs, OUT_TERM},
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 5;
+
+    // x_N from the public x_0 and x_1. With PUBLIC the instance column is
+    // `[x_0, x_1, x_N, p, q]`, otherwise `[x_0, x_1, x_N]` with p and q
+    // fixed in the circuit.
+    #[derive(Default)]
+    struct MyCircuit<F, const PUBLIC: bool> {
+        p: F,
+        q: F,
+        n: usize,
+    }
+
+    impl<F: FieldExt, const PUBLIC: bool> Circuit<F> for MyCircuit<F, PUBLIC> {
+        type Config = (LucasConfig, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                p: self.p,
+                q: self.q,
+                n: self.n,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let x = meta.advice_column();
+            let coefficients = if PUBLIC {
+                Coefficients::Advice([meta.advice_column(), meta.advice_column()])
+            } else {
+                Coefficients::Fixed([meta.fixed_column(), meta.fixed_column()])
+            };
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (LucasChip::configure(meta, x, coefficients), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = LucasChip::construct(config.clone());
+
+            let cells = layouter.assign_region(
+                || "public inputs",
+                |mut region| {
+                    let mut cells = vec![];
+                    for row in [0, 1] {
+                        cells.push(region.assign_advice_from_instance(
+                            || "x",
+                            instance,
+                            row,
+                            config.x,
+                            row,
+                        )?);
+                    }
+                    if let Coefficients::Advice(columns) = config.coefficients {
+                        for (column, row) in columns.into_iter().zip([3, 4]) {
+                            cells.push(region.assign_advice_from_instance(
+                                || "coefficient",
+                                instance,
+                                row,
+                                column,
+                                0,
+                            )?);
+                        }
+                    }
+                    Ok(cells)
+                },
+            )?;
+            let params = match &cells[..] {
+                [_, _, p, q] => LucasParams::Cells(p.clone(), q.clone()),
+                _ => LucasParams::Constants(self.p, self.q),
+            };
+
+            let out = chip.assign(
+                layouter.namespace(|| "sequence"),
+                &params,
+                &cells[0],
+                &cells[1],
+                self.n,
+            )?;
+            layouter.constrain_instance(out.cell(), instance, 2)
+        }
+    }
+
+    fn instance(p: Fp, q: Fp, x0: u64, x1: u64, n: usize, public: bool) -> Vec<Vec<Fp>> {
+        let (x0, x1) = (Fp::from(x0), Fp::from(x1));
+        let mut instance = vec![x0, x1, lucas_sequence(p, q, x0, x1, n)[n]];
+        if public {
+            instance.extend([p, q]);
+        }
+        vec![instance]
+    }
+
+    #[test]
+    fn test_lucas_sequence() {
+        let (one, two) = (Fp::one(), Fp::from(2));
+        let sequence = |p, q, x0, x1| lucas_sequence(p, q, Fp::from(x0), Fp::from(x1), 9);
+
+        let fibonacci = FiboPublicInputs::new(Fp::from(3), Fp::from(5));
+        assert_eq!(sequence(one, -one, 3, 5)[OUT_TERM], fibonacci.out);
+        let lucas = [2, 1, 3, 4, 7, 11, 18, 29, 47, 76].map(Fp::from);
+        assert_eq!(sequence(one, -one, 2, 1), lucas);
+        let pell = [0, 1, 2, 5, 12, 29, 70, 169, 408, 985].map(Fp::from);
+        assert_eq!(sequence(two, -one, 0, 1), pell);
+        // p = 3, q = 2 gives the Mersenne numbers 2^n - 1
+        assert!(sequence(Fp::from(3), two, 0, 1)
+            .into_iter()
+            .zip(0..)
+            .all(|(x, n)| x == Fp::from((1 << n) - 1)));
+
+        assert_eq!(lucas_sequence(one, one, one, one, 1).len(), 2);
+    }
+
+    #[test]
+    fn test_fixed_coefficients() {
+        let (one, two) = (Fp::one(), Fp::from(2));
+
+        // the Fibonacci examples' statement, with their instance layout
+        let circuit = MyCircuit::<Fp, false> {
+            p: one,
+            q: -one,
+            n: OUT_TERM,
+        };
+        let fibonacci = FiboPublicInputs::new(one, one).to_instances(&FiboLayout::single());
+        assert_eq!(fibonacci[0][2], Fp::from(55));
+        let prover = MockProver::run(K, &circuit, fibonacci.clone()).unwrap();
+        prover.assert_satisfied();
+
+        for (p, q, x0, x1, n) in [(two, -one, 0, 1, 12), (Fp::from(3), two, 0, 1, 20)] {
+            let circuit = MyCircuit::<Fp, false> { p, q, n };
+            let instance = instance(p, q, x0, x1, n, false);
+            let prover = MockProver::run(K, &circuit, instance).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // Pell's keys don't prove the Fibonacci numbers
+        let pell = MyCircuit::<Fp, false> {
+            p: two,
+            q: -one,
+            n: OUT_TERM,
+        };
+        assert_unsatisfied_with(
+            K,
+            &pell,
+            fibonacci,
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+    }
+
+    #[test]
+    fn test_public_coefficients() {
+        let (one, two) = (Fp::one(), Fp::from(2));
+        let circuit = MyCircuit::<Fp, true> {
+            n: 12,
+            ..Default::default()
+        };
+
+        // one circuit for every p and q
+        for (p, q, x0, x1) in [
+            (one, -one, 2, 1),
+            (two, -one, 0, 1),
+            (Fp::from(7), two, 3, 4),
+        ] {
+            let prover = MockProver::run(K, &circuit, instance(p, q, x0, x1, 12, true)).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // the output of one sequence claimed for another p, or another q
+        let mut tampered = instance(one, -one, 2, 1, 12, true);
+        tampered[0][3] = two;
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            tampered,
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+        let mut tampered = instance(one, -one, 2, 1, 12, true);
+        tampered[0][4] = one;
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            tampered,
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+    }
+
+    #[test]
+    fn test_mismatched_params() {
+        // constants can't stand in for public coefficients
+        #[derive(Default)]
+        struct Mismatched;
+
+        impl Circuit<Fp> for Mismatched {
+            type Config = LucasConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                Self
+            }
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let x = meta.advice_column();
+                let columns = [meta.advice_column(), meta.advice_column()];
+                LucasChip::configure(meta, x, Coefficients::Advice(columns))
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                let x = layouter.assign_region(
+                    || "x",
+                    |mut region| {
+                        region.assign_advice(|| "x", config.x, 0, || Value::known(Fp::one()))
+                    },
+                )?;
+                let chip = LucasChip::construct(config);
+                let params = LucasParams::Constants(Fp::one(), -Fp::one());
+                chip.assign(layouter, &params, &x, &x, 5)?;
+                Ok(())
+            }
+        }
+
+        assert!(MockProver::run(K, &Mismatched, vec![]).is_err());
+    }
+}
@@ -0,0 +1,293 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use super::{
+    multiset::{MultisetChip, MultisetConfig},
+    poseidon::{PoseidonChip, PoseidonConfig},
+};
+
+// A table of prover supplied values sitting in advice cells, along with the
+// Poseidon hash of them that the circuit can expose or compare against.
+#[derive(Debug, Clone)]
+pub struct DynamicTable<F: FieldExt> {
+    pub cells: Vec<AssignedCell<F, F>>,
+    pub commitment: AssignedCell<F, F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MembershipConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 5],
+    pub q_first: Selector,
+    pub q_rest: Selector,
+    pub multiset: MultisetConfig<F>,
+}
+
+// Proves cells are members of a table held in advice columns. This backend only
+// looks up into fixed columns, so the chip builds halo2's lookup argument out of
+// parts it does have: the queries A (padded with the table itself) and the
+// table S (padded with copies of its first entry) are rearranged into A' and S'
+// such that
+//
+//   A'_0 = S'_0,  and  A'_i = S'_i  or  A'_i = A'_i-1,
+//
+// and `MultisetChip` shows A' and S' are permutations of A and S. Every value
+// in A' then either starts a run, and matches a table entry, or repeats the one
+// before it.
+#[derive(Debug, Clone)]
+pub struct MembershipChip<F: FieldExt> {
+    config: MembershipConfig<F>,
+}
+
+impl<F: FieldExt> MembershipChip<F> {
+    pub fn construct(config: MembershipConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 5],
+        poseidon: PoseidonConfig<F>,
+    ) -> MembershipConfig<F> {
+        let q_first = meta.selector();
+        let q_rest = meta.selector();
+        let multiset = MultisetChip::configure(meta, advice, poseidon);
+
+        meta.create_gate("first permuted", |meta| {
+            //
+            // advice[0] | advice[1] | q_first
+            //    a'_0        s'_0        1
+            //
+            let s = meta.query_selector(q_first);
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let t = meta.query_advice(advice[1], Rotation::cur());
+            vec![s * (a - t)]
+        });
+
+        meta.create_gate("permuted", |meta| {
+            //
+            // advice[0] | advice[1] | q_rest
+            //   a'_i-1       s'_i-1
+            //    a'_i         s'_i        1
+            //
+            let s = meta.query_selector(q_rest);
+            let a_prev = meta.query_advice(advice[0], Rotation::prev());
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let t = meta.query_advice(advice[1], Rotation::cur());
+            vec![s * (a.clone() - t) * (a - a_prev)]
+        });
+
+        MembershipConfig {
+            advice,
+            q_first,
+            q_rest,
+            multiset,
+        }
+    }
+
+    // Witnesses the table and hashes it.
+    pub fn load_table(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: &[Value<F>],
+    ) -> Result<DynamicTable<F>, Error> {
+        assert!(!values.is_empty());
+        let config = &self.config;
+
+        let cells = layouter.assign_region(
+            || "table",
+            |mut region| {
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, v)| {
+                        region.assign_advice(|| "t", config.advice[0], offset, || *v)
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.multiset.poseidon.clone());
+        let commitment = poseidon.hash(layouter.namespace(|| "commit"), &cells)?;
+        Ok(DynamicTable { cells, commitment })
+    }
+
+    // Constrains every query to equal some entry of the table. The argument
+    // costs rows and hashing for the queries and the table together, so it is
+    // cheaper to pass all the queries against one table in a single call.
+    pub fn assert_members(
+        &self,
+        mut layouter: impl Layouter<F>,
+        table: &DynamicTable<F>,
+        queries: &[AssignedCell<F, F>],
+    ) -> Result<(), Error> {
+        if queries.is_empty() {
+            return Ok(());
+        }
+        let config = &self.config;
+
+        // every table entry is trivially a member, so padding the queries with
+        // the table gives A and S the same length
+        let inputs: Vec<_> = queries.iter().chain(table.cells.iter()).cloned().collect();
+        let entries: Vec<_> = table
+            .cells
+            .iter()
+            .chain(std::iter::repeat_n(&table.cells[0], queries.len()))
+            .cloned()
+            .collect();
+        let n = inputs.len();
+
+        let inputs_value: Value<Vec<F>> = inputs.iter().map(|c| c.value().copied()).collect();
+        let entries_value: Value<Vec<F>> = entries.iter().map(|c| c.value().copied()).collect();
+        let permuted = inputs_value.zip(entries_value).map(|(a, s)| permute(a, s));
+        let (a_permuted, s_permuted) = permuted.unzip();
+
+        let (a_permuted, s_permuted) = layouter.assign_region(
+            || "permuted",
+            |mut region| {
+                config.q_first.enable(&mut region, 0)?;
+                for offset in 1..n {
+                    config.q_rest.enable(&mut region, offset)?;
+                }
+
+                let a = a_permuted
+                    .clone()
+                    .transpose_vec(n)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(offset, v)| {
+                        region.assign_advice(|| "a'", config.advice[0], offset, || v)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let s = s_permuted
+                    .clone()
+                    .transpose_vec(n)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(offset, v)| {
+                        region.assign_advice(|| "s'", config.advice[1], offset, || v)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((a, s))
+            },
+        )?;
+
+        let multiset = MultisetChip::construct(config.multiset.clone());
+        multiset.assert_equal(layouter.namespace(|| "inputs"), &inputs, &a_permuted)?;
+        multiset.assert_equal(layouter.namespace(|| "entries"), &entries, &s_permuted)
+    }
+}
+
+// Lines up equal inputs next to each other and puts a matching table entry
+// beside the first of each run, with the unused entries filling the other rows.
+// An input missing from the table gets itself as its entry, which the multiset
+// check on S' then rejects.
+fn permute<F: FieldExt>(mut inputs: Vec<F>, entries: Vec<F>) -> (Vec<F>, Vec<F>) {
+    inputs.sort_by(|a, b| a.to_repr().as_ref().cmp(b.to_repr().as_ref()));
+
+    let mut unused = entries;
+    let mut permuted: Vec<Option<F>> = vec![None; inputs.len()];
+    for (i, a) in inputs.iter().enumerate() {
+        if i == 0 || inputs[i - 1] != *a {
+            permuted[i] = Some(match unused.iter().position(|t| t == a) {
+                Some(position) => unused.swap_remove(position),
+                None => *a,
+            });
+        }
+    }
+
+    let mut unused = unused.into_iter();
+    let permuted = permuted
+        .into_iter()
+        .map(|t| t.unwrap_or_else(|| unused.next().unwrap()))
+        .collect();
+    (inputs, permuted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::poseidon::{self, hash};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        table: Vec<Value<F>>,
+        queries: Vec<Value<F>>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = (MembershipConfig<F>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                table: vec![Value::unknown(); self.table.len()],
+                queries: vec![Value::unknown(); self.queries.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 5].map(|_| meta.advice_column());
+            let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+            let constants = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            let poseidon =
+                PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+            (MembershipChip::configure(meta, advice, poseidon), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = MembershipChip::construct(config.clone());
+            let table = chip.load_table(layouter.namespace(|| "table"), &self.table)?;
+            layouter.constrain_instance(table.commitment.cell(), instance, 0)?;
+
+            let queries = layouter.assign_region(
+                || "queries",
+                |mut region| {
+                    self.queries
+                        .iter()
+                        .enumerate()
+                        .map(|(offset, v)| {
+                            region.assign_advice(|| "q", config.advice[0], offset, || *v)
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                },
+            )?;
+            chip.assert_members(layouter.namespace(|| "members"), &table, &queries)
+        }
+    }
+
+    fn verify(table: &[u64], queries: &[u64], commitment: Fp) -> bool {
+        let values = |list: &[u64]| list.iter().map(|v| Value::known(Fp::from(*v))).collect();
+        let circuit = MyCircuit {
+            table: values(table),
+            queries: values(queries),
+        };
+        let prover = MockProver::run(11, &circuit, vec![vec![commitment]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_membership() {
+        let table = [17, 3, 256, 42, 3, 1 << 40];
+        let commitment = hash(&table.map(Fp::from));
+
+        // repeats, and every entry of the table, in any order
+        assert!(verify(&table, &[42, 3, 42, 1 << 40, 17], commitment));
+        assert!(verify(&table, &[256, 3, 17, 42, 1 << 40, 3], commitment));
+
+        // a value that isn't in the table, even next to ones that are
+        assert!(!verify(&table, &[42, 43, 42], commitment));
+        assert!(!verify(&table, &[0], commitment));
+
+        // a table the prover swapped out for one holding the query
+        let other = [17, 3, 256, 43, 3, 1 << 40];
+        assert!(!verify(&other, &[43], commitment));
+        assert!(verify(&other, &[43], hash(&other.map(Fp::from))));
+    }
+}
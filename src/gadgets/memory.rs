@@ -0,0 +1,385 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use super::{
+    poseidon::PoseidonConfig,
+    range_check::{RangeCheckChip, RangeCheckConfig},
+    sort::{SortChip, SortConfig, SORT_BYTES},
+};
+
+// Sizes of the fields packed into one sortable word, most significant first:
+//
+//   addr | time | is_write | value
+//
+// so sorting the words sorts accesses by address and then by time.
+pub const ADDR_BYTES: usize = 1;
+pub const TIME_BYTES: usize = 2;
+pub const VALUE_BYTES: usize = 4;
+
+const VALUE_SHIFT: u128 = 1 << (8 * VALUE_BYTES);
+const TIME_SHIFT: u128 = 1 << (8 * TIME_BYTES);
+
+// One memory access as the machine performs it. Its time is its position in
+// the list handed to `MemoryChip::check`.
+#[derive(Debug, Clone)]
+pub struct MemoryAccess<F: FieldExt> {
+    pub addr: AssignedCell<F, F>,
+    pub is_write: AssignedCell<F, F>,
+    pub value: AssignedCell<F, F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MemoryConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 7],
+    pub q_pack: Selector,
+    pub q_first: Selector,
+    pub q_step: Selector,
+    pub sort: SortConfig<F>,
+}
+
+// Offline memory checking for machines with a zero-initialised memory. The
+// accesses are packed into words and sorted with `SortChip`, which lines up
+// every address's accesses in time order. Walking the sorted list, each read
+// has to return what the access before it to the same address wrote or read,
+// or 0 if there is none.
+#[derive(Debug, Clone)]
+pub struct MemoryChip<F: FieldExt> {
+    config: MemoryConfig<F>,
+}
+
+fn pack<F: FieldExt>(addr: F, time: F, is_write: F, value: F) -> F {
+    ((addr * F::from_u128(TIME_SHIFT) + time) * F::from(2) + is_write) * F::from_u128(VALUE_SHIFT)
+        + value
+}
+
+impl<F: FieldExt> MemoryChip<F> {
+    pub fn construct(config: MemoryConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 7],
+        range: RangeCheckConfig,
+        poseidon: PoseidonConfig<F>,
+    ) -> MemoryConfig<F> {
+        // the packed word, is_write bit included, has to fit in what SortChip
+        // compares
+        const { assert!(ADDR_BYTES + TIME_BYTES + VALUE_BYTES < SORT_BYTES) };
+        let [col_packed, col_addr, col_time, col_is_write, col_value, col_same, col_inv] = advice;
+        let q_pack = meta.selector();
+        let q_first = meta.selector();
+        let q_step = meta.selector();
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("pack", |meta| {
+            //
+            // packed | addr | time | is_write | value | same | inv | q_pack
+            //   w       a      t       b          v                     1
+            //
+            let s = meta.query_selector(q_pack);
+            let packed = meta.query_advice(col_packed, Rotation::cur());
+            let addr = meta.query_advice(col_addr, Rotation::cur());
+            let time = meta.query_advice(col_time, Rotation::cur());
+            let is_write = meta.query_advice(col_is_write, Rotation::cur());
+            let value = meta.query_advice(col_value, Rotation::cur());
+
+            let one = Expression::Constant(F::one());
+            let time_shift = Expression::Constant(F::from_u128(TIME_SHIFT));
+            let value_shift = Expression::Constant(F::from_u128(VALUE_SHIFT));
+            let two = Expression::Constant(F::from(2));
+            vec![
+                s.clone() * is_write.clone() * (one - is_write.clone()),
+                s * ((((addr * time_shift + time) * two + is_write) * value_shift + value)
+                    - packed),
+            ]
+        });
+
+        meta.create_gate("memory consistency", |meta| {
+            //
+            // packed | addr  | time | is_write | value | same   | inv   | q_first | q_step
+            //   w_0    a_0     t_0    b_0        v_0     same_0   inv_0      1         1
+            //   w_1    a_1     t_1    b_1        v_1     same_1   inv_1                1
+            //   ...
+            //   w_n-1  a_n-1   t_n-1  b_n-1      v_n-1
+            //
+            // same_i says whether a_i+1 == a_i, and a read returns either the
+            // previous value at its address or 0 for an address not seen yet
+            let q_first = meta.query_selector(q_first);
+            let q_step = meta.query_selector(q_step);
+            let addr = meta.query_advice(col_addr, Rotation::cur());
+            let is_write = meta.query_advice(col_is_write, Rotation::cur());
+            let value = meta.query_advice(col_value, Rotation::cur());
+            let same = meta.query_advice(col_same, Rotation::cur());
+            let inv = meta.query_advice(col_inv, Rotation::cur());
+            let addr_next = meta.query_advice(col_addr, Rotation::next());
+            let is_write_next = meta.query_advice(col_is_write, Rotation::next());
+            let value_next = meta.query_advice(col_value, Rotation::next());
+
+            let one = Expression::Constant(F::one());
+            let diff = addr_next - addr;
+            let is_read_next = one.clone() - is_write_next;
+            vec![
+                q_first * (one.clone() - is_write) * value.clone(),
+                q_step.clone() * same.clone() * diff.clone(),
+                q_step.clone() * (diff * inv - (one.clone() - same.clone())),
+                q_step.clone() * is_read_next.clone() * same.clone() * (value_next.clone() - value),
+                q_step * is_read_next * (one - same) * value_next,
+            ]
+        });
+
+        let sort_advice = [0, 1, 2, 3, 4, 5].map(|i| advice[i]);
+        let sort = SortChip::configure(meta, sort_advice, range, poseidon);
+
+        MemoryConfig {
+            advice,
+            q_pack,
+            q_first,
+            q_step,
+            sort,
+        }
+    }
+
+    // Constrains every read in `accesses` to return the value last written to
+    // its address. Addresses have to fit in `ADDR_BYTES` and values in
+    // `VALUE_BYTES`, and there can be at most 2^(8 * TIME_BYTES) accesses.
+    pub fn check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        accesses: &[MemoryAccess<F>],
+    ) -> Result<(), Error> {
+        if accesses.is_empty() {
+            return Ok(());
+        }
+        assert!(accesses.len() <= 1 << (8 * TIME_BYTES));
+        let config = &self.config;
+        let [col_packed, col_addr, col_time, col_is_write, col_value, col_same, col_inv] =
+            config.advice;
+        let range = RangeCheckChip::construct(config.sort.compare.range.clone());
+
+        let trace = layouter.assign_region(
+            || "trace",
+            |mut region| {
+                let mut words = vec![];
+                for (offset, access) in accesses.iter().enumerate() {
+                    config.q_pack.enable(&mut region, offset)?;
+
+                    let addr = access
+                        .addr
+                        .copy_advice(|| "addr", &mut region, col_addr, offset)?;
+                    let time = region.assign_advice_from_constant(
+                        || "time",
+                        col_time,
+                        offset,
+                        F::from(offset as u64),
+                    )?;
+                    let is_write = access.is_write.copy_advice(
+                        || "is_write",
+                        &mut region,
+                        col_is_write,
+                        offset,
+                    )?;
+                    let value =
+                        access
+                            .value
+                            .copy_advice(|| "value", &mut region, col_value, offset)?;
+
+                    let word = addr
+                        .value()
+                        .zip(time.value())
+                        .zip(is_write.value().zip(value.value()))
+                        .map(|((a, t), (b, v))| pack(*a, *t, *b, *v));
+                    words.push(region.assign_advice(|| "packed", col_packed, offset, || word)?);
+                }
+                Ok(words)
+            },
+        )?;
+
+        // the packing is only one-to-one while every field is in range
+        for access in accesses.iter() {
+            range.range_check::<ADDR_BYTES>(layouter.namespace(|| "addr"), &access.addr)?;
+            range.range_check::<VALUE_BYTES>(layouter.namespace(|| "value"), &access.value)?;
+        }
+
+        let sort = SortChip::construct(config.sort.clone());
+        let sorted = sort.sort(layouter.namespace(|| "sort"), &trace)?;
+
+        let fields = layouter.assign_region(
+            || "memory consistency",
+            |mut region| {
+                config.q_first.enable(&mut region, 0)?;
+
+                let mut fields = vec![];
+                for (offset, word) in sorted.iter().enumerate() {
+                    config.q_pack.enable(&mut region, offset)?;
+
+                    let word = word.copy_advice(|| "packed", &mut region, col_packed, offset)?;
+                    let w = word.value().map(|w| w.get_lower_128());
+                    let value = w.map(|w| F::from_u128(w % VALUE_SHIFT));
+                    let is_write = w.map(|w| F::from_u128((w / VALUE_SHIFT) % 2));
+                    let time = w.map(|w| F::from_u128((w / VALUE_SHIFT / 2) % TIME_SHIFT));
+                    let addr = w.map(|w| F::from_u128(w / VALUE_SHIFT / 2 / TIME_SHIFT));
+
+                    let addr = region.assign_advice(|| "addr", col_addr, offset, || addr)?;
+                    let time = region.assign_advice(|| "time", col_time, offset, || time)?;
+                    region.assign_advice(|| "is_write", col_is_write, offset, || is_write)?;
+                    let value = region.assign_advice(|| "value", col_value, offset, || value)?;
+                    fields.push((addr, time, value));
+                }
+
+                for offset in 0..sorted.len() - 1 {
+                    config.q_step.enable(&mut region, offset)?;
+
+                    let diff = fields[offset + 1].0.value().copied() - fields[offset].0.value();
+                    let inv = diff.map(|diff| diff.invert().unwrap_or(F::zero()));
+                    let same = diff.map(|diff| {
+                        if diff == F::zero() {
+                            F::one()
+                        } else {
+                            F::zero()
+                        }
+                    });
+                    region.assign_advice(|| "same", col_same, offset, || same)?;
+                    region.assign_advice(|| "inv", col_inv, offset, || inv)?;
+                }
+
+                Ok(fields)
+            },
+        )?;
+
+        for (addr, time, value) in fields.iter() {
+            range.range_check::<ADDR_BYTES>(layouter.namespace(|| "sorted addr"), addr)?;
+            range.range_check::<TIME_BYTES>(layouter.namespace(|| "sorted time"), time)?;
+            range.range_check::<VALUE_BYTES>(layouter.namespace(|| "sorted value"), value)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::{
+        poseidon::{self, PoseidonChip},
+        range_table::RangeTableConfig,
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        // (addr, is_write, value)
+        accesses: Vec<(u64, bool, Value<F>)>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = MemoryConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                accesses: self
+                    .accesses
+                    .iter()
+                    .map(|(addr, is_write, _)| (*addr, *is_write, Value::unknown()))
+                    .collect(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 7].map(|_| meta.advice_column());
+            let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+            let constants = meta.fixed_column();
+
+            let bytes = RangeTableConfig::configure(meta, 0, 255);
+            let range = RangeCheckChip::configure(meta, [advice[5], advice[6]], bytes);
+            let poseidon =
+                PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+            MemoryChip::configure(meta, advice, range, poseidon)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            RangeCheckChip::construct(config.sort.compare.range.clone()).load(&mut layouter)?;
+
+            let accesses = layouter.assign_region(
+                || "accesses",
+                |mut region| {
+                    let mut accesses = vec![];
+                    for (offset, (addr, is_write, value)) in self.accesses.iter().enumerate() {
+                        let addr = region.assign_advice_from_constant(
+                            || "addr",
+                            config.advice[0],
+                            offset,
+                            F::from(*addr),
+                        )?;
+                        let is_write = region.assign_advice_from_constant(
+                            || "is_write",
+                            config.advice[1],
+                            offset,
+                            F::from(*is_write as u64),
+                        )?;
+                        let value = region.assign_advice(
+                            || "value",
+                            config.advice[2],
+                            offset,
+                            || *value,
+                        )?;
+                        accesses.push(MemoryAccess {
+                            addr,
+                            is_write,
+                            value,
+                        });
+                    }
+                    Ok(accesses)
+                },
+            )?;
+
+            let chip = MemoryChip::construct(config);
+            chip.check(layouter.namespace(|| "memory"), &accesses)
+        }
+    }
+
+    fn verify(accesses: &[(u64, bool, u64)]) -> bool {
+        let circuit = MyCircuit {
+            accesses: accesses
+                .iter()
+                .map(|(addr, is_write, value)| (*addr, *is_write, Value::known(Fp::from(*value))))
+                .collect(),
+        };
+        let prover = MockProver::run(11, &circuit, vec![]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_memory() {
+        let _guard = crate::testing::heavy_test();
+        let (read, write) = (false, true);
+
+        assert!(verify(&[
+            (1, read, 0),
+            (1, write, 7),
+            (2, write, 9),
+            (1, read, 7),
+            (2, read, 9),
+            (1, write, 0xffff_ffff),
+            (1, read, 0xffff_ffff),
+            (255, read, 0),
+        ]));
+
+        // a stale read, a read of memory never written, and a read that
+        // returns another address's value
+        assert!(!verify(&[(1, write, 7), (1, write, 8), (1, read, 7)]));
+        assert!(!verify(&[(1, write, 7), (2, read, 5)]));
+        assert!(!verify(&[(1, write, 7), (2, write, 9), (1, read, 9)]));
+
+        // values and addresses past what the packing holds
+        assert!(!verify(&[(1, write, 1 << 32), (1, read, 1 << 32)]));
+        assert!(!verify(&[(256, write, 7), (256, read, 7)]));
+    }
+}
@@ -0,0 +1,310 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use super::poseidon::{self, PoseidonChip, PoseidonConfig};
+use crate::parallel::map_chunks;
+
+#[derive(Debug, Clone)]
+pub struct MerkleConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 5],
+    pub selector: Selector,
+    pub poseidon: PoseidonConfig<F>,
+}
+
+// Recomputes a Merkle root from a leaf and its authentication path, hashing
+// each pair of nodes with Poseidon. The index bits are witnessed least
+// significant first; bit i says whether the node at level i is a right child.
+#[derive(Debug, Clone)]
+pub struct MerkleChip<F: FieldExt> {
+    config: MerkleConfig<F>,
+}
+
+pub fn hash_pair<F: FieldExt>(left: F, right: F) -> F {
+    poseidon::hash(&[left, right])
+}
+
+// Host-side root of a full tree; the number of leaves has to be a power of two.
+pub fn merkle_root<F: FieldExt>(leaves: &[F]) -> F {
+    assert!(leaves.len().is_power_of_two());
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = map_chunks(&level, 2, |pair| hash_pair(pair[0], pair[1]));
+    }
+    level[0]
+}
+
+// Host-side authentication path of leaf `index`, bottom up.
+pub fn merkle_path<F: FieldExt>(leaves: &[F], mut index: usize) -> Vec<F> {
+    assert!(leaves.len().is_power_of_two());
+    let mut path = vec![];
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        path.push(level[index ^ 1]);
+        level = map_chunks(&level, 2, |pair| hash_pair(pair[0], pair[1]));
+        index >>= 1;
+    }
+    path
+}
+
+impl<F: FieldExt> MerkleChip<F> {
+    pub fn construct(config: MerkleConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 5],
+        poseidon: PoseidonConfig<F>,
+    ) -> MerkleConfig<F> {
+        let selector = meta.selector();
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("merkle swap", |meta| {
+            //
+            // cur | sibling | bit | left | right | selector
+            //                                        1
+            //
+            // (left, right) is (cur, sibling) when bit is 0, swapped when it's 1
+            let s = meta.query_selector(selector);
+            let [cur, sibling, bit, left, right] =
+                advice.map(|column| meta.query_advice(column, Rotation::cur()));
+            let one = Expression::Constant(F::one());
+            vec![
+                s.clone() * bit.clone() * (one - bit.clone()),
+                s.clone() * (cur.clone() + bit * (sibling.clone() - cur.clone()) - left.clone()),
+                s * (cur + sibling - left - right),
+            ]
+        });
+
+        MerkleConfig {
+            advice,
+            selector,
+            poseidon,
+        }
+    }
+
+    pub fn compute_root<const DEPTH: usize>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        leaf: &AssignedCell<F, F>,
+        path: &[Value<F>; DEPTH],
+        index: Value<u64>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        // one index bit per level
+        const { assert!(DEPTH <= 64) };
+        let config = &self.config;
+        let poseidon = PoseidonChip::construct(config.poseidon.clone());
+
+        let mut node = leaf.clone();
+        for (level, sibling) in path.iter().enumerate() {
+            let bit = index.map(|index| F::from((index >> level) & 1));
+
+            let (left, right) = layouter.assign_region(
+                || format!("merkle level {}", level),
+                |mut region| {
+                    let (_, [left, right]) = self.swap(&mut region, 0, &node, *sibling, bit)?;
+                    Ok((left, right))
+                },
+            )?;
+
+            node = poseidon.hash(layouter.namespace(|| "hash pair"), &[left, right])?;
+        }
+
+        Ok(node)
+    }
+
+    // Recomputes the root with `old` and then with `new` at the same index, so
+    // the two roots differ by that one leaf. Returns (old root, new root).
+    #[allow(clippy::type_complexity)]
+    pub fn update<const DEPTH: usize>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        old: &AssignedCell<F, F>,
+        new: &AssignedCell<F, F>,
+        path: &[Value<F>; DEPTH],
+        index: Value<u64>,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        const { assert!(DEPTH <= 64) };
+        let poseidon = PoseidonChip::construct(self.config.poseidon.clone());
+
+        let (mut old, mut new) = (old.clone(), new.clone());
+        for (level, sibling) in path.iter().enumerate() {
+            let bit = index.map(|index| F::from((index >> level) & 1));
+
+            // the new leaf's row shares the old one's sibling and bit
+            let (old_pair, new_pair) = layouter.assign_region(
+                || format!("merkle update level {}", level),
+                |mut region| {
+                    let (shared, old_pair) = self.swap(&mut region, 0, &old, *sibling, bit)?;
+                    let (copies, new_pair) = self.swap(&mut region, 1, &new, *sibling, bit)?;
+                    for (cell, copy) in shared.iter().zip(copies.iter()) {
+                        region.constrain_equal(cell.cell(), copy.cell())?;
+                    }
+                    Ok((old_pair, new_pair))
+                },
+            )?;
+
+            old = poseidon.hash(layouter.namespace(|| "hash old pair"), &old_pair)?;
+            new = poseidon.hash(layouter.namespace(|| "hash new pair"), &new_pair)?;
+        }
+
+        Ok((old, new))
+    }
+
+    // One "merkle swap" row; returns the sibling and bit cells, then the pair
+    // to hash.
+    #[allow(clippy::type_complexity)]
+    fn swap(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        node: &AssignedCell<F, F>,
+        sibling: Value<F>,
+        bit: Value<F>,
+    ) -> Result<([AssignedCell<F, F>; 2], [AssignedCell<F, F>; 2]), Error> {
+        let config = &self.config;
+        config.selector.enable(region, offset)?;
+
+        let cur = node.copy_advice(|| "cur", region, config.advice[0], offset)?;
+        let sibling = region.assign_advice(|| "sibling", config.advice[1], offset, || sibling)?;
+        let bit = region.assign_advice(|| "bit", config.advice[2], offset, || bit)?;
+
+        let (left, right) = cur
+            .value()
+            .zip(sibling.value())
+            .zip(bit.value())
+            .map(|((cur, sibling), bit)| {
+                if *bit == F::one() {
+                    (*sibling, *cur)
+                } else {
+                    (*cur, *sibling)
+                }
+            })
+            .unzip();
+
+        let left = region.assign_advice(|| "left", config.advice[3], offset, || left)?;
+        let right = region.assign_advice(|| "right", config.advice[4], offset, || right)?;
+        Ok(([sibling, bit], [left, right]))
+    }
+
+    // Builds the whole tree over `leaves` bottom up and returns its root.
+    pub fn tree_root<const LEAVES: usize>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        leaves: &[AssignedCell<F, F>; LEAVES],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        const { assert!(LEAVES.is_power_of_two()) };
+        let poseidon = PoseidonChip::construct(self.config.poseidon.clone());
+
+        let mut level = leaves.to_vec();
+        let mut height = 0;
+        while level.len() > 1 {
+            let mut parents = vec![];
+            for (i, pair) in level.chunks(2).enumerate() {
+                parents.push(poseidon.hash(
+                    layouter.namespace(|| format!("node {}/{}", height, i)),
+                    pair,
+                )?);
+            }
+            level = parents;
+            height += 1;
+        }
+
+        Ok(level[0].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const DEPTH: usize = 3;
+
+    struct MyCircuit<F> {
+        leaf: Value<F>,
+        path: [Value<F>; DEPTH],
+        index: Value<u64>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = (MerkleConfig<F>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                leaf: Value::unknown(),
+                path: [Value::unknown(); DEPTH],
+                index: Value::unknown(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 5].map(|_| meta.advice_column());
+            let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+            let constants = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            let poseidon =
+                PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+            (MerkleChip::configure(meta, advice, poseidon), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let leaf = layouter.assign_region(
+                || "leaf",
+                |mut region| region.assign_advice(|| "leaf", config.advice[0], 0, || self.leaf),
+            )?;
+
+            let chip = MerkleChip::construct(config);
+            let root =
+                chip.compute_root(layouter.namespace(|| "root"), &leaf, &self.path, self.index)?;
+            layouter.constrain_instance(root.cell(), instance, 0)
+        }
+    }
+
+    fn path(leaves: &[Fp], index: usize) -> [Value<Fp>; DEPTH] {
+        let path: Vec<_> = merkle_path(leaves, index)
+            .into_iter()
+            .map(Value::known)
+            .collect();
+        path.try_into().unwrap()
+    }
+
+    #[test]
+    fn test_merkle_path() {
+        let k = 10;
+
+        let leaves: Vec<Fp> = (0..1 << DEPTH).map(|i| Fp::from(100 + i)).collect();
+        let root = merkle_root(&leaves);
+
+        for index in [0, 5, 7] {
+            let circuit = MyCircuit {
+                leaf: Value::known(leaves[index]),
+                path: path(&leaves, index),
+                index: Value::known(index as u64),
+            };
+            let prover = MockProver::run(k, &circuit, vec![vec![root]]).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // a leaf that isn't in the tree, and a path for the wrong index
+        let path = path(&leaves, 5);
+        for (leaf, index) in [(Fp::from(99), 5), (leaves[5], 4)] {
+            let circuit = MyCircuit {
+                leaf: Value::known(leaf),
+                path,
+                index: Value::known(index),
+            };
+            let prover = MockProver::run(k, &circuit, vec![vec![root]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+}
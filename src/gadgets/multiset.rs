@@ -0,0 +1,210 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use super::poseidon::{PoseidonChip, PoseidonConfig};
+
+#[derive(Debug, Clone)]
+pub struct MultisetConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 5],
+    pub selector: Selector,
+    pub poseidon: PoseidonConfig<F>,
+}
+
+// Proves two lists of cells hold the same multiset, i.e. one is a permutation
+// of the other, with a grand product argument
+//
+//   prod (a_i + gamma) = prod (b_i + gamma).
+//
+// Ideally gamma would be a verifier challenge drawn after the lists are
+// committed, but this backend has neither challenges nor lookups between advice
+// columns, so gamma is the Poseidon hash of both lists instead. That keeps the
+// prover from choosing it at the price of hashing 2n values.
+#[derive(Debug, Clone)]
+pub struct MultisetChip<F: FieldExt> {
+    config: MultisetConfig<F>,
+}
+
+impl<F: FieldExt> MultisetChip<F> {
+    pub fn construct(config: MultisetConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 5],
+        poseidon: PoseidonConfig<F>,
+    ) -> MultisetConfig<F> {
+        let [col_a, col_b, col_gamma, col_acc_a, col_acc_b] = advice;
+        let selector = meta.selector();
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("grand product", |meta| {
+            //
+            // a     | b     | gamma | acc_a   | acc_b   | selector
+            // a_0     b_0     gamma   1         1            1
+            // a_1     b_1     gamma   acc_a_1   acc_b_1      1
+            // ...
+            // a_n-1   b_n-1   gamma   ...                    1
+            //                         acc_a_n   acc_b_n
+            //
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(col_a, Rotation::cur());
+            let b = meta.query_advice(col_b, Rotation::cur());
+            let gamma = meta.query_advice(col_gamma, Rotation::cur());
+            let acc_a = meta.query_advice(col_acc_a, Rotation::cur());
+            let acc_b = meta.query_advice(col_acc_b, Rotation::cur());
+            let acc_a_next = meta.query_advice(col_acc_a, Rotation::next());
+            let acc_b_next = meta.query_advice(col_acc_b, Rotation::next());
+            vec![
+                s.clone() * (acc_a * (a + gamma.clone()) - acc_a_next),
+                s * (acc_b * (b + gamma) - acc_b_next),
+            ]
+        });
+
+        MultisetConfig {
+            advice,
+            selector,
+            poseidon,
+        }
+    }
+
+    // Constrains `b` to be a permutation of `a`.
+    pub fn assert_equal(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &[AssignedCell<F, F>],
+        b: &[AssignedCell<F, F>],
+    ) -> Result<(), Error> {
+        assert_eq!(a.len(), b.len());
+        if a.is_empty() {
+            return Ok(());
+        }
+        let config = &self.config;
+        let [col_a, col_b, col_gamma, col_acc_a, col_acc_b] = config.advice;
+
+        let poseidon = PoseidonChip::construct(config.poseidon.clone());
+        let message: Vec<_> = a.iter().chain(b.iter()).cloned().collect();
+        let gamma = poseidon.hash(layouter.namespace(|| "gamma"), &message)?;
+
+        layouter.assign_region(
+            || "grand product",
+            |mut region| {
+                let mut acc_a =
+                    region.assign_advice_from_constant(|| "acc_a", col_acc_a, 0, F::one())?;
+                let mut acc_b =
+                    region.assign_advice_from_constant(|| "acc_b", col_acc_b, 0, F::one())?;
+
+                for (offset, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+                    config.selector.enable(&mut region, offset)?;
+
+                    let a = a.copy_advice(|| "a", &mut region, col_a, offset)?;
+                    let b = b.copy_advice(|| "b", &mut region, col_b, offset)?;
+                    let gamma = gamma.copy_advice(|| "gamma", &mut region, col_gamma, offset)?;
+
+                    let acc_a_next = acc_a.value().copied() * (a.value().copied() + gamma.value());
+                    let acc_b_next = acc_b.value().copied() * (b.value().copied() + gamma.value());
+                    acc_a =
+                        region.assign_advice(|| "acc_a", col_acc_a, offset + 1, || acc_a_next)?;
+                    acc_b =
+                        region.assign_advice(|| "acc_b", col_acc_b, offset + 1, || acc_b_next)?;
+                }
+
+                region.constrain_equal(acc_a.cell(), acc_b.cell())
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::poseidon;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        a: Vec<Value<F>>,
+        b: Vec<Value<F>>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = MultisetConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                a: vec![Value::unknown(); self.a.len()],
+                b: vec![Value::unknown(); self.b.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 5].map(|_| meta.advice_column());
+            let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+            let constants = meta.fixed_column();
+
+            let poseidon =
+                PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+            MultisetChip::configure(meta, advice, poseidon)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let (a, b) = layouter.assign_region(
+                || "lists",
+                |mut region| {
+                    let a = self
+                        .a
+                        .iter()
+                        .enumerate()
+                        .map(|(offset, v)| {
+                            region.assign_advice(|| "a", config.advice[0], offset, || *v)
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let b = self
+                        .b
+                        .iter()
+                        .enumerate()
+                        .map(|(offset, v)| {
+                            region.assign_advice(|| "b", config.advice[1], offset, || *v)
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Ok((a, b))
+                },
+            )?;
+
+            let chip = MultisetChip::construct(config);
+            chip.assert_equal(layouter.namespace(|| "multiset"), &a, &b)
+        }
+    }
+
+    fn circuit(a: &[u64], b: &[u64]) -> MyCircuit<Fp> {
+        let values = |list: &[u64]| list.iter().map(|v| Value::known(Fp::from(*v))).collect();
+        MyCircuit {
+            a: values(a),
+            b: values(b),
+        }
+    }
+
+    #[test]
+    fn test_multiset() {
+        let k = 10;
+
+        let a = [3, 1, 4, 1, 5];
+        let prover = MockProver::run(k, &circuit(&a, &[1, 1, 3, 4, 5]), vec![]).unwrap();
+        prover.assert_satisfied();
+        let prover = MockProver::run(k, &circuit(&a, &[5, 4, 1, 3, 1]), vec![]).unwrap();
+        prover.assert_satisfied();
+
+        // same set of values but different multiplicities, and a different value
+        for b in [[1, 3, 3, 4, 5], [1, 1, 3, 4, 6]] {
+            let prover = MockProver::run(k, &circuit(&a, &b), vec![]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+}
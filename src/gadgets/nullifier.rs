@@ -0,0 +1,180 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use super::poseidon::{self, PoseidonChip, PoseidonConfig};
+
+#[derive(Debug, Clone)]
+pub struct NullifierConfig<F: FieldExt> {
+    pub poseidon: PoseidonConfig<F>,
+    pub instance: Column<Instance>,
+    // Hashed in front of the inputs when set, so nullifiers of different
+    // applications can never collide even for the same key and topic.
+    pub domain: Option<u64>,
+}
+
+// Derives `nullifier = H([domain,] secret_key, external_nullifier)`. The same
+// key gives the same nullifier for the same external nullifier, and an
+// unlinkable one for any other.
+#[derive(Debug, Clone)]
+pub struct NullifierChip<F: FieldExt> {
+    config: NullifierConfig<F>,
+}
+
+pub fn derive<F: FieldExt>(domain: Option<u64>, secret_key: F, external_nullifier: F) -> F {
+    let mut message: Vec<F> = domain.map(F::from).into_iter().collect();
+    message.extend([secret_key, external_nullifier]);
+    poseidon::hash(&message)
+}
+
+impl<F: FieldExt> NullifierChip<F> {
+    pub fn construct(config: NullifierConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        poseidon: PoseidonConfig<F>,
+        instance: Column<Instance>,
+        domain: Option<u64>,
+    ) -> NullifierConfig<F> {
+        meta.enable_equality(instance);
+
+        NullifierConfig {
+            poseidon,
+            instance,
+            domain,
+        }
+    }
+
+    pub fn derive(
+        &self,
+        mut layouter: impl Layouter<F>,
+        secret_key: &AssignedCell<F, F>,
+        external_nullifier: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+
+        let mut message = vec![];
+        if let Some(domain) = config.domain {
+            message.push(layouter.assign_region(
+                || "nullifier domain",
+                |mut region| {
+                    region.assign_advice_from_constant(
+                        || "domain",
+                        config.poseidon.state[0],
+                        0,
+                        F::from(domain),
+                    )
+                },
+            )?);
+        }
+        message.extend([secret_key.clone(), external_nullifier.clone()]);
+
+        let poseidon = PoseidonChip::construct(config.poseidon.clone());
+        poseidon.hash(layouter.namespace(|| "nullifier"), &message)
+    }
+
+    // Derives the nullifier and constrains it to the instance at `row`.
+    pub fn derive_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        secret_key: &AssignedCell<F, F>,
+        external_nullifier: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let nullifier = self.derive(
+            layouter.namespace(|| "derive"),
+            secret_key,
+            external_nullifier,
+        )?;
+        layouter.constrain_instance(nullifier.cell(), self.config.instance, row)?;
+        Ok(nullifier)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[derive(Default)]
+    struct MyCircuit<F, const DOMAIN: u64> {
+        secret_key: Value<F>,
+        external_nullifier: Value<F>,
+    }
+
+    impl<F: FieldExt, const DOMAIN: u64> Circuit<F> for MyCircuit<F, DOMAIN> {
+        type Config = NullifierConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let state = [(); poseidon::WIDTH].map(|_| meta.advice_column());
+            let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+            let constants = meta.fixed_column();
+            let instance = meta.instance_column();
+
+            let poseidon = PoseidonChip::configure(meta, state, rc, constants);
+            let domain = if DOMAIN == 0 { None } else { Some(DOMAIN) };
+            NullifierChip::configure(meta, poseidon, instance, domain)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let (secret_key, external_nullifier) = layouter.assign_region(
+                || "inputs",
+                |mut region| {
+                    let state = config.poseidon.state;
+                    Ok((
+                        region.assign_advice(|| "sk", state[0], 0, || self.secret_key)?,
+                        region.assign_advice(|| "ext", state[1], 0, || self.external_nullifier)?,
+                    ))
+                },
+            )?;
+
+            let chip = NullifierChip::construct(config);
+            chip.derive_public(
+                layouter.namespace(|| "nullifier"),
+                &secret_key,
+                &external_nullifier,
+                0,
+            )?;
+            Ok(())
+        }
+    }
+
+    fn run<const DOMAIN: u64>(secret_key: u64, external_nullifier: u64, nullifier: Fp) -> bool {
+        let circuit = MyCircuit::<Fp, DOMAIN> {
+            secret_key: Value::known(Fp::from(secret_key)),
+            external_nullifier: Value::known(Fp::from(external_nullifier)),
+        };
+        let prover = MockProver::run(8, &circuit, vec![vec![nullifier]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_nullifier() {
+        let (sk, ext) = (0x5ec2e7, 2024);
+
+        let plain = derive(None, Fp::from(sk), Fp::from(ext));
+        let tagged = derive(Some(7), Fp::from(sk), Fp::from(ext));
+        assert_ne!(plain, tagged);
+        assert_ne!(plain, derive(None, Fp::from(sk), Fp::from(ext + 1)));
+
+        assert!(run::<0>(sk, ext, plain));
+        assert!(run::<7>(sk, ext, tagged));
+
+        // the domain is part of the circuit, not something the prover picks
+        assert!(!run::<0>(sk, ext, tagged));
+        assert!(!run::<7>(sk, ext, plain));
+        assert!(!run::<8>(sk, ext, tagged));
+
+        // someone else's key
+        assert!(!run::<0>(sk + 1, ext, plain));
+    }
+}
@@ -0,0 +1,310 @@
+use halo2_proofs::{
+    arithmetic::{CurveAffine, CurveExt, Field, FieldExt},
+    circuit::*,
+    pasta::{
+        group::{ff::PrimeField, Curve},
+        pallas,
+    },
+    plonk::*,
+    poly::Rotation,
+};
+
+// Committed values are decomposed into this many bits, which also range checks
+// them. Blinding factors use every bit of a Pallas scalar.
+pub const VALUE_BITS: usize = 64;
+pub const BLINDING_BITS: usize = 255;
+
+const DOMAIN: &str = "halo2_example:pedersen";
+
+#[derive(Debug, Clone)]
+pub struct EccPoint {
+    pub x: AssignedCell<pallas::Base, pallas::Base>,
+    pub y: AssignedCell<pallas::Base, pallas::Base>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PedersenConfig {
+    pub advice: [Column<Advice>; 6],
+    pub fixed: [Column<Fixed>; 5],
+    pub selector: Selector,
+}
+
+// Pedersen commitments `value·G + blinding·H` on Pallas, whose coordinates are
+// native to circuits over its base field. There's no general ECC chip here, so
+// both fixed-base multiplications are done in one double-and-add chain: row i
+// adds either O or O + 2^i·B (B being G or H) depending on bit i, and a last
+// row subtracts the starting point A and all the O's again. The offsets keep
+// every addition away from the identity and from doubling, so incomplete
+// addition is enough.
+#[derive(Debug, Clone)]
+pub struct PedersenChip {
+    config: PedersenConfig,
+}
+
+fn hash_to_curve(name: &[u8]) -> pallas::Point {
+    pallas::Point::hash_to_curve(DOMAIN)(name)
+}
+
+// The generators G and H. Nobody knows the discrete log between them.
+pub fn generators() -> (pallas::Point, pallas::Point) {
+    (hash_to_curve(b"G"), hash_to_curve(b"H"))
+}
+
+pub fn commit(value: u64, blinding: pallas::Scalar) -> pallas::Affine {
+    let (g, h) = generators();
+    (g * pallas::Scalar::from(value) + h * blinding).to_affine()
+}
+
+fn xy(point: pallas::Point) -> (pallas::Base, pallas::Base) {
+    let coordinates = point.to_affine().coordinates().unwrap();
+    (*coordinates.x(), *coordinates.y())
+}
+
+impl PedersenChip {
+    pub fn construct(config: PedersenConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        advice: [Column<Advice>; 6],
+        fixed: [Column<Fixed>; 5],
+        constants: Column<Fixed>,
+    ) -> PedersenConfig {
+        let [col_bit, col_x, col_y, col_lambda, col_inv, col_value] = advice;
+        let selector = meta.selector();
+
+        meta.enable_constant(constants);
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("fixed-base add", |meta| {
+            //
+            // bit | x   | y   | lambda | inv | value   | x0 | y0 | x1 | y1 | coeff | selector
+            // b_0   x_0   y_0   l_0      i_0   0         ...                  1         1
+            // b_1   x_1   y_1   l_1      i_1   v_1       ...                  2         1
+            // ...
+            //       x_n   y_n                  v_n
+            //
+            // (x, y) += (x0, y0) if bit is 0, (x1, y1) if it's 1, and the value
+            // column sums bit * coeff
+            let s = meta.query_selector(selector);
+            let bit = meta.query_advice(col_bit, Rotation::cur());
+            let x = meta.query_advice(col_x, Rotation::cur());
+            let y = meta.query_advice(col_y, Rotation::cur());
+            let lambda = meta.query_advice(col_lambda, Rotation::cur());
+            let inv = meta.query_advice(col_inv, Rotation::cur());
+            let value = meta.query_advice(col_value, Rotation::cur());
+            let x_next = meta.query_advice(col_x, Rotation::next());
+            let y_next = meta.query_advice(col_y, Rotation::next());
+            let value_next = meta.query_advice(col_value, Rotation::next());
+            let [x0, y0, x1, y1, coeff] =
+                fixed.map(|column| meta.query_fixed(column, Rotation::cur()));
+
+            let one = Expression::Constant(pallas::Base::one());
+            let x_q = x0.clone() + bit.clone() * (x1 - x0);
+            let y_q = y0.clone() + bit.clone() * (y1 - y0);
+            let dx = x_q.clone() - x.clone();
+            vec![
+                s.clone() * bit.clone() * (one.clone() - bit.clone()),
+                // the points differ in x, so this isn't a doubling
+                s.clone() * (dx.clone() * inv - one),
+                s.clone() * (lambda.clone() * dx - (y_q - y.clone())),
+                s.clone() * (lambda.clone() * lambda.clone() - x.clone() - x_q - x_next.clone()),
+                s.clone() * (lambda * (x - x_next) - y - y_next),
+                s * (value + bit * coeff - value_next),
+            ]
+        });
+
+        PedersenConfig {
+            advice,
+            fixed,
+            selector,
+        }
+    }
+
+    // Commits to `value`, which has to fit in `VALUE_BITS` bits.
+    pub fn commit(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        value: &AssignedCell<pallas::Base, pallas::Base>,
+        blinding: Value<pallas::Scalar>,
+    ) -> Result<EccPoint, Error> {
+        let config = &self.config;
+        let [col_bit, col_x, col_y, col_lambda, col_inv, col_value] = config.advice;
+        let [col_x0, col_y0, col_x1, col_y1, col_coeff] = config.fixed;
+
+        // (O, O + 2^i·base, 2^i or 0) for every row but the last
+        let (g, h) = generators();
+        let (start, shift) = (hash_to_curve(b"A"), hash_to_curve(b"O"));
+        let mut table = vec![];
+        for (base, num_bits, coefficients) in [(g, VALUE_BITS, true), (h, BLINDING_BITS, false)] {
+            let mut multiple = base;
+            for i in 0..num_bits {
+                let coeff = if coefficients {
+                    pallas::Base::from_u128(1 << i)
+                } else {
+                    pallas::Base::zero()
+                };
+                table.push((shift, shift + multiple, coeff));
+                multiple = multiple + multiple;
+            }
+        }
+        let correction = -(start + shift * pallas::Scalar::from(table.len() as u64));
+        table.push((correction, correction, pallas::Base::zero()));
+
+        let value_bits = value.value().map(|v| {
+            let v = v.get_lower_128();
+            (0..VALUE_BITS)
+                .map(|i| (v >> i) & 1 == 1)
+                .collect::<Vec<_>>()
+        });
+        let blinding_bits = blinding.map(|r| {
+            let repr = r.to_repr();
+            (0..BLINDING_BITS)
+                .map(|i| (repr[i / 8] >> (i % 8)) & 1 == 1)
+                .collect::<Vec<_>>()
+        });
+        let bits = value_bits
+            .zip(blinding_bits)
+            .map(|(mut bits, blinding_bits)| {
+                bits.extend(blinding_bits);
+                bits.push(false);
+                bits
+            })
+            .transpose_vec(table.len());
+
+        layouter.assign_region(
+            || "pedersen",
+            |mut region| {
+                let (x, y) = xy(start);
+                let mut x = region.assign_advice_from_constant(|| "x", col_x, 0, x)?;
+                let mut y = region.assign_advice_from_constant(|| "y", col_y, 0, y)?;
+                let mut acc = region.assign_advice_from_constant(
+                    || "value",
+                    col_value,
+                    0,
+                    pallas::Base::zero(),
+                )?;
+
+                for (offset, ((p0, p1, coeff), bit)) in table.iter().zip(bits.iter()).enumerate() {
+                    config.selector.enable(&mut region, offset)?;
+
+                    let ((x0, y0), (x1, y1)) = (xy(*p0), xy(*p1));
+                    region.assign_fixed(|| "x0", col_x0, offset, || Value::known(x0))?;
+                    region.assign_fixed(|| "y0", col_y0, offset, || Value::known(y0))?;
+                    region.assign_fixed(|| "x1", col_x1, offset, || Value::known(x1))?;
+                    region.assign_fixed(|| "y1", col_y1, offset, || Value::known(y1))?;
+                    region.assign_fixed(|| "coeff", col_coeff, offset, || Value::known(*coeff))?;
+
+                    let bit = bit.map(|bit| pallas::Base::from(bit as u64));
+                    region.assign_advice(|| "bit", col_bit, offset, || bit)?;
+
+                    let x_q = bit.map(|bit| x0 + bit * (x1 - x0));
+                    let y_q = bit.map(|bit| y0 + bit * (y1 - y0));
+                    let inv =
+                        (x_q - x.value()).map(|dx| dx.invert().unwrap_or(pallas::Base::zero()));
+                    let lambda = (y_q - y.value()) * inv;
+                    let x_next = lambda * lambda - x.value() - x_q;
+                    let y_next = lambda * (x.value().copied() - x_next) - y.value();
+                    let acc_next = acc.value().copied() + bit * Value::known(*coeff);
+
+                    region.assign_advice(|| "lambda", col_lambda, offset, || lambda)?;
+                    region.assign_advice(|| "inv", col_inv, offset, || inv)?;
+                    x = region.assign_advice(|| "x", col_x, offset + 1, || x_next)?;
+                    y = region.assign_advice(|| "y", col_y, offset + 1, || y_next)?;
+                    acc = region.assign_advice(|| "value", col_value, offset + 1, || acc_next)?;
+                }
+
+                region.constrain_equal(acc.cell(), value.cell())?;
+
+                Ok(EccPoint { x, y })
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::dev::MockProver;
+
+    #[derive(Default)]
+    struct MyCircuit {
+        value: Value<pallas::Base>,
+        blinding: Value<pallas::Scalar>,
+    }
+
+    impl Circuit<pallas::Base> for MyCircuit {
+        type Config = (PedersenConfig, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let advice = [(); 6].map(|_| meta.advice_column());
+            let fixed = [(); 5].map(|_| meta.fixed_column());
+            let constants = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            (
+                PedersenChip::configure(meta, advice, fixed, constants),
+                instance,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            let value = layouter.assign_region(
+                || "value",
+                |mut region| region.assign_advice(|| "value", config.advice[0], 0, || self.value),
+            )?;
+
+            let chip = PedersenChip::construct(config);
+            let commitment = chip.commit(layouter.namespace(|| "commit"), &value, self.blinding)?;
+            layouter.constrain_instance(commitment.x.cell(), instance, 0)?;
+            layouter.constrain_instance(commitment.y.cell(), instance, 1)
+        }
+    }
+
+    fn run(value: pallas::Base, blinding: pallas::Scalar, commitment: pallas::Affine) -> bool {
+        let circuit = MyCircuit {
+            value: Value::known(value),
+            blinding: Value::known(blinding),
+        };
+        let coordinates = commitment.coordinates().unwrap();
+        let public_input = vec![*coordinates.x(), *coordinates.y()];
+        let prover = MockProver::run(9, &circuit, vec![public_input]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_pedersen() {
+        let blinding = -pallas::Scalar::from(0xb11d);
+
+        for value in [0, 1, 1000, u64::MAX] {
+            let commitment = commit(value, blinding);
+            assert!(run(pallas::Base::from(value), blinding, commitment));
+        }
+
+        // a different value or blinding opens to a different point
+        let commitment = commit(1000, blinding);
+        assert!(!run(pallas::Base::from(1001), blinding, commitment));
+        assert!(!run(
+            pallas::Base::from(1000),
+            blinding + pallas::Scalar::one(),
+            commitment
+        ));
+
+        // values don't wrap around at 2^64
+        let too_big = pallas::Base::from_u128(1 << 64);
+        assert!(!run(too_big, blinding, commit(0, blinding)));
+    }
+}
@@ -0,0 +1,195 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone)]
+pub struct PolyConfig {
+    pub advice: [Column<Advice>; 3],
+    pub selector: Selector,
+}
+
+// Evaluates a polynomial given by its coefficient cells at a point with
+// Horner's rule, one row per coefficient from the highest down.
+#[derive(Debug, Clone)]
+pub struct PolyChip<F: FieldExt> {
+    config: PolyConfig,
+    _marker: PhantomData<F>,
+}
+
+// coeffs[i] is the coefficient of x^i.
+pub fn eval<F: FieldExt>(coeffs: &[F], x: F) -> F {
+    coeffs.iter().rev().fold(F::zero(), |acc, c| acc * x + c)
+}
+
+impl<F: FieldExt> PolyChip<F> {
+    pub fn construct(config: PolyConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+        constants: Column<Fixed>,
+    ) -> PolyConfig {
+        let [col_coeff, col_x, col_acc] = advice;
+        let selector = meta.selector();
+
+        meta.enable_constant(constants);
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("horner", |meta| {
+            //
+            // coeff   | x | acc   | selector
+            // c_n-1     x   0          1
+            // c_n-2     x   acc_1      1
+            // ...
+            // c_0       x   acc_n-1    1
+            //               acc_n
+            //
+            let s = meta.query_selector(selector);
+            let coeff = meta.query_advice(col_coeff, Rotation::cur());
+            let x = meta.query_advice(col_x, Rotation::cur());
+            let acc = meta.query_advice(col_acc, Rotation::cur());
+            let acc_next = meta.query_advice(col_acc, Rotation::next());
+            vec![s * (acc * x + coeff - acc_next)]
+        });
+
+        PolyConfig { advice, selector }
+    }
+
+    // Returns the cell holding sum(coeffs[i] * x^i).
+    pub fn eval_poly(
+        &self,
+        mut layouter: impl Layouter<F>,
+        coeffs: &[AssignedCell<F, F>],
+        x: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        let [col_coeff, col_x, col_acc] = config.advice;
+
+        layouter.assign_region(
+            || "horner",
+            |mut region| {
+                let mut acc =
+                    region.assign_advice_from_constant(|| "acc", col_acc, 0, F::zero())?;
+
+                for (offset, coeff) in coeffs.iter().rev().enumerate() {
+                    config.selector.enable(&mut region, offset)?;
+
+                    let coeff = coeff.copy_advice(|| "coeff", &mut region, col_coeff, offset)?;
+                    let x = x.copy_advice(|| "x", &mut region, col_x, offset)?;
+
+                    let acc_next = acc.value().copied() * x.value() + coeff.value();
+                    acc = region.assign_advice(|| "acc", col_acc, offset + 1, || acc_next)?;
+                }
+
+                Ok(acc)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    // evaluates at a public point, or at a private one when PRIVATE_X is set
+    #[derive(Default)]
+    struct MyCircuit<F, const PRIVATE_X: bool> {
+        coeffs: Vec<Value<F>>,
+        x: Value<F>,
+    }
+
+    impl<F: FieldExt, const PRIVATE_X: bool> Circuit<F> for MyCircuit<F, PRIVATE_X> {
+        type Config = (PolyConfig, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                coeffs: vec![Value::unknown(); self.coeffs.len()],
+                x: Value::unknown(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 3].map(|_| meta.advice_column());
+            let constants = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            (PolyChip::configure(meta, advice, constants), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let (coeffs, x) = layouter.assign_region(
+                || "inputs",
+                |mut region| {
+                    let coeffs = self
+                        .coeffs
+                        .iter()
+                        .enumerate()
+                        .map(|(offset, c)| {
+                            region.assign_advice(|| "coeff", config.advice[0], offset, || *c)
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let x = if PRIVATE_X {
+                        region.assign_advice(|| "x", config.advice[1], 0, || self.x)?
+                    } else {
+                        region.assign_advice_from_instance(
+                            || "x",
+                            instance,
+                            1,
+                            config.advice[1],
+                            0,
+                        )?
+                    };
+                    Ok((coeffs, x))
+                },
+            )?;
+
+            let chip = PolyChip::construct(config);
+            let y = chip.eval_poly(layouter.namespace(|| "p(x)"), &coeffs, &x)?;
+            layouter.constrain_instance(y.cell(), instance, 0)
+        }
+    }
+
+    fn verify<const PRIVATE_X: bool>(coeffs: &[u64], x: u64, y: Fp) -> bool {
+        let circuit = MyCircuit::<Fp, PRIVATE_X> {
+            coeffs: coeffs.iter().map(|c| Value::known(Fp::from(*c))).collect(),
+            x: Value::known(Fp::from(x)),
+        };
+        let public_input = if PRIVATE_X {
+            vec![y]
+        } else {
+            vec![y, Fp::from(x)]
+        };
+        let prover = MockProver::run(5, &circuit, vec![public_input]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_eval_poly() {
+        // 3 + 2x + 5x^3 at x = 4
+        let coeffs = [3, 2, 0, 5];
+        let y = Fp::from(3 + 2 * 4 + 5 * 64);
+        assert_eq!(eval(&coeffs.map(Fp::from), Fp::from(4)), y);
+
+        assert!(verify::<false>(&coeffs, 4, y));
+        assert!(verify::<true>(&coeffs, 4, y));
+        assert!(!verify::<false>(&coeffs, 4, y + Fp::one()));
+        assert!(!verify::<false>(&coeffs, 5, y));
+
+        // the empty polynomial is zero everywhere and a constant one is itself
+        assert!(verify::<false>(&[], 4, Fp::zero()));
+        assert!(verify::<true>(&[7], 9, Fp::from(7)));
+    }
+}
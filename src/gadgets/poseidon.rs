@@ -0,0 +1,563 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+// Poseidon with the P128Pow5T3 parameters used for the Pasta curves: width 3,
+// rate 2, x^5 S-box, 8 full and 56 partial rounds. Constants are derived with
+// the Grain LFSR from the Poseidon reference implementation.
+pub const WIDTH: usize = 3;
+pub const RATE: usize = 2;
+pub const FULL_ROUNDS: usize = 8;
+pub const PARTIAL_ROUNDS: usize = 56;
+
+const GRAIN_STATE: usize = 80;
+
+struct Grain<F: FieldExt> {
+    state: [bool; GRAIN_STATE],
+    next_bit: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Grain<F> {
+    fn new() -> Self {
+        let mut state = [true; GRAIN_STATE];
+        let mut set_bits = |offset: usize, len: usize, value: u16| {
+            for i in 0..len {
+                state[offset + len - 1 - i] = (value >> i) & 1 != 0;
+            }
+        };
+        // prime field, x^alpha S-box, field size, t, R_F, R_P
+        set_bits(0, 2, 1);
+        set_bits(2, 4, 0);
+        set_bits(6, 12, F::NUM_BITS as u16);
+        set_bits(18, 12, WIDTH as u16);
+        set_bits(30, 10, FULL_ROUNDS as u16);
+        set_bits(40, 10, PARTIAL_ROUNDS as u16);
+
+        let mut grain = Grain {
+            state,
+            next_bit: GRAIN_STATE,
+            _marker: PhantomData,
+        };
+        // discard the first 160 bits
+        for _ in 0..20 {
+            grain.load_next_8_bits();
+            grain.next_bit = GRAIN_STATE;
+        }
+        grain
+    }
+
+    fn load_next_8_bits(&mut self) {
+        let mut new_bits = [false; 8];
+        for (i, bit) in new_bits.iter_mut().enumerate() {
+            let s = &self.state;
+            *bit = s[i + 62] ^ s[i + 51] ^ s[i + 38] ^ s[i + 23] ^ s[i + 13] ^ s[i];
+        }
+        self.state.rotate_left(8);
+        self.next_bit -= 8;
+        self.state[self.next_bit..].copy_from_slice(&new_bits);
+    }
+
+    fn get_next_bit(&mut self) -> bool {
+        if self.next_bit == GRAIN_STATE {
+            self.load_next_8_bits();
+        }
+        let bit = self.state[self.next_bit];
+        self.next_bit += 1;
+        bit
+    }
+
+    // self-shrinking: bits come in pairs, the second one is kept if the first is set
+    fn next_bit(&mut self) -> bool {
+        loop {
+            let keep = self.get_next_bit();
+            let bit = self.get_next_bit();
+            if keep {
+                return bit;
+            }
+        }
+    }
+
+    // The reference implementation reads the bits most significant first.
+    fn next_bytes(&mut self, bytes: &mut [u8]) {
+        let num_bits = F::NUM_BITS as usize;
+        for i in 0..num_bits {
+            let position = num_bits - 1 - i;
+            if self.next_bit() {
+                bytes[position / 8] |= 1 << (position % 8);
+            }
+        }
+    }
+
+    fn next_field_element(&mut self) -> F {
+        loop {
+            let mut repr = F::Repr::default();
+            self.next_bytes(repr.as_mut());
+            if let Some(f) = F::from_repr_vartime(repr) {
+                break f;
+            }
+        }
+    }
+
+    fn next_field_element_without_rejection(&mut self) -> F {
+        let mut bytes = [0u8; 64];
+        self.next_bytes(&mut bytes);
+        F::from_bytes_wide(&bytes)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PoseidonParams<F: FieldExt> {
+    pub round_constants: Vec<[F; WIDTH]>,
+    pub mds: [[F; WIDTH]; WIDTH],
+}
+
+impl<F: FieldExt> PoseidonParams<F> {
+    pub fn new() -> Self {
+        let mut grain = Grain::<F>::new();
+
+        let round_constants = (0..FULL_ROUNDS + PARTIAL_ROUNDS)
+            .map(|_| [(); WIDTH].map(|_| grain.next_field_element()))
+            .collect();
+
+        // Cauchy matrix 1 / (x_i + y_j) from 2 * WIDTH distinct elements. The
+        // first sample is the secure one for both Pasta fields.
+        let (xs, ys) = loop {
+            let vals: Vec<F> = (0..2 * WIDTH)
+                .map(|_| grain.next_field_element_without_rejection())
+                .collect();
+            let distinct = vals
+                .iter()
+                .enumerate()
+                .all(|(i, a)| vals[i + 1..].iter().all(|b| a != b));
+            if distinct {
+                break (vals[..WIDTH].to_vec(), vals[WIDTH..].to_vec());
+            }
+        };
+        let mut mds = [[F::zero(); WIDTH]; WIDTH];
+        for (i, row) in mds.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
+                *entry = (xs[i] + ys[j]).invert().unwrap();
+            }
+        }
+
+        PoseidonParams {
+            round_constants,
+            mds,
+        }
+    }
+
+    fn is_full_round(round: usize) -> bool {
+        !(FULL_ROUNDS / 2..FULL_ROUNDS / 2 + PARTIAL_ROUNDS).contains(&round)
+    }
+
+    pub fn round(&self, state: &mut [F; WIDTH], round: usize) {
+        for (word, rc) in state.iter_mut().zip(self.round_constants[round].iter()) {
+            *word += rc;
+        }
+        if Self::is_full_round(round) {
+            for word in state.iter_mut() {
+                *word = pow5(*word);
+            }
+        } else {
+            state[0] = pow5(state[0]);
+        }
+        let mut next = [F::zero(); WIDTH];
+        for (next, row) in next.iter_mut().zip(self.mds.iter()) {
+            for (m, word) in row.iter().zip(state.iter()) {
+                *next += *m * word;
+            }
+        }
+        *state = next;
+    }
+
+    pub fn permute(&self, state: &mut [F; WIDTH]) {
+        for round in 0..FULL_ROUNDS + PARTIAL_ROUNDS {
+            self.round(state, round);
+        }
+    }
+
+    // Sponge hash over a fixed-length message (halo2_gadgets' ConstantLength<L>).
+    pub fn hash(&self, message: &[F]) -> F {
+        let mut state = [F::zero(); WIDTH];
+        state[RATE] = initial_capacity(message.len());
+        for chunk in padded(message).chunks(RATE) {
+            for (word, m) in state.iter_mut().zip(chunk.iter()) {
+                if let Some(m) = m {
+                    *word += m;
+                }
+            }
+            self.permute(&mut state);
+        }
+        state[0]
+    }
+}
+
+impl<F: FieldExt> Default for PoseidonParams<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn pow5<F: FieldExt>(x: F) -> F {
+    x.square().square() * x
+}
+
+fn initial_capacity<F: FieldExt>(len: usize) -> F {
+    F::from_u128((len as u128) << 64)
+}
+
+fn padded<T: Clone>(message: &[T]) -> Vec<Option<T>> {
+    let chunks = message.len().div_ceil(RATE);
+    let mut padded: Vec<_> = message.iter().cloned().map(Some).collect();
+    padded.resize(chunks.max(1) * RATE, None);
+    padded
+}
+
+// Host-side convenience for computing expected digests.
+pub fn hash<F: FieldExt>(message: &[F]) -> F {
+    PoseidonParams::new().hash(message)
+}
+
+#[derive(Debug, Clone)]
+pub struct PoseidonConfig<F: FieldExt> {
+    pub state: [Column<Advice>; WIDTH],
+    pub rc: [Column<Fixed>; WIDTH],
+    pub s_full: Selector,
+    pub s_partial: Selector,
+    pub s_absorb: Selector,
+    pub params: PoseidonParams<F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PoseidonChip<F: FieldExt> {
+    config: PoseidonConfig<F>,
+}
+
+impl<F: FieldExt> PoseidonChip<F> {
+    pub fn construct(config: PoseidonConfig<F>) -> Self {
+        Self { config }
+    }
+
+    // `constants` has to be a fixed column enabled with `enable_constant`; it
+    // holds the capacity word and the message padding.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        state: [Column<Advice>; WIDTH],
+        rc: [Column<Fixed>; WIDTH],
+        constants: Column<Fixed>,
+    ) -> PoseidonConfig<F> {
+        let params = PoseidonParams::new();
+        let s_full = meta.selector();
+        let s_partial = meta.selector();
+        let s_absorb = meta.selector();
+
+        for column in state {
+            meta.enable_equality(column);
+        }
+        meta.enable_constant(constants);
+
+        let mds = params.mds;
+
+        meta.create_gate("full round", |meta| {
+            //
+            // state[0..3]        | rc[0..3] | s_full
+            //   x_0 x_1 x_2         c_0 ..       1
+            //   y_0 y_1 y_2
+            //
+            // y = MDS * (x + c)^5
+            let s = meta.query_selector(s_full);
+            let sboxed: Vec<_> = (0..WIDTH)
+                .map(|i| {
+                    let x = meta.query_advice(state[i], Rotation::cur());
+                    let c = meta.query_fixed(rc[i], Rotation::cur());
+                    pow5_expr(x + c)
+                })
+                .collect();
+            (0..WIDTH)
+                .map(|i| {
+                    let y = meta.query_advice(state[i], Rotation::next());
+                    s.clone() * (mds_row(&mds[i], &sboxed) - y)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        meta.create_gate("partial round", |meta| {
+            // same as a full round, but only the first word goes through the S-box
+            let s = meta.query_selector(s_partial);
+            let sboxed: Vec<_> = (0..WIDTH)
+                .map(|i| {
+                    let x = meta.query_advice(state[i], Rotation::cur());
+                    let c = meta.query_fixed(rc[i], Rotation::cur());
+                    if i == 0 {
+                        pow5_expr(x + c)
+                    } else {
+                        x + c
+                    }
+                })
+                .collect();
+            (0..WIDTH)
+                .map(|i| {
+                    let y = meta.query_advice(state[i], Rotation::next());
+                    s.clone() * (mds_row(&mds[i], &sboxed) - y)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        meta.create_gate("absorb", |meta| {
+            //
+            // state[0..3]
+            //   x_0 x_1 x_2      s_absorb
+            //   m_0 m_1
+            //   y_0 y_1 y_2
+            //
+            // y_i = x_i + m_i for the rate words, the capacity word passes through
+            let s = meta.query_selector(s_absorb);
+            (0..WIDTH)
+                .map(|i| {
+                    let x = meta.query_advice(state[i], Rotation::cur());
+                    let y = meta.query_advice(state[i], Rotation(2));
+                    if i < RATE {
+                        let m = meta.query_advice(state[i], Rotation::next());
+                        s.clone() * (x + m - y)
+                    } else {
+                        s.clone() * (x - y)
+                    }
+                })
+                .collect::<Vec<_>>()
+        });
+
+        PoseidonConfig {
+            state,
+            rc,
+            s_full,
+            s_partial,
+            s_absorb,
+            params,
+        }
+    }
+
+    // Applies the permutation to `state` starting at `offset`, returning the
+    // output words assigned at `offset + 64`.
+    fn permute_in_region(
+        &self,
+        region: &mut Region<'_, F>,
+        mut offset: usize,
+        state: [AssignedCell<F, F>; WIDTH],
+    ) -> Result<[AssignedCell<F, F>; WIDTH], Error> {
+        let config = &self.config;
+        let params = &config.params;
+
+        let mut words = state[0]
+            .value()
+            .zip(state[1].value())
+            .zip(state[2].value())
+            .map(|((a, b), c)| [*a, *b, *c]);
+        let mut output = state;
+        for (round, rc) in params.round_constants.iter().enumerate() {
+            if PoseidonParams::<F>::is_full_round(round) {
+                config.s_full.enable(region, offset)?;
+            } else {
+                config.s_partial.enable(region, offset)?;
+            }
+            for (column, c) in config.rc.iter().zip(rc.iter()) {
+                region.assign_fixed(|| "rc", *column, offset, || Value::known(*c))?;
+            }
+
+            words = words.map(|mut words| {
+                params.round(&mut words, round);
+                words
+            });
+
+            offset += 1;
+            for (i, word) in words.transpose_array().into_iter().enumerate() {
+                output[i] = region.assign_advice(|| "state", config.state[i], offset, || word)?;
+            }
+        }
+
+        Ok(output)
+    }
+
+    pub fn permute(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: &[AssignedCell<F, F>; WIDTH],
+    ) -> Result<[AssignedCell<F, F>; WIDTH], Error> {
+        layouter.assign_region(
+            || "poseidon permutation",
+            |mut region| {
+                let mut initial = state.clone();
+                for (i, cell) in initial.iter_mut().enumerate() {
+                    *cell = cell.copy_advice(|| "state", &mut region, self.config.state[i], 0)?;
+                }
+                self.permute_in_region(&mut region, 0, initial)
+            },
+        )
+    }
+
+    // Sponge hash of a fixed-length message, matching `PoseidonParams::hash`.
+    pub fn hash(
+        &self,
+        mut layouter: impl Layouter<F>,
+        message: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || format!("poseidon hash {}", message.len()),
+            |mut region| {
+                let chunks = padded(message);
+                let chunks: Vec<_> = chunks.chunks(RATE).collect();
+
+                let mut state: Vec<AssignedCell<F, F>> = vec![];
+                let mut offset = 0;
+                for (n, chunk) in chunks.iter().enumerate() {
+                    if n == 0 {
+                        // the sponge starts from zero, so the first chunk is the state
+                        for (i, word) in chunk.iter().enumerate() {
+                            state.push(assign_word(&mut region, config.state[i], offset, word)?);
+                        }
+                        state.push(region.assign_advice_from_constant(
+                            || "capacity",
+                            config.state[RATE],
+                            offset,
+                            initial_capacity::<F>(message.len()),
+                        )?);
+                    } else {
+                        // absorb into the previous permutation's output row
+                        config.s_absorb.enable(&mut region, offset)?;
+                        for (i, word) in chunk.iter().enumerate() {
+                            assign_word(&mut region, config.state[i], offset + 1, word)?;
+                        }
+                        offset += 2;
+                        let mut absorbed = vec![];
+                        for (i, cell) in state.iter().enumerate() {
+                            let mut value = cell.value().copied();
+                            if let Some(Some(word)) = chunk.get(i) {
+                                value = value + word.value();
+                            }
+                            absorbed.push(region.assign_advice(
+                                || "absorbed",
+                                config.state[i],
+                                offset,
+                                || value,
+                            )?);
+                        }
+                        state = absorbed;
+                    }
+
+                    let input = [state[0].clone(), state[1].clone(), state[2].clone()];
+                    state = self.permute_in_region(&mut region, offset, input)?.to_vec();
+                    offset += FULL_ROUNDS + PARTIAL_ROUNDS;
+                }
+
+                Ok(state[0].clone())
+            },
+        )
+    }
+}
+
+fn assign_word<F: FieldExt>(
+    region: &mut Region<'_, F>,
+    column: Column<Advice>,
+    offset: usize,
+    word: &Option<AssignedCell<F, F>>,
+) -> Result<AssignedCell<F, F>, Error> {
+    match word {
+        Some(cell) => cell.copy_advice(|| "message", region, column, offset),
+        None => region.assign_advice_from_constant(|| "padding", column, offset, F::zero()),
+    }
+}
+
+fn pow5_expr<F: FieldExt>(x: Expression<F>) -> Expression<F> {
+    x.clone() * x.clone() * x.clone() * x.clone() * x
+}
+
+fn mds_row<F: FieldExt>(row: &[F; WIDTH], words: &[Expression<F>]) -> Expression<F> {
+    row.iter()
+        .zip(words.iter())
+        .map(|(m, w)| Expression::Constant(*m) * w.clone())
+        .reduce(|acc, term| acc + term)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        message: Vec<Value<F>>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = (PoseidonConfig<F>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                message: vec![Value::unknown(); self.message.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let state = [(); WIDTH].map(|_| meta.advice_column());
+            let rc = [(); WIDTH].map(|_| meta.fixed_column());
+            let constants = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (
+                PoseidonChip::configure(meta, state, rc, constants),
+                instance,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let message = layouter.assign_region(
+                || "message",
+                |mut region| {
+                    self.message
+                        .iter()
+                        .enumerate()
+                        .map(|(i, m)| region.assign_advice(|| "m", config.state[0], i, || *m))
+                        .collect::<Result<Vec<_>, _>>()
+                },
+            )?;
+
+            let chip = PoseidonChip::construct(config);
+            let digest = chip.hash(layouter.namespace(|| "hash"), &message)?;
+            layouter.constrain_instance(digest.cell(), instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_poseidon_hash() {
+        let k = 9;
+
+        for len in 1..=5 {
+            let message: Vec<_> = (0..len).map(|i| Fp::from(i as u64 + 1)).collect();
+            let digest = hash(&message);
+
+            let circuit = MyCircuit {
+                message: message.iter().map(|m| Value::known(*m)).collect(),
+            };
+
+            let prover = MockProver::run(k, &circuit, vec![vec![digest]]).unwrap();
+            prover.assert_satisfied();
+
+            let prover = MockProver::run(k, &circuit, vec![vec![digest + Fp::one()]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_poseidon_domain_separation() {
+        // zero padding doesn't collide with an explicit trailing zero
+        let a = hash(&[Fp::one()]);
+        let b = hash(&[Fp::one(), Fp::zero()]);
+        assert_ne!(a, b);
+    }
+}
@@ -0,0 +1,200 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+use super::range_table::RangeTableConfig;
+
+#[derive(Debug, Clone)]
+pub struct RangeCheckConfig {
+    pub acc: Column<Advice>,
+    pub limb: Column<Advice>,
+    pub q_lookup: Selector,
+    pub q_first: Selector,
+    pub q_step: Selector,
+    pub bytes: RangeTableConfig,
+}
+
+// Range checks a cell to `NUM_BYTES` bytes by decomposing it into byte limbs,
+// most significant first, and rebuilding it as a running sum.
+#[derive(Debug, Clone)]
+pub struct RangeCheckChip<F: FieldExt> {
+    config: RangeCheckConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> RangeCheckChip<F> {
+    pub fn construct(config: RangeCheckConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 2],
+        bytes: RangeTableConfig,
+    ) -> RangeCheckConfig {
+        let [acc, limb] = advice;
+        let q_lookup = meta.complex_selector();
+        let q_first = meta.selector();
+        let q_step = meta.selector();
+
+        meta.enable_equality(acc);
+        meta.enable_equality(limb);
+
+        bytes.lookup(meta, q_lookup, limb);
+
+        meta.create_gate("first limb", |meta| {
+            //
+            // acc    | limb   | q_first | q_step
+            //  l_0      l_0       1
+            //  acc_1    l_1                  1
+            //  ...
+            //
+            let q_first = meta.query_selector(q_first);
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let limb = meta.query_advice(limb, Rotation::cur());
+            vec![q_first * (acc - limb)]
+        });
+
+        meta.create_gate("running sum", |meta| {
+            let q_step = meta.query_selector(q_step);
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let limb = meta.query_advice(limb, Rotation::cur());
+            vec![q_step * (acc - acc_prev * Expression::Constant(F::from(256)) - limb)]
+        });
+
+        RangeCheckConfig {
+            acc,
+            limb,
+            q_lookup,
+            q_first,
+            q_step,
+            bytes,
+        }
+    }
+
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.config.bytes.load(layouter)
+    }
+
+    // Returns the limbs, most significant first.
+    pub fn range_check<const NUM_BYTES: usize>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        // limbs are read out of a u128
+        const { assert!(NUM_BYTES > 0 && NUM_BYTES <= 16) };
+
+        layouter.assign_region(
+            || format!("range check {} bytes", NUM_BYTES),
+            |mut region| {
+                let value = cell.value().map(|v| v.get_lower_128());
+
+                let mut limbs = vec![];
+                let mut acc = Value::known(F::zero());
+                let mut acc_cell = None;
+                for offset in 0..NUM_BYTES {
+                    self.config.q_lookup.enable(&mut region, offset)?;
+                    if offset == 0 {
+                        self.config.q_first.enable(&mut region, offset)?;
+                    } else {
+                        self.config.q_step.enable(&mut region, offset)?;
+                    }
+
+                    let shift = 8 * (NUM_BYTES - 1 - offset);
+                    let limb = value.map(|v| F::from(((v >> shift) & 0xff) as u64));
+                    acc = acc * Value::known(F::from(256)) + limb;
+
+                    limbs.push(region.assign_advice(
+                        || "limb",
+                        self.config.limb,
+                        offset,
+                        || limb,
+                    )?);
+                    acc_cell =
+                        Some(region.assign_advice(|| "acc", self.config.acc, offset, || acc)?);
+                }
+
+                region.constrain_equal(cell.cell(), acc_cell.unwrap().cell())?;
+
+                Ok(limbs)
+            },
+        )
+    }
+
+    // Assigns a fresh value and range checks it.
+    pub fn witness_checked<const NUM_BYTES: usize>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let cell = layouter.assign_region(
+            || "witness",
+            |mut region| region.assign_advice(|| "value", self.config.acc, 0, || value),
+        )?;
+        self.range_check::<NUM_BYTES>(layouter.namespace(|| "range check"), &cell)?;
+        Ok(cell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[derive(Default)]
+    struct MyCircuit<F, const NUM_BYTES: usize> {
+        value: Value<F>,
+    }
+
+    impl<F: FieldExt, const NUM_BYTES: usize> Circuit<F> for MyCircuit<F, NUM_BYTES> {
+        type Config = RangeCheckConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let acc = meta.advice_column();
+            let limb = meta.advice_column();
+            let bytes = RangeTableConfig::configure(meta, 0, 255);
+            RangeCheckChip::configure(meta, [acc, limb], bytes)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = RangeCheckChip::construct(config);
+            chip.load(&mut layouter)?;
+            chip.witness_checked::<NUM_BYTES>(layouter.namespace(|| "value"), self.value)?;
+            Ok(())
+        }
+    }
+
+    fn verify<const NUM_BYTES: usize>(value: Fp) -> bool {
+        let circuit = MyCircuit::<Fp, NUM_BYTES> {
+            value: Value::known(value),
+        };
+        let prover = MockProver::run(9, &circuit, vec![]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_range_check() {
+        assert!(verify::<1>(Fp::from(0)));
+        assert!(verify::<1>(Fp::from(255)));
+        assert!(!verify::<1>(Fp::from(256)));
+        assert!(verify::<4>(Fp::from(0xffff_ffff)));
+        assert!(!verify::<4>(Fp::from(0x1_0000_0000)));
+        assert!(verify::<8>(Fp::from(u64::MAX)));
+
+        // p - 1 must not slip through as a small value
+        assert!(!verify::<16>(-Fp::one()));
+    }
+}
@@ -0,0 +1,56 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+// Fixed lookup table holding every integer in `lo..=hi`. Other chips register a
+// lookup against it for the advice columns they want range checked.
+#[derive(Debug, Clone)]
+pub struct RangeTableConfig {
+    pub table: TableColumn,
+    pub lo: u64,
+    pub hi: u64,
+}
+
+impl RangeTableConfig {
+    pub fn configure<F: FieldExt>(meta: &mut ConstraintSystem<F>, lo: u64, hi: u64) -> Self {
+        assert!(lo <= hi);
+        RangeTableConfig {
+            table: meta.lookup_table_column(),
+            lo,
+            hi,
+        }
+    }
+
+    // `selector` has to be a complex selector, lookups can't use simple ones.
+    // Rows where it is off look up `lo` instead, which is always in the table.
+    pub fn lookup<F: FieldExt>(
+        &self,
+        meta: &mut ConstraintSystem<F>,
+        selector: Selector,
+        column: Column<Advice>,
+    ) {
+        let lo = self.lo;
+        let table = self.table;
+        meta.lookup(|meta| {
+            let q = meta.query_selector(selector);
+            let v = meta.query_advice(column, Rotation::cur());
+            let not_q = Expression::Constant(F::one()) - q.clone();
+            vec![(q * v + not_q * Expression::Constant(F::from(lo)), table)]
+        });
+    }
+
+    pub fn load<F: FieldExt>(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || format!("range {}..={}", self.lo, self.hi),
+            |mut table| {
+                for (offset, v) in (self.lo..=self.hi).enumerate() {
+                    table.assign_cell(
+                        || "value",
+                        self.table,
+                        offset,
+                        || Value::known(F::from(v)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
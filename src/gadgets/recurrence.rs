@@ -0,0 +1,246 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone)]
+pub struct RecurrenceConfig<const K: usize> {
+    pub x: Column<Advice>,
+    pub coefficients: [Column<Fixed>; K],
+    pub selector: Selector,
+}
+
+// The sequence x_n = c_1 * x_{n-1} + ... + c_K * x_{n-K} from x_0 to x_{K-1},
+// with the coefficients in K fixed columns, so they're part of the keys. K = 2
+// with c = [1, 1] is Fibonacci, c = [p, -q] a Lucas sequence, and K = 3 with
+// c = [1, 1, 1] Tribonacci.
+#[derive(Debug, Clone)]
+pub struct RecurrenceChip<F: FieldExt, const K: usize> {
+    config: RecurrenceConfig<K>,
+    _marker: PhantomData<F>,
+}
+
+// x_0 to x_n on the host, `coefficients[i]` being c_{i+1}.
+pub fn linear_recurrence<F: FieldExt>(coefficients: &[F], initial: &[F], n: usize) -> Vec<F> {
+    assert_eq!(coefficients.len(), initial.len());
+    let mut sequence = initial.to_vec();
+    while sequence.len() <= n {
+        let next = coefficients
+            .iter()
+            .zip(sequence.iter().rev())
+            .fold(F::zero(), |acc, (c, x)| acc + *c * x);
+        sequence.push(next);
+    }
+    sequence.truncate(n + 1);
+    sequence
+}
+
+impl<F: FieldExt, const K: usize> RecurrenceChip<F, K> {
+    pub fn construct(config: RecurrenceConfig<K>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        x: Column<Advice>,
+        coefficients: [Column<Fixed>; K],
+    ) -> RecurrenceConfig<K> {
+        assert!(K >= 1, "a recurrence needs at least one term");
+        let selector = meta.selector();
+
+        meta.enable_equality(x);
+
+        meta.create_gate("recurrence", |meta| {
+            //
+            // x       | c_1 .. c_K | selector
+            // x_0       c_1 .. c_K      1
+            // ...
+            // x_K-1
+            // x_K
+            //
+            // the gate on row r makes x_{r+K} from the K terms from row r
+            let s = meta.query_selector(selector);
+            let next = meta.query_advice(x, Rotation(K as i32));
+            let sum = coefficients
+                .iter()
+                .enumerate()
+                .map(|(i, column)| {
+                    let c = meta.query_fixed(*column, Rotation::cur());
+                    c * meta.query_advice(x, Rotation((K - 1 - i) as i32))
+                })
+                .reduce(|acc, term| acc + term)
+                .unwrap();
+            vec![s * (sum - next)]
+        });
+
+        RecurrenceConfig {
+            x,
+            coefficients,
+            selector,
+        }
+    }
+
+    // Returns the cell holding x_n.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        coefficients: [F; K],
+        initial: &[AssignedCell<F, F>; K],
+        n: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(
+            || "recurrence",
+            |mut region| {
+                let mut cells = initial
+                    .iter()
+                    .enumerate()
+                    .map(|(row, cell)| cell.copy_advice(|| "initial", &mut region, config.x, row))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                for row in 0..(n + 1).saturating_sub(K) {
+                    config.selector.enable(&mut region, row)?;
+                    for (column, c) in config.coefficients.iter().zip(coefficients) {
+                        region.assign_fixed(|| "c", *column, row, || Value::known(c))?;
+                    }
+
+                    let next = cells[row..]
+                        .iter()
+                        .rev()
+                        .zip(coefficients)
+                        .fold(Value::known(F::zero()), |acc, (cell, c)| {
+                            acc + cell.value().map(|x| c * x)
+                        });
+                    cells.push(region.assign_advice(|| "x", config.x, row + K, || next)?);
+                }
+
+                Ok(cells.swap_remove(n))
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        circuits::tribonacci::tribonacci,
+        explain::{assert_unsatisfied_with, Failure},
+        fibo::{FiboPublicInputs, OUT_TERM},
+        gadgets::lucas::lucas_sequence,
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 5;
+
+    // x_n from the public x_0 to x_{N-1}. The instance column is
+    // `[x_0, .., x_{N-1}, x_n]`.
+    struct MyCircuit<F, const N: usize> {
+        coefficients: [F; N],
+        n: usize,
+    }
+
+    impl<F: FieldExt, const N: usize> Circuit<F> for MyCircuit<F, N> {
+        type Config = (RecurrenceConfig<N>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                coefficients: self.coefficients,
+                n: self.n,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let x = meta.advice_column();
+            let coefficients = [(); N].map(|_| meta.fixed_column());
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (RecurrenceChip::configure(meta, x, coefficients), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = RecurrenceChip::construct(config.clone());
+            let initial = layouter.assign_region(
+                || "initial values",
+                |mut region| {
+                    let cells = (0..N)
+                        .map(|row| {
+                            region.assign_advice_from_instance(
+                                || "initial",
+                                instance,
+                                row,
+                                config.x,
+                                row,
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Ok(cells.try_into().unwrap())
+                },
+            )?;
+            let out = chip.assign(
+                layouter.namespace(|| "sequence"),
+                self.coefficients,
+                &initial,
+                self.n,
+            )?;
+            layouter.constrain_instance(out.cell(), instance, N)
+        }
+    }
+
+    fn check<const N: usize>(coefficients: [u64; N], initial: [u64; N], n: usize) -> Fp {
+        let (coefficients, initial) = (coefficients.map(Fp::from), initial.map(Fp::from));
+        let out = linear_recurrence(&coefficients, &initial, n)[n];
+        let circuit = MyCircuit { coefficients, n };
+        let mut instance = initial.to_vec();
+        instance.push(out);
+        let prover = MockProver::run(K, &circuit, vec![instance.clone()]).unwrap();
+        prover.assert_satisfied();
+
+        instance[N] += Fp::one();
+        assert_unsatisfied_with(
+            K,
+            &circuit,
+            vec![instance],
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+        out
+    }
+
+    #[test]
+    fn test_known_sequences() {
+        let (one, two) = (Fp::one(), Fp::from(2));
+
+        let fibonacci = FiboPublicInputs::new(one, one).out;
+        assert_eq!(check([1, 1], [1, 1], OUT_TERM), fibonacci);
+
+        let lucas = lucas_sequence(two, -Fp::from(3), one, Fp::from(4), 12)[12];
+        assert_eq!(check([2, 3], [1, 4], 12), lucas);
+
+        let tribonacci = tribonacci([0, 0, 1].map(Fp::from), 20)[20];
+        assert_eq!(check([1, 1, 1], [0, 0, 1], 20), tribonacci);
+
+        // Padovan, x_n = x_{n-2} + x_{n-3}
+        assert_eq!(check([0, 1, 1], [1, 1, 1], 15), Fp::from(49));
+
+        // one term is a geometric sequence, and five terms with every
+        // coefficient 1 the pentanacci numbers
+        assert_eq!(check([3], [1], 10), Fp::from(59049));
+        assert_eq!(check([1; 5], [0, 0, 0, 0, 1], 14), Fp::from(464));
+    }
+
+    #[test]
+    fn test_short_sequences() {
+        // n inside the initial values, so there's no gate at all
+        for n in 0..3 {
+            assert_eq!(check([1, 1, 1], [5, 6, 7], n), Fp::from(5 + n as u64));
+        }
+        assert_eq!(check([1, 1, 1], [5, 6, 7], 3), Fp::from(18));
+    }
+}
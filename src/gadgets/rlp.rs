@@ -0,0 +1,417 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::{marker::PhantomData, ops::Range};
+
+use super::range_table::RangeTableConfig;
+
+// An RLP string or list header for a payload of `len` bytes. `offset` is 0x80
+// for strings and 0xc0 for lists.
+fn header(offset: u8, len: usize) -> Vec<u8> {
+    if len <= 55 {
+        return vec![offset + len as u8];
+    }
+    let len: Vec<u8> = len
+        .to_be_bytes()
+        .into_iter()
+        .skip_while(|b| *b == 0)
+        .collect();
+    [vec![offset + 55 + len.len() as u8], len].concat()
+}
+
+pub fn rlp_string(bytes: &[u8]) -> Vec<u8> {
+    match bytes {
+        [b] if *b < 0x80 => vec![*b],
+        _ => [header(0x80, bytes.len()), bytes.to_vec()].concat(),
+    }
+}
+
+// `items` are already encoded.
+pub fn rlp_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload = items.concat();
+    [header(0xc0, payload.len()), payload].concat()
+}
+
+// Everything about an item that decides where its bytes go: the lengths of its
+// strings and how its lists nest.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RlpShape {
+    // a single byte below 0x80, which is its own encoding
+    Byte,
+    String(usize),
+    List(Vec<RlpShape>),
+}
+
+// An encoding with the header bytes filled in and the payload bytes left
+// empty, where each string's payload is, and the single byte strings whose
+// bytes have to be below 0x80, or at least 0x80 if they have a header, for the
+// encoding to be canonical.
+#[derive(Debug, Default)]
+struct Template {
+    bytes: Vec<Option<u8>>,
+    fields: Vec<Range<usize>>,
+    singles: Vec<(usize, u8)>,
+}
+
+impl RlpShape {
+    pub fn encoded_len(&self) -> usize {
+        match self {
+            RlpShape::Byte => 1,
+            RlpShape::String(len) => header(0x80, *len).len() + len,
+            RlpShape::List(items) => {
+                let len = items.iter().map(RlpShape::encoded_len).sum();
+                header(0xc0, len).len() + len
+            }
+        }
+    }
+
+    fn template(&self) -> Template {
+        let mut template = Template::default();
+        self.write(&mut template);
+        template
+    }
+
+    fn write(&self, template: &mut Template) {
+        let start = template.bytes.len();
+        match self {
+            RlpShape::Byte => {
+                template.singles.push((start, 0));
+                template.fields.push(start..start + 1);
+                template.bytes.push(None);
+            }
+            RlpShape::String(len) => {
+                template
+                    .bytes
+                    .extend(header(0x80, *len).into_iter().map(Some));
+                let start = template.bytes.len();
+                if *len == 1 {
+                    template.singles.push((start, 0x80));
+                }
+                template.fields.push(start..start + len);
+                template.bytes.extend(std::iter::repeat_n(None, *len));
+            }
+            RlpShape::List(items) => {
+                let len = items.iter().map(RlpShape::encoded_len).sum();
+                template
+                    .bytes
+                    .extend(header(0xc0, len).into_iter().map(Some));
+                for item in items {
+                    item.write(template);
+                }
+            }
+        }
+    }
+}
+
+// Decodes one item, returning its shape and its strings' payloads, depth
+// first.
+pub fn decode(encoded: &[u8]) -> (RlpShape, Vec<Vec<u8>>) {
+    let mut fields = vec![];
+    let (shape, len) = decode_item(encoded, &mut fields);
+    assert_eq!(len, encoded.len(), "trailing bytes");
+    (shape, fields)
+}
+
+// Returns the item at the start of `bytes` and how many bytes it took.
+fn decode_item(bytes: &[u8], fields: &mut Vec<Vec<u8>>) -> (RlpShape, usize) {
+    // where the payload starts, and its length
+    let split = |offset: u8| -> (usize, usize) {
+        match bytes[0] - offset {
+            len @ 0..=55 => (1, len as usize),
+            lenlen => {
+                let lenlen = (lenlen - 55) as usize;
+                let len = bytes[1..=lenlen]
+                    .iter()
+                    .fold(0, |len, b| len << 8 | *b as usize);
+                (1 + lenlen, len)
+            }
+        }
+    };
+    match bytes[0] {
+        b if b < 0x80 => {
+            fields.push(vec![b]);
+            (RlpShape::Byte, 1)
+        }
+        b if b < 0xc0 => {
+            let (start, len) = split(0x80);
+            fields.push(bytes[start..start + len].to_vec());
+            (RlpShape::String(len), start + len)
+        }
+        _ => {
+            let (start, len) = split(0xc0);
+            let mut items = vec![];
+            let mut at = start;
+            while at < start + len {
+                let (item, len) = decode_item(&bytes[at..], fields);
+                items.push(item);
+                at += len;
+            }
+            assert_eq!(at, start + len, "item runs past its list");
+            (RlpShape::List(items), at)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RlpConfig {
+    // the encoding, and single byte strings shifted into 0..0x80
+    pub advice: [Column<Advice>; 2],
+    pub shift: Column<Fixed>,
+    pub q_byte: Selector,
+    pub q_single: Selector,
+    pub bytes: RangeTableConfig,
+    pub low: RangeTableConfig,
+}
+
+#[derive(Debug, Clone)]
+pub struct Decoded<F: FieldExt> {
+    pub encoded: Vec<AssignedCell<F, F>>,
+    // each string's payload, depth first
+    pub fields: Vec<Vec<AssignedCell<F, F>>>,
+}
+
+// Decodes an RLP item whose shape is part of the circuit. The shape fixes
+// every header, so they are assigned as constants and parsing is a matter of
+// knowing where each string's payload sits. The payload bytes are range
+// checked, and single byte strings checked to be encoded canonically.
+#[derive(Debug, Clone)]
+pub struct RlpChip<F: FieldExt> {
+    config: RlpConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> RlpChip<F> {
+    pub fn construct(config: RlpConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    // `bytes` is 0..=255 and `low` 0..=127. `constants` holds the headers.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 2],
+        constants: Column<Fixed>,
+        bytes: RangeTableConfig,
+        low: RangeTableConfig,
+    ) -> RlpConfig {
+        assert_eq!((bytes.lo, bytes.hi), (0, 0xff));
+        assert_eq!((low.lo, low.hi), (0, 0x7f));
+        let shift = meta.fixed_column();
+        let q_byte = meta.complex_selector();
+        let q_single = meta.complex_selector();
+
+        meta.enable_equality(advice[0]);
+        meta.enable_constant(constants);
+
+        bytes.lookup(meta, q_byte, advice[0]);
+        low.lookup(meta, q_single, advice[1]);
+
+        meta.create_gate("single byte", |meta| {
+            //
+            // advice[0] | advice[1] | shift | q_single
+            //   byte      low byte    shift      1
+            //
+            // shift is 0x80 for a single byte string with a header and 0 for
+            // one without
+            let s = meta.query_selector(q_single);
+            let byte = meta.query_advice(advice[0], Rotation::cur());
+            let low = meta.query_advice(advice[1], Rotation::cur());
+            let shift = meta.query_fixed(shift, Rotation::cur());
+            vec![s * (byte - shift - low)]
+        });
+
+        RlpConfig {
+            advice,
+            shift,
+            q_byte,
+            q_single,
+            bytes,
+            low,
+        }
+    }
+
+    pub fn decode(
+        &self,
+        mut layouter: impl Layouter<F>,
+        shape: &RlpShape,
+        encoded: &[Value<F>],
+    ) -> Result<Decoded<F>, Error> {
+        let config = &self.config;
+        let template = shape.template();
+        assert_eq!(encoded.len(), template.bytes.len());
+
+        let encoded = layouter.assign_region(
+            || "rlp",
+            |mut region| {
+                let mut cells = vec![];
+                for (offset, byte) in template.bytes.iter().enumerate() {
+                    cells.push(match byte {
+                        Some(byte) => region.assign_advice_from_constant(
+                            || "header",
+                            config.advice[0],
+                            offset,
+                            F::from(*byte as u64),
+                        )?,
+                        None => {
+                            config.q_byte.enable(&mut region, offset)?;
+                            region.assign_advice(
+                                || "byte",
+                                config.advice[0],
+                                offset,
+                                || encoded[offset],
+                            )?
+                        }
+                    });
+                }
+                for (offset, shift) in template.singles.iter() {
+                    let shift = F::from(*shift as u64);
+                    config.q_single.enable(&mut region, *offset)?;
+                    region.assign_fixed(
+                        || "shift",
+                        config.shift,
+                        *offset,
+                        || Value::known(shift),
+                    )?;
+                    region.assign_advice(
+                        || "low byte",
+                        config.advice[1],
+                        *offset,
+                        || encoded[*offset].map(|byte| byte - shift),
+                    )?;
+                }
+                Ok(cells)
+            },
+        )?;
+
+        let fields = template
+            .fields
+            .iter()
+            .map(|range| encoded[range.clone()].to_vec())
+            .collect();
+        Ok(Decoded { encoded, fields })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::tables::TableRegistry;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    // Decodes a witnessed encoding and exposes it, then its fields, so the
+    // instance pins down both.
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        shape: Option<RlpShape>,
+        encoded: Vec<Value<F>>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = (RlpConfig, TableRegistry, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                shape: self.shape.clone(),
+                encoded: vec![Value::unknown(); self.encoded.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 2].map(|_| meta.advice_column());
+            let constants = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            let mut tables = TableRegistry::default();
+            let bytes = tables.bytes(meta);
+            let low = tables.range(meta, 0, 0x7f);
+            let config = RlpChip::configure(meta, advice, constants, bytes, low);
+            (config, tables, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, tables, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            tables.load(&mut layouter)?;
+            let chip = RlpChip::construct(config);
+            let shape = self.shape.as_ref().unwrap();
+            let decoded = chip.decode(layouter.namespace(|| "decode"), shape, &self.encoded)?;
+
+            let cells = decoded
+                .encoded
+                .iter()
+                .chain(decoded.fields.iter().flatten());
+            for (row, cell) in cells.enumerate() {
+                layouter.constrain_instance(cell.cell(), instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn verify(shape: RlpShape, encoded: &[u8], fields: &[Vec<u8>]) -> bool {
+        let circuit = MyCircuit::<Fp> {
+            shape: Some(shape),
+            encoded: encoded
+                .iter()
+                .map(|b| Value::known(Fp::from(*b as u64)))
+                .collect(),
+        };
+        let instance = encoded
+            .iter()
+            .chain(fields.iter().flatten())
+            .map(|b| Fp::from(*b as u64))
+            .collect();
+        let prover = MockProver::run(9, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_encoding() {
+        assert_eq!(rlp_string(&[0x7f]), [0x7f]);
+        assert_eq!(rlp_string(&[0x80]), [0x81, 0x80]);
+        assert_eq!(rlp_string(b""), [0x80]);
+        assert_eq!(rlp_list(&[]), [0xc0]);
+        assert_eq!(header(0xc0, 1024), [0xf9, 0x04, 0x00]);
+        assert_eq!(RlpShape::String(1024).encoded_len(), 1027);
+    }
+
+    #[test]
+    fn test_rlp() {
+        let encoded = rlp_list(&[
+            rlp_string(b"cat"),
+            rlp_list(&[rlp_string(b"dog"), rlp_string(b"")]),
+            rlp_string(&[0x05]),
+            rlp_string(&[0xff]),
+            rlp_string(&[0x42; 60]),
+        ]);
+        let (shape, fields) = decode(&encoded);
+        use RlpShape::*;
+        assert_eq!(
+            shape,
+            List(vec![
+                String(3),
+                List(vec![String(3), String(0)]),
+                Byte,
+                String(1),
+                String(60),
+            ])
+        );
+        assert_eq!(shape.encoded_len(), encoded.len());
+        assert!(verify(shape.clone(), &encoded, &fields));
+
+        // the same length, split differently
+        let encoded = rlp_list(&[rlp_string(b"cat")]);
+        let (_, fields) = decode(&encoded);
+        assert!(!verify(List(vec![String(2), Byte]), &encoded, &fields));
+
+        // a header on a byte that didn't need one
+        assert!(!verify(
+            List(vec![String(1)]),
+            &[0xc2, 0x81, 0x05],
+            &[vec![0x05]]
+        ));
+    }
+}
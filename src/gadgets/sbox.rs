@@ -0,0 +1,197 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+use super::gf256::gf_mul;
+
+// The AES S-box: the inverse in GF(2^8), with 0 mapping to 0, followed by the
+// affine map b ^ rotl(b, 1) ^ rotl(b, 2) ^ rotl(b, 3) ^ rotl(b, 4) ^ 0x63.
+pub fn sbox(x: u8) -> u8 {
+    // x^254 = x^-1, by square and multiply
+    let mut inverse = 1;
+    for bit in (0..8).rev() {
+        inverse = gf_mul(inverse, inverse);
+        if (254 >> bit) & 1 == 1 {
+            inverse = gf_mul(inverse, x);
+        }
+    }
+    let b = inverse;
+    b ^ b.rotate_left(1) ^ b.rotate_left(2) ^ b.rotate_left(3) ^ b.rotate_left(4) ^ 0x63
+}
+
+#[derive(Debug, Clone)]
+pub struct SboxConfig {
+    pub advice: [Column<Advice>; 2],
+    pub q_sub: Selector,
+    pub table: [TableColumn; 2],
+}
+
+// Applies the AES S-box to a byte with one lookup into a 256 row table of
+// (x, sbox(x)) pairs. The S-box has no low degree description, so a table is
+// the cheap way to prove it; inputs that aren't bytes aren't in the table, so
+// `sub_byte` range checks its input for free.
+#[derive(Debug, Clone)]
+pub struct SboxChip<F: FieldExt> {
+    config: SboxConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> SboxChip<F> {
+    pub fn construct(config: SboxConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 2]) -> SboxConfig {
+        let q_sub = meta.complex_selector();
+        let table = [meta.lookup_table_column(), meta.lookup_table_column()];
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.lookup(|meta| {
+            //
+            // advice[0] | advice[1] | q_sub
+            //     x        sbox(x)      1
+            //
+            // rows where q_sub is off look up (0, sbox(0))
+            let q = meta.query_selector(q_sub);
+            let x = meta.query_advice(advice[0], Rotation::cur());
+            let y = meta.query_advice(advice[1], Rotation::cur());
+            let not_q = Expression::Constant(F::one()) - q.clone();
+            let default = Expression::Constant(F::from(sbox(0) as u64));
+            vec![
+                (q.clone() * x, table[0]),
+                (q * y + not_q * default, table[1]),
+            ]
+        });
+
+        SboxConfig {
+            advice,
+            q_sub,
+            table,
+        }
+    }
+
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        let table = self.config.table;
+        layouter.assign_table(
+            || "aes s-box",
+            |mut t| {
+                for x in 0..=255u8 {
+                    let offset = x as usize;
+                    t.assign_cell(|| "x", table[0], offset, || Value::known(F::from(x as u64)))?;
+                    t.assign_cell(
+                        || "sbox(x)",
+                        table[1],
+                        offset,
+                        || Value::known(F::from(sbox(x) as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    pub fn sub_byte(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "sub byte",
+            |mut region| {
+                config.q_sub.enable(&mut region, 0)?;
+                x.copy_advice(|| "x", &mut region, config.advice[0], 0)?;
+                let y = x
+                    .value()
+                    .map(|x| F::from(sbox(x.get_lower_128() as u8) as u64));
+                region.assign_advice(|| "sbox(x)", config.advice[1], 0, || y)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        inputs: Vec<Value<F>>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = (SboxConfig, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                inputs: vec![Value::unknown(); self.inputs.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [meta.advice_column(), meta.advice_column()];
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (SboxChip::configure(meta, advice), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = SboxChip::construct(config.clone());
+            chip.load(&mut layouter)?;
+
+            for (i, x) in self.inputs.iter().enumerate() {
+                let x = layouter.assign_region(
+                    || "input",
+                    |mut region| region.assign_advice(|| "x", config.advice[0], 0, || *x),
+                )?;
+                let y = chip.sub_byte(layouter.namespace(|| "sub byte"), &x)?;
+                layouter.constrain_instance(y.cell(), instance, i)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn verify(inputs: &[u64], outputs: &[u64]) -> bool {
+        let circuit = MyCircuit {
+            inputs: inputs.iter().map(|x| Value::known(Fp::from(*x))).collect(),
+        };
+        let instance = outputs.iter().map(|y| Fp::from(*y)).collect();
+        let prover = MockProver::run(9, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_sbox_values() {
+        // from FIPS-197
+        assert_eq!(sbox(0x00), 0x63);
+        assert_eq!(sbox(0x01), 0x7c);
+        assert_eq!(sbox(0x53), 0xed);
+        assert_eq!(sbox(0xff), 0x16);
+
+        let mut seen = [false; 256];
+        for x in 0..=255u8 {
+            seen[sbox(x) as usize] = true;
+        }
+        assert!(seen.iter().all(|s| *s));
+    }
+
+    #[test]
+    fn test_sub_byte() {
+        assert!(verify(&[0x00, 0x53, 0xff, 0x53], &[0x63, 0xed, 0x16, 0xed]));
+
+        // a wrong output, and an input that isn't a byte
+        assert!(!verify(&[0x53], &[0xee]));
+        assert!(!verify(&[0x153], &[0xed]));
+    }
+}
@@ -0,0 +1,501 @@
+use halo2_proofs::{
+    arithmetic::{CurveAffine, CurveExt, Field},
+    circuit::*,
+    pasta::{
+        group::{ff::PrimeField, Curve},
+        pallas,
+    },
+    plonk::*,
+    poly::Rotation,
+};
+
+use super::{
+    pedersen::{self, EccPoint, PedersenChip, PedersenConfig},
+    poseidon::{self, PoseidonChip, PoseidonConfig},
+};
+
+// Challenges are Pallas base field elements, which all fit in this many bits.
+pub const CHALLENGE_BITS: usize = 255;
+
+const DOMAIN: &str = "halo2_example:schnorr";
+
+#[derive(Debug, Clone, Copy)]
+pub struct Signature {
+    pub r: pallas::Affine,
+    pub s: pallas::Scalar,
+}
+
+pub fn coordinates(point: pallas::Affine) -> (pallas::Base, pallas::Base) {
+    let coordinates = point.coordinates().unwrap();
+    (*coordinates.x(), *coordinates.y())
+}
+
+// Keys and nonces are multiples of the Pedersen generator H, so `s·H` can be
+// computed by committing to zero with blinding `s`.
+pub fn public_key(secret: pallas::Scalar) -> pallas::Affine {
+    (pedersen::generators().1 * secret).to_affine()
+}
+
+pub fn challenge(
+    r: pallas::Affine,
+    public_key: pallas::Affine,
+    message: pallas::Base,
+) -> pallas::Base {
+    let ((rx, ry), (px, py)) = (coordinates(r), coordinates(public_key));
+    poseidon::hash(&[rx, ry, px, py, message])
+}
+
+// The base field is the smaller of the two, so a challenge is a scalar as it
+// is.
+fn to_scalar(e: pallas::Base) -> pallas::Scalar {
+    pallas::Scalar::from_repr(e.to_repr()).unwrap()
+}
+
+pub fn sign(secret: pallas::Scalar, message: pallas::Base, nonce: pallas::Scalar) -> Signature {
+    let r = public_key(nonce);
+    let e = challenge(r, public_key(secret), message);
+    Signature {
+        r,
+        s: nonce + to_scalar(e) * secret,
+    }
+}
+
+pub fn verify(public_key: pallas::Affine, message: pallas::Base, signature: &Signature) -> bool {
+    let e = challenge(signature.r, public_key, message);
+    let h = pedersen::generators().1;
+    h * signature.s == signature.r + public_key * to_scalar(e)
+}
+
+fn hash_to_curve(name: &[u8]) -> pallas::Point {
+    pallas::Point::hash_to_curve(DOMAIN)(name)
+}
+
+// The double-and-add chain starts at T and adds O on every step on top of
+// `bit·P`, so it ends at e·P plus this.
+fn mul_offset() -> pallas::Point {
+    let (t, o) = (hash_to_curve(b"T"), hash_to_curve(b"O"));
+    (0..CHALLENGE_BITS).fold(t, |acc, _| acc + acc + o)
+}
+
+#[derive(Debug, Clone)]
+pub struct SchnorrConfig {
+    pub advice: [Column<Advice>; 11],
+    pub q_on_curve: Selector,
+    pub q_add: Selector,
+    pub q_mul: Selector,
+    pub pedersen: PedersenConfig,
+    pub poseidon: PoseidonConfig<pallas::Base>,
+}
+
+// Schnorr signatures on Pallas, verified as `s·H = R + e·P` with the challenge
+// `e = H(R, P, m)` hashed with Poseidon. `s·H` comes from `PedersenChip`. `e·P`
+// is variable-base, so it gets a double-and-add chain of its own, one row per
+// bit, that doubles and adds O or P + O; like the Pedersen chain, the offsets
+// keep honest additions away from the exceptional cases, and every addition
+// checks its points differ in x so a prover can't use them either. The
+// challenge's bits aren't checked to be its canonical ones, which only lets a
+// prover swap e for e + p, a challenge as hard to answer without the key.
+#[derive(Debug, Clone)]
+pub struct SchnorrChip {
+    config: SchnorrConfig,
+}
+
+impl SchnorrChip {
+    pub fn construct(config: SchnorrConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        advice: [Column<Advice>; 11],
+        pedersen: PedersenConfig,
+        poseidon: PoseidonConfig<pallas::Base>,
+    ) -> SchnorrConfig {
+        let q_on_curve = meta.selector();
+        let q_add = meta.selector();
+        let q_mul = meta.selector();
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        let constant = |v: u64| Expression::Constant(pallas::Base::from(v));
+
+        meta.create_gate("on curve", |meta| {
+            let s = meta.query_selector(q_on_curve);
+            let x = meta.query_advice(advice[0], Rotation::cur());
+            let y = meta.query_advice(advice[1], Rotation::cur());
+            vec![s * (y.clone() * y - x.clone() * x.clone() * x - constant(5))]
+        });
+
+        meta.create_gate("add", |meta| {
+            //
+            // advice[0] | advice[1] | advice[2] | advice[3] | advice[4] | advice[5] | q_add
+            //    x1          y1          x2          y2        lambda       inv         1
+            //    x3          y3
+            //
+            let s = meta.query_selector(q_add);
+            let [x1, y1, x2, y2, lambda, inv] =
+                [0, 1, 2, 3, 4, 5].map(|i| meta.query_advice(advice[i], Rotation::cur()));
+            let x3 = meta.query_advice(advice[0], Rotation::next());
+            let y3 = meta.query_advice(advice[1], Rotation::next());
+            let dx = x2.clone() - x1.clone();
+            vec![
+                s.clone() * (dx.clone() * inv - constant(1)),
+                s.clone() * (lambda.clone() * dx - (y2 - y1.clone())),
+                s.clone() * (lambda.clone() * lambda.clone() - x1.clone() - x2 - x3.clone()),
+                s * (lambda * (x1 - x3) - y1 - y3),
+            ]
+        });
+
+        let (ox, oy) = coordinates(hash_to_curve(b"O").to_affine());
+        meta.create_gate("double and add", |meta| {
+            //
+            // x  | y  | bit | l1 | xd | yd | l2 | inv | px | py | e  | q_mul
+            // x' | y' |                                          e'
+            //
+            // (xd, yd) = 2·(x, y), then (x', y') = (xd, yd) + Q where Q is
+            // P + O in (px, py) if bit is set and O if not. e sums the bits,
+            // most significant first.
+            let s = meta.query_selector(q_mul);
+            let [x, y, bit, l1, xd, yd, l2, inv, px, py, e] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]
+                .map(|i| meta.query_advice(advice[i], Rotation::cur()));
+            let x_next = meta.query_advice(advice[0], Rotation::next());
+            let y_next = meta.query_advice(advice[1], Rotation::next());
+            let e_next = meta.query_advice(advice[10], Rotation::next());
+
+            let ox = Expression::Constant(ox);
+            let oy = Expression::Constant(oy);
+            let qx = ox.clone() + bit.clone() * (px - ox);
+            let qy = oy.clone() + bit.clone() * (py - oy);
+            let dx = qx.clone() - xd.clone();
+            vec![
+                s.clone() * bit.clone() * (constant(1) - bit.clone()),
+                s.clone()
+                    * (l1.clone() * constant(2) * y.clone() - constant(3) * x.clone() * x.clone()),
+                s.clone() * (l1.clone() * l1.clone() - constant(2) * x.clone() - xd.clone()),
+                s.clone() * (l1 * (x - xd.clone()) - y - yd.clone()),
+                s.clone() * (dx.clone() * inv - constant(1)),
+                s.clone() * (l2.clone() * dx - (qy - yd.clone())),
+                s.clone() * (l2.clone() * l2.clone() - xd.clone() - qx - x_next.clone()),
+                s.clone() * (l2 * (xd - x_next) - yd - y_next),
+                s * (e * constant(2) + bit - e_next),
+            ]
+        });
+
+        SchnorrConfig {
+            advice,
+            q_on_curve,
+            q_add,
+            q_mul,
+            pedersen,
+            poseidon,
+        }
+    }
+
+    // Witnesses a point and checks it's on the curve.
+    pub fn witness_point(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        point: Value<pallas::Affine>,
+    ) -> Result<EccPoint, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "point",
+            |mut region| {
+                config.q_on_curve.enable(&mut region, 0)?;
+                let (x, y) = point.map(coordinates).unzip();
+                Ok(EccPoint {
+                    x: region.assign_advice(|| "x", config.advice[0], 0, || x)?,
+                    y: region.assign_advice(|| "y", config.advice[1], 0, || y)?,
+                })
+            },
+        )
+    }
+
+    fn constant_point(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        point: pallas::Point,
+    ) -> Result<EccPoint, Error> {
+        let config = &self.config;
+        let (x, y) = coordinates(point.to_affine());
+        layouter.assign_region(
+            || "constant point",
+            |mut region| {
+                Ok(EccPoint {
+                    x: region.assign_advice_from_constant(|| "x", config.advice[0], 0, x)?,
+                    y: region.assign_advice_from_constant(|| "y", config.advice[1], 0, y)?,
+                })
+            },
+        )
+    }
+
+    // a + b, for points that differ in x
+    pub fn add(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        a: &EccPoint,
+        b: &EccPoint,
+    ) -> Result<EccPoint, Error> {
+        let advice = self.config.advice;
+        layouter.assign_region(
+            || "add",
+            |mut region| {
+                self.config.q_add.enable(&mut region, 0)?;
+                let x1 = a.x.copy_advice(|| "x1", &mut region, advice[0], 0)?;
+                let y1 = a.y.copy_advice(|| "y1", &mut region, advice[1], 0)?;
+                let x2 = b.x.copy_advice(|| "x2", &mut region, advice[2], 0)?;
+                let y2 = b.y.copy_advice(|| "y2", &mut region, advice[3], 0)?;
+
+                let (x1, y1) = (x1.value().copied(), y1.value().copied());
+                let (x2, y2) = (x2.value().copied(), y2.value().copied());
+                let inv = (x2 - x1).map(|dx| dx.invert().unwrap_or(pallas::Base::zero()));
+                let lambda = (y2 - y1) * inv;
+                let x3 = lambda * lambda - x1 - x2;
+                let y3 = lambda * (x1 - x3) - y1;
+
+                region.assign_advice(|| "lambda", advice[4], 0, || lambda)?;
+                region.assign_advice(|| "inv", advice[5], 0, || inv)?;
+                Ok(EccPoint {
+                    x: region.assign_advice(|| "x3", advice[0], 1, || x3)?,
+                    y: region.assign_advice(|| "y3", advice[1], 1, || y3)?,
+                })
+            },
+        )
+    }
+
+    // e·P, for e a base field element read as a scalar.
+    pub fn mul(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        e: &AssignedCell<pallas::Base, pallas::Base>,
+        p: &EccPoint,
+    ) -> Result<EccPoint, Error> {
+        let config = &self.config;
+        let advice = config.advice;
+        let o = self.constant_point(layouter.namespace(|| "O"), hash_to_curve(b"O"))?;
+        let p_plus_o = self.add(layouter.namespace(|| "P + O"), p, &o)?;
+        let (ox, oy) = coordinates(hash_to_curve(b"O").to_affine());
+
+        let bits = e
+            .value()
+            .map(|e| {
+                let repr = e.to_repr();
+                (0..CHALLENGE_BITS)
+                    .rev()
+                    .map(|i| (repr[i / 8] >> (i % 8)) & 1 == 1)
+                    .collect::<Vec<_>>()
+            })
+            .transpose_vec(CHALLENGE_BITS);
+
+        let acc = layouter.assign_region(
+            || "double and add",
+            |mut region| {
+                let (tx, ty) = coordinates(hash_to_curve(b"T").to_affine());
+                let mut x = region.assign_advice_from_constant(|| "x", advice[0], 0, tx)?;
+                let mut y = region.assign_advice_from_constant(|| "y", advice[1], 0, ty)?;
+                let mut sum = region.assign_advice_from_constant(
+                    || "e",
+                    advice[10],
+                    0,
+                    pallas::Base::zero(),
+                )?;
+
+                for (offset, bit) in bits.iter().enumerate() {
+                    config.q_mul.enable(&mut region, offset)?;
+                    let bit = bit.map(|bit| pallas::Base::from(bit as u64));
+                    region.assign_advice(|| "bit", advice[2], offset, || bit)?;
+                    let px = p_plus_o
+                        .x
+                        .copy_advice(|| "px", &mut region, advice[8], offset)?;
+                    let py = p_plus_o
+                        .y
+                        .copy_advice(|| "py", &mut region, advice[9], offset)?;
+
+                    let (x0, y0) = (x.value().copied(), y.value().copied());
+                    let l1 = (x0 * x0 * Value::known(pallas::Base::from(3)))
+                        * (y0 + y0).map(|y| y.invert().unwrap_or(pallas::Base::zero()));
+                    let xd = l1 * l1 - x0 - x0;
+                    let yd = l1 * (x0 - xd) - y0;
+                    let qx = bit.zip(px.value()).map(|(bit, px)| ox + bit * (px - ox));
+                    let qy = bit.zip(py.value()).map(|(bit, py)| oy + bit * (py - oy));
+                    let inv = (qx - xd).map(|dx| dx.invert().unwrap_or(pallas::Base::zero()));
+                    let l2 = (qy - yd) * inv;
+                    let x_next = l2 * l2 - xd - qx;
+                    let y_next = l2 * (xd - x_next) - yd;
+                    let sum_next = sum.value().copied() * Value::known(pallas::Base::from(2)) + bit;
+
+                    region.assign_advice(|| "l1", advice[3], offset, || l1)?;
+                    region.assign_advice(|| "xd", advice[4], offset, || xd)?;
+                    region.assign_advice(|| "yd", advice[5], offset, || yd)?;
+                    region.assign_advice(|| "l2", advice[6], offset, || l2)?;
+                    region.assign_advice(|| "inv", advice[7], offset, || inv)?;
+                    x = region.assign_advice(|| "x", advice[0], offset + 1, || x_next)?;
+                    y = region.assign_advice(|| "y", advice[1], offset + 1, || y_next)?;
+                    sum = region.assign_advice(|| "e", advice[10], offset + 1, || sum_next)?;
+                }
+                region.constrain_equal(sum.cell(), e.cell())?;
+                Ok(EccPoint { x, y })
+            },
+        )?;
+
+        let correction = self.constant_point(layouter.namespace(|| "-offset"), -mul_offset())?;
+        self.add(layouter.namespace(|| "remove offset"), &acc, &correction)
+    }
+
+    // Checks `signature` is the key's signature on `message`.
+    pub fn verify(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        public_key: &EccPoint,
+        message: &AssignedCell<pallas::Base, pallas::Base>,
+        signature: Value<Signature>,
+    ) -> Result<(), Error> {
+        let config = &self.config;
+        let r = self.witness_point(layouter.namespace(|| "R"), signature.map(|sig| sig.r))?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon.clone());
+        let message = [&r.x, &r.y, &public_key.x, &public_key.y, message].map(|cell| cell.clone());
+        let e = poseidon.hash(layouter.namespace(|| "challenge"), &message)?;
+        let e_p = self.mul(layouter.namespace(|| "e·P"), &e, public_key)?;
+        let rhs = self.add(layouter.namespace(|| "R + e·P"), &r, &e_p)?;
+
+        let zero = layouter.assign_region(
+            || "zero",
+            |mut region| {
+                region.assign_advice_from_constant(
+                    || "0",
+                    config.advice[0],
+                    0,
+                    pallas::Base::zero(),
+                )
+            },
+        )?;
+        let pedersen = PedersenChip::construct(config.pedersen.clone());
+        let lhs = pedersen.commit(
+            layouter.namespace(|| "s·H"),
+            &zero,
+            signature.map(|sig| sig.s),
+        )?;
+
+        layouter.assign_region(
+            || "s·H = R + e·P",
+            |mut region| {
+                let x = lhs
+                    .x
+                    .copy_advice(|| "x", &mut region, config.advice[0], 0)?;
+                let y = lhs
+                    .y
+                    .copy_advice(|| "y", &mut region, config.advice[1], 0)?;
+                region.constrain_equal(x.cell(), rhs.x.cell())?;
+                region.constrain_equal(y.cell(), rhs.y.cell())
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::dev::MockProver;
+
+    // Checks a signature on the message in the instance column, under the key
+    // after it.
+    #[derive(Default)]
+    struct MyCircuit {
+        signature: Value<Signature>,
+    }
+
+    impl Circuit<pallas::Base> for MyCircuit {
+        type Config = (SchnorrConfig, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let advice = [(); 11].map(|_| meta.advice_column());
+            let fixed = [(); 5].map(|_| meta.fixed_column());
+            let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+            let constants = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            let pedersen = PedersenChip::configure(
+                meta,
+                [
+                    advice[0], advice[1], advice[2], advice[3], advice[4], advice[5],
+                ],
+                fixed,
+                constants,
+            );
+            let poseidon =
+                PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+            (
+                SchnorrChip::configure(meta, advice, pedersen, poseidon),
+                instance,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            let [message, x, y] = layouter.assign_region(
+                || "public",
+                |mut region| {
+                    let mut cells = vec![];
+                    for row in 0..3 {
+                        cells.push(region.assign_advice_from_instance(
+                            || "public",
+                            instance,
+                            row,
+                            config.advice[row],
+                            0,
+                        )?);
+                    }
+                    Ok(cells.try_into().unwrap())
+                },
+            )?;
+
+            let chip = SchnorrChip::construct(config);
+            chip.verify(
+                layouter.namespace(|| "verify"),
+                &EccPoint { x, y },
+                &message,
+                self.signature,
+            )
+        }
+    }
+
+    fn run(public_key: pallas::Affine, message: pallas::Base, signature: Signature) -> bool {
+        let circuit = MyCircuit {
+            signature: Value::known(signature),
+        };
+        let (x, y) = coordinates(public_key);
+        let prover = MockProver::run(11, &circuit, vec![vec![message, x, y]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_schnorr() {
+        let secret = pallas::Scalar::from(0x5ec2e7);
+        let message = pallas::Base::from(42);
+        let signature = sign(secret, message, -pallas::Scalar::from(0x4e11ce));
+        let key = public_key(secret);
+        assert!(verify(key, message, &signature));
+        assert!(run(key, message, signature));
+
+        // another message, another key, and a tampered response
+        assert!(!run(key, message + pallas::Base::one(), signature));
+        let other = public_key(secret + pallas::Scalar::one());
+        assert!(!run(other, message, signature));
+        let tampered = Signature {
+            s: signature.s + pallas::Scalar::one(),
+            ..signature
+        };
+        assert!(!run(key, message, tampered));
+    }
+}
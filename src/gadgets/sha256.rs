@@ -0,0 +1,384 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use super::{
+    range_check::RangeCheckConfig,
+    uint32::{AssignedU32, U32Chip, U32Config},
+};
+
+pub const IV: [u32; 8] = [
+    0x6a09_e667,
+    0xbb67_ae85,
+    0x3c6e_f372,
+    0xa54f_f53a,
+    0x510e_527f,
+    0x9b05_688c,
+    0x1f83_d9ab,
+    0x5be0_cd19,
+];
+
+const K: [u32; 64] = [
+    0x428a_2f98,
+    0x7137_4491,
+    0xb5c0_fbcf,
+    0xe9b5_dba5,
+    0x3956_c25b,
+    0x59f1_11f1,
+    0x923f_82a4,
+    0xab1c_5ed5,
+    0xd807_aa98,
+    0x1283_5b01,
+    0x2431_85be,
+    0x550c_7dc3,
+    0x72be_5d74,
+    0x80de_b1fe,
+    0x9bdc_06a7,
+    0xc19b_f174,
+    0xe49b_69c1,
+    0xefbe_4786,
+    0x0fc1_9dc6,
+    0x240c_a1cc,
+    0x2de9_2c6f,
+    0x4a74_84aa,
+    0x5cb0_a9dc,
+    0x76f9_88da,
+    0x983e_5152,
+    0xa831_c66d,
+    0xb003_27c8,
+    0xbf59_7fc7,
+    0xc6e0_0bf3,
+    0xd5a7_9147,
+    0x06ca_6351,
+    0x1429_2967,
+    0x27b7_0a85,
+    0x2e1b_2138,
+    0x4d2c_6dfc,
+    0x5338_0d13,
+    0x650a_7354,
+    0x766a_0abb,
+    0x81c2_c92e,
+    0x9272_2c85,
+    0xa2bf_e8a1,
+    0xa81a_664b,
+    0xc24b_8b70,
+    0xc76c_51a3,
+    0xd192_e819,
+    0xd699_0624,
+    0xf40e_3585,
+    0x106a_a070,
+    0x19a4_c116,
+    0x1e37_6c08,
+    0x2748_774c,
+    0x34b0_bcb5,
+    0x391c_0cb3,
+    0x4ed8_aa4a,
+    0x5b9c_ca4f,
+    0x682e_6ff3,
+    0x748f_82ee,
+    0x78a5_636f,
+    0x84c8_7814,
+    0x8cc7_0208,
+    0x90be_fffa,
+    0xa450_6ceb,
+    0xbef9_a3f7,
+    0xc671_78f2,
+];
+
+pub const BLOCK_BYTES: usize = 64;
+
+// Pads a message into big endian 16 word blocks as FIPS 180-4 section 5.1.1
+// does: a 1 bit, zeros, then the length in bits.
+pub fn pad(message: &[u8]) -> Vec<[u32; 16]> {
+    let mut bytes = message.to_vec();
+    bytes.push(0x80);
+    while bytes.len() % BLOCK_BYTES != BLOCK_BYTES - 8 {
+        bytes.push(0);
+    }
+    bytes.extend(((message.len() as u64) * 8).to_be_bytes());
+    bytes
+        .chunks(BLOCK_BYTES)
+        .map(|block| {
+            std::array::from_fn(|i| u32::from_be_bytes(block[4 * i..4 * i + 4].try_into().unwrap()))
+        })
+        .collect()
+}
+
+pub fn compress(h: [u32; 8], block: [u32; 16]) -> [u32; 8] {
+    let mut w = block.to_vec();
+    for t in 16..64 {
+        let s0 = w[t - 15].rotate_right(7) ^ w[t - 15].rotate_right(18) ^ (w[t - 15] >> 3);
+        let s1 = w[t - 2].rotate_right(17) ^ w[t - 2].rotate_right(19) ^ (w[t - 2] >> 10);
+        w.push(
+            w[t - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[t - 7])
+                .wrapping_add(s1),
+        );
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+    for t in 0..64 {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = hh
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(K[t])
+            .wrapping_add(w[t]);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+        (hh, g, f, e, d, c, b, a) = (g, f, e, d.wrapping_add(t1), c, b, a, t1.wrapping_add(t2));
+    }
+
+    let v = [a, b, c, d, e, f, g, hh];
+    std::array::from_fn(|i| h[i].wrapping_add(v[i]))
+}
+
+pub fn sha256(message: &[u8]) -> [u8; 32] {
+    let h = pad(message).into_iter().fold(IV, compress);
+    let mut digest = [0; 32];
+    for (chunk, word) in digest.chunks_mut(4).zip(h) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+#[derive(Debug, Clone)]
+pub struct Sha256Config {
+    pub u32: U32Config,
+}
+
+// The SHA-256 compression function out of `U32Chip` words. Ch and Maj are
+// written with xor and and only, as g ^ (e & (f ^ g)) and
+// (a & b) ^ (c & (a ^ b)). Every bitwise operation decomposes its words into
+// bits, so a block is around 50k rows.
+#[derive(Debug, Clone)]
+pub struct Sha256Chip<F: FieldExt> {
+    u32: U32Chip<F>,
+}
+
+impl<F: FieldExt> Sha256Chip<F> {
+    pub fn construct(config: Sha256Config) -> Self {
+        Self {
+            u32: U32Chip::construct(config.u32),
+        }
+    }
+
+    // `constants` holds the IV and round constants.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 6],
+        constants: Column<Fixed>,
+        range: RangeCheckConfig,
+    ) -> Sha256Config {
+        meta.enable_constant(constants);
+        Sha256Config {
+            u32: U32Chip::configure(meta, advice, range),
+        }
+    }
+
+    pub fn u32(&self) -> &U32Chip<F> {
+        &self.u32
+    }
+
+    pub fn iv(&self, mut layouter: impl Layouter<F>) -> Result<[AssignedU32<F>; 8], Error> {
+        let mut h = vec![];
+        for word in IV {
+            h.push(self.u32.constant(layouter.namespace(|| "iv"), word)?);
+        }
+        Ok(h.try_into().unwrap())
+    }
+
+    fn sum(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        words: &[&AssignedU32<F>],
+    ) -> Result<AssignedU32<F>, Error> {
+        let mut sum = words[0].clone();
+        for word in &words[1..] {
+            sum = self.u32.add(layouter.namespace(|| "add"), &sum, word)?;
+        }
+        Ok(sum)
+    }
+
+    // rotr(x, r0) ^ rotr(x, r1) ^ (x >> shift, or rotr(x, r2) with no shift)
+    fn sigma(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        x: &AssignedU32<F>,
+        [r0, r1, r2]: [u32; 3],
+        shift: bool,
+    ) -> Result<AssignedU32<F>, Error> {
+        let u32 = &self.u32;
+        let x0 = u32.rotate_right(layouter.namespace(|| "rotr"), x, r0)?;
+        let x1 = u32.rotate_right(layouter.namespace(|| "rotr"), x, r1)?;
+        let x2 = match shift {
+            true => u32.shift_right(layouter.namespace(|| "shr"), x, r2)?,
+            false => u32.rotate_right(layouter.namespace(|| "rotr"), x, r2)?,
+        };
+        let y = u32.xor(layouter.namespace(|| "xor"), &x0, &x1)?;
+        u32.xor(layouter.namespace(|| "xor"), &y, &x2)
+    }
+
+    pub fn compress(
+        &self,
+        mut layouter: impl Layouter<F>,
+        h: &[AssignedU32<F>; 8],
+        block: &[AssignedU32<F>; 16],
+    ) -> Result<[AssignedU32<F>; 8], Error> {
+        let u32 = &self.u32;
+
+        let mut w = block.to_vec();
+        for t in 16..64 {
+            let mut layouter = layouter.namespace(|| format!("schedule {}", t));
+            let s0 = self.sigma(&mut layouter, &w[t - 15], [7, 18, 3], true)?;
+            let s1 = self.sigma(&mut layouter, &w[t - 2], [17, 19, 10], true)?;
+            let word = self.sum(&mut layouter, &[&w[t - 16], &s0, &w[t - 7], &s1])?;
+            w.push(word);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h.clone();
+        for (t, w) in w.iter().enumerate() {
+            let mut layouter = layouter.namespace(|| format!("round {}", t));
+            let s1 = self.sigma(&mut layouter, &e, [6, 11, 25], false)?;
+            let fg = u32.xor(layouter.namespace(|| "f ^ g"), &f, &g)?;
+            let ch = u32.and(layouter.namespace(|| "e & (f ^ g)"), &e, &fg)?;
+            let ch = u32.xor(layouter.namespace(|| "ch"), &g, &ch)?;
+            let k = u32.constant(layouter.namespace(|| "k"), K[t])?;
+            let t1 = self.sum(&mut layouter, &[&hh, &s1, &ch, &k, w])?;
+
+            let s0 = self.sigma(&mut layouter, &a, [2, 13, 22], false)?;
+            let ab = u32.and(layouter.namespace(|| "a & b"), &a, &b)?;
+            let a_b = u32.xor(layouter.namespace(|| "a ^ b"), &a, &b)?;
+            let maj = u32.and(layouter.namespace(|| "c & (a ^ b)"), &c, &a_b)?;
+            let maj = u32.xor(layouter.namespace(|| "maj"), &ab, &maj)?;
+            let t2 = u32.add(layouter.namespace(|| "t2"), &s0, &maj)?;
+
+            hh = g;
+            g = f;
+            f = e;
+            e = u32.add(layouter.namespace(|| "e"), &d, &t1)?;
+            d = c;
+            c = b;
+            b = a;
+            a = u32.add(layouter.namespace(|| "a"), &t1, &t2)?;
+        }
+
+        let v = [a, b, c, d, e, f, g, hh];
+        let mut out = vec![];
+        for (h, v) in h.iter().zip(v.iter()) {
+            out.push(u32.add(layouter.namespace(|| "h + v"), h, v)?);
+        }
+        Ok(out.try_into().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::{range_check::RangeCheckChip, range_table::RangeTableConfig};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    // Hashes one padded block from the instance column, exposing the digest
+    // words after it.
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        _marker: std::marker::PhantomData<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = (Sha256Config, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 6].map(|_| meta.advice_column());
+            let constants = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            let bytes = RangeTableConfig::configure(meta, 0, 255);
+            let range = RangeCheckChip::configure(meta, [advice[4], advice[5]], bytes);
+            (
+                Sha256Chip::configure(meta, advice, constants, range),
+                instance,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            RangeCheckChip::construct(config.u32.range.clone()).load(&mut layouter)?;
+            let u32 = U32Chip::construct(config.u32.clone());
+            let chip = Sha256Chip::construct(config.clone());
+
+            let mut block = vec![];
+            for row in 0..16 {
+                let cell = layouter.assign_region(
+                    || "block",
+                    |mut region| {
+                        region.assign_advice_from_instance(
+                            || "word",
+                            instance,
+                            row,
+                            config.u32.advice[0],
+                            0,
+                        )
+                    },
+                )?;
+                block.push(u32.check(layouter.namespace(|| "word"), cell)?);
+            }
+
+            let iv = chip.iv(layouter.namespace(|| "iv"))?;
+            let digest = chip.compress(
+                layouter.namespace(|| "compress"),
+                &iv,
+                &block.try_into().unwrap(),
+            )?;
+            for (row, word) in digest.iter().enumerate() {
+                layouter.constrain_instance(word.cell(), instance, 16 + row)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_sha256() {
+        // FIPS 180-4 example
+        let digest: String = sha256(b"abc")
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        assert_eq!(
+            digest,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(pad(&[0; 56]).len(), 2);
+    }
+
+    #[test]
+    fn test_sha256_compress() {
+        let _guard = crate::testing::heavy_test();
+        let block = pad(b"abc")[0];
+        let verify = |digest: [u32; 8]| {
+            let instance = block
+                .iter()
+                .chain(digest.iter())
+                .map(|w| Fp::from(*w as u64))
+                .collect();
+            let prover = MockProver::run(16, &MyCircuit::default(), vec![instance]).unwrap();
+            prover.verify().is_ok()
+        };
+
+        let digest = compress(IV, block);
+        assert!(verify(digest));
+        let mut wrong = digest;
+        wrong[7] ^= 1;
+        assert!(!verify(wrong));
+    }
+}
@@ -0,0 +1,189 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use super::multiset::{MultisetChip, MultisetConfig};
+
+// How `ShuffleChip` proves column B is a permutation of column A.
+#[derive(Debug, Clone)]
+pub enum ShuffleStrategy<F: FieldExt> {
+    // Copy constraints b_i = a_sigma(i), checked by halo2's own permutation
+    // argument for free. sigma ends up in the proving key though, so every
+    // proof uses the same, public, permutation.
+    Permutation(Vec<usize>),
+    // A grand product over a challenge, which works for any permutation and
+    // keeps it private, at the cost of the hash and a row per value.
+    GrandProduct(MultisetConfig<F>),
+}
+
+#[derive(Debug, Clone)]
+pub struct ShuffleConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 2],
+    pub strategy: ShuffleStrategy<F>,
+}
+
+// Example chip for the two ways of proving a shuffle, side by side.
+#[derive(Debug, Clone)]
+pub struct ShuffleChip<F: FieldExt> {
+    config: ShuffleConfig<F>,
+}
+
+impl<F: FieldExt> ShuffleChip<F> {
+    pub fn construct(config: ShuffleConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 2],
+        strategy: ShuffleStrategy<F>,
+    ) -> ShuffleConfig<F> {
+        if let ShuffleStrategy::Permutation(sigma) = &strategy {
+            let mut sorted = sigma.clone();
+            sorted.sort_unstable();
+            assert!(sorted.iter().copied().eq(0..sigma.len()));
+        }
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        ShuffleConfig { advice, strategy }
+    }
+
+    // Assigns columns A and B side by side and constrains B to be a shuffle
+    // of A. Returns the cells of both.
+    #[allow(clippy::type_complexity)]
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &[Value<F>],
+        b: &[Value<F>],
+    ) -> Result<(Vec<AssignedCell<F, F>>, Vec<AssignedCell<F, F>>), Error> {
+        assert_eq!(a.len(), b.len());
+        let [col_a, col_b] = self.config.advice;
+
+        let (a, b) = layouter.assign_region(
+            || "columns",
+            |mut region| {
+                let mut cells_a = vec![];
+                let mut cells_b = vec![];
+                for (offset, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+                    cells_a.push(region.assign_advice(|| "a", col_a, offset, || *a)?);
+                    cells_b.push(region.assign_advice(|| "b", col_b, offset, || *b)?);
+                }
+                Ok((cells_a, cells_b))
+            },
+        )?;
+
+        self.assert_shuffle(layouter.namespace(|| "shuffle"), &a, &b)?;
+        Ok((a, b))
+    }
+
+    pub fn assert_shuffle(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &[AssignedCell<F, F>],
+        b: &[AssignedCell<F, F>],
+    ) -> Result<(), Error> {
+        assert_eq!(a.len(), b.len());
+
+        match &self.config.strategy {
+            ShuffleStrategy::Permutation(sigma) => {
+                assert_eq!(sigma.len(), a.len());
+                layouter.assign_region(
+                    || "copy constraints",
+                    |mut region| {
+                        for (b, i) in b.iter().zip(sigma.iter()) {
+                            region.constrain_equal(b.cell(), a[*i].cell())?;
+                        }
+                        Ok(())
+                    },
+                )
+            }
+            ShuffleStrategy::GrandProduct(multiset) => {
+                let multiset = MultisetChip::construct(multiset.clone());
+                multiset.assert_equal(layouter.namespace(|| "grand product"), a, b)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::poseidon::{self, PoseidonChip};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    // b_i = a_SIGMA[i]
+    const SIGMA: [usize; 5] = [2, 0, 4, 1, 3];
+
+    #[derive(Default)]
+    struct MyCircuit<F, const GRAND_PRODUCT: bool> {
+        a: Vec<Value<F>>,
+        b: Vec<Value<F>>,
+    }
+
+    impl<F: FieldExt, const GRAND_PRODUCT: bool> Circuit<F> for MyCircuit<F, GRAND_PRODUCT> {
+        type Config = ShuffleConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                a: vec![Value::unknown(); self.a.len()],
+                b: vec![Value::unknown(); self.b.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 5].map(|_| meta.advice_column());
+            let strategy = if GRAND_PRODUCT {
+                let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+                let constants = meta.fixed_column();
+                let poseidon =
+                    PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+                ShuffleStrategy::GrandProduct(MultisetChip::configure(meta, advice, poseidon))
+            } else {
+                ShuffleStrategy::Permutation(SIGMA.to_vec())
+            };
+            ShuffleChip::configure(meta, [advice[0], advice[1]], strategy)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = ShuffleChip::construct(config);
+            chip.assign(layouter.namespace(|| "shuffle"), &self.a, &self.b)?;
+            Ok(())
+        }
+    }
+
+    fn verify<const GRAND_PRODUCT: bool>(a: &[u64], b: &[u64]) -> bool {
+        let values = |list: &[u64]| list.iter().map(|v| Value::known(Fp::from(*v))).collect();
+        let circuit = MyCircuit::<Fp, GRAND_PRODUCT> {
+            a: values(a),
+            b: values(b),
+        };
+        let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_shuffle() {
+        let a = [10, 20, 30, 40, 50];
+        let shuffled = SIGMA.map(|i| a[i]);
+        let other_shuffle = [50, 40, 30, 20, 10];
+
+        assert!(verify::<false>(&a, &shuffled));
+        assert!(verify::<true>(&a, &shuffled));
+
+        // only the grand product accepts a permutation other than SIGMA
+        assert!(!verify::<false>(&a, &other_shuffle));
+        assert!(verify::<true>(&a, &other_shuffle));
+
+        // and neither accepts something that isn't a permutation
+        let not_shuffled = [30, 10, 50, 20, 20];
+        assert!(!verify::<false>(&a, &not_shuffled));
+        assert!(!verify::<true>(&a, &not_shuffled));
+    }
+}
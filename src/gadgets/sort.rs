@@ -0,0 +1,187 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use super::{
+    compare::{CompareChip, CompareConfig},
+    multiset::{MultisetChip, MultisetConfig},
+    poseidon::PoseidonConfig,
+    range_check::{RangeCheckChip, RangeCheckConfig},
+};
+
+// Sorted values are range checked to this many bytes, so neighbours compare
+// as integers rather than wrapping around the field.
+pub const SORT_BYTES: usize = 8;
+
+#[derive(Debug, Clone)]
+pub struct SortConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 6],
+    pub compare: CompareConfig<SORT_BYTES>,
+    pub multiset: MultisetConfig<F>,
+}
+
+// Outputs the input cells in ascending order: `MultisetChip` proves the output
+// is a permutation of the input and `CompareChip` that every value is at most
+// the next one.
+#[derive(Debug, Clone)]
+pub struct SortChip<F: FieldExt> {
+    config: SortConfig<F>,
+}
+
+impl<F: FieldExt> SortChip<F> {
+    pub fn construct(config: SortConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 6],
+        range: RangeCheckConfig,
+        poseidon: PoseidonConfig<F>,
+    ) -> SortConfig<F> {
+        let compare =
+            CompareChip::configure(meta, [advice[0], advice[1], advice[2], advice[3]], range);
+        let multiset = MultisetChip::configure(
+            meta,
+            [advice[0], advice[1], advice[2], advice[3], advice[4]],
+            poseidon,
+        );
+
+        SortConfig {
+            advice,
+            compare,
+            multiset,
+        }
+    }
+
+    // Returns the cells sorted in ascending order.
+    pub fn sort(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cells: &[AssignedCell<F, F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        assert!(!cells.is_empty());
+        let config = &self.config;
+
+        let sorted = layouter.assign_region(
+            || "sorted",
+            |mut region| {
+                let values: Value<Vec<F>> =
+                    cells.iter().map(|cell| cell.value().copied()).collect();
+                let values = values.map(|mut values| {
+                    values.sort_by_key(|v| v.get_lower_128());
+                    values
+                });
+                values
+                    .transpose_vec(cells.len())
+                    .into_iter()
+                    .enumerate()
+                    .map(|(offset, y)| region.assign_advice(|| "y", config.advice[1], offset, || y))
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+
+        let multiset = MultisetChip::construct(config.multiset.clone());
+        multiset.assert_equal(layouter.namespace(|| "permutation"), cells, &sorted)?;
+
+        let range = RangeCheckChip::construct(config.compare.range.clone());
+        for cell in sorted.iter() {
+            range.range_check::<SORT_BYTES>(layouter.namespace(|| "sort bound"), cell)?;
+        }
+
+        let compare = CompareChip::construct(config.compare.clone());
+        for pair in sorted.windows(2) {
+            compare.assert_le(layouter.namespace(|| "ascending"), &pair[0], &pair[1])?;
+        }
+
+        Ok(sorted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::{
+        poseidon::{self, PoseidonChip},
+        range_table::RangeTableConfig,
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        values: Vec<Value<F>>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = (SortConfig<F>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                values: vec![Value::unknown(); self.values.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 6].map(|_| meta.advice_column());
+            let rc = [(); poseidon::WIDTH].map(|_| meta.fixed_column());
+            let constants = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            let bytes = RangeTableConfig::configure(meta, 0, 255);
+            let range = RangeCheckChip::configure(meta, [advice[4], advice[5]], bytes);
+            let poseidon =
+                PoseidonChip::configure(meta, [advice[0], advice[1], advice[2]], rc, constants);
+            (SortChip::configure(meta, advice, range, poseidon), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            RangeCheckChip::construct(config.compare.range.clone()).load(&mut layouter)?;
+
+            let cells = layouter.assign_region(
+                || "values",
+                |mut region| {
+                    self.values
+                        .iter()
+                        .enumerate()
+                        .map(|(offset, v)| {
+                            region.assign_advice(|| "value", config.advice[0], offset, || *v)
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                },
+            )?;
+
+            let chip = SortChip::construct(config);
+            let sorted = chip.sort(layouter.namespace(|| "sort"), &cells)?;
+            for (row, cell) in sorted.iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_sort() {
+        let k = 10;
+
+        let circuit = MyCircuit {
+            values: [5u64, 1, 4, 1, u64::MAX]
+                .map(|v| Value::known(Fp::from(v)))
+                .to_vec(),
+        };
+
+        let sorted = [1u64, 1, 4, 5, u64::MAX].map(Fp::from).to_vec();
+        let prover = MockProver::run(k, &circuit, vec![sorted]).unwrap();
+        prover.assert_satisfied();
+
+        // a permutation that isn't sorted, and a sorted list that isn't a permutation
+        for public_input in [[1u64, 4, 1, 5, u64::MAX], [1, 2, 4, 5, u64::MAX]] {
+            let public_input = public_input.map(Fp::from).to_vec();
+            let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+}
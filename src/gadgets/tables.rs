@@ -0,0 +1,131 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use super::range_table::RangeTableConfig;
+
+// Lookup tables shared by every gadget in a circuit. Gadgets ask for a range
+// table while the circuit is configured; asking again for the same range hands
+// back the same table column, and `load` fills each table exactly once.
+#[derive(Debug, Clone, Default)]
+pub struct TableRegistry {
+    pub tables: Vec<RangeTableConfig>,
+}
+
+impl TableRegistry {
+    pub fn range<F: FieldExt>(
+        &mut self,
+        meta: &mut ConstraintSystem<F>,
+        lo: u64,
+        hi: u64,
+    ) -> RangeTableConfig {
+        if let Some(table) = self.tables.iter().find(|t| t.lo == lo && t.hi == hi) {
+            return table.clone();
+        }
+        let table = RangeTableConfig::configure(meta, lo, hi);
+        self.tables.push(table.clone());
+        table
+    }
+
+    // The 0..=255 table behind `RangeCheckChip`.
+    pub fn bytes<F: FieldExt>(&mut self, meta: &mut ConstraintSystem<F>) -> RangeTableConfig {
+        self.range(meta, 0, 255)
+    }
+
+    // Total rows the tables take up once loaded.
+    pub fn rows(&self) -> usize {
+        self.tables.iter().map(|t| (t.hi - t.lo + 1) as usize).sum()
+    }
+
+    pub fn load<F: FieldExt>(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        for table in self.tables.iter() {
+            table.load(layouter)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::{
+        compare::{CompareChip, CompareConfig},
+        range_check::{RangeCheckChip, RangeCheckConfig},
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        a: Value<F>,
+        b: Value<F>,
+        digit: Value<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = (
+            TableRegistry,
+            RangeCheckConfig,
+            CompareConfig<2>,
+            RangeCheckConfig,
+        );
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 4].map(|_| meta.advice_column());
+            let mut tables = TableRegistry::default();
+
+            // two gadgets that each want the byte table, and one with its own
+            let bytes = tables.bytes(meta);
+            let range = RangeCheckChip::configure(meta, [advice[0], advice[1]], bytes);
+            let bytes = tables.bytes(meta);
+            let compare_range = RangeCheckChip::configure(meta, [advice[2], advice[3]], bytes);
+            let compare = CompareChip::configure(meta, advice, compare_range);
+            let digits = tables.range(meta, 0, 9);
+            let digit_range = RangeCheckChip::configure(meta, [advice[2], advice[3]], digits);
+
+            (tables, range, compare, digit_range)
+        }
+
+        fn synthesize(
+            &self,
+            (tables, range, compare, digit_range): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            tables.load(&mut layouter)?;
+
+            let range = RangeCheckChip::construct(range);
+            let a = range.witness_checked::<2>(layouter.namespace(|| "a"), self.a)?;
+            let b = range.witness_checked::<2>(layouter.namespace(|| "b"), self.b)?;
+            CompareChip::construct(compare).assert_le(layouter.namespace(|| "a <= b"), &a, &b)?;
+
+            let digit_range = RangeCheckChip::construct(digit_range);
+            digit_range.witness_checked::<1>(layouter.namespace(|| "digit"), self.digit)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_shared_tables() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        let (tables, ..) = MyCircuit::configure(&mut meta);
+        assert_eq!(tables.tables.len(), 2);
+        assert_eq!(tables.rows(), 256 + 10);
+
+        let circuit = MyCircuit {
+            a: Value::known(Fp::from(300)),
+            b: Value::known(Fp::from(301)),
+            digit: Value::known(Fp::from(7)),
+        };
+        let prover = MockProver::run(9, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+
+        let circuit = MyCircuit {
+            digit: Value::known(Fp::from(10)),
+            ..circuit
+        };
+        let prover = MockProver::run(9, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,477 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+use super::range_check::{RangeCheckChip, RangeCheckConfig};
+
+const BITS: usize = 32;
+
+// A cell holding a value below 2^32. Only `U32Chip` makes these, so word
+// oriented code can't hand it a cell nothing has range checked.
+#[derive(Debug, Clone)]
+pub struct AssignedU32<F: FieldExt>(AssignedCell<F, F>);
+
+impl<F: FieldExt> AssignedU32<F> {
+    pub fn cell(&self) -> Cell {
+        self.0.cell()
+    }
+
+    pub fn value(&self) -> Value<u32> {
+        self.0.value().map(|v| v.get_lower_128() as u32)
+    }
+
+    pub fn inner(&self) -> &AssignedCell<F, F> {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct U32Config {
+    // bit and running sum for a, b and c
+    pub advice: [Column<Advice>; 6],
+    pub q_first: [Selector; 3],
+    pub q_run: [Selector; 3],
+    pub q_xor: Selector,
+    pub q_and: Selector,
+    pub q_add: Selector,
+    pub range: RangeCheckConfig,
+}
+
+// 32-bit words with wrapping add, xor, and, rotate and shift right, the
+// operations SHA-256 and Blake are built from. Add takes one row and a range
+// check on the sum. The bitwise operations decompose their words into bits,
+// most significant first, one per row, rebuilding each word as a running sum so
+// the bits are tied to it.
+#[derive(Debug, Clone)]
+pub struct U32Chip<F: FieldExt> {
+    config: U32Config,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> U32Chip<F> {
+    pub fn construct(config: U32Config) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 6],
+        range: RangeCheckConfig,
+    ) -> U32Config {
+        let q_first = [(); 3].map(|_| meta.selector());
+        let q_run = [(); 3].map(|_| meta.selector());
+        let q_xor = meta.selector();
+        let q_and = meta.selector();
+        let q_add = meta.selector();
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        for (i, (q_first, q_run)) in q_first.iter().zip(q_run.iter()).enumerate() {
+            let (col_bit, col_acc) = (advice[2 * i], advice[2 * i + 1]);
+            meta.create_gate("first bit", |meta| {
+                //
+                // bit   | acc   | q_first | q_run
+                // b_31    b_31       1
+                // b_30    acc_1               1
+                // ...
+                // b_0     acc_31              1
+                //
+                // acc_i = 2·acc_i-1 + bit, so acc_31 is the word
+                let q_first = meta.query_selector(*q_first);
+                let bit = meta.query_advice(col_bit, Rotation::cur());
+                let acc = meta.query_advice(col_acc, Rotation::cur());
+                let one = Expression::Constant(F::one());
+                vec![
+                    q_first.clone() * bit.clone() * (one - bit.clone()),
+                    q_first * (acc - bit),
+                ]
+            });
+
+            meta.create_gate("running bit", |meta| {
+                let q_run = meta.query_selector(*q_run);
+                let bit = meta.query_advice(col_bit, Rotation::cur());
+                let acc = meta.query_advice(col_acc, Rotation::cur());
+                let acc_prev = meta.query_advice(col_acc, Rotation::prev());
+                let one = Expression::Constant(F::one());
+                let two = Expression::Constant(F::from(2));
+                vec![
+                    q_run.clone() * bit.clone() * (one - bit.clone()),
+                    q_run * (acc - acc_prev * two - bit),
+                ]
+            });
+        }
+
+        meta.create_gate("xor", |meta| {
+            let s = meta.query_selector(q_xor);
+            let [a, b, c] = [0, 2, 4].map(|i| meta.query_advice(advice[i], Rotation::cur()));
+            let two = Expression::Constant(F::from(2));
+            vec![s * (a.clone() + b.clone() - two * a * b - c)]
+        });
+
+        meta.create_gate("and", |meta| {
+            let s = meta.query_selector(q_and);
+            let [a, b, c] = [0, 2, 4].map(|i| meta.query_advice(advice[i], Rotation::cur()));
+            vec![s * (a * b - c)]
+        });
+
+        meta.create_gate("add", |meta| {
+            //
+            // advice[0] | advice[1] | advice[2] | advice[3] | q_add
+            //     a           b           c         carry       1
+            //
+            let s = meta.query_selector(q_add);
+            let [a, b, c, carry] =
+                [0, 1, 2, 3].map(|i| meta.query_advice(advice[i], Rotation::cur()));
+            let one = Expression::Constant(F::one());
+            let word = Expression::Constant(F::from_u128(1 << BITS));
+            vec![
+                s.clone() * carry.clone() * (one - carry.clone()),
+                s * (a + b - c - carry * word),
+            ]
+        });
+
+        U32Config {
+            advice,
+            q_first,
+            q_run,
+            q_xor,
+            q_and,
+            q_add,
+            range,
+        }
+    }
+
+    // Range checks a cell to 32 bits.
+    pub fn check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: AssignedCell<F, F>,
+    ) -> Result<AssignedU32<F>, Error> {
+        let range = RangeCheckChip::construct(self.config.range.clone());
+        range.range_check::<4>(layouter.namespace(|| "u32"), &cell)?;
+        Ok(AssignedU32(cell))
+    }
+
+    pub fn witness(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<u32>,
+    ) -> Result<AssignedU32<F>, Error> {
+        let cell = layouter.assign_region(
+            || "witness",
+            |mut region| {
+                region.assign_advice(
+                    || "value",
+                    self.config.advice[0],
+                    0,
+                    || value.map(|v| F::from(v as u64)),
+                )
+            },
+        )?;
+        self.check(layouter, cell)
+    }
+
+    // A fixed word, such as a round constant. The circuit has to have enabled a
+    // constants column for it.
+    pub fn constant(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: u32,
+    ) -> Result<AssignedU32<F>, Error> {
+        let cell = layouter.assign_region(
+            || "constant",
+            |mut region| {
+                region.assign_advice_from_constant(
+                    || "value",
+                    self.config.advice[0],
+                    0,
+                    F::from(value as u64),
+                )
+            },
+        )?;
+        Ok(AssignedU32(cell))
+    }
+
+    // a + b mod 2^32
+    pub fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedU32<F>,
+        b: &AssignedU32<F>,
+    ) -> Result<AssignedU32<F>, Error> {
+        let config = &self.config;
+        let sum = a.value().zip(b.value()).map(|(a, b)| a as u64 + b as u64);
+
+        let c = layouter.assign_region(
+            || "add",
+            |mut region| {
+                config.q_add.enable(&mut region, 0)?;
+
+                a.0.copy_advice(|| "a", &mut region, config.advice[0], 0)?;
+                b.0.copy_advice(|| "b", &mut region, config.advice[1], 0)?;
+                region.assign_advice(
+                    || "carry",
+                    config.advice[3],
+                    0,
+                    || sum.map(|s| F::from(s >> BITS)),
+                )?;
+                region.assign_advice(
+                    || "c",
+                    config.advice[2],
+                    0,
+                    || sum.map(|s| F::from(s as u32 as u64)),
+                )
+            },
+        )?;
+
+        // with the carry a bit, this is what makes c the wrapped sum
+        self.check(layouter, c)
+    }
+
+    pub fn xor(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedU32<F>,
+        b: &AssignedU32<F>,
+    ) -> Result<AssignedU32<F>, Error> {
+        let c = a.value().zip(b.value()).map(|(a, b)| a ^ b);
+        self.bitwise(layouter, "xor", self.config.q_xor, a, b, c)
+    }
+
+    pub fn and(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedU32<F>,
+        b: &AssignedU32<F>,
+    ) -> Result<AssignedU32<F>, Error> {
+        let c = a.value().zip(b.value()).map(|(a, b)| a & b);
+        self.bitwise(layouter, "and", self.config.q_and, a, b, c)
+    }
+
+    // Decomposes a, b and c side by side with `selector` relating their bits.
+    fn bitwise(
+        &self,
+        mut layouter: impl Layouter<F>,
+        name: &str,
+        selector: Selector,
+        a: &AssignedU32<F>,
+        b: &AssignedU32<F>,
+        c: Value<u32>,
+    ) -> Result<AssignedU32<F>, Error> {
+        layouter.assign_region(
+            || name,
+            |mut region| {
+                for offset in 0..BITS {
+                    selector.enable(&mut region, offset)?;
+                }
+                self.decompose(&mut region, 0, a.value(), Some(&a.0))?;
+                self.decompose(&mut region, 1, b.value(), Some(&b.0))?;
+                let (_, c) = self.decompose(&mut region, 2, c, None)?;
+                Ok(AssignedU32(c))
+            },
+        )
+    }
+
+    // a rotated right by n bits
+    pub fn rotate_right(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedU32<F>,
+        n: u32,
+    ) -> Result<AssignedU32<F>, Error> {
+        let n = n as usize % BITS;
+        let c = a.value().map(|a| a.rotate_right(n as u32));
+
+        layouter.assign_region(
+            || format!("rotate right {}", n),
+            |mut region| {
+                let (a_bits, _) = self.decompose(&mut region, 0, a.value(), Some(&a.0))?;
+                let (c_bits, c) = self.decompose(&mut region, 2, c, None)?;
+                // row r holds bit 31 - r, and bit j of c is bit j + n of a
+                for (r, c_bit) in c_bits.iter().enumerate() {
+                    let j = BITS - 1 - r;
+                    let a_bit = &a_bits[BITS - 1 - (j + n) % BITS];
+                    region.constrain_equal(c_bit.cell(), a_bit.cell())?;
+                }
+                Ok(AssignedU32(c))
+            },
+        )
+    }
+
+    // a shifted right by n bits. The bits shifted in are constrained to the
+    // constant 0, so the circuit has to have enabled a constants column.
+    pub fn shift_right(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedU32<F>,
+        n: u32,
+    ) -> Result<AssignedU32<F>, Error> {
+        let n = n as usize;
+        assert!(n < BITS);
+        let c = a.value().map(|a| a >> n);
+
+        layouter.assign_region(
+            || format!("shift right {}", n),
+            |mut region| {
+                let (a_bits, _) = self.decompose(&mut region, 0, a.value(), Some(&a.0))?;
+                let (c_bits, c) = self.decompose(&mut region, 2, c, None)?;
+                // the top n bits of c are 0 and the rest are a's, n rows up
+                for (r, c_bit) in c_bits.iter().enumerate() {
+                    if r < n {
+                        region.constrain_constant(c_bit.cell(), F::zero())?;
+                    } else {
+                        region.constrain_equal(c_bit.cell(), a_bits[r - n].cell())?;
+                    }
+                }
+                Ok(AssignedU32(c))
+            },
+        )
+    }
+
+    // Lays out the bits of a word in the i-th pair of columns, returning the
+    // bit cells and the word. `word` is the cell the bits have to add up to,
+    // if there already is one.
+    #[allow(clippy::type_complexity)]
+    fn decompose(
+        &self,
+        region: &mut Region<'_, F>,
+        i: usize,
+        value: Value<u32>,
+        word: Option<&AssignedCell<F, F>>,
+    ) -> Result<(Vec<AssignedCell<F, F>>, AssignedCell<F, F>), Error> {
+        let config = &self.config;
+        let (col_bit, col_acc) = (config.advice[2 * i], config.advice[2 * i + 1]);
+
+        let mut bits = vec![];
+        let mut acc = None;
+        for offset in 0..BITS {
+            if offset == 0 {
+                config.q_first[i].enable(region, offset)?;
+            } else {
+                config.q_run[i].enable(region, offset)?;
+            }
+
+            let shift = BITS - 1 - offset;
+            let bit = value.map(|v| F::from(((v >> shift) & 1) as u64));
+            bits.push(region.assign_advice(|| "bit", col_bit, offset, || bit)?);
+            let sum = value.map(|v| F::from((v >> shift) as u64));
+            acc = Some(region.assign_advice(|| "acc", col_acc, offset, || sum)?);
+        }
+
+        let acc = acc.unwrap();
+        if let Some(word) = word {
+            region.constrain_equal(word.cell(), acc.cell())?;
+        }
+        Ok((bits, acc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::range_table::RangeTableConfig;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        a: Value<F>,
+        b: Value<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = (U32Config, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 6].map(|_| meta.advice_column());
+            let constants = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            meta.enable_constant(constants);
+
+            let bytes = RangeTableConfig::configure(meta, 0, 255);
+            let range = RangeCheckChip::configure(meta, [advice[4], advice[5]], bytes);
+            (U32Chip::configure(meta, advice, range), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            RangeCheckChip::construct(config.range.clone()).load(&mut layouter)?;
+            let chip = U32Chip::construct(config.clone());
+
+            let (a, b) = layouter.assign_region(
+                || "inputs",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", config.advice[0], 0, || self.a)?;
+                    let b = region.assign_advice(|| "b", config.advice[1], 0, || self.b)?;
+                    Ok((a, b))
+                },
+            )?;
+            let a = chip.check(layouter.namespace(|| "a"), a)?;
+            let b = chip.check(layouter.namespace(|| "b"), b)?;
+
+            let results = [
+                chip.add(layouter.namespace(|| "a + b"), &a, &b)?,
+                chip.xor(layouter.namespace(|| "a ^ b"), &a, &b)?,
+                chip.rotate_right(layouter.namespace(|| "a >>> 7"), &a, 7)?,
+                chip.and(layouter.namespace(|| "a & b"), &a, &b)?,
+                chip.shift_right(layouter.namespace(|| "a >> 10"), &a, 10)?,
+            ];
+            for (row, result) in results.iter().enumerate() {
+                layouter.constrain_instance(result.cell(), instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn verify(a: u64, b: u64, results: [u64; 5]) -> bool {
+        let circuit = MyCircuit {
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+        };
+        let instance = results.iter().map(|r| Fp::from(*r)).collect();
+        let prover = MockProver::run(9, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_u32() {
+        for (a, b) in [
+            (0x6a09_e667u32, 0xbb67_ae85u32),
+            (0xffff_ffff, 2),
+            (0, 0),
+            (0x8000_0001, 0xffff_ffff),
+        ] {
+            let results =
+                [a.wrapping_add(b), a ^ b, a.rotate_right(7), a & b, a >> 10].map(|r| r as u64);
+            assert!(verify(a as u64, b as u64, results), "{:#x}, {:#x}", a, b);
+
+            for i in 0..5 {
+                let mut wrong = results;
+                wrong[i] ^= 1 << 31;
+                assert!(!verify(a as u64, b as u64, wrong));
+            }
+        }
+
+        // the sum without wrapping
+        assert!(!verify(
+            0xffff_ffff,
+            2,
+            [1 << 32 | 1, 0xffff_fffd, 0xffff_ffff, 2, 0x3f_ffff]
+        ));
+
+        // inputs have to fit in 32 bits
+        assert!(!verify(1 << 32, 0, [0; 5]));
+    }
+}
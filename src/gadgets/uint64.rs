@@ -0,0 +1,513 @@
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+use super::range_check::{RangeCheckChip, RangeCheckConfig};
+
+const BITS: usize = 64;
+
+// A cell holding a value below 2^64. Only `U64Chip` makes these, so lane
+// oriented code can't hand it a cell nothing has range checked.
+#[derive(Debug, Clone)]
+pub struct AssignedU64<F: FieldExt>(AssignedCell<F, F>);
+
+impl<F: FieldExt> AssignedU64<F> {
+    pub fn cell(&self) -> Cell {
+        self.0.cell()
+    }
+
+    pub fn value(&self) -> Value<u64> {
+        self.0.value().map(|v| v.get_lower_128() as u64)
+    }
+
+    pub fn inner(&self) -> &AssignedCell<F, F> {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct U64Config {
+    // bit and running sum for a, b and c
+    pub advice: [Column<Advice>; 6],
+    pub q_first: [Selector; 3],
+    pub q_run: [Selector; 3],
+    pub q_xor: Selector,
+    pub q_add: Selector,
+    pub range: RangeCheckConfig,
+}
+
+// Addition of 64-bit lanes mod 2^64, as Blake2b does it, with the carry out as
+// a witnessed bit for code that chains lanes into wider integers. Words are
+// range checked as 8 byte limbs. Xor and rotate decompose words into bits the
+// same way `U32Chip` does, 64 rows per word.
+#[derive(Debug, Clone)]
+pub struct U64Chip<F: FieldExt> {
+    config: U64Config,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> U64Chip<F> {
+    pub fn construct(config: U64Config) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 6],
+        range: RangeCheckConfig,
+    ) -> U64Config {
+        let q_first = [(); 3].map(|_| meta.selector());
+        let q_run = [(); 3].map(|_| meta.selector());
+        let q_xor = meta.selector();
+        let q_add = meta.selector();
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        for (i, (q_first, q_run)) in q_first.iter().zip(q_run.iter()).enumerate() {
+            let (col_bit, col_acc) = (advice[2 * i], advice[2 * i + 1]);
+            meta.create_gate("first bit", |meta| {
+                //
+                // bit   | acc   | q_first | q_run
+                // b_63    b_63       1
+                // b_62    acc_1               1
+                // ...
+                // b_0     acc_63              1
+                //
+                let q_first = meta.query_selector(*q_first);
+                let bit = meta.query_advice(col_bit, Rotation::cur());
+                let acc = meta.query_advice(col_acc, Rotation::cur());
+                let one = Expression::Constant(F::one());
+                vec![
+                    q_first.clone() * bit.clone() * (one - bit.clone()),
+                    q_first * (acc - bit),
+                ]
+            });
+
+            meta.create_gate("running bit", |meta| {
+                let q_run = meta.query_selector(*q_run);
+                let bit = meta.query_advice(col_bit, Rotation::cur());
+                let acc = meta.query_advice(col_acc, Rotation::cur());
+                let acc_prev = meta.query_advice(col_acc, Rotation::prev());
+                let one = Expression::Constant(F::one());
+                let two = Expression::Constant(F::from(2));
+                vec![
+                    q_run.clone() * bit.clone() * (one - bit.clone()),
+                    q_run * (acc - acc_prev * two - bit),
+                ]
+            });
+        }
+
+        meta.create_gate("xor", |meta| {
+            let s = meta.query_selector(q_xor);
+            let [a, b, c] = [0, 2, 4].map(|i| meta.query_advice(advice[i], Rotation::cur()));
+            let two = Expression::Constant(F::from(2));
+            vec![s * (a.clone() + b.clone() - two * a * b - c)]
+        });
+
+        meta.create_gate("add with carry", |meta| {
+            //
+            // advice[0] | advice[1] | advice[2] | advice[3] | q_add
+            //     a           b           c         carry       1
+            //
+            // a + b = c + carry·2^64, and c is range checked, so with carry a
+            // bit c is the wrapped sum
+            let s = meta.query_selector(q_add);
+            let [a, b, c, carry] =
+                [0, 1, 2, 3].map(|i| meta.query_advice(advice[i], Rotation::cur()));
+            let one = Expression::Constant(F::one());
+            let word = Expression::Constant(F::from_u128(1 << 64));
+            vec![
+                s.clone() * carry.clone() * (one - carry.clone()),
+                s * (a + b - c - carry * word),
+            ]
+        });
+
+        U64Config {
+            advice,
+            q_first,
+            q_run,
+            q_xor,
+            q_add,
+            range,
+        }
+    }
+
+    // Range checks a cell to 64 bits.
+    pub fn check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: AssignedCell<F, F>,
+    ) -> Result<AssignedU64<F>, Error> {
+        let range = RangeCheckChip::construct(self.config.range.clone());
+        range.range_check::<8>(layouter.namespace(|| "u64"), &cell)?;
+        Ok(AssignedU64(cell))
+    }
+
+    pub fn witness(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<u64>,
+    ) -> Result<AssignedU64<F>, Error> {
+        let cell = layouter.assign_region(
+            || "witness",
+            |mut region| {
+                region.assign_advice(|| "value", self.config.advice[0], 0, || value.map(F::from))
+            },
+        )?;
+        self.check(layouter, cell)
+    }
+
+    // A fixed word, such as an IV. The circuit has to have enabled a constants
+    // column for it.
+    pub fn constant(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: u64,
+    ) -> Result<AssignedU64<F>, Error> {
+        let cell = layouter.assign_region(
+            || "constant",
+            |mut region| {
+                region.assign_advice_from_constant(
+                    || "value",
+                    self.config.advice[0],
+                    0,
+                    F::from(value),
+                )
+            },
+        )?;
+        Ok(AssignedU64(cell))
+    }
+
+    // Returns a + b mod 2^64 and the carry, which is 0 or 1.
+    pub fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedU64<F>,
+        b: &AssignedU64<F>,
+    ) -> Result<(AssignedU64<F>, AssignedCell<F, F>), Error> {
+        let config = &self.config;
+        let sum = a.value().zip(b.value()).map(|(a, b)| a.overflowing_add(b));
+
+        let (c, carry) = layouter.assign_region(
+            || "add",
+            |mut region| {
+                config.q_add.enable(&mut region, 0)?;
+
+                a.0.copy_advice(|| "a", &mut region, config.advice[0], 0)?;
+                b.0.copy_advice(|| "b", &mut region, config.advice[1], 0)?;
+                let c = region.assign_advice(
+                    || "c",
+                    config.advice[2],
+                    0,
+                    || sum.map(|(c, _)| F::from(c)),
+                )?;
+                let carry = region.assign_advice(
+                    || "carry",
+                    config.advice[3],
+                    0,
+                    || sum.map(|(_, carry)| F::from(carry as u64)),
+                )?;
+                Ok((c, carry))
+            },
+        )?;
+
+        Ok((self.check(layouter, c)?, carry))
+    }
+
+    pub fn xor(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedU64<F>,
+        b: &AssignedU64<F>,
+    ) -> Result<AssignedU64<F>, Error> {
+        let config = &self.config;
+        let c = a.value().zip(b.value()).map(|(a, b)| a ^ b);
+
+        layouter.assign_region(
+            || "xor",
+            |mut region| {
+                for offset in 0..BITS {
+                    config.q_xor.enable(&mut region, offset)?;
+                }
+                self.decompose(&mut region, 0, a.value(), Some(&a.0))?;
+                self.decompose(&mut region, 1, b.value(), Some(&b.0))?;
+                let (_, c) = self.decompose(&mut region, 2, c, None)?;
+                Ok(AssignedU64(c))
+            },
+        )
+    }
+
+    // a rotated right by n bits
+    pub fn rotate_right(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedU64<F>,
+        n: u32,
+    ) -> Result<AssignedU64<F>, Error> {
+        let n = n as usize % BITS;
+        let c = a.value().map(|a| a.rotate_right(n as u32));
+
+        layouter.assign_region(
+            || format!("rotate right {}", n),
+            |mut region| {
+                let (a_bits, _) = self.decompose(&mut region, 0, a.value(), Some(&a.0))?;
+                let (c_bits, c) = self.decompose(&mut region, 2, c, None)?;
+                // row r holds bit 63 - r, and bit j of c is bit j + n of a
+                for (r, c_bit) in c_bits.iter().enumerate() {
+                    let j = BITS - 1 - r;
+                    let a_bit = &a_bits[BITS - 1 - (j + n) % BITS];
+                    region.constrain_equal(c_bit.cell(), a_bit.cell())?;
+                }
+                Ok(AssignedU64(c))
+            },
+        )
+    }
+
+    // Lays out the bits of a word in the i-th pair of columns, returning the
+    // bit cells and the word. `word` is the cell the bits have to add up to,
+    // if there already is one.
+    #[allow(clippy::type_complexity)]
+    fn decompose(
+        &self,
+        region: &mut Region<'_, F>,
+        i: usize,
+        value: Value<u64>,
+        word: Option<&AssignedCell<F, F>>,
+    ) -> Result<(Vec<AssignedCell<F, F>>, AssignedCell<F, F>), Error> {
+        let config = &self.config;
+        let (col_bit, col_acc) = (config.advice[2 * i], config.advice[2 * i + 1]);
+
+        let mut bits = vec![];
+        let mut acc = None;
+        for offset in 0..BITS {
+            if offset == 0 {
+                config.q_first[i].enable(region, offset)?;
+            } else {
+                config.q_run[i].enable(region, offset)?;
+            }
+
+            let shift = BITS - 1 - offset;
+            let bit = value.map(|v| F::from((v >> shift) & 1));
+            bits.push(region.assign_advice(|| "bit", col_bit, offset, || bit)?);
+            let sum = value.map(|v| F::from(v >> shift));
+            acc = Some(region.assign_advice(|| "acc", col_acc, offset, || sum)?);
+        }
+
+        let acc = acc.unwrap();
+        if let Some(word) = word {
+            region.constrain_equal(word.cell(), acc.cell())?;
+        }
+        Ok((bits, acc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::range_table::RangeTableConfig;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+    use proptest::prelude::*;
+
+    // Anything, with the edges a carry or a rotation gets wrong first.
+    fn word() -> impl Strategy<Value = u64> {
+        prop_oneof![
+            Just(0),
+            Just(1),
+            Just(1 << 63),
+            Just(u64::MAX),
+            any::<u64>(),
+            any::<u64>(),
+        ]
+    }
+
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        pairs: Vec<(Value<F>, Value<F>)>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+        type Config = (U64Config, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                pairs: vec![(Value::unknown(), Value::unknown()); self.pairs.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 6].map(|_| meta.advice_column());
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            let bytes = RangeTableConfig::configure(meta, 0, 255);
+            let range = RangeCheckChip::configure(meta, [advice[4], advice[5]], bytes);
+            (U64Chip::configure(meta, advice, range), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            RangeCheckChip::construct(config.range.clone()).load(&mut layouter)?;
+            let chip = U64Chip::construct(config.clone());
+
+            for (i, (a, b)) in self.pairs.iter().enumerate() {
+                let (a, b) = layouter.assign_region(
+                    || "inputs",
+                    |mut region| {
+                        let a = region.assign_advice(|| "a", config.advice[0], 0, || *a)?;
+                        let b = region.assign_advice(|| "b", config.advice[1], 0, || *b)?;
+                        Ok((a, b))
+                    },
+                )?;
+                let a = chip.check(layouter.namespace(|| "a"), a)?;
+                let b = chip.check(layouter.namespace(|| "b"), b)?;
+
+                let (c, carry) = chip.add(layouter.namespace(|| "a + b"), &a, &b)?;
+                layouter.constrain_instance(c.cell(), instance, 2 * i)?;
+                layouter.constrain_instance(carry.cell(), instance, 2 * i + 1)?;
+            }
+            Ok(())
+        }
+    }
+
+    // Adds each pair, claiming the given sums and carries.
+    fn verify(pairs: &[(u128, u128)], results: &[(u128, bool)]) -> bool {
+        let circuit = MyCircuit {
+            pairs: pairs
+                .iter()
+                .map(|(a, b)| {
+                    (
+                        Value::known(Fp::from_u128(*a)),
+                        Value::known(Fp::from_u128(*b)),
+                    )
+                })
+                .collect(),
+        };
+        let instance = results
+            .iter()
+            .flat_map(|(c, carry)| [Fp::from_u128(*c), Fp::from(*carry as u64)])
+            .collect();
+        let prover = MockProver::run(11, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_u64_add() {
+        // the same sum with the carry dropped, or folded back into the sum
+        let pair = [(u64::MAX as u128, 2)];
+        assert!(verify(&pair, &[(1, true)]));
+        assert!(!verify(&pair, &[(1, false)]));
+        assert!(!verify(&pair, &[(1 << 64 | 1, false)]));
+
+        // inputs have to fit in 64 bits
+        assert!(!verify(&[(1 << 64, 0)], &[(0, true)]));
+    }
+
+    // Exposes a ^ b and a rotated right by 24 and 63, Blake2b's rotations that
+    // aren't whole bytes apart.
+    #[derive(Default)]
+    struct BitsCircuit<F> {
+        a: Value<F>,
+        b: Value<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for BitsCircuit<F> {
+        type Config = (U64Config, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            MyCircuit::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            RangeCheckChip::construct(config.range.clone()).load(&mut layouter)?;
+            let chip = U64Chip::construct(config.clone());
+
+            let (a, b) = layouter.assign_region(
+                || "inputs",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", config.advice[0], 0, || self.a)?;
+                    let b = region.assign_advice(|| "b", config.advice[1], 0, || self.b)?;
+                    Ok((a, b))
+                },
+            )?;
+            let a = chip.check(layouter.namespace(|| "a"), a)?;
+            let b = chip.check(layouter.namespace(|| "b"), b)?;
+
+            let results = [
+                chip.xor(layouter.namespace(|| "a ^ b"), &a, &b)?,
+                chip.rotate_right(layouter.namespace(|| "a >>> 24"), &a, 24)?,
+                chip.rotate_right(layouter.namespace(|| "a >>> 63"), &a, 63)?,
+            ];
+            for (row, result) in results.iter().enumerate() {
+                layouter.constrain_instance(result.cell(), instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn verify_bits(a: u64, b: u64, results: [u64; 3]) -> bool {
+        let circuit = BitsCircuit {
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+        };
+        let instance = results.iter().map(|r| Fp::from(*r)).collect();
+        let prover = MockProver::run(9, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_u64_bits() {
+        // rotations the wrong way round
+        let (a, b) = (0x0123_4567_89ab_cdef, 1);
+        assert!(!verify_bits(
+            a,
+            b,
+            [a ^ b, a.rotate_left(24), a.rotate_right(63)]
+        ));
+        assert!(!verify_bits(
+            a,
+            b,
+            [a ^ b, a.rotate_right(24), a.rotate_left(63)]
+        ));
+        assert!(!verify_bits(
+            a,
+            b,
+            [a | b, a.rotate_right(24), a.rotate_right(63)]
+        ));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn prop_u64_add(a in word(), b in word()) {
+            let c = a.wrapping_add(b);
+            let carry = (a as u128 + b as u128) >> 64 == 1;
+            let pair = [(a as u128, b as u128)];
+            prop_assert!(verify(&pair, &[(c as u128, carry)]));
+            prop_assert!(!verify(&pair, &[(c as u128, !carry)]));
+        }
+
+        #[test]
+        fn prop_u64_bits(a in word(), b in word()) {
+            let results = [a ^ b, a.rotate_right(24), a.rotate_right(63)];
+            prop_assert!(verify_bits(a, b, results));
+        }
+    }
+}
@@ -0,0 +1,237 @@
+// The JSON API behind `fibo serve`:
+//
+//     POST /prove   { "spec": SPEC, "input": INPUT }
+//                -> { "proof": HEX }
+//     POST /verify  { "spec": SPEC, "input": INPUT, "proof": HEX }
+//                -> { "valid": BOOL }
+//
+// SPEC and INPUT are what the spec and input files hold, e.g.
+// `{ "variant": "example3", "k": 4, "steps": 9 }` and
+// `{ "a": 1, "b": 1, "out": 55, "n_steps": 9 }`. A request that doesn't parse
+// or can't be proved gets a 400 with `{ "error": MESSAGE }`, as does one with
+// a k past `stats::MAX_K`, before any keys are made for it. At most `workers`
+// requests prove or verify at once, on tokio's blocking pool; the rest wait
+// their turn.
+
+use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+use crate::{io::FiboInput, keys::KeyCache, service::ProvingService, spec::CircuitSpec};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProveRequest {
+    pub spec: CircuitSpec,
+    pub input: FiboInput,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct ProveResponse {
+    pub proof: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VerifyRequest {
+    pub spec: CircuitSpec,
+    pub input: FiboInput,
+    pub proof: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct VerifyResponse {
+    pub valid: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+type Rejection = (StatusCode, Json<ErrorResponse>);
+
+fn reject(status: StatusCode, error: impl ToString) -> Rejection {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.to_string(),
+        }),
+    )
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>, String> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return Err("the proof isn't hex".to_string());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| "the proof isn't hex".to_string())
+        })
+        .collect()
+}
+
+#[derive(Clone)]
+struct AppState {
+    service: Arc<ProvingService>,
+    workers: Arc<Semaphore>,
+}
+
+// Runs `f` on the blocking pool once a worker is free.
+async fn run<T: Send + 'static>(
+    state: &AppState,
+    f: impl FnOnce(&ProvingService) -> Result<T, String> + Send + 'static,
+) -> Result<T, Rejection> {
+    let _worker = state
+        .workers
+        .acquire()
+        .await
+        .map_err(|e| reject(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let service = state.service.clone();
+    tokio::task::spawn_blocking(move || f(&service))
+        .await
+        .map_err(|e| reject(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .map_err(|e| reject(StatusCode::BAD_REQUEST, e))
+}
+
+async fn prove(
+    State(state): State<AppState>,
+    Json(request): Json<ProveRequest>,
+) -> Result<Json<ProveResponse>, Rejection> {
+    let proof = run(&state, move |service| {
+        service.prove(&request.spec, &request.input)
+    })
+    .await?;
+    Ok(Json(ProveResponse {
+        proof: to_hex(&proof),
+    }))
+}
+
+async fn verify(
+    State(state): State<AppState>,
+    Json(request): Json<VerifyRequest>,
+) -> Result<Json<VerifyResponse>, Rejection> {
+    let proof = from_hex(&request.proof).map_err(|e| reject(StatusCode::BAD_REQUEST, e))?;
+    let valid = run(&state, move |service| {
+        service.verify(&request.spec, &request.input, &proof)
+    })
+    .await?;
+    Ok(Json(VerifyResponse { valid }))
+}
+
+pub fn router(keys: KeyCache, workers: usize) -> Router {
+    let state = AppState {
+        service: Arc::new(ProvingService::with_cache(keys)),
+        workers: Arc::new(Semaphore::new(workers)),
+    };
+    Router::new()
+        .route("/prove", post(prove))
+        .route("/verify", post(verify))
+        .with_state(state)
+}
+
+// Serves the API on `port` until the process is stopped.
+pub async fn serve(port: u16, workers: usize, keys: KeyCache) -> Result<(), String> {
+    if workers == 0 {
+        return Err("there has to be at least one worker".to_string());
+    }
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port))
+        .await
+        .map_err(|e| format!("can't listen on port {}: {}", port, e))?;
+    axum::serve(listener, router(keys, workers))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(workers: usize) -> AppState {
+        AppState {
+            service: Arc::new(ProvingService::new()),
+            workers: Arc::new(Semaphore::new(workers)),
+        }
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(to_hex(&[0, 1, 0xab, 0xff]), "0001abff");
+        assert_eq!(from_hex("0001abff"), Ok(vec![0, 1, 0xab, 0xff]));
+        assert!(from_hex("abc").is_err());
+        assert!(from_hex("zz").is_err());
+        assert!(from_hex("é0").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_prove_verify() {
+        let _guard = crate::testing::heavy_test();
+        let state = state(2);
+        let spec = r#""spec": { "variant": "example3", "k": 4, "steps": 9 }"#;
+        let input = r#""input": { "a": 1, "b": 1, "out": 55, "n_steps": 9 }"#;
+
+        let request = serde_json::from_str(&format!("{{ {}, {} }}", spec, input)).unwrap();
+        let Json(ProveResponse { proof }) =
+            prove(State(state.clone()), Json(request)).await.unwrap();
+
+        let request = serde_json::from_str(&format!(
+            "{{ {}, {}, \"proof\": \"{}\" }}",
+            spec, input, proof
+        ))
+        .unwrap();
+        let response = verify(State(state.clone()), Json(request)).await.unwrap();
+        assert_eq!(response.0, VerifyResponse { valid: true });
+
+        // a wrong output can't be proved, and isn't what the proof says
+        let wrong = r#""input": { "a": 1, "b": 1, "out": 56, "n_steps": 9 }"#;
+        let request = serde_json::from_str(&format!("{{ {}, {} }}", spec, wrong)).unwrap();
+        let (status, _) = prove(State(state.clone()), Json(request))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let request = serde_json::from_str(&format!(
+            "{{ {}, {}, \"proof\": \"{}\" }}",
+            spec, wrong, proof
+        ))
+        .unwrap();
+        let response = verify(State(state.clone()), Json(request)).await.unwrap();
+        assert_eq!(response.0, VerifyResponse { valid: false });
+
+        // a proof that isn't hex, and an unknown field
+        let request =
+            serde_json::from_str(&format!("{{ {}, {}, \"proof\": \"xy\" }}", spec, input)).unwrap();
+        assert!(verify(State(state), Json(request)).await.is_err());
+        assert!(serde_json::from_str::<ProveRequest>(&format!(
+            "{{ {}, {}, \"proof\": \"00\" }}",
+            spec, input
+        ))
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_k_too_big() {
+        let state = state(1);
+        let spec = r#""spec": { "variant": "example3", "k": 40, "steps": 9 }"#;
+        let input = r#""input": { "a": 1, "b": 1, "out": 55, "n_steps": 9 }"#;
+
+        let request = serde_json::from_str(&format!("{{ {}, {} }}", spec, input)).unwrap();
+        let (status, _) = prove(State(state.clone()), Json(request))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let request =
+            serde_json::from_str(&format!("{{ {}, {}, \"proof\": \"00\" }}", spec, input)).unwrap();
+        let (status, _) = verify(State(state.clone()), Json(request))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(state.service.cached(), 0);
+    }
+}
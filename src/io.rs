@@ -0,0 +1,180 @@
+use halo2_proofs::{
+    circuit::Value,
+    pasta::{EqAffine, Fp},
+    plonk::{keygen_pk, keygen_vk, VerifyingKey},
+    poly::commitment::Params,
+};
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::{
+    batch::{prove_many, verify_many},
+    example1::MyCircuit,
+    fibo::{FiboLayout, FiboPublicInputs, OUT_TERM},
+};
+
+// A Fibonacci run as users hand it to the CLI, e.g.
+//
+//     { "a": 1, "b": 1, "out": 55, "n_steps": 9 }
+//
+// `a` and `b` are the private starting values, `out` the claimed F[n_steps].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FiboInput {
+    pub a: u64,
+    pub b: u64,
+    pub out: u64,
+    pub n_steps: usize,
+}
+
+// The smallest k example1 fits in.
+const K: u32 = 4;
+
+// halo2 at this revision can't serialize its keys, so the prover and the
+// verifier both make them from the circuit. With IPA the parameters need no
+// trusted setup, so they come out the same on both sides.
+fn verifying_key(params: &Params<EqAffine>) -> Result<VerifyingKey<EqAffine>, String> {
+    keygen_vk(params, &MyCircuit::<Fp>::default()).map_err(|e| format!("{:?}", e))
+}
+
+impl FiboInput {
+    // The run of `n_steps` from `a` and `b`, as long as every term fits in a
+    // u64 and the output can be written down.
+    pub fn new(a: u64, b: u64, n_steps: usize) -> Result<Self, String> {
+        let (mut x, mut y) = (a, b);
+        for _ in 0..n_steps {
+            let next = x
+                .checked_add(y)
+                .ok_or_else(|| format!("F[{}] doesn't fit in 64 bits", n_steps))?;
+            (x, y) = (y, next);
+        }
+        Ok(Self {
+            a,
+            b,
+            out: x,
+            n_steps,
+        })
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("bad input: {}", e))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+        Self::from_json(&json)
+    }
+
+    // The public inputs, checked against the circuit: the examples' circuits
+    // are laid out for F[OUT_TERM], so that's the only run length they prove.
+    pub fn public_inputs(&self) -> Result<FiboPublicInputs<Fp>, String> {
+        if self.n_steps != OUT_TERM {
+            return Err(format!(
+                "n_steps is {}, but the circuit proves F[{}]",
+                self.n_steps, OUT_TERM
+            ));
+        }
+        let inputs = FiboPublicInputs {
+            a: Fp::from(self.a),
+            b: Fp::from(self.b),
+            out: Fp::from(self.out),
+        };
+        inputs.validate()?;
+        Ok(inputs)
+    }
+
+    // The example1 circuit for this run, and its single instance column.
+    pub fn circuit(&self) -> Result<(MyCircuit<Fp>, Vec<Vec<Fp>>), String> {
+        let inputs = self.public_inputs()?;
+        let circuit = MyCircuit {
+            a: Value::known(inputs.a),
+            b: Value::known(inputs.b),
+        };
+        Ok((circuit, inputs.to_instances(&FiboLayout::single())))
+    }
+
+    // Proves the run with example1.
+    pub fn prove(&self) -> Result<Vec<u8>, String> {
+        let (circuit, instance) = self.circuit()?;
+        let params = Params::<EqAffine>::new(K);
+        let vk = verifying_key(&params)?;
+        let pk = keygen_pk(&params, vk, &circuit).map_err(|e| format!("{:?}", e))?;
+        Ok(prove_many(&params, &pk, &[circuit], &[instance]))
+    }
+
+    // Checks a proof from `prove` against this run's public inputs. A wrong
+    // `out` is a run the proof doesn't verify for rather than an error.
+    pub fn verify(&self, proof: &[u8]) -> Result<bool, String> {
+        if self.n_steps != OUT_TERM {
+            return Err(format!(
+                "n_steps is {}, but the circuit proves F[{}]",
+                self.n_steps, OUT_TERM
+            ));
+        }
+        let instance = FiboPublicInputs {
+            a: Fp::from(self.a),
+            b: Fp::from(self.b),
+            out: Fp::from(self.out),
+        }
+        .to_instances(&FiboLayout::single());
+
+        let params = Params::<EqAffine>::new(K);
+        let vk = verifying_key(&params)?;
+        Ok(verify_many(&params, &vk, &[instance], proof))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::dev::MockProver;
+
+    #[test]
+    fn test_load_input() {
+        let input = FiboInput::from_json(r#"{ "a": 2, "b": 3, "out": 144, "n_steps": 9 }"#);
+        assert_eq!(
+            input,
+            Ok(FiboInput {
+                a: 2,
+                b: 3,
+                out: 144,
+                n_steps: 9
+            })
+        );
+        let (circuit, instances) = input.unwrap().circuit().unwrap();
+        assert_eq!(
+            instances,
+            vec![vec![Fp::from(2), Fp::from(3), Fp::from(144)]]
+        );
+        let prover = MockProver::run(4, &circuit, instances).unwrap();
+        prover.assert_satisfied();
+
+        // the wrong output, a run the circuit isn't laid out for, a missing
+        // field and a misspelt one
+        let wrong = FiboInput::from_json(r#"{ "a": 2, "b": 3, "out": 145, "n_steps": 9 }"#);
+        assert!(wrong.unwrap().circuit().is_err());
+        let longer = FiboInput::from_json(r#"{ "a": 2, "b": 3, "out": 233, "n_steps": 10 }"#);
+        assert!(longer.unwrap().circuit().is_err());
+        assert!(FiboInput::from_json(r#"{ "a": 2, "b": 3, "out": 144 }"#).is_err());
+        assert!(FiboInput::from_json(r#"{ "a": 2, "b": 3, "out": 144, "steps": 9 }"#).is_err());
+        assert!(FiboInput::load("no/such/input.json").is_err());
+    }
+
+    #[test]
+    fn test_prove_verify() {
+        let _guard = crate::testing::heavy_test();
+        let input = FiboInput::new(2, 3, 9).unwrap();
+        assert_eq!(input.out, 144);
+        assert!(FiboInput::new(u64::MAX, 1, 9).is_err());
+
+        let proof = input.prove().unwrap();
+        assert_eq!(input.verify(&proof), Ok(true));
+        assert_eq!(input.verify(&proof[1..]), Ok(false));
+        let wrong = FiboInput { out: 145, ..input };
+        assert_eq!(wrong.verify(&proof), Ok(false));
+        assert!(wrong.prove().is_err());
+        assert!(FiboInput::new(2, 3, 10).unwrap().verify(&proof).is_err());
+    }
+}
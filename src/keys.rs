@@ -0,0 +1,177 @@
+// Keys for every circuit a process proves with, made the first time they're
+// asked for and shared after that. Parameters only depend on k and the
+// backend, so with a directory they're also written there, see `ParamsFile`,
+// and read back by the next process instead of being generated again. The
+// proving and verifying keys can't be written out at this halo2 revision, so
+// they're always made from the parameters, once per process.
+//
+// The map is only locked to find a circuit's slot. Its keys are made holding
+// that slot's lock alone, so requests for keys that are already made never wait
+// on a keygen, and requests for the same new keys wait for the first one to
+// make them rather than all making them.
+
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    poly::commitment::Params,
+};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard, TryLockError},
+};
+
+use crate::{
+    fibo::FiboPublicInputs,
+    params::ParamsFile,
+    spec::{Backend, CircuitSpec, FiboKeys, Variant},
+};
+
+// One circuit's keys, empty until they're made or if making them failed.
+type Slot = Arc<Mutex<Option<Arc<FiboKeys>>>>;
+
+#[derive(Default)]
+pub struct KeyCache {
+    dir: Option<PathBuf>,
+    keys: Mutex<HashMap<(Variant, u32, Backend), Slot>>,
+    // held while parameters are read or written, so two circuits at the same
+    // k don't write the same file at once
+    params: Mutex<()>,
+}
+
+// A caller that panicked while holding a lock didn't leave anything
+// half-inserted.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl KeyCache {
+    // Keeps keys in memory only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Also keeps parameters in `dir`, which is created if it's missing.
+    pub fn on_disk(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: Some(dir.into()),
+            ..Self::default()
+        }
+    }
+
+    // The spec's circuit at its k, or the smallest k it fits in, made from
+    // the run starting at zero, since keys don't depend on the witness.
+    pub fn get(&self, spec: &CircuitSpec) -> Result<Arc<FiboKeys>, String> {
+        let (circuit, instance) = spec.build(&FiboPublicInputs::new(Fp::zero(), Fp::zero()))?;
+        let k = match spec.k {
+            Some(k) => k,
+            None => circuit.min_k(&instance)?,
+        };
+
+        let id = (spec.variant, k, spec.backend);
+        let slot = lock(&self.keys).entry(id).or_default().clone();
+
+        let mut slot = lock(&slot);
+        if let Some(keys) = &*slot {
+            return Ok(keys.clone());
+        }
+        let new = Arc::new(circuit.keygen_with(k, self.params(k, spec.backend)?)?);
+        *slot = Some(new.clone());
+        Ok(new)
+    }
+
+    fn params(&self, k: u32, backend: Backend) -> Result<Params<EqAffine>, String> {
+        let Some(dir) = &self.dir else {
+            return Ok(Params::new(k));
+        };
+        let _params = lock(&self.params);
+        let path = dir.join(format!("{:?}-{}.params", backend, k).to_lowercase());
+        if path.exists() {
+            return Params::load_k(&path, k);
+        }
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("can't create {}: {}", dir.display(), e))?;
+        Params::setup_and_save(k, &path)
+    }
+
+    // How many circuits there are keys for.
+    pub fn len(&self) -> usize {
+        lock(&self.keys)
+            .values()
+            .filter(|slot| match slot.try_lock() {
+                Ok(keys) => keys.is_some(),
+                Err(TryLockError::Poisoned(e)) => e.into_inner().is_some(),
+                // still being made
+                Err(TryLockError::WouldBlock) => false,
+            })
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::FiboInput;
+
+    #[test]
+    fn test_key_cache() {
+        let _guard = crate::testing::heavy_test();
+        let dir = std::env::temp_dir().join(format!("fibo-keys-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let spec = CircuitSpec::from_toml("variant = \"example3\"\nk = 4\nsteps = 9\n").unwrap();
+        let input = FiboInput::new(1, 1, 9).unwrap();
+        let (circuit, instance) = spec.build(&input.public_inputs().unwrap()).unwrap();
+
+        let cache = KeyCache::on_disk(&dir);
+        assert!(cache.is_empty());
+        let keys = cache.get(&spec).unwrap();
+        assert!(Arc::ptr_eq(&keys, &cache.get(&spec).unwrap()));
+        assert!(dir.join("ipa-4.params").exists());
+
+        // another variant at the same k shares the parameters, one at
+        // another k gets its own
+        let example1 = CircuitSpec {
+            variant: Variant::Example1,
+            ..spec
+        };
+        cache.get(&example1).unwrap();
+        cache.get(&CircuitSpec { k: Some(5), ..spec }).unwrap();
+        assert_eq!(cache.len(), 3);
+        assert!(dir.join("ipa-5.params").exists());
+
+        // keys that are still being made hold up neither the ones already
+        // made nor counting them
+        let making = Slot::default();
+        let id = (Variant::Example2, 4, Backend::Ipa);
+        lock(&cache.keys).insert(id, making.clone());
+        let held = lock(&making);
+        assert!(Arc::ptr_eq(&keys, &cache.get(&spec).unwrap()));
+        assert_eq!(cache.len(), 3);
+        drop(held);
+        lock(&cache.keys).remove(&id);
+
+        // a new cache reading the parameters back, and one generating them
+        // again, both verify the first cache's proofs
+        let proof = circuit.prove_with(&keys, instance.clone()).unwrap();
+        let reloaded = KeyCache::on_disk(&dir).get(&spec).unwrap();
+        assert!(reloaded.verify(&instance, &proof));
+        assert!(KeyCache::new()
+            .get(&spec)
+            .unwrap()
+            .verify(&instance, &proof));
+        let mut wrong = instance;
+        wrong[0][2] += Fp::one();
+        assert!(!reloaded.verify(&wrong, &proof));
+
+        // a file that isn't parameters
+        std::fs::write(dir.join("ipa-6.params"), b"not params").unwrap();
+        assert!(cache.get(&CircuitSpec { k: Some(6), ..spec }).is_err());
+        assert_eq!(cache.len(), 3);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
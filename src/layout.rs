@@ -0,0 +1,151 @@
+use halo2_proofs::{dev::CircuitLayout, pasta::Fp, plonk::Circuit};
+use plotters::{
+    coord::Shift,
+    prelude::{BitMapBackend, DrawingArea, DrawingBackend, IntoDrawingArea, SVGBackend, WHITE},
+};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Png,
+    Svg,
+}
+
+impl Format {
+    // The format a file name's extension asks for.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("png") => Ok(Format::Png),
+            Some("svg") => Ok(Format::Svg),
+            _ => Err(format!("{} isn't a .png or .svg file", path.display())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutOptions {
+    pub title: Option<String>,
+    // region names next to the regions
+    pub labels: bool,
+    // cells in copy constraints in red, and lines between them
+    pub equality: bool,
+    pub size: (u32, u32),
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            title: None,
+            labels: true,
+            equality: false,
+            size: (1024, 3096),
+        }
+    }
+}
+
+fn render<C: Circuit<Fp>, DB: DrawingBackend>(
+    circuit: &C,
+    k: u32,
+    root: DrawingArea<DB, Shift>,
+    options: &LayoutOptions,
+) -> Result<(), String> {
+    root.fill(&WHITE).map_err(|e| e.to_string())?;
+    let root = match &options.title {
+        Some(title) => root
+            .titled(title, ("sans-serif", 60))
+            .map_err(|e| e.to_string())?,
+        None => root,
+    };
+    CircuitLayout::default()
+        .show_labels(options.labels)
+        .mark_equality_cells(options.equality)
+        .show_equality_constraints(options.equality)
+        .render(k, circuit, &root)
+        .map_err(|e| e.to_string())?;
+    root.present().map_err(|e| e.to_string())
+}
+
+// Draws `circuit`'s layout at `k`: which columns and rows each region fills,
+// and where the selectors are on.
+pub fn render_layout<C: Circuit<Fp>>(
+    circuit: &C,
+    k: u32,
+    path: impl AsRef<Path>,
+    format: Format,
+    options: &LayoutOptions,
+) -> Result<(), String> {
+    let path = path.as_ref();
+    match format {
+        Format::Png => render(
+            circuit,
+            k,
+            BitMapBackend::new(path, options.size).into_drawing_area(),
+            options,
+        ),
+        Format::Svg => render(
+            circuit,
+            k,
+            SVGBackend::new(path, options.size).into_drawing_area(),
+            options,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example2;
+
+    #[test]
+    fn test_render_svg() {
+        let path = std::env::temp_dir().join("fib-2-layout-test.svg");
+        let options = LayoutOptions {
+            labels: false,
+            equality: true,
+            ..LayoutOptions::default()
+        };
+        let circuit = example2::MyCircuit::<Fp>::default();
+        render_layout(
+            &circuit,
+            4,
+            &path,
+            Format::from_path(&path).unwrap(),
+            &options,
+        )
+        .unwrap();
+        let svg = std::fs::read_to_string(&path).unwrap();
+        assert!(svg.starts_with("<svg"));
+        std::fs::remove_file(path).unwrap();
+
+        assert!(Format::from_path("layout.jpg").is_err());
+    }
+
+    // example1 laid out by both floor planners, which place its regions in
+    // different rows.
+    #[test]
+    fn test_render_planners() {
+        use crate::{example1, planner::Planned};
+        use halo2_proofs::circuit::{floor_planner::V1, SimpleFloorPlanner};
+
+        let options = LayoutOptions::default();
+        let render = |name: &str, svg: &dyn Fn(&Path) -> Result<(), String>| {
+            let path = std::env::temp_dir().join(name);
+            svg(&path).unwrap();
+            let svg = std::fs::read_to_string(&path).unwrap();
+            std::fs::remove_file(path).unwrap();
+            svg
+        };
+        let simple = render("fib-1-simple-test.svg", &|path| {
+            let circuit =
+                Planned::<_, SimpleFloorPlanner>::new(example1::MyCircuit::<Fp>::default());
+            render_layout(&circuit, 4, path, Format::Svg, &options)
+        });
+        let v1 = render("fib-1-v1-test.svg", &|path| {
+            let circuit = Planned::<_, V1>::new(example1::MyCircuit::<Fp>::default());
+            render_layout(&circuit, 4, path, Format::Svg, &options)
+        });
+        assert!(simple.starts_with("<svg") && v1.starts_with("<svg"));
+        assert_ne!(simple, v1);
+    }
+}
@@ -1,3 +1,40 @@
-mod example2;
-mod example1;
-mod example3;
\ No newline at end of file
+pub mod batch;
+pub mod circuits;
+pub mod cost;
+pub mod dot;
+pub mod evm;
+pub mod example1;
+pub mod example2;
+pub mod example3;
+pub mod example4;
+pub mod explain;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fibo;
+pub mod gadgets;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod io;
+pub mod keys;
+#[cfg(feature = "dev-graph")]
+pub mod layout;
+pub mod parallel;
+pub mod params;
+pub mod planner;
+#[cfg(feature = "profiling")]
+pub mod profiling;
+pub mod prover;
+pub mod service;
+pub mod spec;
+pub mod stats;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(test)]
+mod differential;
+#[cfg(test)]
+mod properties;
+#[cfg(test)]
+mod soundness;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
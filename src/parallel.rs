@@ -0,0 +1,34 @@
+// Host-side work the chips do before assigning, like hashing every pair on a
+// level of a Merkle tree, where each item is independent of the others. With
+// the `parallel` feature it's spread over rayon's pool; without it the items
+// are done in order. The result is the same either way.
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+// `f` of every `size`-long chunk of `items`, in order.
+pub fn map_chunks<T: Sync, U: Send>(
+    items: &[T],
+    size: usize,
+    f: impl Fn(&[T]) -> U + Sync + Send,
+) -> Vec<U> {
+    #[cfg(feature = "parallel")]
+    return items.par_chunks(size).map(f).collect();
+    #[cfg(not(feature = "parallel"))]
+    return items.chunks(size).map(f).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_chunks() {
+        let items: Vec<u64> = (0..1001).collect();
+        let sums = map_chunks(&items, 10, |chunk| chunk.iter().sum::<u64>());
+        assert_eq!(sums.len(), 101);
+        assert_eq!(sums[0], 45);
+        assert_eq!(sums[100], 1000);
+        assert_eq!(sums.iter().sum::<u64>(), 1000 * 1001 / 2);
+    }
+}
@@ -0,0 +1,186 @@
+// Parameters written to a file once and read back by every run after, instead
+// of being generated again. `Params::read` believes whatever k a file starts
+// with, so the file is `Params::write`'s bytes behind a header that says what
+// to expect:
+//
+//     magic "FIBOPRMS" | k: u32 | length: u64 | blake2b-256 of the body | body
+//
+// all little-endian. A file is only handed to `Params::read` once its length
+// is the one k implies and its hash matches, so a truncated, corrupted or
+// foreign file is an error rather than a panic or the wrong parameters.
+
+use halo2_proofs::{pasta::EqAffine, poly::commitment::Params};
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+const MAGIC: &[u8; 8] = b"FIBOPRMS";
+const HASH_BYTES: usize = 32;
+const HEADER_BYTES: usize = MAGIC.len() + 4 + 8 + HASH_BYTES;
+// One compressed Pallas point: k, the 2^k bases and as many Lagrange ones, w
+// and u, see `Params::write`.
+const POINT_BYTES: u64 = 32;
+// Past this the body is more than a terabyte, and no file is real parameters.
+const MAX_K: u32 = 32;
+
+fn body_len(k: u32) -> u64 {
+    4 + (2 * (1 << k) + 2) * POINT_BYTES
+}
+
+fn hash(body: &[u8]) -> [u8; HASH_BYTES] {
+    let hash = blake2b_simd::Params::new()
+        .hash_length(HASH_BYTES)
+        .personal(MAGIC)
+        .hash(body);
+    hash.as_bytes().try_into().unwrap()
+}
+
+pub trait ParamsFile: Sized {
+    // Generates parameters for `k` and writes them to `path`.
+    fn setup_and_save(k: u32, path: impl AsRef<Path>) -> Result<Self, String>;
+
+    // Reads parameters back from a file `setup_and_save` wrote.
+    fn load(path: impl AsRef<Path>) -> Result<Self, String>;
+
+    // Like `load`, for a file that has to hold parameters for `k`.
+    fn load_k(path: impl AsRef<Path>, k: u32) -> Result<Self, String>;
+}
+
+// Writes next to `path` and renames, so another process never reads a
+// half-written file.
+fn save(path: &Path, k: u32, params: &Params<EqAffine>) -> Result<(), String> {
+    let mut body = vec![];
+    params.write(&mut body).map_err(|e| e.to_string())?;
+    debug_assert_eq!(body.len() as u64, body_len(k));
+
+    let mut file = Vec::with_capacity(HEADER_BYTES + body.len());
+    file.extend_from_slice(MAGIC);
+    file.extend_from_slice(&k.to_le_bytes());
+    file.extend_from_slice(&(body.len() as u64).to_le_bytes());
+    file.extend_from_slice(&hash(&body));
+    file.extend_from_slice(&body);
+
+    let partial = path.with_extension(format!("partial-{}", std::process::id()));
+    std::fs::write(&partial, file)
+        .map_err(|e| format!("can't write {}: {}", partial.display(), e))?;
+    std::fs::rename(&partial, path).map_err(|e| format!("can't write {}: {}", path.display(), e))
+}
+
+fn load(path: &Path, expected_k: Option<u32>) -> Result<Params<EqAffine>, String> {
+    let bad = |reason: String| format!("{} isn't usable parameters: {}", path.display(), reason);
+    let file = File::open(path).map_err(|e| format!("can't open {}: {}", path.display(), e))?;
+    let size = file
+        .metadata()
+        .map_err(|e| format!("can't read {}: {}", path.display(), e))?
+        .len();
+    let mut reader = BufReader::new(file);
+
+    let mut header = [0; HEADER_BYTES];
+    reader
+        .read_exact(&mut header)
+        .map_err(|_| bad("too short for the header".to_string()))?;
+    if &header[..8] != MAGIC {
+        return Err(bad("not written by setup_and_save".to_string()));
+    }
+    let k = u32::from_le_bytes(header[8..12].try_into().unwrap());
+    let len = u64::from_le_bytes(header[12..20].try_into().unwrap());
+    if let Some(expected) = expected_k.filter(|expected| *expected != k) {
+        return Err(bad(format!("it's for k = {}, not {}", k, expected)));
+    }
+    if k > MAX_K || len != body_len(k) {
+        return Err(bad(format!("a length of {} for k = {}", len, k)));
+    }
+    if size != HEADER_BYTES as u64 + len {
+        return Err(bad(format!(
+            "{} bytes, but the header says {}",
+            size,
+            HEADER_BYTES as u64 + len
+        )));
+    }
+
+    let mut body = Vec::with_capacity(len as usize);
+    reader
+        .read_to_end(&mut body)
+        .map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+    if hash(&body) != header[20..] {
+        return Err(bad("the hash doesn't match".to_string()));
+    }
+    Params::read(&mut &body[..]).map_err(|e| bad(e.to_string()))
+}
+
+impl ParamsFile for Params<EqAffine> {
+    fn setup_and_save(k: u32, path: impl AsRef<Path>) -> Result<Self, String> {
+        let params = Params::new(k);
+        save(path.as_ref(), k, &params)?;
+        Ok(params)
+    }
+
+    fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        load(path.as_ref(), None)
+    }
+
+    fn load_k(path: impl AsRef<Path>, k: u32) -> Result<Self, String> {
+        load(path.as_ref(), Some(k))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_params_file() {
+        let dir = std::env::temp_dir().join(format!("fibo-params-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("ipa-4.params");
+
+        let params = Params::<EqAffine>::setup_and_save(4, &path).unwrap();
+        let file = std::fs::read(&path).unwrap();
+        assert_eq!(file.len() as u64, HEADER_BYTES as u64 + body_len(4));
+
+        let mut expected = vec![];
+        params.write(&mut expected).unwrap();
+        for loaded in [
+            Params::<EqAffine>::load(&path).unwrap(),
+            Params::<EqAffine>::load_k(&path, 4).unwrap(),
+        ] {
+            let mut bytes = vec![];
+            loaded.write(&mut bytes).unwrap();
+            assert_eq!(bytes, expected);
+        }
+        assert!(Params::<EqAffine>::load_k(&path, 5).is_err());
+
+        // every way the file can be wrong
+        let mut broken = vec![
+            ("truncated", file[..file.len() - 1].to_vec()),
+            ("too long", [&file[..], &[0]].concat()),
+            ("header only", file[..HEADER_BYTES].to_vec()),
+            ("empty", vec![]),
+            ("raw Params::write", expected),
+        ];
+        for (name, at) in [
+            ("magic", 0),
+            ("k", 8),
+            ("length", 12),
+            ("hash", 20),
+            ("body", HEADER_BYTES + 100),
+        ] {
+            let mut file = file.clone();
+            file[at] ^= 1;
+            broken.push((name, file));
+        }
+        let mut huge_k = file.clone();
+        huge_k[8..12].copy_from_slice(&200u32.to_le_bytes());
+        broken.push(("k = 200", huge_k));
+
+        for (name, bytes) in broken {
+            std::fs::write(&path, bytes).unwrap();
+            assert!(Params::<EqAffine>::load(&path).is_err(), "{}", name);
+        }
+        assert!(Params::<EqAffine>::load(dir.join("missing.params")).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
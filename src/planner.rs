@@ -0,0 +1,95 @@
+// The examples all hard-code `SimpleFloorPlanner`, which stacks regions one
+// after another in the order they're assigned. `Planned` swaps in another
+// floor planner without touching the circuit, so the same chips can be laid
+// out by, say, `V1`, which measures every region first and packs regions that
+// don't share columns side by side:
+//
+//     Planned::<_, V1>::new(example1::MyCircuit { a, b })
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::Layouter,
+    plonk::{Circuit, ConstraintSystem, Error, FloorPlanner},
+};
+use std::marker::PhantomData;
+
+pub struct Planned<C, P> {
+    pub circuit: C,
+    planner: PhantomData<P>,
+}
+
+impl<C, P> Planned<C, P> {
+    pub fn new(circuit: C) -> Self {
+        Self {
+            circuit,
+            planner: PhantomData,
+        }
+    }
+}
+
+impl<F: Field, C: Circuit<F>, P: FloorPlanner> Circuit<F> for Planned<C, P> {
+    type Config = C::Config;
+    type FloorPlanner = P;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.circuit.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.circuit.synthesize(config, layouter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        example1,
+        fibo::{FiboLayout, FiboPublicInputs},
+    };
+    use halo2_proofs::{
+        circuit::{floor_planner::V1, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+    };
+
+    // The first row of every region, in the order they were assigned.
+    fn region_starts<C: Circuit<Fp>>(circuit: &C) -> Vec<usize> {
+        let instance =
+            FiboPublicInputs::new(Fp::from(1), Fp::from(1)).to_instances(&FiboLayout::single());
+        let prover = MockProver::run(4, circuit, instance).unwrap();
+        prover.assert_satisfied();
+        let debug = format!("{:?}", prover);
+        debug
+            .match_indices("rows: Some((")
+            .map(|(i, _)| {
+                let first = &debug[i + "rows: Some((".len()..];
+                first[..first.find(',').unwrap()].parse().unwrap()
+            })
+            .collect()
+    }
+
+    // example1 assigns a region per row, each across all three advice
+    // columns, so neither planner can put two side by side. The simple
+    // planner stacks them in order; V1 measures them all before placing any,
+    // and its placement puts the table upside down in the same eight rows.
+    #[test]
+    fn test_example1_planners() {
+        let circuit = || example1::MyCircuit::<Fp> {
+            a: Value::known(Fp::from(1)),
+            b: Value::known(Fp::from(1)),
+        };
+        assert_eq!(
+            region_starts(&Planned::<_, SimpleFloorPlanner>::new(circuit())),
+            (0..8).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            region_starts(&Planned::<_, V1>::new(circuit())),
+            (0..8).rev().collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use halo2_proofs::{
+    dev::MockProver,
+    pasta::{EqAffine, Fp},
+    plonk::{keygen_pk, keygen_vk, Circuit},
+    poly::commitment::Params,
+};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::batch::{prove_many, verify_many, Instance};
+
+// With the `profiling` feature the crate installs this as the global
+// allocator, so it counts the whole process's heap: phases timed while other
+// threads allocate, like tests running side by side, see their memory too.
+pub struct CountingAlloc;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(current, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Phase {
+    pub name: String,
+    pub wall: Duration,
+    // the most the heap grew by while the phase ran
+    pub peak_bytes: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    pub phases: Vec<Phase>,
+}
+
+impl Profile {
+    // Runs `f` as the next phase, recording its wall time and peak heap.
+    pub fn phase<T>(&mut self, name: &str, f: impl FnOnce() -> T) -> T {
+        let before = CURRENT.load(Ordering::Relaxed);
+        PEAK.store(before, Ordering::Relaxed);
+        let start = Instant::now();
+        let result = f();
+        let wall = start.elapsed();
+        self.phases.push(Phase {
+            name: name.to_string(),
+            wall,
+            peak_bytes: PEAK.load(Ordering::Relaxed).saturating_sub(before),
+        });
+        result
+    }
+
+    pub fn total(&self) -> Duration {
+        self.phases.iter().map(|phase| phase.wall).sum()
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<12} {:>10} {:>12}",
+            "phase", "wall (ms)", "peak (KiB)"
+        )?;
+        for phase in &self.phases {
+            writeln!(
+                f,
+                "{:<12} {:>10.2} {:>12}",
+                phase.name,
+                phase.wall.as_secs_f64() * 1e3,
+                phase.peak_bytes / 1024
+            )?;
+        }
+        write!(
+            f,
+            "{:<12} {:>10.2}",
+            "total",
+            self.total().as_secs_f64() * 1e3
+        )
+    }
+}
+
+// Proves and verifies `circuit` once at `k`, a phase at a time. halo2 runs
+// witness generation, the commitment rounds and the opening argument all
+// inside `create_proof`, so "synthesis" is timed separately through the mock
+// prover and "prove" covers everything `create_proof` does.
+pub fn profile_proof<C: Circuit<Fp> + Sync>(
+    k: u32,
+    circuit: C,
+    instance: Instance,
+) -> Result<Profile, String> {
+    let mut profile = Profile::default();
+    let params = profile.phase("params", || Params::<EqAffine>::new(k));
+    let vk = profile
+        .phase("keygen_vk", || keygen_vk(&params, &circuit))
+        .map_err(|e| format!("{:?}", e))?;
+    let pk = profile
+        .phase("keygen_pk", || keygen_pk(&params, vk, &circuit))
+        .map_err(|e| format!("{:?}", e))?;
+    profile
+        .phase("synthesis", || {
+            MockProver::run(k, &circuit, instance.clone())
+        })
+        .map_err(|e| format!("{:?}", e))?
+        .verify()
+        .map_err(|failures| format!("{} constraint failures", failures.len()))?;
+
+    let instances = [instance];
+    let proof = profile.phase("prove", || prove_many(&params, &pk, &[circuit], &instances));
+    if !profile.phase("verify", || {
+        verify_many(&params, pk.get_vk(), &instances, &proof)
+    }) {
+        return Err("proof didn't verify".to_string());
+    }
+    Ok(profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        example1::MyCircuit,
+        fibo::{FiboLayout, FiboPublicInputs},
+    };
+    use halo2_proofs::circuit::Value;
+
+    #[test]
+    fn test_profile() {
+        let _guard = crate::testing::heavy_test();
+        let inputs = FiboPublicInputs::new(Fp::from(1), Fp::from(1));
+        let circuit = MyCircuit::<Fp> {
+            a: Value::known(inputs.a),
+            b: Value::known(inputs.b),
+        };
+        let profile =
+            profile_proof(4, circuit, inputs.to_instances(&FiboLayout::single())).unwrap();
+
+        let names: Vec<_> = profile.phases.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "params",
+                "keygen_vk",
+                "keygen_pk",
+                "synthesis",
+                "prove",
+                "verify"
+            ]
+        );
+        // the proving key holds the circuit's polynomials
+        assert!(profile.phases[2].peak_bytes > 0);
+        assert_eq!(profile.to_string().lines().count(), 8);
+    }
+}
@@ -0,0 +1,148 @@
+// Property tests for the Fibonacci circuits: random starting values, and for
+// `FiboSegment` random lengths, against a sequence computed on the host, and
+// random tampering with the public inputs.
+
+use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp, plonk::Circuit};
+use proptest::prelude::*;
+
+use crate::{
+    batch::{FiboSegment, Instance},
+    example1, example2, example3,
+    explain::{assert_unsatisfied_with, Failure},
+    fibo::{FiboLayout, FiboPublicInputs, OUT_TERM},
+};
+
+const K: u32 = 4;
+
+// F[n] for F[0] = a and F[1] = b, kept apart from the circuits' own helpers.
+fn fibonacci(a: Fp, b: Fp, n: usize) -> Fp {
+    let mut sequence = vec![a, b];
+    while sequence.len() <= n {
+        let next = sequence[sequence.len() - 2] + sequence[sequence.len() - 1];
+        sequence.push(next);
+    }
+    sequence[n]
+}
+
+fn field() -> impl Strategy<Value = Fp> {
+    prop_oneof![
+        // small values, where an off-by-one in the sequence is easy to read
+        any::<u16>().prop_map(|v| Fp::from(v as u64)),
+        // anything, reduced mod p
+        any::<[u64; 4]>().prop_map(Fp::from_raw),
+    ]
+}
+
+fn nonzero() -> impl Strategy<Value = Fp> {
+    field().prop_filter("a change of zero", |v| *v != Fp::zero())
+}
+
+fn instance(a: Fp, b: Fp) -> Instance {
+    FiboPublicInputs {
+        a,
+        b,
+        out: fibonacci(a, b, OUT_TERM),
+    }
+    .to_instances(&FiboLayout::single())
+}
+
+fn example1(a: Fp, b: Fp) -> example1::MyCircuit<Fp> {
+    example1::MyCircuit {
+        a: Value::known(a),
+        b: Value::known(b),
+    }
+}
+
+fn assert_satisfied<C: Circuit<Fp>>(k: u32, circuit: &C, instance: Instance) {
+    MockProver::run(k, circuit, instance)
+        .unwrap()
+        .assert_satisfied();
+}
+
+// `STEPS` steps of `FiboSegment` from (a, b), checked against the host and
+// with each of its public inputs tampered with in turn.
+fn check_segment<const STEPS: usize>(a: Fp, b: Fp, row: usize, delta: Fp) {
+    // the table takes STEPS + 1 rows, plus the blinding rows
+    let k = 5;
+    let circuit = FiboSegment::<STEPS>::new(a, b);
+    let instance = vec![vec![
+        a,
+        b,
+        fibonacci(a, b, STEPS),
+        fibonacci(a, b, STEPS + 1),
+    ]];
+    assert_satisfied(k, &circuit, instance.clone());
+
+    let mut tampered = instance;
+    tampered[0][row] += delta;
+    let column = ["A0", "A1", "A0", "A1"][row];
+    assert_unsatisfied_with(
+        k,
+        &circuit,
+        tampered,
+        &[Failure::Copy(column), Failure::Copy("I0")],
+    );
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn prop_examples_satisfied(a in field(), b in field()) {
+        let instance = instance(a, b);
+        prop_assert_eq!(FiboPublicInputs::new(a, b).out, instance[0][2]);
+
+        assert_satisfied(K, &example1(a, b), instance.clone());
+        assert_satisfied(K, &example2::MyCircuit::<Fp>::default(), instance.clone());
+        assert_satisfied(K, &example3::MyCircuit::<Fp>::default(), instance);
+    }
+
+    #[test]
+    fn prop_examples_tampered(a in field(), b in field(), row in 0..3usize, delta in nonzero()) {
+        let mut tampered = instance(a, b);
+        tampered[0][row] += delta;
+
+        // example1's witness is its own, so whichever public input changed
+        // stops matching the cells it's copied to; b's copy cycle reaches
+        // the instance through its copy on the next row
+        let column = ["A0", "A0", "A2"][row];
+        assert_unsatisfied_with(
+            K,
+            &example1(a, b),
+            tampered.clone(),
+            &[Failure::Copy(column), Failure::Copy("I0")],
+        );
+        // example2 and example3 read a and b from the instance, so any change
+        // shows up in the output
+        assert_unsatisfied_with(
+            K,
+            &example2::MyCircuit::<Fp>::default(),
+            tampered.clone(),
+            &[Failure::Copy("A0"), Failure::Copy("I0")],
+        );
+        assert_unsatisfied_with(
+            K,
+            &example3::MyCircuit::<Fp>::default(),
+            tampered,
+            &[Failure::Copy("A1"), Failure::Copy("I0")],
+        );
+    }
+
+    #[test]
+    fn prop_segment(
+        a in field(),
+        b in field(),
+        steps in prop::sample::select(vec![1, 2, 3, 5, 8, 13]),
+        row in 0..4usize,
+        delta in nonzero(),
+    ) {
+        match steps {
+            1 => check_segment::<1>(a, b, row, delta),
+            2 => check_segment::<2>(a, b, row, delta),
+            3 => check_segment::<3>(a, b, row, delta),
+            5 => check_segment::<5>(a, b, row, delta),
+            8 => check_segment::<8>(a, b, row, delta),
+            _ => check_segment::<13>(a, b, row, delta),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+// How many threads a proof gets. halo2 runs its MSMs and FFTs on whichever
+// rayon pool it's called from and splits each MSM into one chunk per thread of
+// that pool, so pinning the threads means proving inside a pool of that size.
+//
+// `chunk_size` is the fewest MSM points a thread should get. halo2 doesn't
+// take a chunk size of its own, so it's applied by capping the threads at
+// rows / chunk_size: for a small k, spinning up every core costs more than it
+// saves.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProverConfig {
+    // rayon's global pool size when unset, which is a thread per core unless
+    // RAYON_NUM_THREADS says otherwise
+    pub threads: Option<usize>,
+    pub chunk_size: Option<usize>,
+}
+
+impl ProverConfig {
+    pub fn threads(threads: usize) -> Self {
+        Self {
+            threads: Some(threads),
+            chunk_size: None,
+        }
+    }
+
+    // The threads a proof over `rows` rows runs on.
+    pub fn pool_size(&self, rows: usize) -> usize {
+        let threads = self.threads.unwrap_or_else(rayon::current_num_threads);
+        let threads = match self.chunk_size {
+            Some(chunk_size) => threads.min(rows / chunk_size.max(1)),
+            None => threads,
+        };
+        threads.max(1)
+    }
+
+    // Runs `f`, usually a `create_proof`, on a pool of `pool_size(rows)`
+    // threads. The default config runs it on the global pool as it is.
+    pub fn install<R: Send>(&self, rows: usize, f: impl FnOnce() -> R + Send) -> R {
+        if *self == Self::default() {
+            return f();
+        }
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(self.pool_size(rows))
+            .build()
+            .expect("couldn't start the prover's thread pool")
+            .install(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_size() {
+        assert_eq!(ProverConfig::threads(4).pool_size(1 << 10), 4);
+        assert_eq!(ProverConfig::threads(0).pool_size(1 << 10), 1);
+
+        let config = ProverConfig {
+            threads: Some(8),
+            chunk_size: Some(256),
+        };
+        assert_eq!(config.pool_size(1 << 12), 8);
+        assert_eq!(config.pool_size(1 << 10), 4);
+        assert_eq!(config.pool_size(16), 1);
+
+        assert_eq!(
+            ProverConfig::default().pool_size(1 << 10),
+            rayon::current_num_threads()
+        );
+    }
+
+    #[test]
+    fn test_install() {
+        for threads in [1, 3] {
+            let config = ProverConfig::threads(threads);
+            assert_eq!(config.install(1 << 10, rayon::current_num_threads), threads);
+        }
+    }
+}
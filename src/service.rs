@@ -0,0 +1,140 @@
+// Proving and verifying Fibonacci runs for many clients at once, behind the
+// servers in src/bin. The keys for a variant and k are made the first time
+// they're asked for and shared by every request after that, see `KeyCache`, so
+// requests only wait on each other while the keys for a new circuit are being
+// made. A k past `MAX_K` is turned away before any keys or parameters are made
+// for it, since the parameters alone take 2^k points.
+
+use halo2_proofs::pasta::Fp;
+
+use crate::{
+    fibo::{FiboLayout, FiboPublicInputs},
+    io::FiboInput,
+    keys::KeyCache,
+    spec::CircuitSpec,
+    stats::MAX_K,
+};
+
+#[derive(Default)]
+pub struct ProvingService {
+    keys: KeyCache,
+}
+
+fn check_steps(spec: &CircuitSpec, input: &FiboInput) -> Result<(), String> {
+    if input.n_steps != spec.steps {
+        return Err(format!(
+            "the input has {} steps, the spec {}",
+            input.n_steps, spec.steps
+        ));
+    }
+    Ok(())
+}
+
+fn check_k(spec: &CircuitSpec) -> Result<(), String> {
+    match spec.k {
+        Some(k) if k > MAX_K => Err(format!("k is {}, but at most {} is served", k, MAX_K)),
+        _ => Ok(()),
+    }
+}
+
+impl ProvingService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Keys from `keys`, which can keep parameters on disk between runs.
+    pub fn with_cache(keys: KeyCache) -> Self {
+        Self { keys }
+    }
+
+    // How many circuits there are keys for.
+    pub fn cached(&self) -> usize {
+        self.keys.len()
+    }
+
+    // Proves `input` with the spec's circuit, checking the proof.
+    pub fn prove(&self, spec: &CircuitSpec, input: &FiboInput) -> Result<Vec<u8>, String> {
+        check_steps(spec, input)?;
+        check_k(spec)?;
+        let keys = self.keys.get(spec)?;
+        let (circuit, instance) = spec.build(&input.public_inputs()?)?;
+        circuit.prove_with(&keys, instance)
+    }
+
+    // Checks a proof from `prove` against `input`. A wrong output is a run the
+    // proof doesn't verify for rather than an error.
+    pub fn verify(
+        &self,
+        spec: &CircuitSpec,
+        input: &FiboInput,
+        proof: &[u8],
+    ) -> Result<bool, String> {
+        check_steps(spec, input)?;
+        check_k(spec)?;
+        let keys = self.keys.get(spec)?;
+        let instance = FiboPublicInputs {
+            a: Fp::from(input.a),
+            b: Fp::from(input.b),
+            out: Fp::from(input.out),
+        }
+        .to_instances(&FiboLayout::single());
+        Ok(keys.verify(&instance, proof))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concurrent_requests() {
+        let _guard = crate::testing::heavy_test();
+        let service = ProvingService::new();
+        let spec = CircuitSpec::from_toml("variant = \"example3\"\nk = 4\nsteps = 9\n").unwrap();
+
+        let inputs: Vec<_> = (1..5).map(|a| FiboInput::new(a, 1, 9).unwrap()).collect();
+        let proofs: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = inputs
+                .iter()
+                .map(|input| scope.spawn(|| service.prove(&spec, input).unwrap()))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(service.cached(), 1);
+
+        for (input, proof) in inputs.iter().zip(&proofs) {
+            assert_eq!(service.verify(&spec, input, proof), Ok(true));
+            let wrong = FiboInput {
+                out: input.out + 1,
+                ..*input
+            };
+            assert_eq!(service.verify(&spec, &wrong, proof), Ok(false));
+            assert!(service.prove(&spec, &wrong).is_err());
+        }
+        // another run's proof
+        assert_eq!(service.verify(&spec, &inputs[0], &proofs[1]), Ok(false));
+
+        // a spec and input that don't agree, and a k that's too small
+        let longer = FiboInput::new(1, 1, 10).unwrap();
+        assert!(service.prove(&spec, &longer).is_err());
+        let small = CircuitSpec { k: Some(3), ..spec };
+        assert!(service.prove(&small, &inputs[0]).is_err());
+        assert_eq!(service.cached(), 1);
+    }
+
+    #[test]
+    fn test_k_too_big() {
+        let service = ProvingService::new();
+        let spec = CircuitSpec::from_toml("variant = \"example3\"\nk = 40\nsteps = 9\n").unwrap();
+        let input = FiboInput::new(1, 1, 9).unwrap();
+        assert!(service.prove(&spec, &input).is_err());
+        assert!(service.verify(&spec, &input, &[]).is_err());
+        assert_eq!(service.cached(), 0);
+
+        let largest = CircuitSpec {
+            k: Some(MAX_K),
+            ..spec
+        };
+        assert_eq!(check_k(&largest), Ok(()));
+    }
+}
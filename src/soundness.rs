@@ -0,0 +1,195 @@
+// Example3 enables its selector on every row but the last, and each gate
+// reaches one row down, so every cell of the table is checked by the gate on
+// its own row or the one above. These tests take an honest witness, break it
+// one row at a time, and check the circuit notices, so an off-by-one in the
+// selector pattern can't ship as an under-constrained example.
+
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    pasta::{EqAffine, Fp},
+    plonk::{keygen_pk, keygen_vk, Circuit, ConstraintSystem, Error},
+    poly::commitment::Params,
+};
+
+use crate::{
+    batch::{prove_many, verify_many, Instance},
+    example3::{self, FiboConfig},
+    explain::{assert_unsatisfied_with, Failure},
+    fibo::{FiboLayout, FiboPublicInputs},
+};
+
+const K: u32 = 4;
+// example3's table is five rows of two terms
+const ROWS: usize = 5;
+
+// Example3's gates and columns with a witness and selector pattern that can
+// be tampered with, assigned the way `example3::FiboChip::assign` does.
+struct Tampered {
+    table: [[Fp; 2]; ROWS],
+    // rows the selector is on
+    selectors: Vec<usize>,
+}
+
+impl Tampered {
+    // The honest witness for `a` and `b`.
+    fn new(a: Fp, b: Fp) -> Self {
+        let mut table = [[a, b]; ROWS];
+        for row in 1..ROWS {
+            let [a, b] = table[row - 1];
+            table[row] = [a + b, a + b + b];
+        }
+        Tampered {
+            table,
+            selectors: (0..ROWS - 1).collect(),
+        }
+    }
+
+    // Bumps one cell and carries on the sequence from it, so only the gate
+    // that reaches into that cell from above sees the change.
+    fn jump(mut self, row: usize, column: usize) -> Self {
+        self.table[row][column] += Fp::one();
+        if column == 0 {
+            self.table[row][1] = self.table[row - 1][1] + self.table[row][0];
+        }
+        for row in row + 1..ROWS {
+            let [a, b] = self.table[row - 1];
+            self.table[row] = [a + b, a + b + b];
+        }
+        self
+    }
+
+    fn out(&self) -> Fp {
+        self.table[ROWS - 1][1]
+    }
+}
+
+impl Circuit<Fp> for Tampered {
+    type Config = FiboConfig<1>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Tampered {
+            table: [[Fp::zero(); 2]; ROWS],
+            selectors: self.selectors.clone(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        example3::MyCircuit::<Fp>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let out = layouter.assign_region(
+            || "entire fibonacci table",
+            |mut region| {
+                for row in &self.selectors {
+                    config.selector.enable(&mut region, *row)?;
+                }
+                // the first row is copied from the instance, as example3 does,
+                // and then overwritten with the tampered values
+                let (a, b) = (config.layout.a, config.layout.b);
+                region.assign_advice_from_instance(
+                    || "a",
+                    config.instance[a.column],
+                    a.row,
+                    config.advice[0],
+                    0,
+                )?;
+                region.assign_advice_from_instance(
+                    || "b",
+                    config.instance[b.column],
+                    b.row,
+                    config.advice[1],
+                    0,
+                )?;
+
+                let mut last = None;
+                for (row, values) in self.table.iter().enumerate() {
+                    for (column, value) in config.advice.iter().zip(values) {
+                        last = Some(region.assign_advice(
+                            || "advice",
+                            *column,
+                            row,
+                            || Value::known(*value),
+                        )?);
+                    }
+                }
+                Ok(last.unwrap())
+            },
+        )?;
+        let at = config.layout.out;
+        layouter.constrain_instance(out.cell(), config.instance[at.column], at.row)
+    }
+}
+
+fn instance(out: Fp) -> Instance {
+    FiboPublicInputs {
+        a: Fp::from(1),
+        b: Fp::from(1),
+        out,
+    }
+    .to_instances(&FiboLayout::single())
+}
+
+// Every single cell changed on its own, with the selectors left alone.
+#[test]
+fn test_tampered_cell() {
+    let honest = Tampered::new(Fp::from(1), Fp::from(1));
+    let out = honest.out();
+    assert_eq!(out, Fp::from(55));
+    let prover = MockProver::run(K, &honest, instance(out)).unwrap();
+    prover.assert_satisfied();
+
+    for row in 0..ROWS {
+        for (column, name) in ["A0", "A1"].into_iter().enumerate() {
+            let mut tampered = Tampered::new(Fp::from(1), Fp::from(1));
+            tampered.table[row][column] += Fp::one();
+
+            let mut expected = vec![Failure::Gate("add1")];
+            // the initial values and the output are also copies
+            if row == 0 || (row, column) == (ROWS - 1, 1) {
+                expected.extend([Failure::Copy(name), Failure::Copy("I0")]);
+            }
+            assert_unsatisfied_with(K, &tampered, instance(out), &expected);
+        }
+    }
+}
+
+// Each selector turned off in turn, with the witness jumping to another
+// sequence on the row below it and claiming that sequence's output. Without
+// the selector nothing else notices, so the mock prover has to accept it;
+// with the real keys, whose fixed columns have the selector on, the proof
+// has to fail.
+#[test]
+fn test_skipped_selector() {
+    let _guard = crate::testing::heavy_test();
+    let params = Params::<EqAffine>::new(K);
+    let honest = Tampered::new(Fp::from(1), Fp::from(1));
+    let vk = keygen_vk(&params, &honest).unwrap();
+    let pk = keygen_pk(&params, vk, &honest).unwrap();
+    let instances = [instance(honest.out())];
+    let proof = prove_many(&params, &pk, &[honest], &instances);
+    assert!(verify_many(&params, pk.get_vk(), &instances, &proof));
+
+    for row in 0..ROWS - 1 {
+        let mut tampered = Tampered::new(Fp::from(1), Fp::from(1)).jump(row + 1, 0);
+        let instances = [instance(tampered.out())];
+        assert_unsatisfied_with(K, &tampered, instances[0].clone(), &[Failure::Gate("add1")]);
+
+        tampered.selectors.retain(|r| *r != row);
+        let prover = MockProver::run(K, &tampered, instances[0].clone()).unwrap();
+        prover.assert_satisfied();
+
+        let proof = prove_many(&params, &pk, &[tampered], &instances);
+        assert!(
+            !verify_many(&params, pk.get_vk(), &instances, &proof),
+            "accepted without the selector on row {}",
+            row
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use halo2_proofs::{
+    circuit::Value,
+    dev::MockProver,
+    pasta::{EqAffine, Fp},
+    plonk::{keygen_pk, keygen_vk, Circuit, ProvingKey},
+    poly::commitment::Params,
+};
+use serde::Deserialize;
+use std::{path::Path, str::FromStr};
+
+#[cfg(feature = "profiling")]
+use crate::profiling::{profile_proof, Profile};
+use crate::{
+    batch::{prove_many, verify_many, Instance},
+    example1, example2, example3,
+    fibo::{FiboLayout, FiboPublicInputs, OUT_TERM},
+    stats::min_k_for,
+};
+
+// Which of the three Fibonacci layouts to run: three advice columns with a
+// region per row, one advice column with the whole table in one region, or
+// two advice columns with two terms per row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Variant {
+    Example1,
+    Example2,
+    Example3,
+}
+
+impl FromStr for Variant {
+    type Err = String;
+
+    fn from_str(variant: &str) -> Result<Self, String> {
+        match variant {
+            "example1" => Ok(Variant::Example1),
+            "example2" => Ok(Variant::Example2),
+            "example3" => Ok(Variant::Example3),
+            _ => Err(format!(
+                "unknown variant {}, expected example1, example2 or example3",
+                variant
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Ipa,
+    Kzg,
+}
+
+// An experiment as a TOML file, e.g.
+//
+//     variant = "example3"
+//     k = 4
+//     steps = 9
+//     backend = "ipa"
+//
+// `backend` can be left out and defaults to IPA, and `k` to the smallest the
+// circuit fits in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CircuitSpec {
+    pub variant: Variant,
+    #[serde(default)]
+    pub k: Option<u32>,
+    pub steps: usize,
+    #[serde(default)]
+    pub backend: Backend,
+}
+
+// The circuit a spec picks.
+pub enum FiboCircuit {
+    Example1(example1::MyCircuit<Fp>),
+    Example2(example2::MyCircuit<Fp>),
+    Example3(example3::MyCircuit<Fp>),
+}
+
+impl CircuitSpec {
+    pub fn from_toml(spec: &str) -> Result<Self, String> {
+        toml::from_str(spec).map_err(|e| format!("bad spec: {}", e))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let spec = std::fs::read_to_string(path)
+            .map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+        Self::from_toml(&spec)
+    }
+
+    // The spec's circuit for `inputs`, and its instance columns.
+    pub fn build(&self, inputs: &FiboPublicInputs<Fp>) -> Result<(FiboCircuit, Instance), String> {
+        // halo2_proofs at this revision only has the IPA commitment scheme
+        // over the Pasta curves.
+        if self.backend == Backend::Kzg {
+            return Err("the KZG backend isn't available in this halo2 version".to_string());
+        }
+        // Every variant's table is laid out for F[OUT_TERM].
+        if self.steps != OUT_TERM {
+            return Err(format!(
+                "steps is {}, but the circuits prove F[{}]",
+                self.steps, OUT_TERM
+            ));
+        }
+        inputs.validate()?;
+
+        let circuit = match self.variant {
+            Variant::Example1 => FiboCircuit::Example1(example1::MyCircuit {
+                a: Value::known(inputs.a),
+                b: Value::known(inputs.b),
+            }),
+            Variant::Example2 => FiboCircuit::Example2(example2::MyCircuit::default()),
+            Variant::Example3 => FiboCircuit::Example3(example3::MyCircuit::default()),
+        };
+        Ok((circuit, inputs.to_instances(&FiboLayout::single())))
+    }
+}
+
+fn mock<C: Circuit<Fp>>(k: u32, circuit: &C, instance: &Instance) -> Result<(), String> {
+    let prover = MockProver::run(k, circuit, instance.clone()).map_err(|e| format!("{:?}", e))?;
+    prover
+        .verify()
+        .map_err(|failures| format!("{} constraint failures", failures.len()))
+}
+
+fn prove<C: Circuit<Fp> + Sync>(
+    k: Option<u32>,
+    circuit: C,
+    instance: Instance,
+) -> Result<Vec<u8>, String> {
+    let k = match k {
+        Some(k) => k,
+        None => min_k_for(&circuit, &instance)?,
+    };
+    let keys = keygen(k, Params::new(k), &circuit)?;
+    prove_with(&keys, circuit, instance)
+}
+
+fn keygen<C: Circuit<Fp>>(
+    k: u32,
+    params: Params<EqAffine>,
+    circuit: &C,
+) -> Result<FiboKeys, String> {
+    let vk = keygen_vk(&params, circuit).map_err(|e| format!("{:?}", e))?;
+    let pk = keygen_pk(&params, vk, circuit).map_err(|e| format!("{:?}", e))?;
+    Ok(FiboKeys { k, params, pk })
+}
+
+fn prove_with<C: Circuit<Fp> + Sync>(
+    keys: &FiboKeys,
+    circuit: C,
+    instance: Instance,
+) -> Result<Vec<u8>, String> {
+    // `create_proof` only panics on a bad witness, so rule that out first.
+    mock(keys.k, &circuit, &instance)?;
+    let instances = [instance];
+    let proof = prove_many(&keys.params, &keys.pk, &[circuit], &instances);
+    if !verify_many(&keys.params, keys.pk.get_vk(), &instances, &proof) {
+        return Err("proof didn't verify".to_string());
+    }
+    Ok(proof)
+}
+
+// The parameters and proving key of one variant at one k, to prove any number
+// of runs with.
+pub struct FiboKeys {
+    pub k: u32,
+    pub params: Params<EqAffine>,
+    pub pk: ProvingKey<EqAffine>,
+}
+
+impl FiboKeys {
+    // Checks a proof made with these keys against its instance.
+    pub fn verify(&self, instance: &Instance, proof: &[u8]) -> bool {
+        verify_many(
+            &self.params,
+            self.pk.get_vk(),
+            std::slice::from_ref(instance),
+            proof,
+        )
+    }
+}
+
+impl FiboCircuit {
+    // Runs the circuit in the mock prover at `k`.
+    pub fn mock(&self, k: u32, instance: &Instance) -> Result<(), String> {
+        match self {
+            FiboCircuit::Example1(circuit) => mock(k, circuit, instance),
+            FiboCircuit::Example2(circuit) => mock(k, circuit, instance),
+            FiboCircuit::Example3(circuit) => mock(k, circuit, instance),
+        }
+    }
+
+    // The smallest k the circuit fits in, see `min_k_for`.
+    pub fn min_k(&self, instance: &Instance) -> Result<u32, String> {
+        match self {
+            FiboCircuit::Example1(circuit) => min_k_for(circuit, instance),
+            FiboCircuit::Example2(circuit) => min_k_for(circuit, instance),
+            FiboCircuit::Example3(circuit) => min_k_for(circuit, instance),
+        }
+    }
+
+    // Proves the circuit at `k`, or the smallest k it fits in, and checks the
+    // proof, returning it.
+    pub fn prove(self, k: Option<u32>, instance: Instance) -> Result<Vec<u8>, String> {
+        match self {
+            FiboCircuit::Example1(circuit) => prove(k, circuit, instance),
+            FiboCircuit::Example2(circuit) => prove(k, circuit, instance),
+            FiboCircuit::Example3(circuit) => prove(k, circuit, instance),
+        }
+    }
+
+    // The keys at `k`. Keys don't depend on the witness, so the ones made
+    // from any circuit of a variant prove every run of it.
+    pub fn keygen(&self, k: u32) -> Result<FiboKeys, String> {
+        self.keygen_with(k, Params::new(k))
+    }
+
+    // The keys at `k` from parameters made or read elsewhere, which have to
+    // be for the same k.
+    pub fn keygen_with(&self, k: u32, params: Params<EqAffine>) -> Result<FiboKeys, String> {
+        match self {
+            FiboCircuit::Example1(circuit) => keygen(k, params, circuit),
+            FiboCircuit::Example2(circuit) => keygen(k, params, circuit),
+            FiboCircuit::Example3(circuit) => keygen(k, params, circuit),
+        }
+    }
+
+    // Proves the circuit with keys from `keygen`, and checks the proof.
+    pub fn prove_with(self, keys: &FiboKeys, instance: Instance) -> Result<Vec<u8>, String> {
+        match self {
+            FiboCircuit::Example1(circuit) => prove_with(keys, circuit, instance),
+            FiboCircuit::Example2(circuit) => prove_with(keys, circuit, instance),
+            FiboCircuit::Example3(circuit) => prove_with(keys, circuit, instance),
+        }
+    }
+
+    // Proves the circuit at `k` a phase at a time, see `profile_proof`.
+    #[cfg(feature = "profiling")]
+    pub fn profile(self, k: u32, instance: Instance) -> Result<Profile, String> {
+        match self {
+            FiboCircuit::Example1(circuit) => profile_proof(k, circuit, instance),
+            FiboCircuit::Example2(circuit) => profile_proof(k, circuit, instance),
+            FiboCircuit::Example3(circuit) => profile_proof(k, circuit, instance),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec() {
+        let spec = CircuitSpec::from_toml("variant = \"example3\"\nk = 4\nsteps = 9\n");
+        assert_eq!(
+            spec,
+            Ok(CircuitSpec {
+                variant: Variant::Example3,
+                k: Some(4),
+                steps: 9,
+                backend: Backend::Ipa,
+            })
+        );
+
+        let inputs = FiboPublicInputs::new(Fp::from(1), Fp::from(1));
+        for variant in ["example1", "example2", "example3"] {
+            let toml = format!("variant = \"{}\"\nk = 4\nsteps = 9\n", variant);
+            let (circuit, instance) = CircuitSpec::from_toml(&toml)
+                .unwrap()
+                .build(&inputs)
+                .unwrap();
+            assert_eq!(circuit.mock(4, &instance), Ok(()));
+            // too few rows
+            assert!(circuit.mock(3, &instance).is_err());
+        }
+
+        // KZG, a run length the circuits aren't laid out for, an unknown
+        // variant and an unknown key
+        let kzg = "variant = \"example1\"\nk = 4\nsteps = 9\nbackend = \"kzg\"\n";
+        assert!(CircuitSpec::from_toml(kzg).unwrap().build(&inputs).is_err());
+        let longer = "variant = \"example1\"\nk = 4\nsteps = 12\n";
+        assert!(CircuitSpec::from_toml(longer)
+            .unwrap()
+            .build(&inputs)
+            .is_err());
+        assert!(CircuitSpec::from_toml("variant = \"example4\"\nk = 4\nsteps = 9\n").is_err());
+        assert_eq!("example2".parse(), Ok(Variant::Example2));
+        assert!("example4".parse::<Variant>().is_err());
+        assert!(
+            CircuitSpec::from_toml("variant = \"example1\"\nk = 4\nsteps = 9\nn = 1\n").is_err()
+        );
+    }
+
+    #[test]
+    fn test_spec_prove() {
+        let _guard = crate::testing::heavy_test();
+        let spec = CircuitSpec::from_toml("variant = \"example2\"\nk = 4\nsteps = 9\n").unwrap();
+        let inputs = FiboPublicInputs::new(Fp::from(2), Fp::from(3));
+        let (circuit, instance) = spec.build(&inputs).unwrap();
+        assert!(circuit.prove(spec.k, instance.clone()).is_ok());
+        // without a k
+        let (circuit, _) = spec.build(&inputs).unwrap();
+        assert!(circuit.prove(None, instance).is_ok());
+
+        // the wrong output is caught before proving
+        let wrong = FiboPublicInputs {
+            out: inputs.out + Fp::one(),
+            ..inputs
+        };
+        let (circuit, _) = spec.build(&inputs).unwrap();
+        assert!(circuit
+            .prove(spec.k, wrong.to_instances(&FiboLayout::single()))
+            .is_err());
+    }
+}
@@ -0,0 +1,215 @@
+use halo2_proofs::{
+    dev::{CircuitGates, MockProver},
+    pasta::{EqAffine, Fp},
+    plonk::{keygen_vk, Circuit, ConstraintSystem},
+    poly::commitment::Params,
+};
+
+use crate::{batch::Instance, cost::read};
+
+// The largest k `min_k_for` tries; the parameters alone take a while past it.
+pub const MAX_K: u32 = 20;
+
+// The smallest k whose rows `circuit` fits in when the mock prover runs it on
+// `instance`. Whether the constraints hold doesn't matter, only whether every
+// region has room, so a bad witness still gets a k.
+pub fn min_k_for<C: Circuit<Fp>>(circuit: &C, instance: &Instance) -> Result<u32, String> {
+    // no k below what the blinding rows and the constraints' degree need
+    let mut cs = ConstraintSystem::<Fp>::default();
+    C::configure(&mut cs);
+    let min_rows = cs.minimum_rows().max(cs.degree());
+    let start = usize::BITS - (min_rows - 1).leading_zeros();
+
+    let mut last = None;
+    for k in start..=MAX_K {
+        match MockProver::run(k, circuit, instance.clone()) {
+            Ok(_) => return Ok(k),
+            Err(e) => last = Some(e),
+        }
+    }
+    Err(format!("doesn't fit in k = {}: {:?}", MAX_K, last.unwrap()))
+}
+
+// The fixed columns the verifying key for `circuit` commits to at `k`. Keygen
+// folds simple selectors that are never on in the same row into shared fixed
+// columns, so this can be fewer than `CircuitStats`' fixed columns and
+// selectors together, which are counted before that.
+pub fn keygen_fixed_columns<C: Circuit<Fp>>(k: u32, circuit: &C) -> Result<usize, String> {
+    let params = Params::<EqAffine>::new(k);
+    let vk = keygen_vk(&params, circuit).map_err(|e| format!("{:?}", e))?;
+    Ok(read(&format!("{:?}", vk.pinned()), "num_fixed_columns"))
+}
+
+// The size of a circuit, for tests that pin it so a refactor can't quietly
+// make a chip bigger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitStats {
+    pub gates: usize,
+    pub constraints: usize,
+    pub advice_columns: usize,
+    pub fixed_columns: usize,
+    pub instance_columns: usize,
+    pub selectors: usize,
+    // the furthest any query reaches from the row it's made on
+    pub max_rotation: usize,
+    pub lookups: usize,
+    // rows the circuit's regions cover, once it has been run
+    pub rows: Option<usize>,
+}
+
+impl CircuitStats {
+    // What `C` configures. `rows` is left empty, since counting them means
+    // synthesizing a circuit with its witness.
+    pub fn collect<C: Circuit<Fp>>() -> Self {
+        let gates = CircuitGates::collect::<Fp, C>().to_string();
+        let mut cs = ConstraintSystem::<Fp>::default();
+        C::configure(&mut cs);
+        let pinned = format!("{:?}", cs.pinned());
+
+        let max_rotation = pinned
+            .match_indices("Rotation(")
+            .map(|(i, _)| {
+                let rotation = &pinned[i + "Rotation(".len()..];
+                let end = rotation.find(')').unwrap();
+                rotation[..end].parse::<i32>().unwrap().unsigned_abs() as usize
+            })
+            .max()
+            .unwrap_or(0);
+
+        CircuitStats {
+            gates: read(&gates, "Total gates"),
+            constraints: read(&gates, "Total custom constraint polynomials"),
+            advice_columns: read(&pinned, "num_advice_columns"),
+            fixed_columns: read(&pinned, "num_fixed_columns"),
+            instance_columns: read(&pinned, "num_instance_columns"),
+            selectors: read(&pinned, "num_selectors"),
+            max_rotation,
+            lookups: pinned.matches("input_expressions").count(),
+            rows: None,
+        }
+    }
+
+    // What `C` configures, and the rows `circuit` uses when the mock prover
+    // runs it at `k` on `instance`.
+    pub fn collect_with_rows<C: Circuit<Fp>>(
+        k: u32,
+        circuit: &C,
+        instance: Instance,
+    ) -> Result<Self, String> {
+        let prover = MockProver::run(k, circuit, instance).map_err(|e| format!("{:?}", e))?;
+        // Each region's extent is only in the prover's debug output, as
+        // `rows: Some((first, last))`.
+        let debug = format!("{:?}", prover);
+        let rows = debug
+            .match_indices("rows: Some((")
+            .map(|(i, _)| {
+                let extent = &debug[i..];
+                let last = &extent[extent.find(", ").unwrap() + ", ".len()..];
+                let end = last.find(')').unwrap();
+                last[..end].parse::<usize>().unwrap() + 1
+            })
+            .max()
+            .unwrap_or(0);
+        Ok(CircuitStats {
+            rows: Some(rows),
+            ..Self::collect::<C>()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        example1, example2, example3, example4,
+        fibo::{FiboLayout, FiboPublicInputs},
+    };
+    use halo2_proofs::circuit::Value;
+
+    fn stats(
+        advice_columns: usize,
+        constraints: usize,
+        max_rotation: usize,
+        rows: usize,
+    ) -> CircuitStats {
+        CircuitStats {
+            gates: 1,
+            constraints,
+            advice_columns,
+            fixed_columns: 0,
+            instance_columns: 1,
+            selectors: 1,
+            max_rotation,
+            lookups: 0,
+            rows: Some(rows),
+        }
+    }
+
+    // The three Fibonacci layouts, as they are now. Growing any of them
+    // should be a deliberate change to these numbers.
+    #[test]
+    fn test_fibonacci_stats() {
+        let inputs = FiboPublicInputs::new(Fp::from(1), Fp::from(1));
+        let instance = inputs.to_instances(&FiboLayout::single());
+        let example1 = example1::MyCircuit::<Fp> {
+            a: Value::known(inputs.a),
+            b: Value::known(inputs.b),
+        };
+
+        assert_eq!(
+            CircuitStats::collect_with_rows(4, &example1, instance.clone()),
+            Ok(stats(3, 1, 0, 8))
+        );
+        assert_eq!(
+            CircuitStats::collect_with_rows(
+                4,
+                &example2::MyCircuit::<Fp>::default(),
+                instance.clone()
+            ),
+            Ok(stats(1, 1, 2, 10))
+        );
+        assert_eq!(
+            CircuitStats::collect_with_rows(
+                4,
+                &example3::MyCircuit::<Fp>::default(),
+                instance.clone()
+            ),
+            Ok(stats(2, 2, 1, 5))
+        );
+        // example2's table, the gate reaching back instead of forward
+        assert_eq!(
+            CircuitStats::collect_with_rows(4, &example4::MyCircuit::<Fp>::default(), instance),
+            Ok(stats(1, 1, 2, 10))
+        );
+        assert_eq!(
+            CircuitStats::collect::<example1::MyCircuit<Fp, 1>>().rows,
+            None
+        );
+    }
+
+    #[test]
+    fn test_min_k() {
+        let inputs = FiboPublicInputs::new(Fp::from(1), Fp::from(1));
+        let instance = inputs.to_instances(&FiboLayout::single());
+        let example1 = example1::MyCircuit::<Fp> {
+            a: Value::known(inputs.a),
+            b: Value::known(inputs.b),
+        };
+        // 8 rows and 10, plus the blinding rows
+        assert_eq!(min_k_for(&example1, &instance), Ok(4));
+        assert_eq!(
+            min_k_for(&example2::MyCircuit::<Fp>::default(), &instance),
+            Ok(4)
+        );
+        // 5 rows fit in 8 with the blinding rows
+        assert_eq!(
+            min_k_for(&example3::MyCircuit::<Fp>::default(), &instance),
+            Ok(4)
+        );
+
+        // a wrong output still fits
+        let mut wrong = instance.clone();
+        wrong[0][2] += Fp::one();
+        assert_eq!(min_k_for(&example1, &wrong), Ok(4));
+    }
+}
@@ -0,0 +1,161 @@
+use halo2_proofs::{arithmetic::FieldExt, dev::MockProver, plonk::Circuit};
+use std::sync::{Mutex, MutexGuard, Once};
+
+// Threads halo2 gets for FFTs and MSMs when `ci-small` is on.
+const CI_THREADS: usize = 2;
+
+static HEAVY: Mutex<()> = Mutex::new(());
+static POOL: Once = Once::new();
+
+// Call at the top of tests that run keygen or use a large k. With the
+// `ci-small` feature the returned guard keeps those tests from running at the
+// same time, and the global rayon pool is capped at `CI_THREADS`, so halo2
+// still goes through its parallel code paths without every test holding its
+// own copy of the domain in memory. Without the feature it does nothing.
+pub fn heavy_test() -> Option<MutexGuard<'static, ()>> {
+    if !cfg!(feature = "ci-small") {
+        return None;
+    }
+
+    POOL.call_once(|| {
+        // fails if something already touched the global pool, which is only
+        // the case when the cap doesn't matter anyway
+        let _ = rayon::ThreadPoolBuilder::new()
+            .num_threads(CI_THREADS)
+            .build_global();
+    });
+
+    // a test that panicked while holding the lock has already failed on its own
+    Some(HEAVY.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+// Instance columns close to a valid one, each with a note on what changed: the
+// last row dropped, every row off by one or negated, and every pair of
+// neighbouring rows that differ swapped. Negating is how a small integer
+// wraps around the field, so it catches public values that aren't range
+// checked. A row added on the end isn't here, since MockProver only looks at
+// the rows a circuit copies from; a real verifier rejects it because the whole
+// column goes into the transcript.
+pub fn near_misses<F: FieldExt>(instance: &[Vec<F>]) -> Vec<(String, Vec<Vec<F>>)> {
+    let mut misses = vec![];
+    for (column, values) in instance.iter().enumerate() {
+        let mut tweak = |note: String, f: &dyn Fn(&mut Vec<F>)| {
+            let mut instance = instance.to_vec();
+            f(&mut instance[column]);
+            misses.push((format!("column {}: {}", column, note), instance));
+        };
+
+        // missing rows are read as zero
+        if values.last().is_some_and(|v| *v != F::zero()) {
+            tweak("last row dropped".to_string(), &|values| {
+                values.pop();
+            });
+        }
+        for (row, value) in values.iter().enumerate() {
+            tweak(format!("row {} - 1", row), &|values| {
+                values[row] -= F::one()
+            });
+            if *value != -*value {
+                tweak(format!("row {} negated", row), &|values| {
+                    values[row] = -values[row]
+                });
+            }
+        }
+        for row in 1..values.len() {
+            if values[row - 1] != values[row] {
+                tweak(format!("rows {} and {} swapped", row - 1, row), &|values| {
+                    values.swap(row - 1, row)
+                });
+            }
+        }
+    }
+    misses
+}
+
+// Encodings of `value` that aren't canonical: its bytes with the modulus added
+// once, twice and so on while the sum still fits in a `Repr`. Each is a number
+// at least the modulus that reduces to `value`, so a decoder that reduces
+// instead of rejecting would read any of them as `value`, and two encodings of
+// one public input would both verify.
+pub fn non_canonical_encodings<F: FieldExt>(value: &F) -> Vec<(String, F::Repr)> {
+    let len = F::Repr::default().as_ref().len();
+    let hex = format!("{:0>1$}", F::MODULUS.trim_start_matches("0x"), 2 * len);
+    // little-endian, like the repr
+    let modulus: Vec<u8> = (0..len)
+        .rev()
+        .map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap())
+        .collect();
+
+    let mut encodings = vec![];
+    let mut repr = value.to_repr();
+    for times in 1.. {
+        let mut carry = 0;
+        for (byte, m) in repr.as_mut().iter_mut().zip(&modulus) {
+            let sum = *byte as u16 + *m as u16 + carry;
+            *byte = sum as u8;
+            carry = sum >> 8;
+        }
+        if carry != 0 {
+            break;
+        }
+        encodings.push((format!("value + {} * modulus", times), repr));
+    }
+    encodings
+}
+
+// Checks the circuit accepts `instance` and rejects every near miss of it,
+// either failing verification or not running at all. `still_true` names the
+// near misses that are true statements too, like a looser public bound, and
+// have to be accepted instead.
+#[track_caller]
+pub fn assert_rejects_near_misses<F: FieldExt, C: Circuit<F>>(
+    k: u32,
+    circuit: &C,
+    instance: Vec<Vec<F>>,
+    still_true: &[&str],
+) {
+    let prover = MockProver::run(k, circuit, instance.clone()).unwrap();
+    prover.assert_satisfied();
+
+    for (note, instance) in near_misses(&instance) {
+        let rejected = match MockProver::run(k, circuit, instance) {
+            Ok(prover) => prover.verify().is_err(),
+            Err(_) => true,
+        };
+        if still_true.contains(&note.as_str()) {
+            assert!(!rejected, "rejected with {}", note);
+        } else {
+            assert!(rejected, "accepted with {}", note);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::pasta::{group::ff::PrimeField, Fp};
+
+    #[test]
+    fn test_non_canonical_encodings() {
+        // the modulus is a little over 2^254, so three more fit in 256 bits
+        // on top of a small value and two on top of one close to it
+        for (value, n) in [
+            (Fp::zero(), 3),
+            (Fp::one(), 3),
+            (Fp::from(u64::MAX), 3),
+            (-Fp::one(), 2),
+        ] {
+            let encodings = non_canonical_encodings(&value);
+            assert_eq!(encodings.len(), n);
+            for (note, repr) in encodings {
+                assert_ne!(repr, value.to_repr(), "{}", note);
+                assert!(bool::from(Fp::from_repr(repr).is_none()), "{}", note);
+            }
+        }
+
+        // zero plus the modulus is the modulus
+        let (_, modulus) = &non_canonical_encodings(&Fp::zero())[0];
+        assert_eq!(modulus[0], 1);
+        assert_eq!(modulus[31], 0x40);
+    }
+}
@@ -0,0 +1,52 @@
+// Proving and verifying example1 from JavaScript, built with
+//
+//     wasm-pack build --target web -- --features wasm
+//
+// The keys are made on the spot on both sides, see `FiboInput::prove`. Public
+// inputs go over as a `BigUint64Array` of `[a, b, out]`.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{fibo::OUT_TERM, io::FiboInput};
+
+// A proof that F[n] is the Fibonacci term from `a` and `b`. The circuit only
+// proves the term `OUT_TERM`, so any other `n` is an error.
+#[wasm_bindgen]
+pub fn prove_fibonacci(a: u64, b: u64, n: usize) -> Result<Vec<u8>, String> {
+    FiboInput::new(a, b, n)?.prove()
+}
+
+// Checks a proof from `prove_fibonacci` against `publics`, `[a, b, out]`.
+#[wasm_bindgen]
+pub fn verify(proof: &[u8], publics: &[u64]) -> Result<bool, String> {
+    let [a, b, out] = publics else {
+        return Err(format!(
+            "expected [a, b, out], got {} values",
+            publics.len()
+        ));
+    };
+    let input = FiboInput {
+        a: *a,
+        b: *b,
+        out: *out,
+        n_steps: OUT_TERM,
+    };
+    input.verify(proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prove_verify() {
+        let _guard = crate::testing::heavy_test();
+        let proof = prove_fibonacci(1, 1, 9).unwrap();
+        assert_eq!(verify(&proof, &[1, 1, 55]), Ok(true));
+        assert_eq!(verify(&proof, &[1, 1, 56]), Ok(false));
+        assert!(verify(&proof, &[1, 1]).is_err());
+
+        // the circuit is laid out for F[9] only
+        assert!(prove_fibonacci(1, 1, 10).is_err());
+    }
+}
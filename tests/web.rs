@@ -0,0 +1,25 @@
+// The browser bindings under wasm-pack, against the proof the demo in www/
+// ships with:
+//
+//     wasm-pack test --headless --firefox -- --features wasm
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use halo2_examples::wasm::{prove_fibonacci, verify};
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+const PROOF: &[u8] = include_bytes!("../www/proof.bin");
+
+#[wasm_bindgen_test]
+fn test_verify_proof_file() {
+    assert_eq!(verify(PROOF, &[1, 1, 55]), Ok(true));
+    assert_eq!(verify(PROOF, &[1, 1, 56]), Ok(false));
+    assert_eq!(verify(&PROOF[1..], &[1, 1, 55]), Ok(false));
+}
+
+#[wasm_bindgen_test]
+fn test_prove_in_browser() {
+    let proof = prove_fibonacci(2, 3, 9).unwrap();
+    assert_eq!(verify(&proof, &[2, 3, 144]), Ok(true));
+}
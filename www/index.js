@@ -0,0 +1,61 @@
+// Glue between the page and the wasm bindings in src/wasm.rs. Build them into
+// www/pkg and serve this directory:
+//
+//     wasm-pack build --target web --out-dir www/pkg -- --features wasm
+//     python3 -m http.server -d www
+//
+// halo2 can't serialize a verifying key at the revision this crate uses, so
+// there's no key file to fetch: `verify` derives it from the circuit, which
+// for IPA needs no trusted setup and takes a moment at k = 4. proof.bin is a
+// proof of F[9] = 55 from a = b = 1, made natively.
+
+import init, { prove_fibonacci, verify } from "./pkg/halo2_examples.js";
+
+const result = document.getElementById("result");
+const field = (id) => BigInt(document.getElementById(id).value);
+
+function publics() {
+  return BigUint64Array.of(field("a"), field("b"), field("out"));
+}
+
+async function proof() {
+  const [file] = document.getElementById("proof").files;
+  if (file) {
+    return new Uint8Array(await file.arrayBuffer());
+  }
+  const response = await fetch("./proof.bin");
+  return new Uint8Array(await response.arrayBuffer());
+}
+
+function show(message) {
+  result.textContent = message;
+}
+
+async function main() {
+  await init();
+
+  document.getElementById("verify").addEventListener("submit", async (event) => {
+    event.preventDefault();
+    try {
+      show(verify(await proof(), publics()) ? "valid" : "invalid");
+    } catch (e) {
+      show(`error: ${e}`);
+    }
+  });
+
+  // proves (a, b) and offers the proof for download
+  document.getElementById("prove").addEventListener("click", () => {
+    try {
+      const bytes = prove_fibonacci(field("a"), field("b"), 9);
+      const link = document.createElement("a");
+      link.href = URL.createObjectURL(new Blob([bytes]));
+      link.download = "proof.bin";
+      link.click();
+      show(`${bytes.length} byte proof`);
+    } catch (e) {
+      show(`error: ${e}`);
+    }
+  });
+}
+
+main();